@@ -0,0 +1,614 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialize equal values to identical output.
+//!
+//! [`CanonicalSerializer`] wraps any `Serializer` and removes the choices that
+//! make the output for equal values differ:
+//!
+//! - Map entries are written in ascending order of their keys rather than in
+//!   iteration order, so a `HashMap` serializes the same way every time.
+//!   Every map is given its exact length. Two keys that serialize to the same
+//!   content are reported as an error.
+//! - Negative zero is written as `0.0`, and every NaN is written as the same
+//!   NaN.
+//!
+//! Everything else is passed through unchanged, including whether the format
+//! is human-readable.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::collections::HashMap;
+//!
+//! #[derive(Serialize)]
+//! struct Manifest {
+//!     // Serialized in sorted order regardless of the HashMap's hasher.
+//!     #[serde(serialize_with = "serde::canonical::serialize")]
+//!     files: HashMap<String, u64>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! Keys are ordered by their serialized content: numbers numerically, strings
+//! and bytes lexicographically by byte, sequences, tuples and structs element
+//! by element, and enum variants by name. Keys of different kinds, which a
+//! single map type does not normally produce, are ordered by kind.
+//!
+//! The entries of each map are buffered until the map ends, so serializing a
+//! map through this module allocates memory proportional to the size of the
+//! map.
+//!
+//! [`CanonicalSerializer`]: struct.CanonicalSerializer.html
+
+use lib::*;
+use lib::cmp::Ordering;
+
+use ser::{self, Serialize, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+          SerializeTupleStruct, SerializeTupleVariant, Serializer};
+
+use private::ser::{Content, ContentSerializer};
+
+/// Serializes `value` into `serializer` in canonical form.
+///
+/// This has the signature expected by `#[serde(serialize_with = "...")]`.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    value.serialize(CanonicalSerializer::new(serializer))
+}
+
+/// A `Serializer` that forwards a canonical form of every value to the
+/// wrapped serializer.
+///
+/// See the [module documentation](index.html) for what is normalized.
+pub struct CanonicalSerializer<S> {
+    serializer: S,
+}
+
+impl<S> CanonicalSerializer<S>
+where
+    S: Serializer,
+{
+    /// Wraps `serializer`.
+    pub fn new(serializer: S) -> Self {
+        CanonicalSerializer { serializer: serializer }
+    }
+}
+
+// Serializes the wrapped value through a `CanonicalSerializer`.
+struct Canonical<'a, T: ?Sized + 'a>(&'a T);
+
+impl<'a, T: ?Sized> Serialize for Canonical<'a, T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(CanonicalSerializer::new(serializer))
+    }
+}
+
+macro_rules! forward_primitive {
+    ($($(#[$attr:meta])* $method:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                self.serializer.$method(v)
+            }
+        )*
+    }
+}
+
+impl<S> Serializer for CanonicalSerializer<S>
+where
+    S: Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeSeq = Compound<S::SerializeSeq>;
+    type SerializeTuple = Compound<S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+    type SerializeMap = SortedMap<S>;
+    type SerializeStruct = Compound<S::SerializeStruct>;
+    type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+    forward_primitive! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        serialize_u128(u128),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_f32(normalize_f32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_f64(normalize_f64(v))
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        self.serializer.serialize_some(&Canonical(value))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.serializer
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        self.serializer
+            .serialize_newtype_struct(name, &Canonical(value))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        self.serializer
+            .serialize_newtype_variant(name, variant_index, variant, &Canonical(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        self.serializer.serialize_seq(len).map(Compound)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        self.serializer.serialize_tuple(len).map(Compound)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        self.serializer
+            .serialize_tuple_struct(name, len)
+            .map(Compound)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        self.serializer
+            .serialize_tuple_variant(name, variant_index, variant, len)
+            .map(Compound)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SortedMap<S>, S::Error> {
+        let human_readable = self.serializer.is_human_readable();
+        Ok(
+            SortedMap {
+                serializer: self.serializer,
+                human_readable: human_readable,
+                entries: Vec::with_capacity(len.unwrap_or(0)),
+                key: None,
+            },
+        )
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        self.serializer.serialize_struct(name, len).map(Compound)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        self.serializer
+            .serialize_struct_variant(name, variant_index, variant, len)
+            .map(Compound)
+    }
+
+    fn collect_str<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+    {
+        self.serializer.collect_str(value)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.serializer.is_human_readable()
+    }
+}
+
+/// The compound serializer returned by `CanonicalSerializer` for sequences,
+/// structs and variants.
+pub struct Compound<C>(C);
+
+macro_rules! canonical_serialize_compound {
+    ($trait:ident, $method:ident($($key:ident: $key_ty:ty)*)) => {
+        impl<C> $trait for Compound<C>
+        where
+            C: $trait,
+        {
+            type Ok = C::Ok;
+            type Error = C::Error;
+
+            fn $method<T: ?Sized>(&mut self, $($key: $key_ty,)* value: &T) -> Result<(), C::Error>
+            where
+                T: Serialize,
+            {
+                self.0.$method($($key,)* &Canonical(value))
+            }
+
+            fn end(self) -> Result<C::Ok, C::Error> {
+                self.0.end()
+            }
+        }
+    };
+}
+
+macro_rules! canonical_serialize_struct {
+    ($trait:ident) => {
+        impl<C> $trait for Compound<C>
+        where
+            C: $trait,
+        {
+            type Ok = C::Ok;
+            type Error = C::Error;
+
+            fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), C::Error>
+            where
+                T: Serialize,
+            {
+                self.0.serialize_field(key, &Canonical(value))
+            }
+
+            fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+                self.0.skip_field(key)
+            }
+
+            fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), C::Error> {
+                self.0.annotate(key, annotation)
+            }
+
+            fn end(self) -> Result<C::Ok, C::Error> {
+                self.0.end()
+            }
+        }
+    };
+}
+
+canonical_serialize_compound!(SerializeSeq, serialize_element());
+canonical_serialize_compound!(SerializeTuple, serialize_element());
+canonical_serialize_compound!(SerializeTupleStruct, serialize_field());
+canonical_serialize_compound!(SerializeTupleVariant, serialize_field());
+canonical_serialize_struct!(SerializeStruct);
+canonical_serialize_struct!(SerializeStructVariant);
+
+/// The map serializer returned by `CanonicalSerializer`, which buffers the
+/// entries and writes them in sorted order when the map ends.
+pub struct SortedMap<S> {
+    serializer: S,
+    human_readable: bool,
+    entries: Vec<(Content, Content)>,
+    key: Option<Content>,
+}
+
+impl<S> SortedMap<S>
+where
+    S: Serializer,
+{
+    fn buffer<T: ?Sized>(&self, value: &T) -> Result<Content, S::Error>
+    where
+        T: Serialize,
+    {
+        Canonical(value).serialize(ContentSerializer::with_human_readable(self.human_readable))
+    }
+}
+
+impl<S> ser::SerializeMap for SortedMap<S>
+where
+    S: Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), S::Error>
+    where
+        T: Serialize,
+    {
+        self.key = Some(try!(self.buffer(key)));
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), S::Error>
+    where
+        T: Serialize,
+    {
+        let key = self.key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let value = try!(self.buffer(value));
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<S::Ok, S::Error> {
+        self.entries.sort_by(|a, b| cmp_content(&a.0, &b.0));
+        for pair in self.entries.windows(2) {
+            if cmp_content(&pair[0].0, &pair[1].0) == Ordering::Equal {
+                return Err(ser::Error::custom("duplicate map key in canonical form"));
+            }
+        }
+
+        let mut map = try!(self.serializer.serialize_map(Some(self.entries.len())));
+        for &(ref key, ref value) in &self.entries {
+            try!(map.serialize_entry(&Canonical(key), &Canonical(value)));
+        }
+        map.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg_attr(feature = "cargo-clippy", allow(eq_op))]
+fn normalize_f32(v: f32) -> f32 {
+    if v == 0.0 {
+        0.0
+    } else if v != v {
+        f32::NAN
+    } else {
+        v
+    }
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(eq_op))]
+fn normalize_f64(v: f64) -> f64 {
+    if v == 0.0 {
+        0.0
+    } else if v != v {
+        f64::NAN
+    } else {
+        v
+    }
+}
+
+// Maps a float to an integer with the same order, placing NaN above infinity.
+fn float_key(v: f64) -> u64 {
+    let bits: u64 = unsafe { mem::transmute(normalize_f64(v)) };
+    if bits >> 63 == 1 { !bits } else { bits | 1 << 63 }
+}
+
+// Position of each kind of content in the order of keys of different kinds.
+fn rank(content: &Content) -> u8 {
+    match *content {
+        Content::Bool(_) => 0,
+        Content::U8(_) | Content::U16(_) | Content::U32(_) | Content::U64(_) => 1,
+        #[cfg(feature = "integer128")]
+        Content::U128(_) => 1,
+        Content::I8(_) | Content::I16(_) | Content::I32(_) | Content::I64(_) => 2,
+        #[cfg(feature = "integer128")]
+        Content::I128(_) => 2,
+        Content::F32(_) | Content::F64(_) => 3,
+        Content::Char(_) => 4,
+        Content::String(_) => 5,
+        Content::Bytes(_) => 6,
+        Content::None => 7,
+        Content::Some(_) => 8,
+        Content::Unit => 9,
+        Content::UnitStruct(_) => 10,
+        Content::UnitVariant(..) => 11,
+        Content::NewtypeStruct(..) => 12,
+        Content::NewtypeVariant(..) => 13,
+        Content::Seq(_) => 14,
+        Content::Tuple(_) => 15,
+        Content::TupleStruct(..) => 16,
+        Content::TupleVariant(..) => 17,
+        Content::Map(_) => 18,
+        Content::Struct(..) => 19,
+        Content::StructVariant(..) => 20,
+    }
+}
+
+// The widest integer types, which every integer key is widened to before
+// comparing.
+#[cfg(feature = "integer128")]
+type Unsigned = u128;
+#[cfg(not(feature = "integer128"))]
+type Unsigned = u64;
+#[cfg(feature = "integer128")]
+type Signed = i128;
+#[cfg(not(feature = "integer128"))]
+type Signed = i64;
+
+fn unsigned(content: &Content) -> Unsigned {
+    match *content {
+        Content::U8(v) => v as Unsigned,
+        Content::U16(v) => v as Unsigned,
+        Content::U32(v) => v as Unsigned,
+        Content::U64(v) => v as Unsigned,
+        #[cfg(feature = "integer128")]
+        Content::U128(v) => v,
+        _ => unreachable!(),
+    }
+}
+
+fn signed(content: &Content) -> Signed {
+    match *content {
+        Content::I8(v) => v as Signed,
+        Content::I16(v) => v as Signed,
+        Content::I32(v) => v as Signed,
+        Content::I64(v) => v as Signed,
+        #[cfg(feature = "integer128")]
+        Content::I128(v) => v,
+        _ => unreachable!(),
+    }
+}
+
+fn float(content: &Content) -> f64 {
+    match *content {
+        Content::F32(v) => v as f64,
+        Content::F64(v) => v,
+        _ => unreachable!(),
+    }
+}
+
+fn cmp_seq(a: &[Content], b: &[Content]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        match cmp_content(a, b) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn cmp_fields(a: &[(&'static str, Content)], b: &[(&'static str, Content)]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        match then_cmp(a.0.cmp(b.0), &a.1, &b.1) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn cmp_entries(a: &[(Content, Content)], b: &[(Content, Content)]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        match then_cmp(cmp_content(&a.0, &b.0), &a.1, &b.1) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+// Compares `a` and `b` if the preceding parts compared equal.
+fn then_cmp(ordering: Ordering, a: &Content, b: &Content) -> Ordering {
+    match ordering {
+        Ordering::Equal => cmp_content(a, b),
+        ordering => ordering,
+    }
+}
+
+fn cmp_content(a: &Content, b: &Content) -> Ordering {
+    match rank(a).cmp(&rank(b)) {
+        Ordering::Equal => {}
+        ordering => return ordering,
+    }
+    match (a, b) {
+        (&Content::Bool(a), &Content::Bool(b)) => a.cmp(&b),
+        (&Content::Char(a), &Content::Char(b)) => a.cmp(&b),
+        (&Content::String(ref a), &Content::String(ref b)) => a.cmp(b),
+        (&Content::Bytes(ref a), &Content::Bytes(ref b)) => a.cmp(b),
+        (&Content::Some(ref a), &Content::Some(ref b)) => cmp_content(a, b),
+        (&Content::UnitStruct(a), &Content::UnitStruct(b)) => a.cmp(b),
+        (&Content::UnitVariant(a, _, av), &Content::UnitVariant(b, _, bv)) => (a, av).cmp(&(b, bv)),
+        (&Content::NewtypeStruct(a, ref ac), &Content::NewtypeStruct(b, ref bc)) => {
+            then_cmp(a.cmp(b), ac, bc)
+        }
+        (&Content::NewtypeVariant(a, _, av, ref ac), &Content::NewtypeVariant(b, _, bv, ref bc)) => {
+            then_cmp((a, av).cmp(&(b, bv)), ac, bc)
+        }
+        (&Content::Seq(ref a), &Content::Seq(ref b)) |
+        (&Content::Tuple(ref a), &Content::Tuple(ref b)) => cmp_seq(a, b),
+        (&Content::TupleStruct(a, ref af), &Content::TupleStruct(b, ref bf)) => {
+            match a.cmp(b) {
+                Ordering::Equal => cmp_seq(af, bf),
+                ordering => ordering,
+            }
+        }
+        (&Content::TupleVariant(a, _, av, ref af), &Content::TupleVariant(b, _, bv, ref bf)) => {
+            match (a, av).cmp(&(b, bv)) {
+                Ordering::Equal => cmp_seq(af, bf),
+                ordering => ordering,
+            }
+        }
+        (&Content::Map(ref a), &Content::Map(ref b)) => cmp_entries(a, b),
+        (&Content::Struct(a, ref af), &Content::Struct(b, ref bf)) => {
+            match a.cmp(b) {
+                Ordering::Equal => cmp_fields(af, bf),
+                ordering => ordering,
+            }
+        }
+        (&Content::StructVariant(a, _, av, ref af), &Content::StructVariant(b, _, bv, ref bf)) => {
+            match (a, av).cmp(&(b, bv)) {
+                Ordering::Equal => cmp_fields(af, bf),
+                ordering => ordering,
+            }
+        }
+        _ => {
+            match rank(a) {
+                1 => unsigned(a).cmp(&unsigned(b)),
+                2 => signed(a).cmp(&signed(b)),
+                3 => float_key(float(a)).cmp(&float_key(float(b))),
+                // None and Unit.
+                _ => Ordering::Equal,
+            }
+        }
+    }
+}
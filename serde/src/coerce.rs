@@ -0,0 +1,679 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lenient conversion of numbers during deserialization, configured in one
+//! place.
+//!
+//! The `Deserialize` impls of the primitive number types accept any integer
+//! that fits and reject everything else. Data from loosely typed sources
+//! often needs more: `42.0` for a `u32`, `"8080"` for a `u16`, or a value
+//! that is clamped instead of rejected when it is out of range. Rather than
+//! each format deciding on its own, [`CoerceDeserializer`] wraps any
+//! deserializer and converts numbers according to a [`Policy`] before they
+//! reach the `Deserialize` impl that asked for them:
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use serde::Deserialize;
+//! use serde::coerce::{CoerceDeserializer, FloatToInt, Overflow, Policy};
+//! use serde::de::IntoDeserializer;
+//! use serde::de::value::Error;
+//!
+//! # fn main() {
+//! let policy = Policy::strict()
+//!     .float_to_int(FloatToInt::Exact)
+//!     .overflow(Overflow::Saturate);
+//!
+//! let de = IntoDeserializer::<Error>::into_deserializer(42.0f64);
+//! assert_eq!(u32::deserialize(CoerceDeserializer::new(de, policy)), Ok(42));
+//!
+//! let de = IntoDeserializer::<Error>::into_deserializer(300i32);
+//! assert_eq!(u8::deserialize(CoerceDeserializer::new(de, policy)), Ok(255));
+//!
+//! let de = IntoDeserializer::<Error>::into_deserializer(42.5f64);
+//! assert!(u32::deserialize(CoerceDeserializer::new(de, policy)).is_err());
+//! # }
+//! ```
+//!
+//! The policy applies to every integer and float requested through
+//! `deserialize_i8` to `deserialize_f64`, at any depth. A value the policy
+//! does not allow to be converted is passed on unchanged, so it fails with
+//! the usual error.
+//!
+//! [`CoerceDeserializer`]: struct.CoerceDeserializer.html
+//! [`Policy`]: struct.Policy.html
+
+use lib::*;
+
+use de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+         Visitor};
+
+/// Whether a float may be deserialized as an integer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FloatToInt {
+    /// Floats are never integers.
+    Error,
+    /// Floats without a fractional part, like `42.0`, are integers.
+    Exact,
+    /// Floats are rounded towards zero, so `-2.7` becomes `-2`.
+    Truncate,
+}
+
+/// What happens to an integer that does not fit the requested type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// The integer is rejected.
+    Error,
+    /// The integer is truncated to the low bits of the requested type, like
+    /// an `as` cast, so `300` becomes `44` as a `u8`. Floats outside the range
+    /// of `i64` and `u64` are still rejected.
+    Wrap,
+    /// The integer is clamped to the nearest value of the requested type, so
+    /// `300` becomes `255` as a `u8` and `-1` becomes `0`.
+    Saturate,
+}
+
+/// The conversions a `CoerceDeserializer` performs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    float_to_int: FloatToInt,
+    overflow: Overflow,
+    parse_strings: bool,
+}
+
+impl Policy {
+    /// A policy that converts nothing, which behaves like the wrapped
+    /// deserializer on its own.
+    pub fn strict() -> Self {
+        Policy {
+            float_to_int: FloatToInt::Error,
+            overflow: Overflow::Error,
+            parse_strings: false,
+        }
+    }
+
+    /// Sets whether floats may be deserialized as integers.
+    pub fn float_to_int(mut self, float_to_int: FloatToInt) -> Self {
+        self.float_to_int = float_to_int;
+        self
+    }
+
+    /// Sets what happens to integers that do not fit the requested type.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets whether a string containing a number, like `"42"` or `"0.5"`, may
+    /// be deserialized as that number. The number is then subject to the rest
+    /// of the policy.
+    pub fn parse_strings(mut self, parse_strings: bool) -> Self {
+        self.parse_strings = parse_strings;
+        self
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::strict()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Deserializer` that converts numbers according to a `Policy` before
+/// passing them to the visitor.
+pub struct CoerceDeserializer<D> {
+    deserializer: D,
+    policy: Policy,
+}
+
+impl<'de, D> CoerceDeserializer<D>
+where
+    D: Deserializer<'de>,
+{
+    /// Wraps `deserializer` so that numbers are converted according to
+    /// `policy`.
+    pub fn new(deserializer: D, policy: Policy) -> Self {
+        CoerceDeserializer {
+            deserializer: deserializer,
+            policy: policy,
+        }
+    }
+}
+
+macro_rules! coerce_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*) $target:ident;)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let visitor = CoerceVisitor {
+                    visitor: visitor,
+                    policy: self.policy,
+                    target: Target::$target,
+                };
+                self.deserializer.$method($($arg,)* visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, D> Deserializer<'de> for CoerceDeserializer<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    coerce_deserialize! {
+        deserialize_any() Other;
+        deserialize_bool() Other;
+        deserialize_i8() I8;
+        deserialize_i16() I16;
+        deserialize_i32() I32;
+        deserialize_i64() I64;
+        deserialize_u8() U8;
+        deserialize_u16() U16;
+        deserialize_u32() U32;
+        deserialize_u64() U64;
+        deserialize_f32() F32;
+        deserialize_f64() F64;
+        deserialize_char() Other;
+        deserialize_str() Other;
+        deserialize_string() Other;
+        deserialize_bytes() Other;
+        deserialize_byte_buf() Other;
+        deserialize_option() Other;
+        deserialize_unit() Other;
+        deserialize_unit_struct(name: &'static str) Other;
+        deserialize_newtype_struct(name: &'static str) Other;
+        deserialize_seq() Other;
+        deserialize_tuple(len: usize) Other;
+        deserialize_tuple_struct(name: &'static str, len: usize) Other;
+        deserialize_map() Other;
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]) Other;
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]) Other;
+        deserialize_identifier() Other;
+        deserialize_ignored_any() Other;
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The number type requested from the deserializer, if any.
+#[derive(Copy, Clone, PartialEq)]
+enum Target {
+    Other,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+// A number as it was visited.
+enum Number {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+// An integer to convert, or a float that was out of range of all integers.
+enum Int {
+    Signed(i64),
+    Unsigned(u64),
+    Below,
+    Above,
+}
+
+// A converted number and the visitor method to pass it to.
+enum Converted {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F64(f64),
+}
+
+impl Converted {
+    fn visit<'de, V, E>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+        E: de::Error,
+    {
+        match self {
+            Converted::I8(n) => visitor.visit_i8(n),
+            Converted::I16(n) => visitor.visit_i16(n),
+            Converted::I32(n) => visitor.visit_i32(n),
+            Converted::I64(n) => visitor.visit_i64(n),
+            Converted::U8(n) => visitor.visit_u8(n),
+            Converted::U16(n) => visitor.visit_u16(n),
+            Converted::U32(n) => visitor.visit_u32(n),
+            Converted::U64(n) => visitor.visit_u64(n),
+            Converted::F64(n) => visitor.visit_f64(n),
+        }
+    }
+}
+
+fn float_to_int(f: f64, float_to_int: FloatToInt) -> Option<Int> {
+    let f = match float_to_int {
+        FloatToInt::Error => return None,
+        FloatToInt::Exact if f.fract() != 0.0 => return None,
+        FloatToInt::Exact => f,
+        FloatToInt::Truncate => f.trunc(),
+    };
+    // Both bounds are powers of two and so exactly representable.
+    Some(
+        if f.is_nan() {
+            return None;
+        } else if f < -9223372036854775808.0 {
+            Int::Below
+        } else if f < 0.0 {
+            Int::Signed(f as i64)
+        } else if f < 18446744073709551616.0 {
+            Int::Unsigned(f as u64)
+        } else {
+            Int::Above
+        },
+    )
+}
+
+macro_rules! convert_int {
+    ($($name:ident: $ty:ident,)*) => {
+        $(
+            fn $name(n: Int, overflow: Overflow) -> Option<$ty> {
+                // Every integer type's minimum fits in an i64 and its maximum
+                // in a u64.
+                let min = $ty::min_value() as i64;
+                let max = $ty::max_value() as u64;
+                match n {
+                    Int::Signed(n) if n >= min && (n < 0 || n as u64 <= max) => Some(n as $ty),
+                    Int::Unsigned(n) if n <= max => Some(n as $ty),
+                    Int::Signed(n) if overflow == Overflow::Wrap => Some(n as $ty),
+                    Int::Unsigned(n) if overflow == Overflow::Wrap => Some(n as $ty),
+                    _ if overflow != Overflow::Saturate => None,
+                    Int::Signed(n) if n < 0 => Some($ty::min_value()),
+                    Int::Below => Some($ty::min_value()),
+                    _ => Some($ty::max_value()),
+                }
+            }
+        )*
+    }
+}
+
+convert_int! {
+    convert_i8: i8,
+    convert_i16: i16,
+    convert_i32: i32,
+    convert_i64: i64,
+    convert_u8: u8,
+    convert_u16: u16,
+    convert_u32: u32,
+    convert_u64: u64,
+}
+
+fn convert(n: Number, target: Target, policy: Policy) -> Option<Converted> {
+    let n = match n {
+        // The float impls accept integers already.
+        _ if target == Target::Other || target == Target::F32 || target == Target::F64 => {
+            return None
+        }
+        Number::Signed(n) => Int::Signed(n),
+        Number::Unsigned(n) => Int::Unsigned(n),
+        Number::Float(f) => {
+            match float_to_int(f, policy.float_to_int) {
+                Some(n) => n,
+                None => return None,
+            }
+        }
+    };
+    let overflow = policy.overflow;
+    match target {
+        Target::I8 => convert_i8(n, overflow).map(Converted::I8),
+        Target::I16 => convert_i16(n, overflow).map(Converted::I16),
+        Target::I32 => convert_i32(n, overflow).map(Converted::I32),
+        Target::I64 => convert_i64(n, overflow).map(Converted::I64),
+        Target::U8 => convert_u8(n, overflow).map(Converted::U8),
+        Target::U16 => convert_u16(n, overflow).map(Converted::U16),
+        Target::U32 => convert_u32(n, overflow).map(Converted::U32),
+        Target::U64 => convert_u64(n, overflow).map(Converted::U64),
+        Target::Other | Target::F32 | Target::F64 => None,
+    }
+}
+
+fn parse(s: &str, target: Target, policy: Policy) -> Option<Converted> {
+    if !policy.parse_strings || target == Target::Other {
+        return None;
+    }
+    let n = if let Ok(n) = s.parse() {
+        Number::Signed(n)
+    } else if let Ok(n) = s.parse() {
+        Number::Unsigned(n)
+    } else if let Ok(f) = s.parse() {
+        Number::Float(f)
+    } else {
+        return None;
+    };
+    match (n, target) {
+        (Number::Signed(n), Target::F32) |
+        (Number::Signed(n), Target::F64) => Some(Converted::I64(n)),
+        (Number::Unsigned(n), Target::F32) |
+        (Number::Unsigned(n), Target::F64) => Some(Converted::U64(n)),
+        (Number::Float(f), Target::F32) |
+        (Number::Float(f), Target::F64) => Some(Converted::F64(f)),
+        (n, _) => convert(n, target, policy),
+    }
+}
+
+struct CoerceVisitor<V> {
+    visitor: V,
+    policy: Policy,
+    target: Target,
+}
+
+macro_rules! coerce_visit_number {
+    ($($method:ident($ty:ty) $number:ident($as:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                match convert(Number::$number(v as $as), self.target, self.policy) {
+                    Some(n) => n.visit(self.visitor),
+                    None => self.visitor.$method(v),
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! coerce_visit_string {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                match parse(&v, self.target, self.policy) {
+                    Some(n) => n.visit(self.visitor),
+                    None => self.visitor.$method(v),
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visitor.$method(v)
+            }
+        )*
+    }
+}
+
+impl<'de, V> Visitor<'de> for CoerceVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    coerce_visit_number! {
+        visit_i8(i8) Signed(i64),
+        visit_i16(i16) Signed(i64),
+        visit_i32(i32) Signed(i64),
+        visit_i64(i64) Signed(i64),
+        visit_u8(u8) Unsigned(u64),
+        visit_u16(u16) Unsigned(u64),
+        visit_u32(u32) Unsigned(u64),
+        visit_u64(u64) Unsigned(u64),
+        visit_f32(f32) Float(f64),
+        visit_f64(f64) Float(f64),
+    }
+
+    coerce_visit_string! {
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+    }
+
+    forward_visit! {
+        visit_bool(bool),
+        visit_char(char),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor
+            .visit_some(CoerceDeserializer::new(deserializer, self.policy))
+    }
+
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor
+            .visit_newtype_struct(CoerceDeserializer::new(deserializer, self.policy))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.visitor.visit_seq(CoerceAccess::new(seq, self.policy))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(CoerceAccess::new(map, self.policy))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(CoerceAccess::new(data, self.policy))
+    }
+}
+
+struct CoerceSeed<T> {
+    seed: T,
+    policy: Policy,
+}
+
+impl<'de, T> DeserializeSeed<'de> for CoerceSeed<T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed
+            .deserialize(CoerceDeserializer::new(deserializer, self.policy))
+    }
+}
+
+struct CoerceAccess<A> {
+    access: A,
+    policy: Policy,
+}
+
+impl<A> CoerceAccess<A> {
+    fn new(access: A, policy: Policy) -> Self {
+        CoerceAccess {
+            access: access,
+            policy: policy,
+        }
+    }
+
+    fn seed<T>(&self, seed: T) -> CoerceSeed<T> {
+        CoerceSeed {
+            seed: seed,
+            policy: self.policy,
+        }
+    }
+
+    fn visitor<V>(&self, visitor: V) -> CoerceVisitor<V> {
+        CoerceVisitor {
+            visitor: visitor,
+            policy: self.policy,
+            target: Target::Other,
+        }
+    }
+}
+
+impl<'de, A> SeqAccess<'de> for CoerceAccess<A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, A> MapAccess<'de> for CoerceAccess<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.next_key_seed(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, A> EnumAccess<'de> for CoerceAccess<A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = CoerceAccess<A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let policy = self.policy;
+        let seed = self.seed(seed);
+        self.access
+            .variant_seed(seed)
+            .map(|(value, variant)| (value, CoerceAccess::new(variant, policy)))
+    }
+}
+
+impl<'de, A> VariantAccess<'de> for CoerceAccess<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.access.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = self.visitor(visitor);
+        self.access.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = self.visitor(visitor);
+        self.access.struct_variant(fields, visitor)
+    }
+}
@@ -14,6 +14,9 @@ use de::{Deserialize, Deserializer, EnumAccess, Error, SeqAccess, Unexpected, Va
 #[cfg(any(feature = "std", feature = "alloc"))]
 use de::MapAccess;
 
+#[cfg(feature = "std")]
+use de::Expected;
+
 use de::from_primitive::FromPrimitive;
 
 #[cfg(any(feature = "std", feature = "alloc"))]
@@ -358,38 +361,62 @@ impl<'de> Visitor<'de> for CStringVisitor {
             values.push(value);
         }
 
-        CString::new(values).map_err(Error::custom)
+        CString::new(values).map_err(|err| nul_error(err, false))
     }
 
     fn visit_bytes<E>(self, v: &[u8]) -> Result<CString, E>
     where
         E: Error,
     {
-        CString::new(v).map_err(Error::custom)
+        CString::new(v).map_err(|err| nul_error(err, false))
     }
 
     fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<CString, E>
     where
         E: Error,
     {
-        CString::new(v).map_err(Error::custom)
+        CString::new(v).map_err(|err| nul_error(err, false))
     }
 
     fn visit_str<E>(self, v: &str) -> Result<CString, E>
     where
         E: Error,
     {
-        CString::new(v).map_err(Error::custom)
+        CString::new(v).map_err(|err| nul_error(err, true))
     }
 
     fn visit_string<E>(self, v: String) -> Result<CString, E>
     where
         E: Error,
     {
-        CString::new(v).map_err(Error::custom)
+        CString::new(v).map_err(|err| nul_error(err, true))
     }
 }
 
+// Reports the content that was rejected, as a string if it came from one,
+// along with the position of its first nul byte.
+#[cfg(feature = "std")]
+fn nul_error<E>(err: NulError, from_str: bool) -> E
+where
+    E: Error,
+{
+    struct NulPosition(usize);
+
+    impl Expected for NulPosition {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "no nul bytes, found one at position {}", self.0)
+        }
+    }
+
+    let position = err.nul_position();
+    let bytes = err.into_vec();
+    let unexpected = match str::from_utf8(&bytes) {
+        Ok(s) if from_str => Unexpected::Str(s),
+        _ => Unexpected::Bytes(&bytes),
+    };
+    Error::invalid_value(unexpected, &NulPosition(position))
+}
+
 #[cfg(feature = "std")]
 impl<'de> Deserialize<'de> for CString {
     fn deserialize<D>(deserializer: D) -> Result<CString, D::Error>
@@ -1134,6 +1161,20 @@ impl<'de> Visitor<'de> for PathBufVisitor {
     {
         Ok(From::from(v))
     }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<PathBuf, E>
+    where
+        E: Error,
+    {
+        os_string_from_bytes(v.to_vec(), &self).map(From::from)
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<PathBuf, E>
+    where
+        E: Error,
+    {
+        os_string_from_bytes(v, &self).map(From::from)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -1148,6 +1189,28 @@ impl<'de> Deserialize<'de> for PathBuf {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// Bytes are the native form of OS strings on Unix. Elsewhere they are accepted
+// only if they are valid UTF-8.
+#[cfg(all(feature = "std", unix))]
+fn os_string_from_bytes<E>(v: Vec<u8>, _expected: &Expected) -> Result<OsString, E>
+where
+    E: Error,
+{
+    use std::os::unix::ffi::OsStringExt;
+    Ok(OsString::from_vec(v))
+}
+
+#[cfg(all(feature = "std", not(unix)))]
+fn os_string_from_bytes<E>(v: Vec<u8>, expected: &Expected) -> Result<OsString, E>
+where
+    E: Error,
+{
+    match String::from_utf8(v) {
+        Ok(s) => Ok(From::from(s)),
+        Err(err) => Err(Error::invalid_value(Unexpected::Bytes(&err.into_bytes()), expected)),
+    }
+}
+
 // If this were outside of the serde crate, it would just use:
 //
 //    #[derive(Deserialize)]
@@ -1198,6 +1261,34 @@ impl<'de> Visitor<'de> for OsStringVisitor {
             (OsStringKind::Unix, _) => Err(Error::custom("cannot deserialize Unix OS string on Windows",),),
         }
     }
+
+    fn visit_str<E>(self, v: &str) -> Result<OsString, E>
+    where
+        E: Error,
+    {
+        Ok(From::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<OsString, E>
+    where
+        E: Error,
+    {
+        Ok(From::from(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<OsString, E>
+    where
+        E: Error,
+    {
+        os_string_from_bytes(v.to_vec(), &self)
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<OsString, E>
+    where
+        E: Error,
+    {
+        os_string_from_bytes(v, &self)
+    }
 }
 
 #[cfg(all(feature = "std", any(unix, windows)))]
@@ -1567,19 +1658,200 @@ where
     Idx: Deserialize<'de>,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (start, end) = try!(
+            deserializer.deserialize_struct(
+                "Range",
+                range::FIELDS,
+                range::RangeVisitor {
+                    expecting: "struct Range",
+                    phantom: PhantomData,
+                },
+            )
+        );
+        Ok(start..end)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "ranges"))]
+impl<'de, Idx> Deserialize<'de> for ops::RangeInclusive<Idx>
+where
+    Idx: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (start, end) = try!(
+            deserializer.deserialize_struct(
+                "RangeInclusive",
+                range::FIELDS,
+                range::RangeVisitor {
+                    expecting: "struct RangeInclusive",
+                    phantom: PhantomData,
+                },
+            )
+        );
+        Ok(ops::RangeInclusive::new(start, end))
+    }
+}
+
+// The fields and visitor shared by Range and RangeInclusive.
+#[cfg(feature = "std")]
+mod range {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Visitor};
+
+    pub const FIELDS: &'static [&'static str] = &["start", "end"];
+
+    // If this were outside of the serde crate, it would just use:
+    //
+    //    #[derive(Deserialize)]
+    //    #[serde(field_identifier, rename_all = "lowercase")]
+    enum Field {
+        Start,
+        End,
+    }
+
+    impl<'de> Deserialize<'de> for Field {
+        fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct FieldVisitor;
+
+            impl<'de> Visitor<'de> for FieldVisitor {
+                type Value = Field;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("`start` or `end`")
+                }
+
+                fn visit_str<E>(self, value: &str) -> Result<Field, E>
+                where
+                    E: Error,
+                {
+                    match value {
+                        "start" => Ok(Field::Start),
+                        "end" => Ok(Field::End),
+                        _ => Err(Error::unknown_field(value, FIELDS)),
+                    }
+                }
+
+                fn visit_bytes<E>(self, value: &[u8]) -> Result<Field, E>
+                where
+                    E: Error,
+                {
+                    match value {
+                        b"start" => Ok(Field::Start),
+                        b"end" => Ok(Field::End),
+                        _ => {
+                            let value = String::from_utf8_lossy(value);
+                            Err(Error::unknown_field(&value, FIELDS))
+                        }
+                    }
+                }
+            }
+
+            deserializer.deserialize_identifier(FieldVisitor)
+        }
+    }
+
+    pub struct RangeVisitor<Idx> {
+        pub expecting: &'static str,
+        pub phantom: PhantomData<Idx>,
+    }
+
+    impl<'de, Idx> Visitor<'de> for RangeVisitor<Idx>
+    where
+        Idx: Deserialize<'de>,
+    {
+        type Value = (Idx, Idx);
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str(self.expecting)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let start: Idx = match try!(seq.next_element()) {
+                Some(value) => value,
+                None => {
+                    return Err(Error::invalid_length(0, &self));
+                }
+            };
+            let end: Idx = match try!(seq.next_element()) {
+                Some(value) => value,
+                None => {
+                    return Err(Error::invalid_length(1, &self));
+                }
+            };
+            Ok((start, end))
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut start: Option<Idx> = None;
+            let mut end: Option<Idx> = None;
+            while let Some(key) = try!(map.next_key()) {
+                match key {
+                    Field::Start => {
+                        if start.is_some() {
+                            return Err(<A::Error as Error>::duplicate_field("start"));
+                        }
+                        start = Some(try!(map.next_value()));
+                    }
+                    Field::End => {
+                        if end.is_some() {
+                            return Err(<A::Error as Error>::duplicate_field("end"));
+                        }
+                        end = Some(try!(map.next_value()));
+                    }
+                }
+            }
+            let start = match start {
+                Some(start) => start,
+                None => return Err(<A::Error as Error>::missing_field("start")),
+            };
+            let end = match end {
+                Some(end) => end,
+                None => return Err(<A::Error as Error>::missing_field("end")),
+            };
+            Ok((start, end))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "ranges")]
+impl<'de, T> Deserialize<'de> for Bound<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Bound<T>, D::Error>
     where
         D: Deserializer<'de>,
     {
         // If this were outside of the serde crate, it would just use:
         //
         //    #[derive(Deserialize)]
-        //    #[serde(field_identifier, rename_all = "lowercase")]
+        //    #[serde(variant_identifier)]
         enum Field {
-            Start,
-            End,
-        };
+            Unbounded,
+            Included,
+            Excluded,
+        }
 
         impl<'de> Deserialize<'de> for Field {
+            #[inline]
             fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
             where
                 D: Deserializer<'de>,
@@ -1590,7 +1862,21 @@ where
                     type Value = Field;
 
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("`start` or `end`")
+                        formatter.write_str("`Unbounded`, `Included` or `Excluded`")
+                    }
+
+                    fn visit_u32<E>(self, value: u32) -> Result<Field, E>
+                    where
+                        E: Error,
+                    {
+                        match value {
+                            0 => Ok(Field::Unbounded),
+                            1 => Ok(Field::Included),
+                            2 => Ok(Field::Excluded),
+                            _ => {
+                                Err(Error::invalid_value(Unexpected::Unsigned(value as u64), &self),)
+                            }
+                        }
                     }
 
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
@@ -1598,9 +1884,10 @@ where
                         E: Error,
                     {
                         match value {
-                            "start" => Ok(Field::Start),
-                            "end" => Ok(Field::End),
-                            _ => Err(Error::unknown_field(value, FIELDS)),
+                            "Unbounded" => Ok(Field::Unbounded),
+                            "Included" => Ok(Field::Included),
+                            "Excluded" => Ok(Field::Excluded),
+                            _ => Err(Error::unknown_variant(value, VARIANTS)),
                         }
                     }
 
@@ -1609,11 +1896,16 @@ where
                         E: Error,
                     {
                         match value {
-                            b"start" => Ok(Field::Start),
-                            b"end" => Ok(Field::End),
+                            b"Unbounded" => Ok(Field::Unbounded),
+                            b"Included" => Ok(Field::Included),
+                            b"Excluded" => Ok(Field::Excluded),
                             _ => {
-                                let value = String::from_utf8_lossy(value);
-                                Err(Error::unknown_field(&value, FIELDS))
+                                match str::from_utf8(value) {
+                                    Ok(value) => Err(Error::unknown_variant(value, VARIANTS)),
+                                    Err(_) => {
+                                        Err(Error::invalid_value(Unexpected::Bytes(value), &self))
+                                    }
+                                }
                             }
                         }
                     }
@@ -1623,75 +1915,33 @@ where
             }
         }
 
-        struct RangeVisitor<Idx> {
-            phantom: PhantomData<Idx>,
-        }
+        struct BoundVisitor<T>(PhantomData<Bound<T>>);
 
-        impl<'de, Idx> Visitor<'de> for RangeVisitor<Idx>
+        impl<'de, T> Visitor<'de> for BoundVisitor<T>
         where
-            Idx: Deserialize<'de>,
+            T: Deserialize<'de>,
         {
-            type Value = ops::Range<Idx>;
+            type Value = Bound<T>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("struct Range")
-            }
-
-            fn visit_seq<A>(self, mut seq: A) -> Result<ops::Range<Idx>, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                let start: Idx = match try!(seq.next_element()) {
-                    Some(value) => value,
-                    None => {
-                        return Err(Error::invalid_length(0, &self));
-                    }
-                };
-                let end: Idx = match try!(seq.next_element()) {
-                    Some(value) => value,
-                    None => {
-                        return Err(Error::invalid_length(1, &self));
-                    }
-                };
-                Ok(start..end)
+                formatter.write_str("enum Bound")
             }
 
-            fn visit_map<A>(self, mut map: A) -> Result<ops::Range<Idx>, A::Error>
+            fn visit_enum<A>(self, data: A) -> Result<Bound<T>, A::Error>
             where
-                A: MapAccess<'de>,
+                A: EnumAccess<'de>,
             {
-                let mut start: Option<Idx> = None;
-                let mut end: Option<Idx> = None;
-                while let Some(key) = try!(map.next_key()) {
-                    match key {
-                        Field::Start => {
-                            if start.is_some() {
-                                return Err(<A::Error as Error>::duplicate_field("start"));
-                            }
-                            start = Some(try!(map.next_value()));
-                        }
-                        Field::End => {
-                            if end.is_some() {
-                                return Err(<A::Error as Error>::duplicate_field("end"));
-                            }
-                            end = Some(try!(map.next_value()));
-                        }
-                    }
+                match try!(data.variant()) {
+                    (Field::Unbounded, v) => v.unit_variant().map(|()| Bound::Unbounded),
+                    (Field::Included, v) => v.newtype_variant().map(Bound::Included),
+                    (Field::Excluded, v) => v.newtype_variant().map(Bound::Excluded),
                 }
-                let start = match start {
-                    Some(start) => start,
-                    None => return Err(<A::Error as Error>::missing_field("start")),
-                };
-                let end = match end {
-                    Some(end) => end,
-                    None => return Err(<A::Error as Error>::missing_field("end")),
-                };
-                Ok(start..end)
             }
         }
 
-        const FIELDS: &'static [&'static str] = &["start", "end"];
-        deserializer.deserialize_struct("Range", FIELDS, RangeVisitor { phantom: PhantomData })
+        const VARIANTS: &'static [&'static str] = &["Unbounded", "Included", "Excluded"];
+
+        deserializer.deserialize_enum("Bound", VARIANTS, BoundVisitor(PhantomData))
     }
 }
 
@@ -1709,11 +1959,36 @@ where
         let value = try!(Deserialize::deserialize(deserializer));
         match NonZero::new(value) {
             Some(nonzero) => Ok(nonzero),
-            None => Err(Error::custom("expected a non-zero value")),
+            None => Err(Error::invalid_value(Unexpected::Unsigned(0), &"a non-zero value")),
         }
     }
 }
 
+#[cfg(feature = "nonzero")]
+macro_rules! nonzero_integers {
+    ($($ty:ident)*) => {
+        $(
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D>(deserializer: D) -> Result<$ty, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    let value = try!(Deserialize::deserialize(deserializer));
+                    match $ty::new(value) {
+                        Some(nonzero) => Ok(nonzero),
+                        None => Err(Error::invalid_value(Unexpected::Unsigned(0), &"a non-zero value")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "nonzero")]
+nonzero_integers! {
+    NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64 NonZeroUsize
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 impl<'de, T, E> Deserialize<'de> for Result<T, E>
@@ -98,7 +98,10 @@
 //!    - Path
 //!    - PathBuf
 //!    - Range\<T\>
+//!    - RangeInclusive\<T\> and Bound\<T\> (with the `ranges` feature)
 //!    - NonZero\<T\> (unstable)
+//!    - NonZeroU8 through NonZeroU64 and NonZeroUsize (with the `nonzero`
+//!      feature)
 //!  - **Net types**:
 //!    - IpAddr
 //!    - Ipv4Addr
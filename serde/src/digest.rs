@@ -0,0 +1,527 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Compute a hash of a value's serialized content without producing the
+//! serialized form.
+//!
+//! [`DigestSerializer`] feeds every event of the Serde data model into a
+//! [`Hasher`], framed so that distinct sequences of events produce distinct
+//! byte streams. Nothing is buffered and nothing is allocated, so hashing a
+//! large value costs no more memory than the hasher itself.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use std::collections::hash_map::DefaultHasher;
+//!
+//! use serde::digest;
+//!
+//! # fn main() {
+//! let a = digest::hash_of::<DefaultHasher, _>(&("key", vec![1, 2, 3])).unwrap();
+//! let b = digest::hash_of::<DefaultHasher, _>(&("key", vec![1, 2, 3])).unwrap();
+//! let c = digest::hash_of::<DefaultHasher, _>(&("key", vec![1, 2])).unwrap();
+//! assert_eq!(a, b);
+//! assert_ne!(a, c);
+//! # }
+//! ```
+//!
+//! The byte stream depends only on the events, not on the platform: integers
+//! are written in little-endian order and lengths as 64-bit integers. Whether
+//! the hash itself is stable across platforms and releases depends on the
+//! hasher; `DefaultHasher` makes no such promise. Cryptographic digests can be
+//! used by implementing `Hasher` for a wrapper whose `write` method updates the
+//! digest, and passing it to [`hash_with`] to get the wrapper back for
+//! finalization.
+//!
+//! The hash covers everything the `Serialize` impl reports, including struct,
+//! variant and field names, so renaming a field changes the hash. Sequence and
+//! map length hints do not contribute. Floating point numbers are hashed by
+//! their bit pattern, so `0.0` and `-0.0` hash differently. Hashing a map
+//! whose iteration order is unspecified, such as a `HashMap`, produces an
+//! unspecified hash.
+//!
+//! The format is not human-readable, so types with a separate compact form
+//! such as `IpAddr` are hashed in that form.
+//!
+//! [`DigestSerializer`]: struct.DigestSerializer.html
+//! [`Hasher`]: https://doc.rust-lang.org/std/hash/trait.Hasher.html
+//! [`hash_with`]: fn.hash_with.html
+
+use lib::*;
+
+use de::value::Error;
+use ser::{self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+          SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+
+/// Hashes `value` with a default-constructed `H` and returns the result of
+/// `H::finish`.
+pub fn hash_of<H, T: ?Sized>(value: &T) -> Result<u64, Error>
+where
+    H: Hasher + Default,
+    T: Serialize,
+{
+    hash_with(value, H::default()).map(|hasher| hasher.finish())
+}
+
+/// Feeds `value` into `hasher` and returns the hasher.
+pub fn hash_with<H, T: ?Sized>(value: &T, hasher: H) -> Result<H, Error>
+where
+    H: Hasher,
+    T: Serialize,
+{
+    let mut serializer = DigestSerializer::new(hasher);
+    try!(value.serialize(&mut serializer));
+    Ok(serializer.into_inner())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Every value starts with one of these, which makes the encoding of a value
+// unambiguous given the tags of its enclosing values.
+enum Tag {
+    Bool = 1,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    None,
+    Some,
+    Unit,
+    UnitStruct,
+    UnitVariant,
+    NewtypeStruct,
+    NewtypeVariant,
+    Seq,
+    Tuple,
+    TupleStruct,
+    TupleVariant,
+    Map,
+    Struct,
+    StructVariant,
+    // Precedes every element, map entry or field of a compound value.
+    Element,
+    // Follows the last element of a compound value.
+    End,
+    #[cfg(feature = "integer128")]
+    I128,
+    #[cfg(feature = "integer128")]
+    U128,
+}
+
+/// A `Serializer` that writes the content of a value into a `Hasher`.
+///
+/// See the [module documentation](index.html) for the properties of the
+/// hashed byte stream.
+pub struct DigestSerializer<H> {
+    hasher: H,
+}
+
+impl<H> DigestSerializer<H>
+where
+    H: Hasher,
+{
+    /// Creates a serializer writing into `hasher`.
+    pub fn new(hasher: H) -> Self {
+        DigestSerializer { hasher: hasher }
+    }
+
+    /// Returns the hasher, for example to call `finish` on it.
+    pub fn into_inner(self) -> H {
+        self.hasher
+    }
+
+    fn tag(&mut self, tag: Tag) {
+        self.hasher.write(&[tag as u8]);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.hasher.write(&[v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]);
+    }
+
+    fn u64(&mut self, v: u64) {
+        let mut bytes = [0; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (v >> (8 * i)) as u8;
+        }
+        self.hasher.write(&bytes);
+    }
+
+    fn bytes(&mut self, v: &[u8]) {
+        self.u64(v.len() as u64);
+        self.hasher.write(v);
+    }
+
+    fn name(&mut self, name: &str) {
+        self.bytes(name.as_bytes());
+    }
+
+    fn element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.tag(Tag::Element);
+        value.serialize(self)
+    }
+}
+
+impl<'a, H> ser::Serializer for &'a mut DigestSerializer<H>
+where
+    H: Hasher,
+{
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.tag(Tag::Bool);
+        self.hasher.write(&[v as u8]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.tag(Tag::I8);
+        self.hasher.write(&[v as u8]);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.tag(Tag::I16);
+        self.hasher.write(&[v as u8, (v >> 8) as u8]);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.tag(Tag::I32);
+        self.u32(v as u32);
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.tag(Tag::I64);
+        self.u64(v as u64);
+        Ok(())
+    }
+
+    #[cfg(feature = "integer128")]
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.tag(Tag::I128);
+        self.u64(v as u64);
+        self.u64((v >> 64) as u64);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.tag(Tag::U8);
+        self.hasher.write(&[v]);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.tag(Tag::U16);
+        self.hasher.write(&[v as u8, (v >> 8) as u8]);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.tag(Tag::U32);
+        self.u32(v);
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.tag(Tag::U64);
+        self.u64(v);
+        Ok(())
+    }
+
+    #[cfg(feature = "integer128")]
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.tag(Tag::U128);
+        self.u64(v as u64);
+        self.u64((v >> 64) as u64);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.tag(Tag::F32);
+        let bits: u32 = unsafe { mem::transmute(v) };
+        self.u32(bits);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.tag(Tag::F64);
+        let bits: u64 = unsafe { mem::transmute(v) };
+        self.u64(bits);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.tag(Tag::Char);
+        self.u32(v as u32);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.tag(Tag::Str);
+        self.bytes(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.tag(Tag::Bytes);
+        self.bytes(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.tag(Tag::None);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.tag(Tag::Some);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.tag(Tag::Unit);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        self.tag(Tag::UnitStruct);
+        self.name(name);
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.tag(Tag::UnitVariant);
+        self.name(name);
+        self.name(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.tag(Tag::NewtypeStruct);
+        self.name(name);
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.tag(Tag::NewtypeVariant);
+        self.name(name);
+        self.name(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, Error> {
+        self.tag(Tag::Seq);
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+        self.tag(Tag::Tuple);
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.tag(Tag::TupleStruct);
+        self.name(name);
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.tag(Tag::TupleVariant);
+        self.name(name);
+        self.name(variant);
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, Error> {
+        self.tag(Tag::Map);
+        Ok(self)
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.tag(Tag::Struct);
+        self.name(name);
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.tag(Tag::StructVariant);
+        self.name(name);
+        self.name(variant);
+        Ok(self)
+    }
+
+    fn collect_str<T: ?Sized>(self, value: &T) -> Result<(), Error>
+    where
+        T: Display,
+    {
+        use lib::fmt::Write;
+
+        // The length has to be written before the content, so format the
+        // value twice rather than buffering it.
+        let mut counter = CountWriter(0);
+        if write!(counter, "{}", value).is_err() {
+            return Err(ser::Error::custom("formatting failed"));
+        }
+        self.tag(Tag::Str);
+        self.u64(counter.0 as u64);
+        let mut writer = HashWriter {
+            hasher: &mut self.hasher,
+            remaining: counter.0,
+        };
+        if write!(writer, "{}", value).is_err() || writer.remaining != 0 {
+            return Err(ser::Error::custom("Display impl is not deterministic"));
+        }
+        Ok(())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct CountWriter(usize);
+
+impl fmt::Write for CountWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+struct HashWriter<'a, H: 'a> {
+    hasher: &'a mut H,
+    remaining: usize,
+}
+
+impl<'a, H> fmt::Write for HashWriter<'a, H>
+where
+    H: Hasher,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > self.remaining {
+            return Err(fmt::Error);
+        }
+        self.remaining -= s.len();
+        self.hasher.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+macro_rules! digest_serialize_compound {
+    ($trait:ident, $method:ident($($key:ident: $key_ty:ty)*)) => {
+        impl<'a, H> $trait for &'a mut DigestSerializer<H>
+        where
+            H: Hasher,
+        {
+            type Ok = ();
+            type Error = Error;
+
+            fn $method<T: ?Sized>(&mut self, $($key: $key_ty,)* value: &T) -> Result<(), Error>
+            where
+                T: Serialize,
+            {
+                self.tag(Tag::Element);
+                $(self.name($key);)*
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<(), Error> {
+                self.tag(Tag::End);
+                Ok(())
+            }
+        }
+    };
+}
+
+digest_serialize_compound!(SerializeSeq, serialize_element());
+digest_serialize_compound!(SerializeTuple, serialize_element());
+digest_serialize_compound!(SerializeTupleStruct, serialize_field());
+digest_serialize_compound!(SerializeTupleVariant, serialize_field());
+digest_serialize_compound!(SerializeStruct, serialize_field(key: &'static str));
+digest_serialize_compound!(SerializeStructVariant, serialize_field(key: &'static str));
+
+impl<'a, H> SerializeMap for &'a mut DigestSerializer<H>
+where
+    H: Hasher,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.element(key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(Tag::End);
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialize records whose fields are only known at runtime.
+//!
+//! Derived `Serialize` impls need the set of fields at compile time. Code
+//! that builds records from a schema loaded at runtime, such as a scripting
+//! layer or a database mapper, can instead put the values into a
+//! [`DynamicMap`], or describe the record once with a [`Descriptor`] and fill
+//! in [`DynamicStruct`]s for it. Any `Serialize` value can be stored.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use serde::dynamic::{Descriptor, DynamicStruct};
+//!
+//! # fn main() {
+//! // Loaded from a configuration file, for example.
+//! let person = Descriptor::new("Person").field("name").field("age");
+//!
+//! let mut record = DynamicStruct::new(&person);
+//! record.set("name", "Ferris").unwrap();
+//! record.set("age", 7u8).unwrap();
+//! assert!(record.set("email", "").is_err());
+//! # }
+//! ```
+//!
+//! Both types serialize as a map from field name to value, because the
+//! struct and field names passed to a `Serializer` have to be `&'static str`.
+//! Self-describing formats such as JSON represent a struct and a map the same
+//! way.
+//!
+//! A stored value is serialized when the record is serialized, not when it is
+//! stored, so it sees whether the eventual format is human-readable. It is
+//! converted into an intermediate representation first, which costs an
+//! allocation per string, sequence and map inside it.
+//!
+//! [`DynamicMap`]: struct.DynamicMap.html
+//! [`DynamicStruct`]: struct.DynamicStruct.html
+//! [`Descriptor`]: struct.Descriptor.html
+
+use lib::*;
+
+use de::value::Error;
+use ser::{self, Serialize, SerializeMap, Serializer};
+
+use private::ser::{Content, ContentSerializer};
+
+/// An ordered map from field names known at runtime to values of any type.
+///
+/// Entries are serialized in insertion order. Inserting a name twice keeps
+/// both entries.
+pub struct DynamicMap<'a> {
+    entries: Vec<(String, Box<ErasedSerialize + 'a>)>,
+}
+
+impl<'a> DynamicMap<'a> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        DynamicMap { entries: Vec::new() }
+    }
+
+    /// Appends an entry.
+    pub fn insert<K, T>(&mut self, name: K, value: T)
+    where
+        K: Into<String>,
+        T: Serialize + 'a,
+    {
+        self.entries.push((name.into(), Box::new(value)));
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> Default for DynamicMap<'a> {
+    fn default() -> Self {
+        DynamicMap::new()
+    }
+}
+
+impl<'a> Serialize for DynamicMap<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let human_readable = serializer.is_human_readable();
+        let mut map = try!(serializer.serialize_map(Some(self.entries.len())));
+        for &(ref name, ref value) in &self.entries {
+            let content = try!(value.to_content(human_readable).map_err(ser::Error::custom));
+            try!(map.serialize_entry(name, &content));
+        }
+        map.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// The name and fields of a kind of record, for building `DynamicStruct`s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Descriptor {
+    name: String,
+    fields: Vec<String>,
+}
+
+impl Descriptor {
+    /// Creates a descriptor for a record called `name` with no fields.
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        Descriptor {
+            name: name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field after the existing ones.
+    pub fn field<N>(mut self, name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        self.fields.push(name.into());
+        self
+    }
+
+    /// The name of the record, used in error messages.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of the fields, in the order they are serialized.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+}
+
+/// A record with the fields of a `Descriptor`.
+///
+/// Fields are serialized in the order of the descriptor. Fields that have
+/// not been set are left out.
+pub struct DynamicStruct<'a> {
+    descriptor: &'a Descriptor,
+    values: Vec<Option<Box<ErasedSerialize + 'a>>>,
+}
+
+impl<'a> DynamicStruct<'a> {
+    /// Creates a record with none of the fields of `descriptor` set.
+    pub fn new(descriptor: &'a Descriptor) -> Self {
+        DynamicStruct {
+            descriptor: descriptor,
+            values: descriptor.fields.iter().map(|_| None).collect(),
+        }
+    }
+
+    /// The descriptor of this record.
+    pub fn descriptor(&self) -> &'a Descriptor {
+        self.descriptor
+    }
+
+    /// Sets the field called `name`, replacing its previous value. Fails if
+    /// the descriptor has no such field.
+    pub fn set<T>(&mut self, name: &str, value: T) -> Result<(), Error>
+    where
+        T: Serialize + 'a,
+    {
+        match self.descriptor.fields.iter().position(|field| field == name) {
+            Some(index) => {
+                self.values[index] = Some(Box::new(value));
+                Ok(())
+            }
+            None => {
+                Err(
+                    ser::Error::custom(
+                        format_args!("no field `{}` in {}", name, self.descriptor.name),
+                    ),
+                )
+            }
+        }
+    }
+
+    /// Unsets the field called `name`. Returns whether it was set.
+    pub fn unset(&mut self, name: &str) -> bool {
+        match self.descriptor.fields.iter().position(|field| field == name) {
+            Some(index) => self.values[index].take().is_some(),
+            None => false,
+        }
+    }
+
+    /// Whether the field called `name` is set.
+    pub fn is_set(&self, name: &str) -> bool {
+        match self.descriptor.fields.iter().position(|field| field == name) {
+            Some(index) => self.values[index].is_some(),
+            None => false,
+        }
+    }
+}
+
+impl<'a> Serialize for DynamicStruct<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let human_readable = serializer.is_human_readable();
+        let len = self.values.iter().filter(|value| value.is_some()).count();
+        let mut map = try!(serializer.serialize_map(Some(len)));
+        for (name, value) in self.descriptor.fields.iter().zip(&self.values) {
+            if let Some(ref value) = *value {
+                let content = try!(value.to_content(human_readable).map_err(ser::Error::custom));
+                try!(map.serialize_entry(name, &content));
+            }
+        }
+        map.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// An object-safe stand-in for `Serialize`. The serializer is only known once
+// the record is serialized, so values are captured as `Content` at that point
+// using the serializer's human-readability.
+trait ErasedSerialize {
+    fn to_content(&self, human_readable: bool) -> Result<Content, Error>;
+}
+
+impl<T> ErasedSerialize for T
+where
+    T: Serialize,
+{
+    fn to_content(&self, human_readable: bool) -> Result<Content, Error> {
+        self.serialize(ContentSerializer::with_human_readable(human_readable))
+    }
+}
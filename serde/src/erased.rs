@@ -0,0 +1,810 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Object-safe serialization for use through trait objects.
+//!
+//! `Serialize::serialize` is generic over the serializer, so `Serialize`
+//! cannot be made into a trait object. This module provides object-safe
+//! counterparts of both traits, bridged to the generic ones in each
+//! direction:
+//!
+//! - Every `Serialize` type implements [`ErasedSerialize`], and
+//!   `ErasedSerialize` trait objects implement `Serialize`. A plugin system
+//!   can hold `Box<ErasedSerialize>` values of different types and serialize
+//!   them with any format.
+//! - [`Erase`] wraps any `Serializer` as an [`ErasedSerializer`], and
+//!   `&mut ErasedSerializer` implements `Serializer`. Code that cannot be
+//!   generic over the format can receive the format as a trait object.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use std::collections::BTreeMap;
+//!
+//! use serde::erased::ErasedSerialize;
+//!
+//! # fn main() {
+//! let mut plugins: BTreeMap<&str, Box<ErasedSerialize>> = BTreeMap::new();
+//! plugins.insert("retries", Box::new(3u8));
+//! plugins.insert("hosts", Box::new(vec!["a", "b"]));
+//!
+//! // The map is Serialize because each Box<ErasedSerialize> is.
+//! # fn check<T: serde::Serialize>(_: &T) {}
+//! # check(&plugins);
+//! # }
+//! ```
+//!
+//! Errors returned by the underlying serializer are passed through the erased
+//! layer unchanged. Errors raised by `Serialize` impls in between are
+//! reconstructed from their message with `ser::Error::custom`.
+//!
+//! [`ErasedSerialize`]: trait.ErasedSerialize.html
+//! [`ErasedSerializer`]: trait.ErasedSerializer.html
+//! [`Erase`]: struct.Erase.html
+
+use lib::*;
+
+use ser::{self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+          SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer};
+
+/// An object-safe form of `Serialize`, implemented for every `Serialize`
+/// type.
+pub trait ErasedSerialize {
+    /// Serializes this value into the given erased serializer.
+    fn erased_serialize(&self, serializer: &mut ErasedSerializer) -> Result<(), Error>;
+}
+
+impl<T: ?Sized> ErasedSerialize for T
+where
+    T: Serialize,
+{
+    fn erased_serialize(&self, serializer: &mut ErasedSerializer) -> Result<(), Error> {
+        self.serialize(serializer)
+    }
+}
+
+macro_rules! serialize_trait_object {
+    ($($object:ty),*) => {
+        $(
+            impl<'a> Serialize for $object {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    let mut erased = Erase::new(serializer);
+                    let result = self.erased_serialize(&mut erased);
+                    erased.finish(result)
+                }
+            }
+        )*
+    }
+}
+
+serialize_trait_object! {
+    ErasedSerialize + 'a,
+    ErasedSerialize + Send + 'a,
+    ErasedSerialize + Send + Sync + 'a
+}
+
+/// An object-safe form of `Serializer`.
+///
+/// The compound values of the data model are written with the same methods
+/// as by the `Serialize*` traits: after `erased_serialize_seq`, for example,
+/// each element is passed to `erased_serialize_element` and the sequence is
+/// finished with `erased_end`. Calls out of this order result in an error.
+///
+/// Use [`Erase`] to obtain an `ErasedSerializer` from a `Serializer`.
+///
+/// [`Erase`]: struct.Erase.html
+#[allow(missing_docs)]
+pub trait ErasedSerializer {
+    fn erased_serialize_bool(&mut self, v: bool) -> Result<(), Error>;
+    fn erased_serialize_i8(&mut self, v: i8) -> Result<(), Error>;
+    fn erased_serialize_i16(&mut self, v: i16) -> Result<(), Error>;
+    fn erased_serialize_i32(&mut self, v: i32) -> Result<(), Error>;
+    fn erased_serialize_i64(&mut self, v: i64) -> Result<(), Error>;
+    fn erased_serialize_u8(&mut self, v: u8) -> Result<(), Error>;
+    fn erased_serialize_u16(&mut self, v: u16) -> Result<(), Error>;
+    fn erased_serialize_u32(&mut self, v: u32) -> Result<(), Error>;
+    fn erased_serialize_u64(&mut self, v: u64) -> Result<(), Error>;
+    #[cfg(feature = "integer128")]
+    fn erased_serialize_i128(&mut self, v: i128) -> Result<(), Error>;
+    #[cfg(feature = "integer128")]
+    fn erased_serialize_u128(&mut self, v: u128) -> Result<(), Error>;
+    fn erased_serialize_f32(&mut self, v: f32) -> Result<(), Error>;
+    fn erased_serialize_f64(&mut self, v: f64) -> Result<(), Error>;
+    fn erased_serialize_char(&mut self, v: char) -> Result<(), Error>;
+    fn erased_serialize_str(&mut self, v: &str) -> Result<(), Error>;
+    fn erased_serialize_bytes(&mut self, v: &[u8]) -> Result<(), Error>;
+    fn erased_serialize_none(&mut self) -> Result<(), Error>;
+    fn erased_serialize_some(&mut self, value: &ErasedSerialize) -> Result<(), Error>;
+    fn erased_serialize_unit(&mut self) -> Result<(), Error>;
+    fn erased_serialize_unit_struct(&mut self, name: &'static str) -> Result<(), Error>;
+    fn erased_serialize_unit_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error>;
+    fn erased_serialize_newtype_struct(
+        &mut self,
+        name: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error>;
+    fn erased_serialize_newtype_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error>;
+    fn erased_serialize_seq(&mut self, len: Option<usize>) -> Result<(), Error>;
+    fn erased_serialize_tuple(&mut self, len: usize) -> Result<(), Error>;
+    fn erased_serialize_tuple_struct(&mut self, name: &'static str, len: usize) -> Result<(), Error>;
+    fn erased_serialize_tuple_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<(), Error>;
+    fn erased_serialize_map(&mut self, len: Option<usize>) -> Result<(), Error>;
+    fn erased_serialize_struct(&mut self, name: &'static str, len: usize) -> Result<(), Error>;
+    fn erased_serialize_struct_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<(), Error>;
+
+    /// Serializes an element of a sequence, tuple, tuple struct or tuple
+    /// variant.
+    fn erased_serialize_element(&mut self, value: &ErasedSerialize) -> Result<(), Error>;
+    /// Serializes a map key.
+    fn erased_serialize_key(&mut self, key: &ErasedSerialize) -> Result<(), Error>;
+    /// Serializes a map value.
+    fn erased_serialize_value(&mut self, value: &ErasedSerialize) -> Result<(), Error>;
+    /// Serializes a field of a struct or struct variant.
+    fn erased_serialize_field(
+        &mut self,
+        key: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error>;
+    /// Indicates that a field of a struct or struct variant has been skipped.
+    fn erased_skip_field(&mut self, key: &'static str) -> Result<(), Error>;
+    /// Attaches an annotation to the next field of a struct or struct
+    /// variant.
+    fn erased_annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Error>;
+    /// Finishes the current compound value.
+    fn erased_end(&mut self) -> Result<(), Error>;
+
+    fn erased_is_human_readable(&self) -> bool;
+}
+
+/// The error type of the erased layer.
+///
+/// When the underlying serializer fails, its own error is kept by [`Erase`]
+/// and returned from [`Erase::finish`]; this error only carries the message
+/// across the erased layer.
+///
+/// [`Erase`]: struct.Erase.html
+/// [`Erase::finish`]: struct.Erase.html#method.finish
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    msg: String,
+}
+
+impl ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Error { msg: msg.to_string() }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        &self.msg
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Adapts a `Serializer` into an `ErasedSerializer`.
+///
+/// ```rust
+/// # extern crate serde;
+/// #
+/// # use serde::Serializer;
+/// use serde::erased::{Erase, ErasedSerialize, ErasedSerializer, Error};
+///
+/// // Compiled once, independently of the data format.
+/// fn write_header(serializer: &mut ErasedSerializer) -> Result<(), Error> {
+///     serializer.erased_serialize_str("header")
+/// }
+///
+/// fn to_format<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+///     let mut erased = Erase::new(serializer);
+///     let result = write_header(&mut erased);
+///     erased.finish(result)
+/// }
+/// #
+/// # fn main() {}
+/// ```
+pub struct Erase<S>
+where
+    S: Serializer,
+{
+    state: State<S>,
+}
+
+enum State<S>
+where
+    S: Serializer,
+{
+    Ready(S),
+    Seq(S::SerializeSeq),
+    Tuple(S::SerializeTuple),
+    TupleStruct(S::SerializeTupleStruct),
+    TupleVariant(S::SerializeTupleVariant),
+    Map(S::SerializeMap),
+    Struct(S::SerializeStruct),
+    StructVariant(S::SerializeStructVariant),
+    Complete(S::Ok),
+    Failed(S::Error),
+    Used,
+}
+
+impl<S> Erase<S>
+where
+    S: Serializer,
+{
+    /// Wraps a serializer to be driven through the `ErasedSerializer` trait.
+    pub fn new(serializer: S) -> Self {
+        Erase { state: State::Ready(serializer) }
+    }
+
+    /// Returns the output of the serializer.
+    ///
+    /// `result` is the outcome of the erased serialization that used this
+    /// adapter. If the serializer failed, its error is returned; an error
+    /// that arose elsewhere is converted with `ser::Error::custom`. Finishing
+    /// before a complete value was serialized is an error.
+    pub fn finish(self, result: Result<(), Error>) -> Result<S::Ok, S::Error> {
+        match (self.state, result) {
+            (State::Failed(err), _) => Err(err),
+            (_, Err(err)) => Err(ser::Error::custom(err)),
+            (State::Complete(ok), Ok(())) => Ok(ok),
+            (_, Ok(())) => Err(ser::Error::custom("erased serialization did not complete")),
+        }
+    }
+
+    fn take(&mut self) -> State<S> {
+        mem::replace(&mut self.state, State::Used)
+    }
+
+    fn ready(&mut self) -> Result<S, Error> {
+        match self.take() {
+            State::Ready(serializer) => Ok(serializer),
+            _ => Err(out_of_order()),
+        }
+    }
+
+    // Records the outcome of a call that consumed the serializer.
+    fn complete(&mut self, result: Result<S::Ok, S::Error>) -> Result<(), Error> {
+        match result {
+            Ok(ok) => {
+                self.state = State::Complete(ok);
+                Ok(())
+            }
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+
+    // Records the outcome of a call that began a compound value.
+    fn begin<C, F>(&mut self, result: Result<C, S::Error>, state: F) -> Result<(), Error>
+    where
+        F: FnOnce(C) -> State<S>,
+    {
+        match result {
+            Ok(compound) => {
+                self.state = state(compound);
+                Ok(())
+            }
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+
+    // Records the outcome of a call on a compound value that did not end it.
+    fn proceed(&mut self, result: Result<(), S::Error>) -> Result<(), Error> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+
+    fn fail(&mut self, err: S::Error) -> Error {
+        let erased = Error { msg: err.to_string() };
+        self.state = State::Failed(err);
+        erased
+    }
+}
+
+fn out_of_order() -> Error {
+    ser::Error::custom("erased serializer method called out of order")
+}
+
+macro_rules! erase_primitive {
+    ($($(#[$attr:meta])* $erased:ident => $method:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $erased(&mut self, v: $ty) -> Result<(), Error> {
+                let serializer = try!(self.ready());
+                self.complete(serializer.$method(v))
+            }
+        )*
+    }
+}
+
+// Calls a method on the compound value in progress. The state is put back
+// unless the method fails.
+macro_rules! on_compound {
+    ($self_:ident, $($state:ident($compound:ident) => $call:expr,)*) => {
+        match $self_.take() {
+            $(
+                State::$state(mut $compound) => {
+                    let result = $call;
+                    $self_.state = State::$state($compound);
+                    $self_.proceed(result)
+                }
+            )*
+            _ => Err(out_of_order()),
+        }
+    }
+}
+
+impl<S> ErasedSerializer for Erase<S>
+where
+    S: Serializer,
+{
+    erase_primitive! {
+        erased_serialize_bool => serialize_bool(bool),
+        erased_serialize_i8 => serialize_i8(i8),
+        erased_serialize_i16 => serialize_i16(i16),
+        erased_serialize_i32 => serialize_i32(i32),
+        erased_serialize_i64 => serialize_i64(i64),
+        erased_serialize_u8 => serialize_u8(u8),
+        erased_serialize_u16 => serialize_u16(u16),
+        erased_serialize_u32 => serialize_u32(u32),
+        erased_serialize_u64 => serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        erased_serialize_i128 => serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        erased_serialize_u128 => serialize_u128(u128),
+        erased_serialize_f32 => serialize_f32(f32),
+        erased_serialize_f64 => serialize_f64(f64),
+        erased_serialize_char => serialize_char(char),
+        erased_serialize_str => serialize_str(&str),
+        erased_serialize_bytes => serialize_bytes(&[u8]),
+        erased_serialize_unit_struct => serialize_unit_struct(&'static str),
+    }
+
+    fn erased_serialize_none(&mut self) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_none())
+    }
+
+    fn erased_serialize_some(&mut self, value: &ErasedSerialize) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_some(value))
+    }
+
+    fn erased_serialize_unit(&mut self) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_unit())
+    }
+
+    fn erased_serialize_unit_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_unit_variant(name, variant_index, variant))
+    }
+
+    fn erased_serialize_newtype_struct(
+        &mut self,
+        name: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_newtype_struct(name, value))
+    }
+
+    fn erased_serialize_newtype_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.complete(serializer.serialize_newtype_variant(name, variant_index, variant, value))
+    }
+
+    fn erased_serialize_seq(&mut self, len: Option<usize>) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.begin(serializer.serialize_seq(len), State::Seq)
+    }
+
+    fn erased_serialize_tuple(&mut self, len: usize) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.begin(serializer.serialize_tuple(len), State::Tuple)
+    }
+
+    fn erased_serialize_tuple_struct(&mut self, name: &'static str, len: usize) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.begin(serializer.serialize_tuple_struct(name, len), State::TupleStruct)
+    }
+
+    fn erased_serialize_tuple_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        let result = serializer.serialize_tuple_variant(name, variant_index, variant, len);
+        self.begin(result, State::TupleVariant)
+    }
+
+    fn erased_serialize_map(&mut self, len: Option<usize>) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.begin(serializer.serialize_map(len), State::Map)
+    }
+
+    fn erased_serialize_struct(&mut self, name: &'static str, len: usize) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        self.begin(serializer.serialize_struct(name, len), State::Struct)
+    }
+
+    fn erased_serialize_struct_variant(
+        &mut self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<(), Error> {
+        let serializer = try!(self.ready());
+        let result = serializer.serialize_struct_variant(name, variant_index, variant, len);
+        self.begin(result, State::StructVariant)
+    }
+
+    fn erased_serialize_element(&mut self, value: &ErasedSerialize) -> Result<(), Error> {
+        on_compound!(self,
+            Seq(seq) => seq.serialize_element(value),
+            Tuple(tuple) => tuple.serialize_element(value),
+            TupleStruct(tuple) => tuple.serialize_field(value),
+            TupleVariant(tuple) => tuple.serialize_field(value),
+        )
+    }
+
+    fn erased_serialize_key(&mut self, key: &ErasedSerialize) -> Result<(), Error> {
+        on_compound!(self,
+            Map(map) => map.serialize_key(key),
+        )
+    }
+
+    fn erased_serialize_value(&mut self, value: &ErasedSerialize) -> Result<(), Error> {
+        on_compound!(self,
+            Map(map) => map.serialize_value(value),
+        )
+    }
+
+    fn erased_serialize_field(
+        &mut self,
+        key: &'static str,
+        value: &ErasedSerialize,
+    ) -> Result<(), Error> {
+        on_compound!(self,
+            Struct(fields) => fields.serialize_field(key, value),
+            StructVariant(fields) => fields.serialize_field(key, value),
+        )
+    }
+
+    fn erased_skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        on_compound!(self,
+            Struct(fields) => fields.skip_field(key),
+            StructVariant(fields) => fields.skip_field(key),
+        )
+    }
+
+    fn erased_annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Error> {
+        on_compound!(self,
+            Struct(fields) => fields.annotate(key, annotation),
+            StructVariant(fields) => fields.annotate(key, annotation),
+        )
+    }
+
+    fn erased_end(&mut self) -> Result<(), Error> {
+        let result = match self.take() {
+            State::Seq(seq) => seq.end(),
+            State::Tuple(tuple) => tuple.end(),
+            State::TupleStruct(tuple) => tuple.end(),
+            State::TupleVariant(tuple) => tuple.end(),
+            State::Map(map) => map.end(),
+            State::Struct(fields) => fields.end(),
+            State::StructVariant(fields) => fields.end(),
+            _ => return Err(out_of_order()),
+        };
+        self.complete(result)
+    }
+
+    fn erased_is_human_readable(&self) -> bool {
+        match self.state {
+            State::Ready(ref serializer) => serializer.is_human_readable(),
+            _ => true,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// The compound serializer of `&mut ErasedSerializer`, for every kind of
+/// compound value.
+pub struct Compound<'a, 'b: 'a> {
+    serializer: &'a mut (ErasedSerializer + 'b),
+}
+
+macro_rules! forward_primitive {
+    ($($(#[$attr:meta])* $method:ident => $erased:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(self, v: $ty) -> Result<(), Error> {
+                self.$erased(v)
+            }
+        )*
+    }
+}
+
+impl<'a, 'b> Serializer for &'a mut (ErasedSerializer + 'b) {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, 'b>;
+    type SerializeTuple = Compound<'a, 'b>;
+    type SerializeTupleStruct = Compound<'a, 'b>;
+    type SerializeTupleVariant = Compound<'a, 'b>;
+    type SerializeMap = Compound<'a, 'b>;
+    type SerializeStruct = Compound<'a, 'b>;
+    type SerializeStructVariant = Compound<'a, 'b>;
+
+    forward_primitive! {
+        serialize_bool => erased_serialize_bool(bool),
+        serialize_i8 => erased_serialize_i8(i8),
+        serialize_i16 => erased_serialize_i16(i16),
+        serialize_i32 => erased_serialize_i32(i32),
+        serialize_i64 => erased_serialize_i64(i64),
+        serialize_u8 => erased_serialize_u8(u8),
+        serialize_u16 => erased_serialize_u16(u16),
+        serialize_u32 => erased_serialize_u32(u32),
+        serialize_u64 => erased_serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        serialize_i128 => erased_serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        serialize_u128 => erased_serialize_u128(u128),
+        serialize_f32 => erased_serialize_f32(f32),
+        serialize_f64 => erased_serialize_f64(f64),
+        serialize_char => erased_serialize_char(char),
+        serialize_str => erased_serialize_str(&str),
+        serialize_bytes => erased_serialize_bytes(&[u8]),
+        serialize_unit_struct => erased_serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.erased_serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.erased_serialize_some(&value)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.erased_serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.erased_serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.erased_serialize_newtype_struct(name, &value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.erased_serialize_newtype_variant(name, variant_index, variant, &value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_seq(len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_tuple(len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_tuple_struct(name, len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_tuple_variant(name, variant_index, variant, len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_map(len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_struct(name, len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, 'b>, Error> {
+        try!(self.erased_serialize_struct_variant(name, variant_index, variant, len));
+        Ok(Compound { serializer: self })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.erased_is_human_readable()
+    }
+}
+
+macro_rules! compound_element {
+    ($($tr:ident::$method:ident,)*) => {
+        $(
+            impl<'a, 'b> $tr for Compound<'a, 'b> {
+                type Ok = ();
+                type Error = Error;
+
+                fn $method<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+                where
+                    T: Serialize,
+                {
+                    self.serializer.erased_serialize_element(&value)
+                }
+
+                fn end(self) -> Result<(), Error> {
+                    self.serializer.erased_end()
+                }
+            }
+        )*
+    }
+}
+
+compound_element! {
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field,
+}
+
+impl<'a, 'b> SerializeMap for Compound<'a, 'b> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.serializer.erased_serialize_key(&key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.serializer.erased_serialize_value(&value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.serializer.erased_end()
+    }
+}
+
+macro_rules! compound_field {
+    ($($tr:ident,)*) => {
+        $(
+            impl<'a, 'b> $tr for Compound<'a, 'b> {
+                type Ok = ();
+                type Error = Error;
+
+                fn serialize_field<T: ?Sized>(
+                    &mut self,
+                    key: &'static str,
+                    value: &T,
+                ) -> Result<(), Error>
+                where
+                    T: Serialize,
+                {
+                    self.serializer.erased_serialize_field(key, &value)
+                }
+
+                fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+                    self.serializer.erased_skip_field(key)
+                }
+
+                fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Error> {
+                    self.serializer.erased_annotate(key, annotation)
+                }
+
+                fn end(self) -> Result<(), Error> {
+                    self.serializer.erased_end()
+                }
+            }
+        )*
+    }
+}
+
+compound_field! {
+    SerializeStruct,
+    SerializeStructVariant,
+}
@@ -14,6 +14,9 @@ pub use lib::marker::PhantomData;
 pub use lib::option::Option::{self, None, Some};
 pub use lib::result::Result::{self, Ok, Err};
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use lib::String;
+
 pub use self::string::from_utf8_lossy;
 
 mod string {
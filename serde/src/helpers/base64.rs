@@ -0,0 +1,127 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent bytes as a base64 string in human-readable formats.
+//!
+//! Human-readable formats get a string in the standard base64 alphabet of
+//! RFC 4648 with padding. Deserialization also accepts the string without
+//! padding. Compact formats get the bytes as they are, serialized with
+//! `serialize_bytes`.
+//!
+//! The field may be of any type that implements `AsRef<[u8]>` and
+//! [`FromBytes`], such as `Vec<u8>` or `[u8; 32]`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Attachment {
+//!     name: String,
+//!     #[serde(with = "serde::helpers::base64")]
+//!     content: Vec<u8>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! [`FromBytes`]: ../trait.FromBytes.html
+
+use lib::*;
+
+use de::Deserializer;
+use ser::Serializer;
+
+use super::FromBytes;
+use super::bytes::{self, Encoding};
+
+static BASE64: Encoding = Encoding {
+    name: "base64",
+    encode: encode,
+    decode: decode,
+};
+
+const ALPHABET: &'static [u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Serializes `bytes` as a base64 string or as raw bytes, depending on
+/// whether the format is human-readable.
+pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + AsRef<[u8]>,
+    S: Serializer,
+{
+    bytes::serialize(bytes.as_ref(), serializer, &BASE64)
+}
+
+/// Deserializes a base64 string or raw bytes, depending on whether the format
+/// is human-readable.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromBytes,
+    D: Deserializer<'de>,
+{
+    bytes::deserialize(deserializer, &BASE64)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn encode(bytes: &[u8], formatter: &mut fmt::Formatter) -> fmt::Result {
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as usize;
+        let b1 = chunk.get(1).map_or(0, |&b| b as usize);
+        let b2 = chunk.get(2).map_or(0, |&b| b as usize);
+        let mut encoded = [
+            ALPHABET[b0 >> 2],
+            ALPHABET[(b0 & 0x03) << 4 | b1 >> 4],
+            ALPHABET[(b1 & 0x0f) << 2 | b2 >> 6],
+            ALPHABET[b2 & 0x3f],
+        ];
+        if chunk.len() < 3 {
+            encoded[3] = b'=';
+        }
+        if chunk.len() < 2 {
+            encoded[2] = b'=';
+        }
+        try!(formatter.write_str(str::from_utf8(&encoded).unwrap()));
+    }
+    Ok(())
+}
+
+fn decode(input: &str) -> Option<Vec<u8>> {
+    let mut input = input.as_bytes();
+    if input.len() % 4 == 0 {
+        for _ in 0..2 {
+            if input.last() == Some(&b'=') {
+                input = &input[..input.len() - 1];
+            }
+        }
+    }
+    if input.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &b in input {
+        let value = match ALPHABET.iter().position(|&c| c == b) {
+            Some(value) => value,
+            None => return None,
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
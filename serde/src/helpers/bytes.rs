@@ -0,0 +1,197 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Shared implementation of the `base64` and `hex` adapters.
+
+use lib::*;
+
+use de::{self, Deserializer, SeqAccess, Unexpected, Visitor};
+use ser::Serializer;
+
+use private::de::size_hint;
+
+/// A byte container that can be built by the [`base64`] and [`hex`] adapters.
+///
+/// Implemented for `Vec<u8>`, `Box<[u8]>` and byte arrays of length 0 through
+/// 32.
+///
+/// [`base64`]: base64/index.html
+/// [`hex`]: hex/index.html
+pub trait FromBytes: Sized {
+    /// Converts the decoded bytes into `Self`, failing if they do not have an
+    /// acceptable length.
+    fn from_bytes<E>(bytes: Vec<u8>) -> Result<Self, E>
+    where
+        E: de::Error;
+}
+
+impl FromBytes for Vec<u8> {
+    fn from_bytes<E>(bytes: Vec<u8>) -> Result<Self, E>
+    where
+        E: de::Error,
+    {
+        Ok(bytes)
+    }
+}
+
+impl FromBytes for Box<[u8]> {
+    fn from_bytes<E>(bytes: Vec<u8>) -> Result<Self, E>
+    where
+        E: de::Error,
+    {
+        Ok(bytes.into_boxed_slice())
+    }
+}
+
+struct ExpectedLen(usize);
+
+impl de::Expected for ExpectedLen {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes", self.0)
+    }
+}
+
+macro_rules! array_impls {
+    ($($len:expr)+) => {
+        $(
+            impl FromBytes for [u8; $len] {
+                fn from_bytes<E>(bytes: Vec<u8>) -> Result<Self, E>
+                where
+                    E: de::Error,
+                {
+                    if bytes.len() != $len {
+                        return Err(de::Error::invalid_length(bytes.len(), &ExpectedLen($len)));
+                    }
+                    let mut array = [0; $len];
+                    array.copy_from_slice(&bytes);
+                    Ok(array)
+                }
+            }
+        )+
+    }
+}
+
+array_impls! {
+    0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16
+    17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A textual encoding of bytes used by human-readable formats.
+pub struct Encoding {
+    pub name: &'static str,
+    pub encode: fn(&[u8], &mut fmt::Formatter) -> fmt::Result,
+    pub decode: fn(&str) -> Option<Vec<u8>>,
+}
+
+struct Encoded<'a> {
+    bytes: &'a [u8],
+    encoding: &'static Encoding,
+}
+
+impl<'a> Display for Encoded<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        (self.encoding.encode)(self.bytes, formatter)
+    }
+}
+
+pub fn serialize<S>(bytes: &[u8], serializer: S, encoding: &'static Encoding) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.collect_str(
+            &Encoded {
+                bytes: bytes,
+                encoding: encoding,
+            },
+        )
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D, encoding: &'static Encoding) -> Result<T, D::Error>
+where
+    T: FromBytes,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        let visitor = BytesVisitor {
+            encoding: Some(encoding),
+            marker: PhantomData,
+        };
+        deserializer.deserialize_str(visitor)
+    } else {
+        let visitor = BytesVisitor {
+            encoding: None,
+            marker: PhantomData,
+        };
+        deserializer.deserialize_byte_buf(visitor)
+    }
+}
+
+// Accepts a string in the given encoding if there is one, otherwise raw bytes.
+struct BytesVisitor<T> {
+    encoding: Option<&'static Encoding>,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> Visitor<'de> for BytesVisitor<T>
+where
+    T: FromBytes,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.encoding {
+            Some(encoding) => write!(formatter, "a {} string", encoding.name),
+            None => formatter.write_str("a byte array"),
+        }
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => return Err(de::Error::invalid_type(Unexpected::Str(value), &self)),
+        };
+        match (encoding.decode)(value) {
+            Some(bytes) => T::from_bytes(bytes),
+            None => Err(de::Error::invalid_value(Unexpected::Str(value), &self)),
+        }
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        T::from_bytes(value.to_vec())
+    }
+
+    fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        T::from_bytes(value)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(size_hint::cautious(seq.size_hint()));
+        while let Some(byte) = try!(seq.next_element()) {
+            bytes.push(byte);
+        }
+        T::from_bytes(bytes)
+    }
+}
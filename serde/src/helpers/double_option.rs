@@ -0,0 +1,67 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Distinguish a missing field from a field that is explicitly null.
+//!
+//! A field of type `Option<Option<T>>` has three states:
+//!
+//! - `None` when the field is absent from the input,
+//! - `Some(None)` when the field is present with a null value,
+//! - `Some(Some(value))` when the field is present with a value.
+//!
+//! The field needs to be marked `default` so that an absent field becomes
+//! `None`, and `skip_serializing_if = "Option::is_none"` so that `None` is
+//! serialized by leaving the field out.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct UserPatch {
+//!     // Absent: leave the nickname alone.
+//!     // Null: remove the nickname.
+//!     // String: replace the nickname.
+//!     #[serde(default,
+//!             skip_serializing_if = "Option::is_none",
+//!             with = "serde::helpers::double_option")]
+//!     nickname: Option<Option<String>>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+
+use lib::*;
+
+use de::{Deserialize, Deserializer};
+use ser::{Serialize, Serializer};
+
+/// Serializes `Some(Some(value))` as `value` and both `Some(None)` and `None`
+/// as null.
+pub fn serialize<T, S>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    match *value {
+        Some(Some(ref value)) => serializer.serialize_some(value),
+        Some(None) | None => serializer.serialize_none(),
+    }
+}
+
+/// Deserializes a present field, mapping null to `Some(None)` and any other
+/// value to `Some(Some(value))`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
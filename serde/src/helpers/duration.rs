@@ -0,0 +1,187 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent a `Duration` as a single number of seconds or milliseconds.
+//!
+//! The `Serialize` and `Deserialize` impls of `Duration` use a struct of
+//! seconds and nanoseconds. The submodules of this module provide the
+//! representations most often expected by other systems:
+//!
+//! - [`seconds`] as a `u64` number of whole seconds,
+//! - [`milliseconds`] as a `u64` number of whole milliseconds,
+//! - [`seconds_f64`] as an `f64` number of seconds,
+//! - [`milliseconds_f64`] as an `f64` number of milliseconds.
+//!
+//! The integer representations round down to the nearest second or
+//! millisecond. The floating point representations round to the nearest
+//! nanosecond when deserializing and reject negative, infinite and NaN values.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::time::Duration;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Timeouts {
+//!     #[serde(with = "serde::helpers::duration::seconds")]
+//!     connect: Duration,
+//!     #[serde(with = "serde::helpers::duration::milliseconds_f64")]
+//!     read: Duration,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! [`seconds`]: seconds/index.html
+//! [`milliseconds`]: milliseconds/index.html
+//! [`seconds_f64`]: seconds_f64/index.html
+//! [`milliseconds_f64`]: milliseconds_f64/index.html
+
+use lib::*;
+
+use de::{self, Unexpected};
+
+const NANOS_PER_SEC: f64 = 1e9;
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const MILLIS_PER_SEC: u64 = 1_000;
+
+/// Represent a `Duration` as whole seconds.
+pub mod seconds {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::Serializer;
+
+    /// Serializes `duration` as a `u64` number of seconds.
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    /// Deserializes a `u64` number of seconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// Represent a `Duration` as whole milliseconds.
+pub mod milliseconds {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::{self, Serializer};
+
+    use super::{MILLIS_PER_SEC, NANOS_PER_MILLI};
+
+    /// Serializes `duration` as a `u64` number of milliseconds.
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let millis = duration
+            .as_secs()
+            .checked_mul(MILLIS_PER_SEC)
+            .and_then(|millis| millis.checked_add((duration.subsec_nanos() / NANOS_PER_MILLI) as u64));
+        match millis {
+            Some(millis) => serializer.serialize_u64(millis),
+            None => Err(ser::Error::custom("Duration out of range for milliseconds")),
+        }
+    }
+
+    /// Deserializes a `u64` number of milliseconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = try!(u64::deserialize(deserializer));
+        let nanos = (millis % MILLIS_PER_SEC) as u32 * NANOS_PER_MILLI;
+        Ok(Duration::new(millis / MILLIS_PER_SEC, nanos))
+    }
+}
+
+/// Represent a `Duration` as fractional seconds.
+pub mod seconds_f64 {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::Serializer;
+
+    use super::{NANOS_PER_SEC, from_secs_f64};
+
+    /// Serializes `duration` as an `f64` number of seconds.
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let secs = duration.as_secs() as f64 + duration.subsec_nanos() as f64 / NANOS_PER_SEC;
+        serializer.serialize_f64(secs)
+    }
+
+    /// Deserializes an `f64` number of seconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = try!(f64::deserialize(deserializer));
+        from_secs_f64(secs, secs, "a non-negative number of seconds")
+    }
+}
+
+/// Represent a `Duration` as fractional milliseconds.
+pub mod milliseconds_f64 {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::Serializer;
+
+    use super::{MILLIS_PER_SEC, NANOS_PER_MILLI, from_secs_f64};
+
+    /// Serializes `duration` as an `f64` number of milliseconds.
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let millis = duration.as_secs() as f64 * MILLIS_PER_SEC as f64 +
+                     duration.subsec_nanos() as f64 / NANOS_PER_MILLI as f64;
+        serializer.serialize_f64(millis)
+    }
+
+    /// Deserializes an `f64` number of milliseconds.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = try!(f64::deserialize(deserializer));
+        from_secs_f64(millis / MILLIS_PER_SEC as f64, millis, "a non-negative number of milliseconds")
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Converts `secs` to a `Duration`, reporting `original` in the error if it is
+// out of range.
+fn from_secs_f64<E>(secs: f64, original: f64, expected: &'static str) -> Result<Duration, E>
+where
+    E: de::Error,
+{
+    // 2^64, the first value whose whole part does not fit in a u64.
+    if !(secs >= 0.0 && secs < 18_446_744_073_709_551_616.0) {
+        return Err(de::Error::invalid_value(Unexpected::Float(original), &expected));
+    }
+    let whole = secs.trunc();
+    let nanos = ((secs - whole) * NANOS_PER_SEC).round();
+    Ok(Duration::new(whole as u64, nanos as u32))
+}
@@ -0,0 +1,116 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent `None` as an empty string.
+//!
+//! Deserializing accepts a string, which is parsed with `FromStr` unless it is
+//! empty, as well as null. Both the empty string and null become `None`.
+//! Serializing writes `Some(value)` using its `Display` impl and `None` as an
+//! empty string.
+//!
+//! To keep serializing `None` as null, name only the `deserialize` function
+//! with `deserialize_with`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct SignupForm {
+//!     #[serde(with = "serde::helpers::empty_as_none")]
+//!     nickname: Option<String>,
+//!     #[serde(deserialize_with = "serde::helpers::empty_as_none::deserialize")]
+//!     age: Option<u32>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+
+use lib::*;
+use lib::str::FromStr;
+
+use de::{self, Deserializer, Visitor};
+use ser::Serializer;
+
+/// Serializes `Some(value)` as a string using its `Display` impl and `None` as
+/// an empty string.
+pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    match *value {
+        Some(ref value) => serializer.collect_str(value),
+        None => serializer.serialize_str(""),
+    }
+}
+
+/// Deserializes an empty string or null as `None` and any other string as
+/// `Some` of its parsed value.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(EmptyAsNoneVisitor { marker: PhantomData })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct EmptyAsNoneVisitor<T> {
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> Visitor<'de> for EmptyAsNoneVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or null")
+    }
+
+    fn visit_none<E>(self) -> Result<Option<T>, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Option<T>, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Option<T>, E>
+    where
+        E: de::Error,
+    {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        match value.parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => Err(de::Error::custom(err)),
+        }
+    }
+}
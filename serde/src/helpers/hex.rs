@@ -0,0 +1,102 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent bytes as a hex string in human-readable formats.
+//!
+//! Human-readable formats get a string of two lowercase hex digits per byte.
+//! Deserialization accepts both lowercase and uppercase digits. Compact formats
+//! get the bytes as they are, serialized with `serialize_bytes`.
+//!
+//! The field may be of any type that implements `AsRef<[u8]>` and
+//! [`FromBytes`], such as `Vec<u8>` or `[u8; 32]`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Commit {
+//!     #[serde(with = "serde::helpers::hex")]
+//!     id: [u8; 20],
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! [`FromBytes`]: ../trait.FromBytes.html
+
+use lib::*;
+
+use de::Deserializer;
+use ser::Serializer;
+
+use super::FromBytes;
+use super::bytes::{self, Encoding};
+
+static HEX: Encoding = Encoding {
+    name: "hex",
+    encode: encode,
+    decode: decode,
+};
+
+const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+
+/// Serializes `bytes` as a hex string or as raw bytes, depending on whether
+/// the format is human-readable.
+pub fn serialize<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + AsRef<[u8]>,
+    S: Serializer,
+{
+    bytes::serialize(bytes.as_ref(), serializer, &HEX)
+}
+
+/// Deserializes a hex string or raw bytes, depending on whether the format is
+/// human-readable.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromBytes,
+    D: Deserializer<'de>,
+{
+    bytes::deserialize(deserializer, &HEX)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn encode(bytes: &[u8], formatter: &mut fmt::Formatter) -> fmt::Result {
+    for &b in bytes {
+        let encoded = [DIGITS[(b >> 4) as usize], DIGITS[(b & 0x0f) as usize]];
+        try!(formatter.write_str(str::from_utf8(&encoded).unwrap()));
+    }
+    Ok(())
+}
+
+fn decode(input: &str) -> Option<Vec<u8>> {
+    fn digit(b: u8) -> Option<u8> {
+        match (b as char).to_digit(16) {
+            Some(digit) => Some(digit as u8),
+            None => None,
+        }
+    }
+
+    let input = input.as_bytes();
+    if input.len() % 2 != 0 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(input.len() / 2);
+    for pair in input.chunks(2) {
+        match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => bytes.push(high << 4 | low),
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,94 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent a map as a sequence of `[key, value]` pairs.
+//!
+//! Many formats, JSON among them, only allow strings as map keys. Serializing
+//! the map as a sequence of 2-tuples instead lets keys of any type, such as
+//! integers, tuples or structs, round trip through those formats.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Distances {
+//!     // Serialized as [[["a", "b"], 3], [["a", "c"], 5]].
+//!     #[serde(with = "serde::helpers::map_as_pairs")]
+//!     between: BTreeMap<(String, String), u32>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! Any type that can be iterated by reference as `(&K, &V)` can be serialized,
+//! and any type implementing `Default` and `Extend<(K, V)>` that iterates by
+//! value as `(K, V)` can be deserialized. If the input contains the same key
+//! more than once, the map's `Extend` implementation decides which value is
+//! kept; for the standard maps that is the last one.
+
+use lib::*;
+
+use de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use ser::{Serialize, Serializer};
+
+/// Serializes `map` as a sequence of `(key, value)` tuples.
+pub fn serialize<'a, M, K, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Serialize + 'a,
+    V: Serialize + 'a,
+    S: Serializer,
+{
+    serializer.collect_seq(map)
+}
+
+/// Deserializes a sequence of `(key, value)` tuples into a map.
+pub fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<M, D::Error>
+where
+    M: Default + Extend<(K, V)> + IntoIterator<Item = (K, V)>,
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(PairsVisitor { marker: PhantomData })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct PairsVisitor<M, K, V> {
+    marker: PhantomData<(M, K, V)>,
+}
+
+impl<'de, M, K, V> Visitor<'de> for PairsVisitor<M, K, V>
+where
+    M: Default + Extend<(K, V)>,
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+{
+    type Value = M;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of key-value pairs")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<M, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut map = M::default();
+        while let Some(pair) = try!(seq.next_element::<(K, V)>()) {
+            map.extend(Some(pair));
+        }
+        Ok(map)
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Ready-made adapters for use with `#[serde(with = "...")]`.
+//!
+//! Each submodule provides a `serialize` and a `deserialize` function with the
+//! signatures expected by the `with` attribute, so a field opts into an
+//! alternative representation by naming the module:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::collections::HashMap;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Grid {
+//!     #[serde(with = "serde::helpers::map_as_pairs")]
+//!     cells: HashMap<(u32, u32), char>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! The adapters are written against the Serde data model only and work with
+//! any data format.
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod bytes;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use self::bytes::FromBytes;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod base64;
+pub mod double_option;
+#[cfg(feature = "std")]
+pub mod duration;
+pub mod empty_as_none;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod hex;
+pub mod map_as_pairs;
+pub mod null_as_default;
+pub mod string_or_struct;
+#[cfg(feature = "std")]
+pub mod system_time;
@@ -0,0 +1,57 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Deserialize an explicit null as the default value of the field's type.
+//!
+//! A present value is deserialized as usual. Combine this with
+//! `#[serde(default)]` to also accept the field being absent.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! #[derive(Deserialize)]
+//! struct Listing {
+//!     // `"count": null` becomes 0.
+//!     #[serde(deserialize_with = "serde::helpers::null_as_default::deserialize")]
+//!     count: u64,
+//!     // `"tags": null` and a missing `tags` both become an empty Vec.
+//!     #[serde(default, deserialize_with = "serde::helpers::null_as_default::deserialize")]
+//!     tags: Vec<String>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! The module also provides a `serialize` function that serializes the value
+//! as usual, so it can be named with `#[serde(with = "...")]`.
+
+use lib::*;
+
+use de::{Deserialize, Deserializer};
+use ser::{Serialize, Serializer};
+
+/// Serializes `value` with its own `Serialize` impl.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    value.serialize(serializer)
+}
+
+/// Deserializes a `T`, or `T::default()` if the input is null.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Default + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(|value| value.unwrap_or_else(T::default))
+}
@@ -0,0 +1,164 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Accept either a string shorthand or the full struct.
+//!
+//! Configuration formats often allow a value to be given as a single string
+//! when only one of its fields matters, and as a map of all fields otherwise.
+//! Deserializing through this module parses a string with the type's
+//! `FromStr` impl and deserializes a map with its `Deserialize` impl.
+//! Serializing always produces the full struct.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::str::FromStr;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Cache {
+//!     url: String,
+//!     #[serde(default)]
+//!     pool: u32,
+//! }
+//!
+//! impl FromStr for Cache {
+//!     type Err = String;
+//!
+//!     fn from_str(url: &str) -> Result<Self, String> {
+//!         Ok(Cache { url: url.to_owned(), pool: 0 })
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Service {
+//!     // Either "redis://host" or { "url": "redis://host", "pool": 8 }.
+//!     #[serde(with = "serde::helpers::string_or_struct")]
+//!     cache: Cache,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! Types without a `FromStr` impl can use [`deserialize_with`] from a
+//! function of their own, passing the function that builds the value from a
+//! string.
+//!
+//! [`deserialize_with`]: fn.deserialize_with.html
+
+use lib::*;
+use lib::str::FromStr;
+
+use de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use de::value::MapAccessDeserializer;
+use ser::{Serialize, Serializer};
+
+/// Serializes `value` with its own `Serialize` impl.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    value.serialize(serializer)
+}
+
+/// Deserializes a string using `T::from_str` or a map using
+/// `T::deserialize`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr + Deserialize<'de>,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserialize_with(deserializer, T::from_str)
+}
+
+/// Deserializes a string using `from_str` or a map using `T::deserialize`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// extern crate serde;
+///
+/// use serde::Deserializer;
+/// use serde::helpers::string_or_struct;
+///
+/// #[derive(Deserialize)]
+/// struct Build {
+///     context: String,
+///     #[serde(default)]
+///     dockerfile: Option<String>,
+/// }
+///
+/// fn build_from_context<'de, D>(deserializer: D) -> Result<Build, D::Error>
+/// where
+///     D: Deserializer<'de>,
+/// {
+///     string_or_struct::deserialize_with(deserializer, |context| {
+///         Ok::<_, String>(Build { context: context.to_owned(), dockerfile: None })
+///     })
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Service {
+///     #[serde(deserialize_with = "build_from_context")]
+///     build: Build,
+/// }
+/// #
+/// # fn main() {}
+/// ```
+pub fn deserialize_with<'de, T, D, F, M>(deserializer: D, from_str: F) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+    F: FnOnce(&str) -> Result<T, M>,
+    M: Display,
+{
+    let visitor = StringOrStructVisitor {
+        from_str: from_str,
+        marker: PhantomData,
+    };
+    deserializer.deserialize_any(visitor)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct StringOrStructVisitor<T, F> {
+    from_str: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, F, M> Visitor<'de> for StringOrStructVisitor<T, F>
+where
+    T: Deserialize<'de>,
+    F: FnOnce(&str) -> Result<T, M>,
+    M: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or a map")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<T, E>
+    where
+        E: de::Error,
+    {
+        (self.from_str)(value).map_err(de::Error::custom)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<T, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        T::deserialize(MapAccessDeserializer::new(map))
+    }
+}
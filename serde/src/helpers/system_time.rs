@@ -0,0 +1,424 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Represent a `SystemTime` as a Unix timestamp or an RFC 3339 string.
+//!
+//! The `Serialize` and `Deserialize` impls of `SystemTime` use a struct of
+//! seconds and nanoseconds since the Unix epoch. The submodules of this module
+//! provide the representations most often expected by other systems:
+//!
+//! - [`seconds`] as an `i64` number of whole seconds since the epoch,
+//! - [`milliseconds`] as an `i64` number of whole milliseconds since the epoch,
+//! - [`rfc3339`] as a string like `"2017-11-03T09:41:07.5Z"`.
+//!
+//! Unlike the `Serialize` impl of `SystemTime`, these accept times before the
+//! epoch, which are represented as negative numbers. The numeric
+//! representations round down to the nearest second or millisecond.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::time::SystemTime;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Event {
+//!     #[serde(with = "serde::helpers::system_time::rfc3339")]
+//!     occurred: SystemTime,
+//!     #[serde(with = "serde::helpers::system_time::milliseconds")]
+//!     received: SystemTime,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! [`seconds`]: seconds/index.html
+//! [`milliseconds`]: milliseconds/index.html
+//! [`rfc3339`]: rfc3339/index.html
+
+use lib::*;
+
+use ser;
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const MILLIS_PER_SEC: i64 = 1_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Represent a `SystemTime` as whole seconds since the Unix epoch.
+pub mod seconds {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::Serializer;
+
+    use super::{from_unix, to_unix};
+
+    /// Serializes `time` as an `i64` number of seconds since the Unix epoch.
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (secs, _) = try!(to_unix::<S::Error>(time));
+        serializer.serialize_i64(secs)
+    }
+
+    /// Deserializes an `i64` number of seconds since the Unix epoch.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = try!(i64::deserialize(deserializer));
+        Ok(from_unix(secs, 0))
+    }
+}
+
+/// Represent a `SystemTime` as whole milliseconds since the Unix epoch.
+pub mod milliseconds {
+    use lib::*;
+
+    use de::{Deserialize, Deserializer};
+    use ser::{self, Serializer};
+
+    use super::{MILLIS_PER_SEC, NANOS_PER_MILLI, from_unix, to_unix};
+
+    /// Serializes `time` as an `i64` number of milliseconds since the Unix
+    /// epoch.
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (secs, nanos) = try!(to_unix::<S::Error>(time));
+        let millis = secs
+            .checked_mul(MILLIS_PER_SEC)
+            .and_then(|millis| millis.checked_add((nanos / NANOS_PER_MILLI) as i64));
+        match millis {
+            Some(millis) => serializer.serialize_i64(millis),
+            None => Err(ser::Error::custom("SystemTime out of range for milliseconds")),
+        }
+    }
+
+    /// Deserializes an `i64` number of milliseconds since the Unix epoch.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = try!(i64::deserialize(deserializer));
+        let mut secs = millis / MILLIS_PER_SEC;
+        let mut rem = millis % MILLIS_PER_SEC;
+        if rem < 0 {
+            secs -= 1;
+            rem += MILLIS_PER_SEC;
+        }
+        Ok(from_unix(secs, rem as u32 * NANOS_PER_MILLI))
+    }
+}
+
+/// Represent a `SystemTime` as an RFC 3339 string in UTC.
+///
+/// Serialization always produces a `Z` suffix and only as many fractional
+/// digits as needed, for example `"1970-01-01T00:00:00Z"` or
+/// `"2017-11-03T09:41:07.25Z"`. Deserialization accepts any UTC offset,
+/// fractions of up to nanosecond precision (further digits are truncated) and
+/// a leap second `60`, which is counted as the first second of the next minute.
+pub mod rfc3339 {
+    use lib::*;
+
+    use de::{self, Deserializer, Unexpected, Visitor};
+    use ser::{self, Serializer};
+
+    use super::{Rfc3339, from_unix, parse_rfc3339, to_unix};
+
+    /// Serializes `time` as an RFC 3339 string.
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (secs, nanos) = try!(to_unix::<S::Error>(time));
+        match Rfc3339::new(secs, nanos) {
+            Some(formatted) => serializer.collect_str(&formatted),
+            None => Err(ser::Error::custom("SystemTime out of range for RFC 3339")),
+        }
+    }
+
+    /// Deserializes an RFC 3339 string.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(Rfc3339Visitor)
+    }
+
+    struct Rfc3339Visitor;
+
+    impl<'de> Visitor<'de> for Rfc3339Visitor {
+        type Value = SystemTime;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an RFC 3339 timestamp")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<SystemTime, E>
+        where
+            E: de::Error,
+        {
+            match parse_rfc3339(value.as_bytes()) {
+                Some((secs, nanos)) => Ok(from_unix(secs, nanos)),
+                None => Err(de::Error::invalid_value(Unexpected::Str(value), &self)),
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Splits `time` into whole seconds since the epoch, rounded down, and the
+// nanoseconds past that second.
+fn to_unix<E>(time: &SystemTime) -> Result<(i64, u32), E>
+where
+    E: ser::Error,
+{
+    let out_of_range = || ser::Error::custom("SystemTime out of range");
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => {
+            if after.as_secs() > i64::max_value() as u64 {
+                return Err(out_of_range());
+            }
+            Ok((after.as_secs() as i64, after.subsec_nanos()))
+        }
+        Err(err) => {
+            let before = err.duration();
+            if before.as_secs() > i64::max_value() as u64 {
+                return Err(out_of_range());
+            }
+            let secs = -(before.as_secs() as i64);
+            match before.subsec_nanos() {
+                0 => Ok((secs, 0)),
+                nanos => {
+                    match secs.checked_sub(1) {
+                        Some(secs) => Ok((secs, NANOS_PER_SEC - nanos)),
+                        None => Err(out_of_range()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Inverse of `to_unix`.
+fn from_unix(secs: i64, nanos: u32) -> SystemTime {
+    let nanos = Duration::new(0, nanos);
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64) + nanos
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.wrapping_neg() as u64) + nanos
+    }
+}
+
+// Days since 1970-01-01 of the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Proleptic Gregorian date of the given number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// A timestamp broken down into its RFC 3339 fields, in UTC.
+struct Rfc3339 {
+    year: i64,
+    month: u32,
+    day: u32,
+    secs_of_day: u32,
+    nanos: u32,
+}
+
+impl Rfc3339 {
+    // Returns `None` if the year is not representable with four digits.
+    fn new(secs: i64, nanos: u32) -> Option<Self> {
+        let mut days = secs / SECS_PER_DAY;
+        let mut secs_of_day = secs % SECS_PER_DAY;
+        if secs_of_day < 0 {
+            days -= 1;
+            secs_of_day += SECS_PER_DAY;
+        }
+        let (year, month, day) = civil_from_days(days);
+        if year < 0 || year > 9999 {
+            return None;
+        }
+        Some(
+            Rfc3339 {
+                year: year,
+                month: month,
+                day: day,
+                secs_of_day: secs_of_day as u32,
+                nanos: nanos,
+            },
+        )
+    }
+}
+
+impl Display for Rfc3339 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        try!(
+            write!(
+                formatter,
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                self.year,
+                self.month,
+                self.day,
+                self.secs_of_day / 3600,
+                self.secs_of_day / 60 % 60,
+                self.secs_of_day % 60
+            )
+        );
+        if self.nanos != 0 {
+            let mut fraction = self.nanos;
+            let mut width = 9;
+            while fraction % 10 == 0 {
+                fraction /= 10;
+                width -= 1;
+            }
+            try!(write!(formatter, ".{:0width$}", fraction, width = width));
+        }
+        formatter.write_str("Z")
+    }
+}
+
+// Parses an RFC 3339 timestamp into whole seconds since the epoch and the
+// nanoseconds past that second.
+fn parse_rfc3339(input: &[u8]) -> Option<(i64, u32)> {
+    macro_rules! try_opt {
+        ($e:expr) => {
+            match $e {
+                Some(value) => value,
+                None => return None,
+            }
+        }
+    }
+
+    fn digits(input: &[u8], pos: &mut usize, len: usize) -> Option<u32> {
+        if input.len() < *pos + len {
+            return None;
+        }
+        let mut value = 0;
+        for &b in &input[*pos..*pos + len] {
+            if b < b'0' || b > b'9' {
+                return None;
+            }
+            value = value * 10 + (b - b'0') as u32;
+        }
+        *pos += len;
+        Some(value)
+    }
+
+    fn expect(input: &[u8], pos: &mut usize, expected: &[u8]) -> Option<()> {
+        match input.get(*pos) {
+            Some(b) if expected.contains(b) => {
+                *pos += 1;
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    let mut pos = 0;
+    let year = try_opt!(digits(input, &mut pos, 4)) as i64;
+    try_opt!(expect(input, &mut pos, b"-"));
+    let month = try_opt!(digits(input, &mut pos, 2));
+    try_opt!(expect(input, &mut pos, b"-"));
+    let day = try_opt!(digits(input, &mut pos, 2));
+    try_opt!(expect(input, &mut pos, b"Tt "));
+    let hour = try_opt!(digits(input, &mut pos, 2));
+    try_opt!(expect(input, &mut pos, b":"));
+    let minute = try_opt!(digits(input, &mut pos, 2));
+    try_opt!(expect(input, &mut pos, b":"));
+    let second = try_opt!(digits(input, &mut pos, 2));
+
+    if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) || hour > 23 ||
+       minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut nanos = 0;
+    if input.get(pos) == Some(&b'.') {
+        pos += 1;
+        let start = pos;
+        while pos < input.len() && input[pos] >= b'0' && input[pos] <= b'9' {
+            if pos - start < 9 {
+                nanos = nanos * 10 + (input[pos] - b'0') as u32;
+            }
+            pos += 1;
+        }
+        if pos == start {
+            return None;
+        }
+        for _ in pos - start..9 {
+            nanos *= 10;
+        }
+    }
+
+    let offset = match input.get(pos) {
+        Some(&b'Z') | Some(&b'z') => {
+            pos += 1;
+            0
+        }
+        Some(&sign) if sign == b'+' || sign == b'-' => {
+            pos += 1;
+            let offset_hour = try_opt!(digits(input, &mut pos, 2));
+            try_opt!(expect(input, &mut pos, b":"));
+            let offset_minute = try_opt!(digits(input, &mut pos, 2));
+            if offset_hour > 23 || offset_minute > 59 {
+                return None;
+            }
+            let offset = (offset_hour * 3600 + offset_minute * 60) as i64;
+            if sign == b'+' { offset } else { -offset }
+        }
+        _ => return None,
+    };
+
+    if pos != input.len() {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * SECS_PER_DAY +
+               (hour * 3600 + minute * 60 + second) as i64 - offset;
+    Some((secs, nanos))
+}
@@ -0,0 +1,178 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Deserialize a value from several sources, where later sources override
+//! earlier ones field by field.
+//!
+//! A typical use is configuration: built-in defaults, overridden by a system
+//! file, overridden by a user file. Each source only has to contain the
+//! fields it changes. Sources are buffered as they are added, and the merged
+//! result is deserialized into the target type at the end:
+//!
+//! ```rust
+//! # #[macro_use]
+//! # extern crate serde_derive;
+//! #
+//! # extern crate serde;
+//! #
+//! use std::collections::BTreeMap;
+//!
+//! use serde::de::IntoDeserializer;
+//! use serde::de::value::Error;
+//! use serde::layer::Layers;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Config {
+//!     threads: u32,
+//!     port: u32,
+//! }
+//!
+//! # fn main() {
+//! let mut defaults = BTreeMap::new();
+//! defaults.insert("threads", 4);
+//! defaults.insert("port", 80);
+//!
+//! let mut user = BTreeMap::new();
+//! user.insert("port", 8080);
+//!
+//! let mut layers = Layers::new();
+//! layers.add::<_, Error>(defaults.into_deserializer()).unwrap();
+//! layers.add::<_, Error>(user.into_deserializer()).unwrap();
+//!
+//! let config: Config = layers.deserialize().unwrap();
+//! assert_eq!(config, Config { threads: 4, port: 8080 });
+//! # }
+//! ```
+//!
+//! Two maps are merged by key, recursively, so nested structs are overridden
+//! field by field as well. Any other value in a later source replaces the
+//! value in an earlier one entirely. In particular sequences are replaced
+//! rather than concatenated, and a `None` or unit in a later source clears
+//! the earlier value.
+//!
+//! Sources are read with `deserialize_any`, so they have to be in a
+//! self-describing format. Map keys are compared as strings, byte strings,
+//! integers, booleans or chars; keys of any other type are never considered
+//! equal, so such entries are all kept.
+
+use lib::*;
+
+use de::{Deserialize, Deserializer};
+use de::value::Error;
+
+use private::de::{Content, ContentDeserializer};
+
+/// Sources merged so far.
+pub struct Layers<'de> {
+    merged: Option<Content<'de>>,
+}
+
+impl<'de> Layers<'de> {
+    /// Creates an empty set of layers.
+    pub fn new() -> Self {
+        Layers { merged: None }
+    }
+
+    /// Reads `source` and merges it over the sources added before.
+    pub fn add<D, E>(&mut self, source: D) -> Result<(), E>
+    where
+        D: Deserializer<'de, Error = E>,
+    {
+        let content = try!(Content::deserialize(source));
+        self.merged = Some(
+            match self.merged.take() {
+                Some(base) => overlay(base, content),
+                None => content,
+            },
+        );
+        Ok(())
+    }
+
+    /// Deserializes the merged sources as a `T`.
+    ///
+    /// With no sources added, `T` is deserialized from a unit, which succeeds
+    /// for types like `Option` and unit structs.
+    pub fn deserialize<T>(self) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let content = self.merged.unwrap_or(Content::Unit);
+        T::deserialize(ContentDeserializer::new(content))
+    }
+}
+
+impl<'de> Default for Layers<'de> {
+    fn default() -> Self {
+        Layers::new()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn overlay<'de>(base: Content<'de>, over: Content<'de>) -> Content<'de> {
+    match (base, over) {
+        (Content::Map(mut base), Content::Map(over)) => {
+            for (key, value) in over {
+                let existing = base.iter().position(|entry| same_key(&entry.0, &key));
+                match existing {
+                    Some(index) => {
+                        let (key, old) = base.remove(index);
+                        base.insert(index, (key, overlay(old, value)));
+                    }
+                    None => base.push((key, value)),
+                }
+            }
+            Content::Map(base)
+        }
+        (_, over) => over,
+    }
+}
+
+#[derive(PartialEq)]
+enum Key<'a> {
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    Unsigned(u64),
+    Signed(i64),
+    Bool(bool),
+    Char(char),
+}
+
+fn key<'a>(content: &'a Content) -> Option<Key<'a>> {
+    let signed = |n: i64| if n < 0 {
+        Key::Signed(n)
+    } else {
+        Key::Unsigned(n as u64)
+    };
+    Some(
+        match *content {
+            Content::String(ref s) => Key::Str(s),
+            Content::Str(s) => Key::Str(s),
+            Content::ByteBuf(ref b) => Key::Bytes(b),
+            Content::Bytes(b) => Key::Bytes(b),
+            Content::U8(n) => Key::Unsigned(n as u64),
+            Content::U16(n) => Key::Unsigned(n as u64),
+            Content::U32(n) => Key::Unsigned(n as u64),
+            Content::U64(n) => Key::Unsigned(n),
+            Content::I8(n) => signed(n as i64),
+            Content::I16(n) => signed(n as i64),
+            Content::I32(n) => signed(n as i64),
+            Content::I64(n) => signed(n),
+            Content::Bool(b) => Key::Bool(b),
+            Content::Char(c) => Key::Char(c),
+            _ => return None,
+        },
+    )
+}
+
+fn same_key(a: &Content, b: &Content) -> bool {
+    match (key(a), key(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
@@ -156,6 +156,7 @@ mod lib {
     pub use self::core::convert::{self, From, Into};
     pub use self::core::default::{self, Default};
     pub use self::core::fmt::{self, Debug, Display};
+    pub use self::core::hash::Hasher;
     pub use self::core::marker::{self, PhantomData};
     pub use self::core::option::{self, Option};
     pub use self::core::result::{self, Result};
@@ -201,7 +202,7 @@ mod lib {
     #[cfg(feature = "std")]
     pub use std::collections::{HashMap, HashSet};
     #[cfg(feature = "std")]
-    pub use std::ffi::{CString, CStr, OsString, OsStr};
+    pub use std::ffi::{CString, CStr, NulError, OsString, OsStr};
     #[cfg(feature = "std")]
     pub use std::hash::{Hash, BuildHasher};
     #[cfg(feature = "std")]
@@ -215,6 +216,10 @@ mod lib {
 
     #[cfg(feature = "unstable")]
     pub use core::nonzero::{NonZero, Zeroable};
+    #[cfg(feature = "ranges")]
+    pub use self::core::ops::Bound;
+    #[cfg(feature = "nonzero")]
+    pub use self::core::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize};
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -224,6 +229,38 @@ mod macros;
 
 pub mod ser;
 pub mod de;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod coerce;
+pub mod digest;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod dynamic;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod erased;
+pub mod helpers;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod layer;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod matching;
+pub mod merge;
+#[cfg(feature = "std")]
+pub mod read;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod rename;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod schema;
+#[cfg(all(feature = "rc", feature = "std"))]
+pub mod shared;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod size;
+pub mod trace;
+pub mod transcode;
+pub mod version;
+#[cfg(feature = "std")]
+pub mod warning;
+#[cfg(feature = "std")]
+pub mod write;
 
 #[doc(inline)]
 pub use ser::{Serialize, Serializer};
@@ -0,0 +1,743 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Match struct field names loosely when deserializing.
+//!
+//! [`MatchingDeserializer`] wraps an arbitrary data format and rewrites the
+//! keys of every struct it deserializes before they reach the struct's
+//! `Deserialize` impl. A [`Matcher`] decides which field a key refers to,
+//! either through an explicit alias table or by ignoring ASCII case along with
+//! `_` and `-` separators, so that `UserID`, `userid` and `user-id` all match a
+//! field called `user_id`. Keys that do not match any field are passed through
+//! unchanged.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::collections::BTreeMap;
+//!
+//! use serde::Deserialize;
+//! use serde::de::IntoDeserializer;
+//! use serde::de::value::{Error, MapDeserializer};
+//! use serde::matching::{Matcher, MatchingDeserializer};
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct User {
+//!     user_id: String,
+//!     display_name: String,
+//! }
+//!
+//! # fn main() {
+//! let mut input = BTreeMap::new();
+//! input.insert("UserID", "u42");
+//! input.insert("login", "alice");
+//!
+//! let matcher = Matcher::new().case_insensitive().alias("login", "display_name");
+//! let deserializer: MapDeserializer<_, Error> = input.into_deserializer();
+//! let user = User::deserialize(MatchingDeserializer::new(deserializer, &matcher)).unwrap();
+//!
+//! assert_eq!(
+//!     user,
+//!     User {
+//!         user_id: "u42".to_owned(),
+//!         display_name: "alice".to_owned(),
+//!     }
+//! );
+//! # }
+//! ```
+//!
+//! [`MatchingDeserializer`]: struct.MatchingDeserializer.html
+//! [`Matcher`]: struct.Matcher.html
+
+use lib::*;
+
+use de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+         Visitor};
+
+/// Decides which struct field a deserialized key refers to.
+#[derive(Clone, Debug, Default)]
+pub struct Matcher {
+    case_insensitive: bool,
+    aliases: Vec<(String, &'static str)>,
+}
+
+impl Matcher {
+    /// Creates a matcher that only accepts keys equal to a field name.
+    pub fn new() -> Self {
+        Matcher::default()
+    }
+
+    /// Also accepts keys that equal a field name after ignoring ASCII case and
+    /// any `_` or `-` characters.
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Accepts `alias` as a key for the field called `field`, in every struct
+    /// that has such a field.
+    pub fn alias(mut self, alias: &str, field: &'static str) -> Self {
+        self.aliases.push((alias.to_owned(), field));
+        self
+    }
+
+    /// Returns the field among `fields` that `key` refers to, if any.
+    pub fn resolve(&self, key: &str, fields: &'static [&'static str]) -> Option<&'static str> {
+        if let Some(&field) = fields.iter().find(|&&field| field == key) {
+            return Some(field);
+        }
+
+        for &(ref alias, field) in &self.aliases {
+            if fields.contains(&field) && self.matches(key, alias) {
+                return Some(field);
+            }
+        }
+
+        if self.case_insensitive {
+            fields.iter().cloned().find(|field| loosely_equal(key, field))
+        } else {
+            None
+        }
+    }
+
+    fn matches(&self, key: &str, name: &str) -> bool {
+        if self.case_insensitive {
+            loosely_equal(key, name)
+        } else {
+            key == name
+        }
+    }
+}
+
+fn loosely_equal(a: &str, b: &str) -> bool {
+    fn significant(b: &u8) -> bool {
+        *b != b'_' && *b != b'-'
+    }
+
+    fn lowercase(b: u8) -> u8 {
+        if b'A' <= b && b <= b'Z' {
+            b + (b'a' - b'A')
+        } else {
+            b
+        }
+    }
+
+    let a = a.bytes().filter(significant).map(lowercase);
+    let b = b.bytes().filter(significant).map(lowercase);
+    a.eq(b)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Deserializer` that rewrites struct keys according to a `Matcher` before
+/// forwarding them to the struct's `Deserialize` impl.
+pub struct MatchingDeserializer<'m, D> {
+    deserializer: D,
+    matcher: &'m Matcher,
+}
+
+impl<'de, 'm, D> MatchingDeserializer<'m, D>
+where
+    D: Deserializer<'de>,
+{
+    /// Wraps `deserializer` so that struct keys are resolved by `matcher`.
+    pub fn new(deserializer: D, matcher: &'m Matcher) -> Self {
+        MatchingDeserializer {
+            deserializer: deserializer,
+            matcher: matcher,
+        }
+    }
+}
+
+macro_rules! matching_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let visitor = MatchingVisitor::new(visitor, self.matcher, None);
+                self.deserializer.$method($($arg,)* visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, 'm, D> Deserializer<'de> for MatchingDeserializer<'m, D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    matching_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = MatchingVisitor::new(visitor, self.matcher, Some(fields));
+        self.deserializer.deserialize_struct(name, fields, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+// Forwards to the wrapped visitor, wrapping every nested deserializer and
+// access. If `fields` is set, the visitor belongs to a struct and map keys are
+// resolved against those fields.
+struct MatchingVisitor<'m, V> {
+    visitor: V,
+    matcher: &'m Matcher,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<'m, V> MatchingVisitor<'m, V> {
+    fn new(visitor: V, matcher: &'m Matcher, fields: Option<&'static [&'static str]>) -> Self {
+        MatchingVisitor {
+            visitor: visitor,
+            matcher: matcher,
+            fields: fields,
+        }
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visitor.$method(v)
+            }
+        )*
+    }
+}
+
+impl<'de, 'm, V> Visitor<'de> for MatchingVisitor<'m, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+        visit_byte_buf(Vec<u8>),
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor
+            .visit_some(MatchingDeserializer::new(deserializer, self.matcher))
+    }
+
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor
+            .visit_newtype_struct(MatchingDeserializer::new(deserializer, self.matcher))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.visitor
+            .visit_seq(MatchingAccess::new(seq, self.matcher, None))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.visitor
+            .visit_map(MatchingAccess::new(map, self.matcher, self.fields))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.visitor
+            .visit_enum(MatchingAccess::new(data, self.matcher, None))
+    }
+}
+
+struct MatchingSeed<'m, T> {
+    seed: T,
+    matcher: &'m Matcher,
+}
+
+impl<'de, 'm, T> DeserializeSeed<'de> for MatchingSeed<'m, T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed
+            .deserialize(MatchingDeserializer::new(deserializer, self.matcher))
+    }
+}
+
+struct MatchingAccess<'m, A> {
+    access: A,
+    matcher: &'m Matcher,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<'m, A> MatchingAccess<'m, A> {
+    fn new(access: A, matcher: &'m Matcher, fields: Option<&'static [&'static str]>) -> Self {
+        MatchingAccess {
+            access: access,
+            matcher: matcher,
+            fields: fields,
+        }
+    }
+
+    fn seed<T>(&self, seed: T) -> MatchingSeed<'m, T> {
+        MatchingSeed {
+            seed: seed,
+            matcher: self.matcher,
+        }
+    }
+}
+
+impl<'de, 'm, A> SeqAccess<'de> for MatchingAccess<'m, A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 'm, A> MapAccess<'de> for MatchingAccess<'m, A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields {
+            Some(fields) => {
+                let seed = KeySeed {
+                    seed: seed,
+                    matcher: self.matcher,
+                    fields: fields,
+                };
+                self.access.next_key_seed(seed)
+            }
+            None => {
+                let seed = self.seed(seed);
+                self.access.next_key_seed(seed)
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 'm, A> EnumAccess<'de> for MatchingAccess<'m, A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = MatchingAccess<'m, A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let matcher = self.matcher;
+        self.access
+            .variant_seed(seed)
+            .map(|(value, variant)| (value, MatchingAccess::new(variant, matcher, None)))
+    }
+}
+
+impl<'de, 'm, A> VariantAccess<'de> for MatchingAccess<'m, A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.access.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = self.seed(seed);
+        self.access.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = MatchingVisitor::new(visitor, self.matcher, None);
+        self.access.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = MatchingVisitor::new(visitor, self.matcher, Some(fields));
+        self.access.struct_variant(fields, visitor)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Deserializes a struct key, replacing it with the field it resolves to.
+struct KeySeed<'m, K> {
+    seed: K,
+    matcher: &'m Matcher,
+    fields: &'static [&'static str],
+}
+
+impl<'de, 'm, K> DeserializeSeed<'de> for KeySeed<'m, K>
+where
+    K: DeserializeSeed<'de>,
+{
+    type Value = K::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<K::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed.deserialize(
+            KeyDeserializer {
+                deserializer: deserializer,
+                matcher: self.matcher,
+                fields: self.fields,
+            },
+        )
+    }
+}
+
+struct KeyDeserializer<'m, D> {
+    deserializer: D,
+    matcher: &'m Matcher,
+    fields: &'static [&'static str],
+}
+
+macro_rules! key_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let visitor = KeyVisitor {
+                    visitor: visitor,
+                    matcher: self.matcher,
+                    fields: self.fields,
+                };
+                self.deserializer.$method($($arg,)* visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, 'm, D> Deserializer<'de> for KeyDeserializer<'m, D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    key_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+// Replaces string keys that resolve to a field with the field name and
+// forwards everything else unchanged.
+struct KeyVisitor<'m, V> {
+    visitor: V,
+    matcher: &'m Matcher,
+    fields: &'static [&'static str],
+}
+
+impl<'de, 'm, V> Visitor<'de> for KeyVisitor<'m, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.matcher.resolve(v, self.fields) {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_str(v),
+        }
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.matcher.resolve(v, self.fields) {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_borrowed_str(v),
+        }
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.matcher.resolve(&v, self.fields) {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_string(v),
+        }
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        match str::from_utf8(v).ok().and_then(|v| self.matcher.resolve(v, self.fields)) {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_bytes(v),
+        }
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        match str::from_utf8(v).ok().and_then(|v| self.matcher.resolve(v, self.fields)) {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_borrowed_bytes(v),
+        }
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        let resolved = str::from_utf8(&v).ok().and_then(|v| self.matcher.resolve(v, self.fields));
+        match resolved {
+            Some(field) => self.visitor.visit_str(field),
+            None => self.visitor.visit_byte_buf(v),
+        }
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_some(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.visitor.visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(map)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(data)
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serializing the entries of several values as one map.
+//!
+//! [`Merge`] combines two values whose shape is only known at runtime, such
+//! as a common envelope and a per-request payload, into a single map holding
+//! the fields of both. Nesting merges combines any number of values.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use serde::merge::Merge;
+//!
+//! #[derive(Serialize)]
+//! struct Envelope {
+//!     id: u64,
+//!     version: u32,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Login {
+//!     user: String,
+//! }
+//!
+//! # fn main() {
+//! let envelope = Envelope { id: 1, version: 2 };
+//! let login = Login { user: "ferris".to_owned() };
+//!
+//! // Serializes as a map with the entries `id`, `version` and `user`.
+//! let message = Merge(&envelope, &login);
+//! # let _ = message;
+//! # }
+//! ```
+//!
+//! The merged map is written in one pass without buffering either value, so
+//! its length is not known up front and duplicate keys are passed on to the
+//! data format unchanged.
+//!
+//! [`Merge`]: struct.Merge.html
+
+use lib::*;
+
+use ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, Serializer};
+
+/// Serializes the entries of `A` followed by the entries of `B` as one map.
+///
+/// Each of the two values must serialize as a map or a struct. Newtype
+/// structs and `Some` are looked through, and `None` contributes no entries.
+/// Serializing anything else, including enum variants, is an error.
+#[derive(Clone, Copy, Debug)]
+pub struct Merge<A, B>(pub A, pub B);
+
+impl<A, B> Serialize for Merge<A, B>
+where
+    A: Serialize,
+    B: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let human_readable = serializer.is_human_readable();
+        let mut map = try!(serializer.serialize_map(None));
+        try!(self.0.serialize(Entries::new(&mut map, human_readable)));
+        try!(self.1.serialize(Entries::new(&mut map, human_readable)));
+        map.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Writes the entries of a map or struct into a map that is already open.
+struct Entries<'a, M: 'a> {
+    map: &'a mut M,
+    human_readable: bool,
+}
+
+impl<'a, M> Entries<'a, M> {
+    fn new(map: &'a mut M, human_readable: bool) -> Self {
+        Entries {
+            map: map,
+            human_readable: human_readable,
+        }
+    }
+}
+
+fn not_map_like<E>(found: &str) -> E
+where
+    E: ser::Error,
+{
+    ser::Error::custom(format_args!("can only merge maps and structs, found {}", found))
+}
+
+macro_rules! reject {
+    ($($method:ident($($ty:ty),*) -> $ret:ty = $found:expr;)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<$ret, M::Error> {
+                Err(not_map_like($found))
+            }
+        )*
+    };
+}
+
+impl<'a, M> Serializer for Entries<'a, M>
+where
+    M: SerializeMap,
+{
+    type Ok = ();
+    type Error = M::Error;
+
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = Impossible<(), M::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), M::Error>;
+
+    reject! {
+        serialize_bool(bool) -> () = "a boolean";
+        serialize_i8(i8) -> () = "an integer";
+        serialize_i16(i16) -> () = "an integer";
+        serialize_i32(i32) -> () = "an integer";
+        serialize_i64(i64) -> () = "an integer";
+        serialize_u8(u8) -> () = "an integer";
+        serialize_u16(u16) -> () = "an integer";
+        serialize_u32(u32) -> () = "an integer";
+        serialize_u64(u64) -> () = "an integer";
+        serialize_f32(f32) -> () = "a float";
+        serialize_f64(f64) -> () = "a float";
+        serialize_char(char) -> () = "a char";
+        serialize_str(&str) -> () = "a string";
+        serialize_bytes(&[u8]) -> () = "a byte array";
+        serialize_unit() -> () = "unit";
+        serialize_unit_struct(&'static str) -> () = "a unit struct";
+        serialize_unit_variant(&'static str, u32, &'static str) -> () = "an enum variant";
+        serialize_seq(Option<usize>) -> Self::SerializeSeq = "a sequence";
+        serialize_tuple(usize) -> Self::SerializeTuple = "a tuple";
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct = "a tuple struct";
+        serialize_tuple_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeTupleVariant = "an enum variant";
+        serialize_struct_variant(&'static str, u32, &'static str, usize)
+            -> Self::SerializeStructVariant = "an enum variant";
+    }
+
+    #[cfg(feature = "integer128")]
+    reject! {
+        serialize_i128(i128) -> () = "an integer";
+        serialize_u128(u128) -> () = "an integer";
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        Err(not_map_like("an enum variant"))
+    }
+
+    fn serialize_none(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn collect_str<T: ?Sized>(self, _: &T) -> Result<(), M::Error>
+    where
+        T: Display,
+    {
+        Err(not_map_like("a string"))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+}
+
+impl<'a, M> SerializeMap for Entries<'a, M>
+where
+    M: SerializeMap,
+{
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        self.map.serialize_key(key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        self.map.serialize_value(value)
+    }
+
+    fn serialize_entry<K: ?Sized, V: ?Sized>(&mut self, key: &K, value: &V) -> Result<(), M::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        self.map.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, M> SerializeStruct for Entries<'a, M>
+where
+    M: SerializeMap,
+{
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        self.map.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
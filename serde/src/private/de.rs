@@ -11,11 +11,11 @@ use lib::*;
 use de::{Deserialize, Deserializer, IntoDeserializer, Error, Visitor};
 
 #[cfg(any(feature = "std", feature = "alloc"))]
-use de::Unexpected;
+use de::{MapAccess, Unexpected};
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 pub use self::content::{Content, ContentRefDeserializer, ContentDeserializer,
-                        TaggedContentVisitor, TagOrContentField, TagOrContentFieldVisitor,
+                        TaggedContentVisitor, has_known_tag, TagOrContentField, TagOrContentFieldVisitor,
                         TagContentOtherField, TagContentOtherFieldVisitor,
                         InternallyTaggedUnitVisitor, UntaggedUnitVisitor};
 
@@ -188,6 +188,153 @@ where
     deserializer.deserialize_str(CowBytesVisitor)
 }
 
+/// Used by `#[serde(validate = "...")]` to check a deserialized value. The
+/// message of a failed check becomes a custom error.
+pub fn validate<T, E, F, M>(result: Result<T, E>, validate: F) -> Result<T, E>
+where
+    E: Error,
+    F: FnOnce(&T) -> Result<(), M>,
+    M: Display,
+{
+    let value = try!(result);
+    match validate(&value) {
+        Ok(()) => Ok(value),
+        Err(msg) => Err(E::custom(msg)),
+    }
+}
+
+/// Used by fields with `#[serde(with_readable = "...")]` or
+/// `#[serde(with_compact = "...")]` to pick the representation matching the
+/// deserializer.
+pub fn deserialize_by_readability<'de, D, T, R, C>(
+    deserializer: D,
+    readable: R,
+    compact: C,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    R: FnOnce(D) -> Result<T, D::Error>,
+    C: FnOnce(D) -> Result<T, D::Error>,
+{
+    if deserializer.is_human_readable() {
+        readable(deserializer)
+    } else {
+        compact(deserializer)
+    }
+}
+
+/// Used by `#[serde(lenient)]` fields. The value is buffered first so that a
+/// failure partway through leaves the outer deserializer in a consistent state
+/// and the fallback can be substituted.
+///
+/// Buffering goes through `deserialize_any`, so lenient fields only work with
+/// self-describing formats. An error while reading the input into the buffer,
+/// such as an I/O or syntax error, is returned as is. Only a failure to turn
+/// the buffered value into the field type is replaced by the fallback.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn deserialize_lenient<'de, D, T, F, G>(
+    deserializer: D,
+    deserialize: F,
+    fallback: G,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(ContentDeserializer<'de, D::Error>) -> Result<T, D::Error>,
+    G: FnOnce() -> T,
+{
+    let human_readable = deserializer.is_human_readable();
+    let content = try!(Content::deserialize(deserializer));
+    match deserialize(ContentDeserializer::with_human_readable(content, human_readable)) {
+        Ok(value) => Ok(value),
+        Err(_) => Ok(fallback()),
+    }
+}
+
+/// Without `std` or `alloc` there is nowhere to buffer a lenient field. No
+/// deserializer implements this trait, so a `#[serde(lenient)]` field fails
+/// to compile with an error that names the missing feature.
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+pub trait LenientFieldsRequireStdOrAllocFeature {}
+
+/// Used by `#[serde(lenient)]` fields when neither `std` nor `alloc` is
+/// enabled, to reject them. It is never called.
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+pub fn deserialize_lenient<'de, D, T, F, G>(
+    deserializer: D,
+    deserialize: F,
+    _fallback: G,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de> + LenientFieldsRequireStdOrAllocFeature,
+    F: FnOnce(D) -> Result<T, D::Error>,
+    G: FnOnce() -> T,
+{
+    deserialize(deserializer)
+}
+
+/// Used by structs with a `#[serde(collect_unknown)]` field to take ownership
+/// of an unrecognized key, which the format may provide as a string or as
+/// bytes.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub trait ToUnknownKey {
+    fn to_unknown_key(&self) -> String;
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl ToUnknownKey for str {
+    fn to_unknown_key(&self) -> String {
+        self.to_owned()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl ToUnknownKey for [u8] {
+    fn to_unknown_key(&self) -> String {
+        String::from_utf8_lossy(self).into_owned()
+    }
+}
+
+/// Implemented by the map types that a `#[serde(collect_unknown)]` field may
+/// have. The value of an unrecognized entry is deserialized as the value type
+/// of the map and inserted under its key.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub trait CollectUnknown<'de> {
+    fn insert_unknown<A>(&mut self, key: String, map: &mut A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>;
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'de, V> CollectUnknown<'de> for BTreeMap<String, V>
+where
+    V: Deserialize<'de>,
+{
+    fn insert_unknown<A>(&mut self, key: String, map: &mut A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let value = try!(map.next_value());
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, V, S> CollectUnknown<'de> for HashMap<String, V, S>
+where
+    V: Deserialize<'de>,
+    S: BuildHasher,
+{
+    fn insert_unknown<A>(&mut self, key: String, map: &mut A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let value = try!(map.next_value());
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
 pub mod size_hint {
     use lib::*;
 
@@ -786,6 +933,51 @@ mod content {
         }
     }
 
+    /// Used by generated code to deserialize a tagged enum whose last
+    /// variants are untagged. Returns whether the buffered content carries a
+    /// tag that names one of `variants`, the tagged variants in declaration
+    /// order. `tag` is the tag field of an internally or adjacently tagged
+    /// enum, or `None` for an externally tagged one.
+    ///
+    /// Only content with a known tag is deserialized as a tagged variant, so
+    /// errors inside that variant are reported as they are. Content with an
+    /// unknown or missing tag goes to the untagged variants.
+    ///
+    /// Not public API.
+    pub fn has_known_tag(content: &Content, tag: Option<&str>, variants: &[&str]) -> bool {
+        let value = match (tag, content) {
+            (None, &Content::Str(_)) | (None, &Content::String(_)) => Some(content),
+            (None, &Content::Map(ref entries)) if entries.len() == 1 => Some(&entries[0].0),
+            (Some(_), &Content::Seq(ref elements)) => elements.first(),
+            (Some(tag), &Content::Map(ref entries)) => {
+                entries
+                    .iter()
+                    .find(|&&(ref key, _)| is_named(key, tag))
+                    .map(|&(_, ref value)| value)
+            }
+            _ => None,
+        };
+
+        match value {
+            Some(&Content::U8(index)) => (index as u64) < variants.len() as u64,
+            Some(&Content::U16(index)) => (index as u64) < variants.len() as u64,
+            Some(&Content::U32(index)) => (index as u64) < variants.len() as u64,
+            Some(&Content::U64(index)) => index < variants.len() as u64,
+            Some(value) => variants.iter().any(|variant| is_named(value, variant)),
+            None => false,
+        }
+    }
+
+    fn is_named(content: &Content, name: &str) -> bool {
+        match *content {
+            Content::Str(s) => s == name,
+            Content::String(ref s) => s == name,
+            Content::Bytes(b) => b == name.as_bytes(),
+            Content::ByteBuf(ref b) => b == name.as_bytes(),
+            _ => false,
+        }
+    }
+
     /// Used by generated code to deserialize an internally tagged enum.
     ///
     /// Not public API.
@@ -986,6 +1178,7 @@ mod content {
     /// Not public API
     pub struct ContentDeserializer<'de, E> {
         content: Content<'de>,
+        human_readable: bool,
         err: PhantomData<E>,
     }
 
@@ -1001,6 +1194,7 @@ mod content {
         where
             V: Visitor<'de>,
         {
+            let human_readable = self.human_readable;
             match self.content {
                 Content::Bool(v) => visitor.visit_bool(v),
                 Content::U8(v) => visitor.visit_u8(v),
@@ -1020,10 +1214,17 @@ mod content {
                 Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
                 Content::Unit => visitor.visit_unit(),
                 Content::None => visitor.visit_none(),
-                Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
-                Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v)),
+                Content::Some(v) => {
+                    visitor.visit_some(ContentDeserializer::with_human_readable(*v, human_readable))
+                }
+                Content::Newtype(v) => {
+                    visitor.visit_newtype_struct(
+                        ContentDeserializer::with_human_readable(*v, human_readable),
+                    )
+                }
                 Content::Seq(v) => {
-                    let seq = v.into_iter().map(ContentDeserializer::new);
+                    let seq = v.into_iter()
+                        .map(|v| ContentDeserializer::with_human_readable(v, human_readable));
                     let mut seq_visitor = de::value::SeqDeserializer::new(seq);
                     let value = try!(visitor.visit_seq(&mut seq_visitor));
                     try!(seq_visitor.end());
@@ -1031,9 +1232,9 @@ mod content {
                 }
                 Content::Map(v) => {
                     let map = v.into_iter().map(|(k, v)| {
-                                                    (ContentDeserializer::new(k),
-                                                    ContentDeserializer::new(v))
-                                                });
+                        (ContentDeserializer::with_human_readable(k, human_readable),
+                         ContentDeserializer::with_human_readable(v, human_readable))
+                    });
                     let mut map_visitor = de::value::MapDeserializer::new(map);
                     let value = try!(visitor.visit_map(&mut map_visitor));
                     try!(map_visitor.end());
@@ -1048,7 +1249,10 @@ mod content {
         {
             match self.content {
                 Content::None => visitor.visit_none(),
-                Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v)),
+                Content::Some(v) => {
+                    let human_readable = self.human_readable;
+                    visitor.visit_some(ContentDeserializer::with_human_readable(*v, human_readable))
+                }
                 Content::Unit => visitor.visit_unit(),
                 _ => visitor.visit_some(self),
             }
@@ -1109,6 +1313,7 @@ mod content {
                 EnumDeserializer {
                     variant: variant,
                     value: value,
+                    human_readable: self.human_readable,
                     err: PhantomData,
                 },
             )
@@ -1119,13 +1324,23 @@ mod content {
             byte_buf unit unit_struct seq tuple tuple_struct map struct
             identifier ignored_any
         }
+
+        fn is_human_readable(&self) -> bool {
+            self.human_readable
+        }
     }
 
     impl<'de, E> ContentDeserializer<'de, E> {
         /// private API, don't use
         pub fn new(content: Content<'de>) -> Self {
+            ContentDeserializer::with_human_readable(content, true)
+        }
+
+        /// private API, don't use
+        pub fn with_human_readable(content: Content<'de>, human_readable: bool) -> Self {
             ContentDeserializer {
                 content: content,
+                human_readable: human_readable,
                 err: PhantomData,
             }
         }
@@ -1137,6 +1352,7 @@ mod content {
     {
         variant: Content<'de>,
         value: Option<Content<'de>>,
+        human_readable: bool,
         err: PhantomData<E>,
     }
 
@@ -1153,9 +1369,10 @@ mod content {
         {
             let visitor = VariantDeserializer {
                 value: self.value,
+                human_readable: self.human_readable,
                 err: PhantomData,
             };
-            seed.deserialize(ContentDeserializer::new(self.variant))
+            seed.deserialize(ContentDeserializer::with_human_readable(self.variant, self.human_readable))
                 .map(|v| (v, visitor))
         }
     }
@@ -1165,6 +1382,7 @@ mod content {
         E: de::Error,
     {
         value: Option<Content<'de>>,
+        human_readable: bool,
         err: PhantomData<E>,
     }
 
@@ -1176,7 +1394,11 @@ mod content {
 
         fn unit_variant(self) -> Result<(), E> {
             match self.value {
-                Some(value) => de::Deserialize::deserialize(ContentDeserializer::new(value)),
+                Some(value) => {
+                    de::Deserialize::deserialize(
+                        ContentDeserializer::with_human_readable(value, self.human_readable),
+                    )
+                }
                 None => Ok(()),
             }
         }
@@ -1186,7 +1408,9 @@ mod content {
             T: de::DeserializeSeed<'de>,
         {
             match self.value {
-                Some(value) => seed.deserialize(ContentDeserializer::new(value)),
+                Some(value) => {
+                    seed.deserialize(ContentDeserializer::with_human_readable(value, self.human_readable))
+                }
                 None => {
                     Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"newtype variant"),)
                 }
@@ -1199,7 +1423,7 @@ mod content {
         {
             match self.value {
                 Some(Content::Seq(v)) => {
-                    de::Deserializer::deserialize_any(SeqDeserializer::new(v), visitor)
+                    de::Deserializer::deserialize_any(SeqDeserializer::new(v, self.human_readable), visitor)
                 }
                 Some(other) => Err(de::Error::invalid_type(other.unexpected(), &"tuple variant"),),
                 None => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"tuple variant"),),
@@ -1216,7 +1440,7 @@ mod content {
         {
             match self.value {
                 Some(Content::Map(v)) => {
-                    de::Deserializer::deserialize_any(MapDeserializer::new(v), visitor)
+                    de::Deserializer::deserialize_any(MapDeserializer::new(v, self.human_readable), visitor)
                 }
                 Some(other) => Err(de::Error::invalid_type(other.unexpected(), &"struct variant"),),
                 _ => Err(de::Error::invalid_type(de::Unexpected::UnitVariant, &"struct variant"),),
@@ -1229,6 +1453,7 @@ mod content {
         E: de::Error,
     {
         iter: <Vec<Content<'de>> as IntoIterator>::IntoIter,
+        human_readable: bool,
         err: PhantomData<E>,
     }
 
@@ -1236,9 +1461,10 @@ mod content {
     where
         E: de::Error,
     {
-        fn new(vec: Vec<Content<'de>>) -> Self {
+        fn new(vec: Vec<Content<'de>>, human_readable: bool) -> Self {
             SeqDeserializer {
                 iter: vec.into_iter(),
+                human_readable: human_readable,
                 err: PhantomData,
             }
         }
@@ -1274,6 +1500,10 @@ mod content {
             byte_buf option unit unit_struct newtype_struct seq tuple
             tuple_struct map struct enum identifier ignored_any
         }
+
+        fn is_human_readable(&self) -> bool {
+            self.human_readable
+        }
     }
 
     impl<'de, E> de::SeqAccess<'de> for SeqDeserializer<'de, E>
@@ -1288,7 +1518,7 @@ mod content {
         {
             match self.iter.next() {
                 Some(value) => {
-                    seed.deserialize(ContentDeserializer::new(value))
+                    seed.deserialize(ContentDeserializer::with_human_readable(value, self.human_readable))
                         .map(Some)
                 }
                 None => Ok(None),
@@ -1306,6 +1536,7 @@ mod content {
     {
         iter: <Vec<(Content<'de>, Content<'de>)> as IntoIterator>::IntoIter,
         value: Option<Content<'de>>,
+        human_readable: bool,
         err: PhantomData<E>,
     }
 
@@ -1313,10 +1544,11 @@ mod content {
     where
         E: de::Error,
     {
-        fn new(map: Vec<(Content<'de>, Content<'de>)>) -> Self {
+        fn new(map: Vec<(Content<'de>, Content<'de>)>, human_readable: bool) -> Self {
             MapDeserializer {
                 iter: map.into_iter(),
                 value: None,
+                human_readable: human_readable,
                 err: PhantomData,
             }
         }
@@ -1335,7 +1567,8 @@ mod content {
             match self.iter.next() {
                 Some((key, value)) => {
                     self.value = Some(value);
-                    seed.deserialize(ContentDeserializer::new(key)).map(Some)
+                    seed.deserialize(ContentDeserializer::with_human_readable(key, self.human_readable))
+                        .map(Some)
                 }
                 None => Ok(None),
             }
@@ -1346,7 +1579,9 @@ mod content {
             T: de::DeserializeSeed<'de>,
         {
             match self.value.take() {
-                Some(value) => seed.deserialize(ContentDeserializer::new(value)),
+                Some(value) => {
+                    seed.deserialize(ContentDeserializer::with_human_readable(value, self.human_readable))
+                }
                 None => Err(de::Error::custom("value is missing")),
             }
         }
@@ -1375,6 +1610,10 @@ mod content {
             byte_buf option unit unit_struct newtype_struct seq tuple
             tuple_struct map struct enum identifier ignored_any
         }
+
+        fn is_human_readable(&self) -> bool {
+            self.human_readable
+        }
     }
 
     /// Not public API.
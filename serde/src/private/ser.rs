@@ -12,6 +12,10 @@ use ser::{self, Serialize, Serializer, SerializeMap, SerializeStruct, Impossible
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 use self::content::{SerializeTupleVariantAsMapValue, SerializeStructVariantAsMapValue};
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use self::content::{Content, ContentSerializer};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use size::CostModel;
 
 /// Used to check that serde(getter) attributes return the expected type.
 /// Not public API.
@@ -19,6 +23,139 @@ pub fn constrain<T: ?Sized>(t: &T) -> &T {
     t
 }
 
+/// Used to copy each field of a `#[repr(packed)]` struct out before it is
+/// serialized, since the field may be unaligned. Requiring `Copy` here
+/// reports a field type that cannot be copied as a missing trait bound
+/// rather than as a move out of `self`. Not public API.
+pub fn copy_packed<T: Copy>(t: T) -> T {
+    t
+}
+
+/// Used by structs with a `#[serde(collect_unknown)]` field to count the
+/// collected entries that are serialized after the named fields.
+/// Not public API.
+pub fn unknown_len<I>(entries: I) -> usize
+where
+    I: IntoIterator,
+{
+    entries.into_iter().count()
+}
+
+/// Used by fields with `#[serde(with_readable = "...")]` or
+/// `#[serde(with_compact = "...")]` to pick the representation matching the
+/// serializer.
+/// Not public API.
+pub fn serialize_by_readability<T, S, R, C>(
+    value: &T,
+    serializer: S,
+    readable: R,
+    compact: C,
+) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized,
+    S: Serializer,
+    R: FnOnce(&T, S) -> Result<S::Ok, S::Error>,
+    C: FnOnce(&T, S) -> Result<S::Ok, S::Error>,
+{
+    if serializer.is_human_readable() {
+        readable(value, serializer)
+    } else {
+        compact(value, serializer)
+    }
+}
+
+/// Used by `serde::version::serialize` and by the derived Serialize impl of a
+/// `#[serde(version = "N")]` container to write the version number before the
+/// payload.
+/// Not public API.
+pub fn serialize_versioned<T, S>(version: u32, payload: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    let mut state = try!(serializer.serialize_struct("Versioned", 2));
+    try!(state.serialize_field("version", &version));
+    try!(state.serialize_field("value", payload));
+    state.end()
+}
+
+/// Used by the derived `MaxSize` impl to add up the bounds of the parts of a
+/// value. `None` means unbounded, including when the sum overflows.
+/// Not public API.
+pub fn size_add(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => a.checked_add(b),
+        _ => None,
+    }
+}
+
+/// Used by the derived `MaxSize` impl to take the larger bound of two
+/// alternatives, such as the variants of an enum.
+/// Not public API.
+pub fn size_max(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(cmp::max(a, b)),
+        _ => None,
+    }
+}
+
+/// Used by the derived `MaxSize` impl for the header of a struct whose length
+/// depends on `skip_serializing_if`.
+/// Not public API.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn struct_size<C>(model: &mut C, name: &'static str, min_len: usize, max_len: usize) -> usize
+where
+    C: CostModel,
+{
+    (min_len..max_len + 1)
+        .map(|len| model.struct_(name, len))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Used by the derived `MaxSize` impl for the key of a struct field whose
+/// index depends on how many fields before it were skipped.
+/// Not public API.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn field_size<C>(model: &mut C, min_index: usize, max_index: usize, key: &'static str) -> usize
+where
+    C: CostModel,
+{
+    (min_index..max_index + 1)
+        .map(|index| model.field(index, key))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Used by the derived `MaxSize` impl for the separator in front of a tuple
+/// element whose index depends on how many elements before it were skipped.
+/// Not public API.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn element_size<C>(model: &mut C, min_index: usize, max_index: usize) -> usize
+where
+    C: CostModel,
+{
+    (min_index..max_index + 1)
+        .map(|index| model.seq_element(index))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Used by the derived `MaxSize` impl of a `#[serde(version = "N")]` container
+/// to add the calls made by `serialize_versioned` to the bound of the payload.
+/// Not public API.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn versioned_size<C>(model: &mut C, payload: Option<usize>) -> Option<usize>
+where
+    C: CostModel,
+{
+    let mut size = Some(model.struct_("Versioned", 2));
+    size = size_add(size, Some(model.field(0, "version")));
+    size = size_add(size, model.max_u32());
+    size = size_add(size, Some(model.field(1, "value")));
+    size_add(size, payload)
+}
+
 /// Not public API.
 pub fn serialize_tagged_newtype<S, T>(
     serializer: S,
@@ -450,18 +587,22 @@ mod content {
     }
 
     #[derive(Debug)]
-    enum Content {
+    pub enum Content {
         Bool(bool),
 
         U8(u8),
         U16(u16),
         U32(u32),
         U64(u64),
+        #[cfg(feature = "integer128")]
+        U128(u128),
 
         I8(i8),
         I16(i16),
         I32(i32),
         I64(i64),
+        #[cfg(feature = "integer128")]
+        I128(i128),
 
         F32(f32),
         F64(f64),
@@ -499,10 +640,14 @@ mod content {
                 Content::U16(u) => serializer.serialize_u16(u),
                 Content::U32(u) => serializer.serialize_u32(u),
                 Content::U64(u) => serializer.serialize_u64(u),
+                #[cfg(feature = "integer128")]
+                Content::U128(u) => serializer.serialize_u128(u),
                 Content::I8(i) => serializer.serialize_i8(i),
                 Content::I16(i) => serializer.serialize_i16(i),
                 Content::I32(i) => serializer.serialize_i32(i),
                 Content::I64(i) => serializer.serialize_i64(i),
+                #[cfg(feature = "integer128")]
+                Content::I128(i) => serializer.serialize_i128(i),
                 Content::F32(f) => serializer.serialize_f32(f),
                 Content::F64(f) => serializer.serialize_f64(f),
                 Content::Char(c) => serializer.serialize_char(c),
@@ -570,13 +715,21 @@ mod content {
         }
     }
 
-    struct ContentSerializer<E> {
+    pub struct ContentSerializer<E> {
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
     impl<E> ContentSerializer<E> {
-        fn new() -> Self {
-            ContentSerializer { error: PhantomData }
+        pub fn new() -> Self {
+            ContentSerializer::with_human_readable(true)
+        }
+
+        pub fn with_human_readable(human_readable: bool) -> Self {
+            ContentSerializer {
+                human_readable: human_readable,
+                error: PhantomData,
+            }
         }
     }
 
@@ -615,6 +768,11 @@ mod content {
             Ok(Content::I64(v))
         }
 
+        #[cfg(feature = "integer128")]
+        fn serialize_i128(self, v: i128) -> Result<Content, E> {
+            Ok(Content::I128(v))
+        }
+
         fn serialize_u8(self, v: u8) -> Result<Content, E> {
             Ok(Content::U8(v))
         }
@@ -631,6 +789,11 @@ mod content {
             Ok(Content::U64(v))
         }
 
+        #[cfg(feature = "integer128")]
+        fn serialize_u128(self, v: u128) -> Result<Content, E> {
+            Ok(Content::U128(v))
+        }
+
         fn serialize_f32(self, v: f32) -> Result<Content, E> {
             Ok(Content::F32(v))
         }
@@ -714,6 +877,7 @@ mod content {
             Ok(
                 SerializeSeq {
                     elements: Vec::with_capacity(len.unwrap_or(0)),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -723,6 +887,7 @@ mod content {
             Ok(
                 SerializeTuple {
                     elements: Vec::with_capacity(len),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -737,6 +902,7 @@ mod content {
                 SerializeTupleStruct {
                     name: name,
                     fields: Vec::with_capacity(len),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -755,6 +921,7 @@ mod content {
                     variant_index: variant_index,
                     variant: variant,
                     fields: Vec::with_capacity(len),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -765,6 +932,7 @@ mod content {
                 SerializeMap {
                     entries: Vec::with_capacity(len.unwrap_or(0)),
                     key: None,
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -779,6 +947,7 @@ mod content {
                 SerializeStruct {
                     name: name,
                     fields: Vec::with_capacity(len),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
@@ -797,14 +966,20 @@ mod content {
                     variant_index: variant_index,
                     variant: variant,
                     fields: Vec::with_capacity(len),
+                    human_readable: self.human_readable,
                     error: PhantomData,
                 },
             )
         }
+
+        fn is_human_readable(&self) -> bool {
+            self.human_readable
+        }
     }
 
-    struct SerializeSeq<E> {
+    pub struct SerializeSeq<E> {
         elements: Vec<Content>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -819,7 +994,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.elements.push(value);
             Ok(())
         }
@@ -829,8 +1004,9 @@ mod content {
         }
     }
 
-    struct SerializeTuple<E> {
+    pub struct SerializeTuple<E> {
         elements: Vec<Content>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -845,7 +1021,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.elements.push(value);
             Ok(())
         }
@@ -855,9 +1031,10 @@ mod content {
         }
     }
 
-    struct SerializeTupleStruct<E> {
+    pub struct SerializeTupleStruct<E> {
         name: &'static str,
         fields: Vec<Content>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -872,7 +1049,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.fields.push(value);
             Ok(())
         }
@@ -882,11 +1059,12 @@ mod content {
         }
     }
 
-    struct SerializeTupleVariant<E> {
+    pub struct SerializeTupleVariant<E> {
         name: &'static str,
         variant_index: u32,
         variant: &'static str,
         fields: Vec<Content>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -901,7 +1079,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.fields.push(value);
             Ok(())
         }
@@ -911,9 +1089,10 @@ mod content {
         }
     }
 
-    struct SerializeMap<E> {
+    pub struct SerializeMap<E> {
         entries: Vec<(Content, Content)>,
         key: Option<Content>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -928,7 +1107,7 @@ mod content {
         where
             T: Serialize,
         {
-            let key = try!(key.serialize(ContentSerializer::<E>::new()));
+            let key = try!(key.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.key = Some(key);
             Ok(())
         }
@@ -940,7 +1119,7 @@ mod content {
             let key = self.key
                 .take()
                 .expect("serialize_value called before serialize_key");
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.entries.push((key, value));
             Ok(())
         }
@@ -954,16 +1133,17 @@ mod content {
             K: Serialize,
             V: Serialize,
         {
-            let key = try!(key.serialize(ContentSerializer::<E>::new()));
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let key = try!(key.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.entries.push((key, value));
             Ok(())
         }
     }
 
-    struct SerializeStruct<E> {
+    pub struct SerializeStruct<E> {
         name: &'static str,
         fields: Vec<(&'static str, Content)>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -978,7 +1158,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.fields.push((key, value));
             Ok(())
         }
@@ -988,11 +1168,12 @@ mod content {
         }
     }
 
-    struct SerializeStructVariant<E> {
+    pub struct SerializeStructVariant<E> {
         name: &'static str,
         variant_index: u32,
         variant: &'static str,
         fields: Vec<(&'static str, Content)>,
+        human_readable: bool,
         error: PhantomData<E>,
     }
 
@@ -1007,7 +1188,7 @@ mod content {
         where
             T: Serialize,
         {
-            let value = try!(value.serialize(ContentSerializer::<E>::new()));
+            let value = try!(value.serialize(ContentSerializer::<E>::with_human_readable(self.human_readable)));
             self.fields.push((key, value));
             Ok(())
         }
@@ -0,0 +1,367 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Byte-at-a-time input with one byte of lookahead and position tracking, for
+//! implementing text formats.
+//!
+//! A format's `Deserializer` is usually generic over the [`Read`] trait so
+//! that it can be built over a byte slice, a string or an `io::Read`:
+//!
+//! - [`SliceRead`] reads from a `&[u8]` and can hand out subslices of it,
+//!   which lets the deserializer borrow strings and bytes from the input.
+//! - [`StrRead`] reads from a `&str`. Its input is known to be UTF-8.
+//! - [`IoRead`] reads from any `io::Read`, copying into a scratch buffer where
+//!   the other two borrow. It reads one byte at a time, so a reader that is
+//!   not already buffered should be wrapped in a `BufReader`.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use serde::read::{Read, StrRead};
+//!
+//! # fn main() {
+//! let mut read = StrRead::new("key = value\nnext");
+//! let mut scratch = Vec::new();
+//!
+//! let key = read.take_while(&mut scratch, |b| b != b' ').unwrap();
+//! assert_eq!(&*key, b"key");
+//! assert_eq!(read.peek().unwrap(), Some(b' '));
+//!
+//! while read.next().unwrap() != Some(b'\n') {}
+//! assert_eq!(read.position().line, 2);
+//! assert_eq!(read.position().column, 0);
+//! # }
+//! ```
+//!
+//! This module is only input plumbing. Serde itself contains no data format
+//! and does not use it. Parsing, error reporting and the `from_str` or
+//! `from_reader` entry points remain the responsibility of each format crate.
+//!
+//! [`Read`]: trait.Read.html
+//! [`SliceRead`]: struct.SliceRead.html
+//! [`StrRead`]: struct.StrRead.html
+//! [`IoRead`]: struct.IoRead.html
+
+use lib::*;
+
+use std::io;
+
+/// A location in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// The line of the last byte consumed, starting at 1.
+    pub line: usize,
+    /// How many bytes of the current line have been consumed, so 0 right
+    /// after a newline.
+    pub column: usize,
+    /// How many bytes of the input have been consumed.
+    pub offset: usize,
+}
+
+/// Bytes that are either borrowed from the input or copied into a scratch
+/// buffer.
+#[derive(Debug)]
+pub enum Reference<'b, 'c, T: ?Sized + 'static> {
+    /// Borrowed from the input for its whole lifetime `'b`.
+    Borrowed(&'b T),
+    /// Copied into the scratch buffer, valid until its next use.
+    Copied(&'c T),
+}
+
+impl<'b, 'c, T: ?Sized + 'static> ops::Deref for Reference<'b, 'c, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match *self {
+            Reference::Borrowed(b) => b,
+            Reference::Copied(c) => c,
+        }
+    }
+}
+
+/// Input that a text format deserializer reads from.
+///
+/// `'de` is the lifetime of the input, which data borrowed from it through
+/// [`Reference::Borrowed`] may outlive the reader for.
+///
+/// [`Reference::Borrowed`]: enum.Reference.html#variant.Borrowed
+pub trait Read<'de> {
+    /// Consumes and returns the next byte, or `None` at the end of the input.
+    fn next(&mut self) -> io::Result<Option<u8>>;
+
+    /// Returns the next byte without consuming it.
+    fn peek(&mut self) -> io::Result<Option<u8>>;
+
+    /// Consumes the byte returned by the last call to `peek`.
+    ///
+    /// Calling this without a preceding `peek` consumes nothing.
+    fn discard(&mut self);
+
+    /// The position after the last byte consumed.
+    fn position(&self) -> Position;
+
+    /// Consumes bytes as long as `predicate` holds for them and returns them,
+    /// borrowed from the input if possible or copied into `scratch`
+    /// otherwise. The first byte for which `predicate` fails is left to be
+    /// read next.
+    ///
+    /// `scratch` is cleared before use.
+    fn take_while<'s, F>(
+        &mut self,
+        scratch: &'s mut Vec<u8>,
+        predicate: F,
+    ) -> io::Result<Reference<'de, 's, [u8]>>
+    where
+        F: FnMut(u8) -> bool;
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Reads from a byte slice.
+#[derive(Debug)]
+pub struct SliceRead<'a> {
+    slice: &'a [u8],
+    index: usize,
+}
+
+impl<'a> SliceRead<'a> {
+    /// Creates a reader positioned at the start of `slice`.
+    pub fn new(slice: &'a [u8]) -> Self {
+        SliceRead {
+            slice: slice,
+            index: 0,
+        }
+    }
+
+    /// The input that has not been consumed yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.slice[self.index..]
+    }
+}
+
+impl<'a> Read<'a> for SliceRead<'a> {
+    #[inline]
+    fn next(&mut self) -> io::Result<Option<u8>> {
+        Ok(
+            if self.index < self.slice.len() {
+                let ch = self.slice[self.index];
+                self.index += 1;
+                Some(ch)
+            } else {
+                None
+            },
+        )
+    }
+
+    #[inline]
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.slice.get(self.index).cloned())
+    }
+
+    #[inline]
+    fn discard(&mut self) {
+        if self.index < self.slice.len() {
+            self.index += 1;
+        }
+    }
+
+    fn position(&self) -> Position {
+        // Computed on demand, which is only needed for error messages, so
+        // that reading stays a bounds check and an increment.
+        let consumed = &self.slice[..self.index];
+        let mut position = Position {
+            line: 1,
+            column: 0,
+            offset: self.index,
+        };
+        for &ch in consumed {
+            if ch == b'\n' {
+                position.line += 1;
+                position.column = 0;
+            } else {
+                position.column += 1;
+            }
+        }
+        position
+    }
+
+    fn take_while<'s, F>(
+        &mut self,
+        _scratch: &'s mut Vec<u8>,
+        mut predicate: F,
+    ) -> io::Result<Reference<'a, 's, [u8]>>
+    where
+        F: FnMut(u8) -> bool,
+    {
+        let start = self.index;
+        while self.index < self.slice.len() && predicate(self.slice[self.index]) {
+            self.index += 1;
+        }
+        Ok(Reference::Borrowed(&self.slice[start..self.index]))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Reads from a string slice.
+///
+/// This is a `SliceRead` over the bytes of the string. A format can rely on
+/// the input being valid UTF-8, for example to convert borrowed bytes to a
+/// `&str` without checking, as long as it only splits them at ASCII bytes.
+#[derive(Debug)]
+pub struct StrRead<'a> {
+    s: &'a str,
+    delegate: SliceRead<'a>,
+}
+
+impl<'a> StrRead<'a> {
+    /// Creates a reader positioned at the start of `s`.
+    pub fn new(s: &'a str) -> Self {
+        StrRead {
+            s: s,
+            delegate: SliceRead::new(s.as_bytes()),
+        }
+    }
+
+    /// The input that has not been consumed yet, or `None` if the last byte
+    /// consumed was in the middle of a multi-byte character.
+    pub fn remaining(&self) -> Option<&'a str> {
+        let index = self.delegate.index;
+        if self.s.is_char_boundary(index) {
+            Some(&self.s[index..])
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Read<'a> for StrRead<'a> {
+    #[inline]
+    fn next(&mut self) -> io::Result<Option<u8>> {
+        self.delegate.next()
+    }
+
+    #[inline]
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        self.delegate.peek()
+    }
+
+    #[inline]
+    fn discard(&mut self) {
+        self.delegate.discard();
+    }
+
+    fn position(&self) -> Position {
+        self.delegate.position()
+    }
+
+    fn take_while<'s, F>(
+        &mut self,
+        scratch: &'s mut Vec<u8>,
+        predicate: F,
+    ) -> io::Result<Reference<'a, 's, [u8]>>
+    where
+        F: FnMut(u8) -> bool,
+    {
+        self.delegate.take_while(scratch, predicate)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Reads from an `io::Read`.
+///
+/// Nothing can be borrowed from the input, so `take_while` always copies.
+pub struct IoRead<R>
+where
+    R: io::Read,
+{
+    bytes: io::Bytes<R>,
+    peeked: Option<u8>,
+    position: Position,
+}
+
+impl<R> IoRead<R>
+where
+    R: io::Read,
+{
+    /// Creates a reader over `reader`, which should be buffered.
+    pub fn new(reader: R) -> Self {
+        IoRead {
+            bytes: reader.bytes(),
+            peeked: None,
+            position: Position {
+                line: 1,
+                column: 0,
+                offset: 0,
+            },
+        }
+    }
+
+    fn consume(&mut self, ch: u8) {
+        self.position.offset += 1;
+        if ch == b'\n' {
+            self.position.line += 1;
+            self.position.column = 0;
+        } else {
+            self.position.column += 1;
+        }
+    }
+}
+
+impl<'de, R> Read<'de> for IoRead<R>
+where
+    R: io::Read,
+{
+    fn next(&mut self) -> io::Result<Option<u8>> {
+        let next = match self.peeked.take() {
+            Some(ch) => Some(ch),
+            None => try!(self.bytes.next().map_or(Ok(None), |ch| ch.map(Some))),
+        };
+        if let Some(ch) = next {
+            self.consume(ch);
+        }
+        Ok(next)
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        if self.peeked.is_none() {
+            self.peeked = try!(self.bytes.next().map_or(Ok(None), |ch| ch.map(Some)));
+        }
+        Ok(self.peeked)
+    }
+
+    fn discard(&mut self) {
+        if let Some(ch) = self.peeked.take() {
+            self.consume(ch);
+        }
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn take_while<'s, F>(
+        &mut self,
+        scratch: &'s mut Vec<u8>,
+        mut predicate: F,
+    ) -> io::Result<Reference<'de, 's, [u8]>>
+    where
+        F: FnMut(u8) -> bool,
+    {
+        scratch.clear();
+        while let Some(ch) = try!(self.peek()) {
+            if !predicate(ch) {
+                break;
+            }
+            self.discard();
+            scratch.push(ch);
+        }
+        Ok(Reference::Copied(scratch))
+    }
+}
@@ -0,0 +1,512 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A serializer wrapper that renames struct fields and enum variants at
+//! runtime.
+//!
+//! `#[serde(rename_all = "...")]` fixes the naming policy at compile time.
+//! [`RenameSerializer`] wraps any `Serializer` and passes every struct field
+//! name and variant name through a callback instead, so the policy can depend
+//! on configuration or on the caller:
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use serde::{Serialize, Serializer};
+//! use serde::rename::{NameKind, RenameSerializer};
+//!
+//! // Writes field names in upper case if the caller asks for it.
+//! fn serialize_upper<T, S>(value: &T, serializer: S, upper: bool) -> Result<S::Ok, S::Error>
+//! where
+//!     T: Serialize,
+//!     S: Serializer,
+//! {
+//!     let rename = |kind: NameKind, name: &str| match kind {
+//!         NameKind::Field if upper => name.to_uppercase(),
+//!         _ => name.to_owned(),
+//!     };
+//!     value.serialize(RenameSerializer::new(serializer, &rename))
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! Struct names, map keys and string values are left alone, only the field
+//! names of structs and struct variants and the names of variants are
+//! renamed. This includes the tag field of an internally tagged enum, which
+//! is serialized as a struct field.
+//!
+//! The names a `Serializer` receives for fields and variants have to be
+//! `&'static str`, which a name computed at runtime is not. Anything that
+//! carries a name is therefore written in the form a self-describing format
+//! such as JSON uses for it, with the renamed names as owned strings:
+//!
+//! - Structs and the fields of struct variants are written as maps from
+//!   field name to value. `skip_field` and `annotate` have no counterpart on
+//!   maps and are not passed on.
+//! - Unit variants are written as the variant name.
+//! - Newtype, tuple and struct variants are written as a map with a single
+//!   entry from the variant name to the content of the variant. The content
+//!   of tuple and struct variants is buffered until the variant ends.
+//!
+//! This is the same output as the derived impls produce for a format like
+//! JSON, but formats that encode structs or variants differently from maps
+//! and strings, such as by index, will see a different shape.
+//!
+//! [`RenameSerializer`]: struct.RenameSerializer.html
+
+use lib::*;
+
+use ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+          SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer};
+
+use private::ser::{Content, ContentSerializer};
+
+/// Which kind of name is being renamed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NameKind {
+    /// The name of a field of a struct or struct variant.
+    Field,
+    /// The name of an enum variant.
+    Variant,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Serializer` that renames struct fields and enum variants before
+/// forwarding them to the wrapped serializer.
+pub struct RenameSerializer<'r, S, F: 'r> {
+    serializer: S,
+    rename: &'r F,
+}
+
+impl<'r, S, F> RenameSerializer<'r, S, F>
+where
+    S: Serializer,
+    F: Fn(NameKind, &str) -> String,
+{
+    /// Wraps `serializer` so that every field and variant name is replaced by
+    /// what `rename` returns for it.
+    pub fn new(serializer: S, rename: &'r F) -> Self {
+        RenameSerializer {
+            serializer: serializer,
+            rename: rename,
+        }
+    }
+
+    // Starts the single-entry map that a variant is written as.
+    fn variant(self, variant: &'static str) -> Result<S::SerializeMap, S::Error> {
+        let variant = (self.rename)(NameKind::Variant, variant);
+        let mut map = try!(self.serializer.serialize_map(Some(1)));
+        try!(map.serialize_key(&variant));
+        Ok(map)
+    }
+}
+
+struct RenameValue<'a, 'r, T: ?Sized + 'a, F: 'r> {
+    value: &'a T,
+    rename: &'r F,
+}
+
+impl<'a, 'r, T: ?Sized, F> Serialize for RenameValue<'a, 'r, T, F>
+where
+    T: Serialize,
+    F: Fn(NameKind, &str) -> String,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value
+            .serialize(RenameSerializer::new(serializer, self.rename))
+    }
+}
+
+fn compound<'r, C, F, E>(rename: &'r F, result: Result<C, E>) -> Result<Compound<'r, C, F>, E> {
+    result.map(
+        |compound| {
+            Compound {
+                compound: compound,
+                rename: rename,
+            }
+        },
+    )
+}
+
+fn buffered<'r, M, F, T>(
+    rename: &'r F,
+    human_readable: bool,
+    result: Result<M, M::Error>,
+) -> Result<Buffered<'r, M, F, T>, M::Error>
+where
+    M: SerializeMap,
+{
+    result.map(
+        |map| {
+            Buffered {
+                map: map,
+                rename: rename,
+                human_readable: human_readable,
+                content: Vec::new(),
+            }
+        },
+    )
+}
+
+macro_rules! rename_serialize_primitive {
+    ($($(#[$attr:meta])* $method:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                self.serializer.$method(v)
+            }
+        )*
+    }
+}
+
+impl<'r, S, F> Serializer for RenameSerializer<'r, S, F>
+where
+    S: Serializer,
+    F: Fn(NameKind, &str) -> String,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeSeq = Compound<'r, S::SerializeSeq, F>;
+    type SerializeTuple = Compound<'r, S::SerializeTuple, F>;
+    type SerializeTupleStruct = Compound<'r, S::SerializeTupleStruct, F>;
+    type SerializeTupleVariant = Buffered<'r, S::SerializeMap, F, Content>;
+    type SerializeMap = Compound<'r, S::SerializeMap, F>;
+    type SerializeStruct = Compound<'r, S::SerializeMap, F>;
+    type SerializeStructVariant = Buffered<'r, S::SerializeMap, F, (Content, Content)>;
+
+    rename_serialize_primitive! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        let value = RenameValue {
+            value: value,
+            rename: self.rename,
+        };
+        self.serializer.serialize_some(&value)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.serializer.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        let variant = (self.rename)(NameKind::Variant, variant);
+        self.serializer.serialize_str(&variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        let value = RenameValue {
+            value: value,
+            rename: self.rename,
+        };
+        self.serializer.serialize_newtype_struct(name, &value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        let rename = self.rename;
+        let mut map = try!(self.variant(variant));
+        let value = RenameValue {
+            value: value,
+            rename: rename,
+        };
+        try!(map.serialize_value(&value));
+        map.end()
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        compound(self.rename, self.serializer.serialize_seq(len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        compound(self.rename, self.serializer.serialize_tuple(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        compound(self.rename, self.serializer.serialize_tuple_struct(name, len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let rename = self.rename;
+        let human_readable = self.serializer.is_human_readable();
+        buffered(rename, human_readable, self.variant(variant))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        compound(self.rename, self.serializer.serialize_map(len))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        compound(self.rename, self.serializer.serialize_map(Some(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let rename = self.rename;
+        let human_readable = self.serializer.is_human_readable();
+        buffered(rename, human_readable, self.variant(variant))
+    }
+
+    fn collect_str<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+    {
+        self.serializer.collect_str(value)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.serializer.is_human_readable()
+    }
+}
+
+/// The compound serializer returned by `RenameSerializer` for sequences,
+/// maps and structs.
+pub struct Compound<'r, C, F: 'r> {
+    compound: C,
+    rename: &'r F,
+}
+
+macro_rules! rename_serialize_compound {
+    ($trait:ident, $method:ident) => {
+        impl<'r, C, F> $trait for Compound<'r, C, F>
+        where
+            C: $trait,
+            F: Fn(NameKind, &str) -> String,
+        {
+            type Ok = C::Ok;
+            type Error = C::Error;
+
+            fn $method<T: ?Sized>(&mut self, value: &T) -> Result<(), C::Error>
+            where
+                T: Serialize,
+            {
+                let value = RenameValue {
+                    value: value,
+                    rename: self.rename,
+                };
+                self.compound.$method(&value)
+            }
+
+            fn end(self) -> Result<C::Ok, C::Error> {
+                self.compound.end()
+            }
+        }
+    };
+}
+
+rename_serialize_compound!(SerializeSeq, serialize_element);
+rename_serialize_compound!(SerializeTuple, serialize_element);
+rename_serialize_compound!(SerializeTupleStruct, serialize_field);
+
+impl<'r, C, F> SerializeMap for Compound<'r, C, F>
+where
+    C: SerializeMap,
+    F: Fn(NameKind, &str) -> String,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), C::Error>
+    where
+        T: Serialize,
+    {
+        let key = RenameValue {
+            value: key,
+            rename: self.rename,
+        };
+        self.compound.serialize_key(&key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), C::Error>
+    where
+        T: Serialize,
+    {
+        let value = RenameValue {
+            value: value,
+            rename: self.rename,
+        };
+        self.compound.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.compound.end()
+    }
+}
+
+impl<'r, C, F> SerializeStruct for Compound<'r, C, F>
+where
+    C: SerializeMap,
+    F: Fn(NameKind, &str) -> String,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), C::Error>
+    where
+        T: Serialize,
+    {
+        let key = (self.rename)(NameKind::Field, key);
+        let value = RenameValue {
+            value: value,
+            rename: self.rename,
+        };
+        self.compound.serialize_entry(&key, &value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.compound.end()
+    }
+}
+
+/// The compound serializer returned by `RenameSerializer` for tuple and
+/// struct variants, which buffers the content of the variant and writes it as
+/// the value of a single-entry map when the variant ends.
+pub struct Buffered<'r, M, F: 'r, T> {
+    map: M,
+    rename: &'r F,
+    human_readable: bool,
+    content: Vec<T>,
+}
+
+impl<'r, M, F, T> Buffered<'r, M, F, T>
+where
+    M: SerializeMap,
+    F: Fn(NameKind, &str) -> String,
+{
+    fn buffer<V: ?Sized>(&self, value: &V) -> Result<Content, M::Error>
+    where
+        V: Serialize,
+    {
+        let value = RenameValue {
+            value: value,
+            rename: self.rename,
+        };
+        value.serialize(ContentSerializer::with_human_readable(self.human_readable))
+    }
+}
+
+impl<'r, M, F> SerializeTupleVariant for Buffered<'r, M, F, Content>
+where
+    M: SerializeMap,
+    F: Fn(NameKind, &str) -> String,
+{
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        let value = try!(self.buffer(value));
+        self.content.push(value);
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<M::Ok, M::Error> {
+        try!(self.map.serialize_value(&Content::Seq(self.content)));
+        self.map.end()
+    }
+}
+
+impl<'r, M, F> SerializeStructVariant for Buffered<'r, M, F, (Content, Content)>
+where
+    M: SerializeMap,
+    F: Fn(NameKind, &str) -> String,
+{
+    type Ok = M::Ok;
+    type Error = M::Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), M::Error>
+    where
+        T: Serialize,
+    {
+        let key = Content::String((self.rename)(NameKind::Field, key));
+        let value = try!(self.buffer(value));
+        self.content.push((key, value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<M::Ok, M::Error> {
+        try!(self.map.serialize_value(&Content::Map(self.content)));
+        self.map.end()
+    }
+}
@@ -0,0 +1,726 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Describe the Serde data model of a type without having a value of it.
+//!
+//! [`describe`] drives a type's `Deserialize` impl with a special
+//! `Deserializer` that records every request the impl makes (the struct and
+//! field names, element types, optionality, enum variants and so on) and
+//! answers each request with a placeholder value. Enums are explored by
+//! deserializing the type repeatedly until every variant has been visited. The
+//! result is a [`Schema`] tree that tooling such as documentation generators
+//! or schema validators can consume.
+//!
+//! Two kinds of types cannot be described this way:
+//!
+//! - Recursive types, because describing them would never terminate. An error
+//!   naming the recursive type is returned instead.
+//! - Types whose `Deserialize` impl relies on `deserialize_any`, such as
+//!   untagged or internally tagged enums, because their shape is decided by
+//!   the input rather than by the type.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use serde::schema::{describe, Schema, Variant};
+//!
+//! #[derive(Deserialize)]
+//! enum Shape {
+//!     Circle { radius: f64 },
+//!     Point,
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(
+//!     describe::<Option<Shape>>().unwrap(),
+//!     Schema::Option(Box::new(Schema::Enum(
+//!         "Shape",
+//!         vec![
+//!             ("Circle", Variant::Struct(vec![("radius", Schema::F64)])),
+//!             ("Point", Variant::Unit),
+//!         ],
+//!     ))),
+//! );
+//! # }
+//! ```
+//!
+//! [`describe`]: fn.describe.html
+//! [`Schema`]: enum.Schema.html
+
+use lib::*;
+
+use de::{self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer,
+         MapAccess, SeqAccess, VariantAccess, Visitor};
+use de::value::Error;
+
+/// The data model of a type as seen by its `Deserialize` impl.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    /// A `bool`.
+    Bool,
+    /// An `i8`.
+    I8,
+    /// An `i16`.
+    I16,
+    /// An `i32`.
+    I32,
+    /// An `i64`.
+    I64,
+    /// A `u8`.
+    U8,
+    /// A `u16`.
+    U16,
+    /// A `u32`.
+    U32,
+    /// A `u64`.
+    U64,
+    /// An `f32`.
+    F32,
+    /// An `f64`.
+    F64,
+    /// A `char`.
+    Char,
+    /// A string, whether borrowed or owned.
+    Str,
+    /// A byte array, whether borrowed or owned.
+    Bytes,
+    /// An optional value.
+    Option(Box<Schema>),
+    /// The type `()`.
+    Unit,
+    /// A unit struct with the given name.
+    UnitStruct(&'static str),
+    /// A newtype struct with the given name wrapping a value.
+    NewtypeStruct(&'static str, Box<Schema>),
+    /// A variably sized sequence of elements.
+    Seq(Box<Schema>),
+    /// A fixed size sequence of elements.
+    Tuple(Vec<Schema>),
+    /// A tuple struct with the given name and fields.
+    TupleStruct(&'static str, Vec<Schema>),
+    /// A map with the given key and value.
+    Map(Box<Schema>, Box<Schema>),
+    /// A struct with the given name and named fields.
+    Struct(&'static str, Vec<(&'static str, Schema)>),
+    /// An enum with the given name and variants.
+    Enum(&'static str, Vec<(&'static str, Variant)>),
+}
+
+/// The content of one enum variant.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Variant {
+    /// A variant without data.
+    Unit,
+    /// A variant containing one unnamed value.
+    Newtype(Schema),
+    /// A variant containing unnamed fields.
+    Tuple(Vec<Schema>),
+    /// A variant containing named fields.
+    Struct(Vec<(&'static str, Schema)>),
+}
+
+/// Describes the data model of `T`.
+///
+/// See the [module documentation] for the types that cannot be described.
+///
+/// [module documentation]: index.html
+pub fn describe<'de, T>() -> Result<Schema, Error>
+where
+    T: Deserialize<'de>,
+{
+    let mut state = State {
+        stack: Vec::new(),
+        enums: BTreeMap::new(),
+        progress: false,
+    };
+
+    loop {
+        let mut schema = Schema::Unit;
+        state.progress = false;
+        try!(T::deserialize(Describer::new(&mut state, &mut schema)));
+
+        match state.incomplete() {
+            None => {
+                state.fill(&mut schema);
+                return Ok(schema);
+            }
+            Some(name) => {
+                if !state.progress {
+                    return Err(
+                        de::Error::custom(
+                            format_args!("unable to reach every variant of enum `{}`", name),
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct State {
+    // Named types currently being described, used to detect recursion.
+    stack: Vec<&'static str>,
+    // Every enum seen so far along with the variants explored so far.
+    enums: BTreeMap<&'static str, EnumState>,
+    // Whether the current pass explored a variant that was not seen before.
+    progress: bool,
+}
+
+struct EnumState {
+    names: &'static [&'static str],
+    variants: Vec<Option<Variant>>,
+}
+
+impl State {
+    fn enter(&mut self, name: &'static str) -> Result<(), Error> {
+        if self.stack.contains(&name) {
+            return Err(de::Error::custom(format_args!("cannot describe recursive type `{}`", name)));
+        }
+        self.stack.push(name);
+        Ok(())
+    }
+
+    fn exit(&mut self) {
+        self.stack.pop();
+    }
+
+    fn incomplete(&self) -> Option<&'static str> {
+        self.enums
+            .iter()
+            .find(|&(_, e)| e.variants.iter().any(Option::is_none))
+            .map(|(&name, _)| name)
+    }
+
+    // Picks a variant that has not been explored yet if there is one, otherwise
+    // a variant that leads to some other enum with unexplored variants.
+    fn choose(&self, name: &'static str) -> usize {
+        let variants = &self.enums[name].variants;
+        if let Some(index) = variants.iter().position(Option::is_none) {
+            return index;
+        }
+        variants
+            .iter()
+            .position(
+                |variant| match *variant {
+                    Some(ref variant) => self.variant_leads_to_incomplete(variant),
+                    None => false,
+                },
+            )
+            .unwrap_or(0)
+    }
+
+    fn variant_leads_to_incomplete(&self, variant: &Variant) -> bool {
+        match *variant {
+            Variant::Unit => false,
+            Variant::Newtype(ref schema) => self.leads_to_incomplete(schema),
+            Variant::Tuple(ref schemas) => schemas.iter().any(|s| self.leads_to_incomplete(s)),
+            Variant::Struct(ref fields) => fields.iter().any(|&(_, ref s)| self.leads_to_incomplete(s)),
+        }
+    }
+
+    fn leads_to_incomplete(&self, schema: &Schema) -> bool {
+        match *schema {
+            Schema::Option(ref s) | Schema::NewtypeStruct(_, ref s) | Schema::Seq(ref s) => {
+                self.leads_to_incomplete(s)
+            }
+            Schema::Tuple(ref schemas) | Schema::TupleStruct(_, ref schemas) => {
+                schemas.iter().any(|s| self.leads_to_incomplete(s))
+            }
+            Schema::Map(ref k, ref v) => self.leads_to_incomplete(k) || self.leads_to_incomplete(v),
+            Schema::Struct(_, ref fields) => fields.iter().any(|&(_, ref s)| self.leads_to_incomplete(s)),
+            Schema::Enum(name, _) => {
+                let e = &self.enums[name];
+                e.variants.iter().any(
+                    |variant| match *variant {
+                        Some(ref variant) => self.variant_leads_to_incomplete(variant),
+                        None => true,
+                    },
+                )
+            }
+            _ => false,
+        }
+    }
+
+    // Enum nodes are recorded without their variants while exploring. Once
+    // every variant is known, copy them into the tree.
+    fn fill(&self, schema: &mut Schema) {
+        match *schema {
+            Schema::Option(ref mut s) |
+            Schema::NewtypeStruct(_, ref mut s) |
+            Schema::Seq(ref mut s) => self.fill(s),
+            Schema::Tuple(ref mut schemas) |
+            Schema::TupleStruct(_, ref mut schemas) => {
+                for s in schemas {
+                    self.fill(s);
+                }
+            }
+            Schema::Map(ref mut k, ref mut v) => {
+                self.fill(k);
+                self.fill(v);
+            }
+            Schema::Struct(_, ref mut fields) => {
+                for &mut (_, ref mut s) in fields {
+                    self.fill(s);
+                }
+            }
+            Schema::Enum(name, ref mut variants) => {
+                let e = &self.enums[name];
+                *variants = e.names
+                    .iter()
+                    .cloned()
+                    .zip(e.variants.iter().cloned().map(Option::unwrap))
+                    .collect();
+                for &mut (_, ref mut variant) in variants {
+                    match *variant {
+                        Variant::Unit => {}
+                        Variant::Newtype(ref mut s) => self.fill(s),
+                        Variant::Tuple(ref mut schemas) => {
+                            for s in schemas {
+                                self.fill(s);
+                            }
+                        }
+                        Variant::Struct(ref mut fields) => {
+                            for &mut (_, ref mut s) in fields {
+                                self.fill(s);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Describer<'a> {
+    state: &'a mut State,
+    schema: &'a mut Schema,
+}
+
+impl<'a> Describer<'a> {
+    fn new(state: &'a mut State, schema: &'a mut Schema) -> Self {
+        Describer {
+            state: state,
+            schema: schema,
+        }
+    }
+}
+
+macro_rules! describe_primitive {
+    ($($method:ident => $schema:ident, $visit:ident($($v:expr)*);)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                *self.schema = Schema::$schema;
+                visitor.$visit($($v)*)
+            }
+        )*
+    }
+}
+
+impl<'de, 'a> Deserializer<'de> for Describer<'a> {
+    type Error = Error;
+
+    describe_primitive! {
+        deserialize_bool => Bool, visit_bool(false);
+        deserialize_i8 => I8, visit_i8(0);
+        deserialize_i16 => I16, visit_i16(0);
+        deserialize_i32 => I32, visit_i32(0);
+        deserialize_i64 => I64, visit_i64(0);
+        deserialize_u8 => U8, visit_u8(0);
+        deserialize_u16 => U16, visit_u16(0);
+        deserialize_u32 => U32, visit_u32(0);
+        deserialize_u64 => U64, visit_u64(0);
+        deserialize_f32 => F32, visit_f32(0.0);
+        deserialize_f64 => F64, visit_f64(0.0);
+        deserialize_char => Char, visit_char('\0');
+        deserialize_str => Str, visit_borrowed_str("");
+        deserialize_string => Str, visit_borrowed_str("");
+        deserialize_bytes => Bytes, visit_borrowed_bytes(&[]);
+        deserialize_byte_buf => Bytes, visit_borrowed_bytes(&[]);
+        deserialize_unit => Unit, visit_unit();
+        deserialize_ignored_any => Unit, visit_unit();
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+        Err(de::Error::custom("cannot describe a type that relies on deserialize_any"))
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let _ = visitor;
+        Err(de::Error::custom("cannot describe a standalone identifier"))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut inner = Schema::Unit;
+        let value = try!(visitor.visit_some(Describer::new(self.state, &mut inner)));
+        *self.schema = Schema::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        *self.schema = Schema::UnitStruct(name);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        try!(self.state.enter(name));
+        let mut inner = Schema::Unit;
+        let value = try!(visitor.visit_newtype_struct(Describer::new(self.state, &mut inner)));
+        self.state.exit();
+        *self.schema = Schema::NewtypeStruct(name, Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut elements = Vec::new();
+        let value = try!(visitor.visit_seq(SeqDescriber::new(self.state, 1, &mut elements)));
+        *self.schema = Schema::Seq(Box::new(elements.pop().unwrap_or(Schema::Unit)));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut elements = Vec::new();
+        let value = try!(visitor.visit_seq(SeqDescriber::new(self.state, len, &mut elements)));
+        *self.schema = Schema::Tuple(elements);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        try!(self.state.enter(name));
+        let mut elements = Vec::new();
+        let value = try!(visitor.visit_seq(SeqDescriber::new(self.state, len, &mut elements)));
+        self.state.exit();
+        *self.schema = Schema::TupleStruct(name, elements);
+        Ok(value)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut entry = None;
+        let value = try!(
+            visitor.visit_map(
+                MapDescriber {
+                    state: self.state,
+                    entry: &mut entry,
+                    key: None,
+                },
+            )
+        );
+        *self.schema = match entry {
+            Some((k, v)) => Schema::Map(Box::new(k), Box::new(v)),
+            None => Schema::Map(Box::new(Schema::Unit), Box::new(Schema::Unit)),
+        };
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        try!(self.state.enter(name));
+        let mut described = Vec::new();
+        let value = try!(visitor.visit_map(StructDescriber::new(self.state, fields, &mut described)));
+        self.state.exit();
+        *self.schema = Schema::Struct(name, described);
+        Ok(value)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        try!(self.state.enter(name));
+        self.state
+            .enums
+            .entry(name)
+            .or_insert_with(
+                || {
+                    EnumState {
+                        names: variants,
+                        variants: variants.iter().map(|_| None).collect(),
+                    }
+                },
+            );
+        if variants.is_empty() {
+            return Err(de::Error::custom(format_args!("cannot describe empty enum `{}`", name)));
+        }
+
+        let index = self.state.choose(name);
+        let mut variant = Variant::Unit;
+        let value = try!(
+            visitor.visit_enum(
+                EnumDescriber {
+                    state: self.state,
+                    name: variants[index],
+                    variant: &mut variant,
+                },
+            )
+        );
+        self.state.exit();
+
+        let explored = &mut self.state.enums.get_mut(name).unwrap().variants[index];
+        if explored.is_none() {
+            *explored = Some(variant);
+            self.state.progress = true;
+        }
+        *self.schema = Schema::Enum(name, Vec::new());
+        Ok(value)
+    }
+}
+
+struct SeqDescriber<'a> {
+    state: &'a mut State,
+    remaining: usize,
+    elements: &'a mut Vec<Schema>,
+}
+
+impl<'a> SeqDescriber<'a> {
+    fn new(state: &'a mut State, len: usize, elements: &'a mut Vec<Schema>) -> Self {
+        SeqDescriber {
+            state: state,
+            remaining: len,
+            elements: elements,
+        }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for SeqDescriber<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let mut element = Schema::Unit;
+        let value = try!(seed.deserialize(Describer::new(self.state, &mut element)));
+        self.elements.push(element);
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct MapDescriber<'a> {
+    state: &'a mut State,
+    entry: &'a mut Option<(Schema, Schema)>,
+    key: Option<Schema>,
+}
+
+impl<'de, 'a> MapAccess<'de> for MapDescriber<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.entry.is_some() || self.key.is_some() {
+            return Ok(None);
+        }
+        let mut key = Schema::Unit;
+        let value = try!(seed.deserialize(Describer::new(self.state, &mut key)));
+        self.key = Some(key);
+        Ok(Some(value))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err(de::Error::custom("value is missing a key")),
+        };
+        let mut schema = Schema::Unit;
+        let value = try!(seed.deserialize(Describer::new(self.state, &mut schema)));
+        *self.entry = Some((key, schema));
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.entry.is_some() { 0 } else { 1 })
+    }
+}
+
+struct StructDescriber<'a> {
+    state: &'a mut State,
+    fields: &'static [&'static str],
+    index: usize,
+    described: &'a mut Vec<(&'static str, Schema)>,
+}
+
+impl<'a> StructDescriber<'a> {
+    fn new(
+        state: &'a mut State,
+        fields: &'static [&'static str],
+        described: &'a mut Vec<(&'static str, Schema)>,
+    ) -> Self {
+        StructDescriber {
+            state: state,
+            fields: fields,
+            index: 0,
+            described: described,
+        }
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for StructDescriber<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.get(self.index) {
+            Some(&field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let field = match self.fields.get(self.index) {
+            Some(&field) => field,
+            None => return Err(de::Error::custom("value is missing a key")),
+        };
+        self.index += 1;
+        let mut schema = Schema::Unit;
+        let value = try!(seed.deserialize(Describer::new(self.state, &mut schema)));
+        self.described.push((field, schema));
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len() - self.index)
+    }
+}
+
+struct EnumDescriber<'a> {
+    state: &'a mut State,
+    name: &'static str,
+    variant: &'a mut Variant,
+}
+
+impl<'de, 'a> EnumAccess<'de> for EnumDescriber<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = try!(seed.deserialize(self.name.into_deserializer()));
+        Ok((value, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for EnumDescriber<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        *self.variant = Variant::Unit;
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut schema = Schema::Unit;
+        let value = try!(seed.deserialize(Describer::new(self.state, &mut schema)));
+        *self.variant = Variant::Newtype(schema);
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut elements = Vec::new();
+        let value = try!(visitor.visit_seq(SeqDescriber::new(self.state, len, &mut elements)));
+        *self.variant = Variant::Tuple(elements);
+        Ok(value)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut described = Vec::new();
+        let value = try!(visitor.visit_map(StructDescriber::new(self.state, fields, &mut described)));
+        *self.variant = Variant::Struct(described);
+        Ok(value)
+    }
+}
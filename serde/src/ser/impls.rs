@@ -44,6 +44,11 @@ primitive_impl!(f32, serialize_f32);
 primitive_impl!(f64, serialize_f64);
 primitive_impl!(char, serialize_char);
 
+#[cfg(feature = "integer128")]
+primitive_impl!(i128, serialize_i128);
+#[cfg(feature = "integer128")]
+primitive_impl!(u128, serialize_u128);
+
 ////////////////////////////////////////////////////////////////////////////////
 
 impl Serialize for str {
@@ -233,6 +238,46 @@ where
     }
 }
 
+#[cfg(all(feature = "std", feature = "ranges"))]
+impl<Idx> Serialize for ops::RangeInclusive<Idx>
+where
+    Idx: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use super::SerializeStruct;
+        let mut state = try!(serializer.serialize_struct("RangeInclusive", 2));
+        try!(state.serialize_field("start", self.start()));
+        try!(state.serialize_field("end", self.end()));
+        state.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "ranges")]
+impl<T> Serialize for Bound<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Bound::Unbounded => serializer.serialize_unit_variant("Bound", 0, "Unbounded"),
+            Bound::Included(ref value) => {
+                serializer.serialize_newtype_variant("Bound", 1, "Included", value)
+            }
+            Bound::Excluded(ref value) => {
+                serializer.serialize_newtype_variant("Bound", 2, "Excluded", value)
+            }
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 impl Serialize for () {
@@ -339,12 +384,38 @@ deref_impl!(<'a, T: ?Sized> Serialize for &'a mut T where T: Serialize);
 #[cfg(any(feature = "std", feature = "alloc"))]
 deref_impl!(<T: ?Sized> Serialize for Box<T> where T: Serialize);
 
-#[cfg(all(feature = "rc", any(feature = "std", feature = "alloc")))]
+#[cfg(all(feature = "rc", feature = "alloc", not(feature = "std")))]
 deref_impl!(<T: ?Sized> Serialize for Rc<T> where T: Serialize);
 
-#[cfg(all(feature = "rc", any(feature = "std", feature = "alloc")))]
+#[cfg(all(feature = "rc", feature = "alloc", not(feature = "std")))]
 deref_impl!(<T: ?Sized> Serialize for Arc<T> where T: Serialize);
 
+// With the standard library, serializing through `shared::serialize_acyclic`
+// detects cycles of these pointers.
+#[cfg(all(feature = "rc", feature = "std"))]
+macro_rules! pointer_impl {
+    ($ty:ident) => {
+        impl<T: ?Sized> Serialize for $ty<T>
+        where
+            T: Serialize,
+        {
+            #[inline]
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                ::shared::serialize_pointee(&**self, serializer)
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "rc", feature = "std"))]
+pointer_impl!(Rc);
+
+#[cfg(all(feature = "rc", feature = "std"))]
+pointer_impl!(Arc);
+
 #[cfg(any(feature = "std", feature = "alloc"))]
 deref_impl!(<'a, T: ?Sized> Serialize for Cow<'a, T> where T: Serialize + ToOwned);
 
@@ -363,6 +434,27 @@ where
     }
 }
 
+#[cfg(feature = "nonzero")]
+macro_rules! nonzero_integers {
+    ($($ty:ident)*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    self.get().serialize(serializer)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "nonzero")]
+nonzero_integers! {
+    NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64 NonZeroUsize
+}
+
 impl<T> Serialize for Cell<T>
 where
     T: Serialize + Copy,
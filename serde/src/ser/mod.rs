@@ -50,6 +50,7 @@
 //!    - bool
 //!    - i8, i16, i32, i64, isize
 //!    - u8, u16, u32, u64, usize
+//!    - i128, u128 (with the `integer128` feature)
 //!    - f32, f64
 //!    - char
 //!    - str
@@ -93,7 +94,10 @@
 //!    - Path
 //!    - PathBuf
 //!    - Range\<T\>
+//!    - RangeInclusive\<T\> and Bound\<T\> (with the `ranges` feature)
 //!    - NonZero\<T\> (unstable)
+//!    - NonZeroU8 through NonZeroU64 and NonZeroUsize (with the `nonzero`
+//!      feature)
 //!  - **Net types**:
 //!    - IpAddr
 //!    - Ipv4Addr
@@ -486,6 +490,18 @@ pub trait Serializer: Sized {
     /// ```
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error>;
 
+    /// Serialize an `i128` value.
+    ///
+    /// This method is available with the `integer128` feature. The default
+    /// implementation returns an error, so that formats written before
+    /// 128-bit integers existed keep compiling. Formats that can represent
+    /// them should override it.
+    #[cfg(feature = "integer128")]
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        let _ = v;
+        Err(Error::custom("i128 is not supported"))
+    }
+
     /// Serialize a `u8` value.
     ///
     /// If the format does not differentiate between `u8` and `u64`, a
@@ -586,6 +602,18 @@ pub trait Serializer: Sized {
     /// ```
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error>;
 
+    /// Serialize a `u128` value.
+    ///
+    /// This method is available with the `integer128` feature. The default
+    /// implementation returns an error, so that formats written before
+    /// 128-bit integers existed keep compiling. Formats that can represent
+    /// them should override it.
+    #[cfg(feature = "integer128")]
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        let _ = v;
+        Err(Error::custom("u128 is not supported"))
+    }
+
     /// Serialize an `f32` value.
     ///
     /// If the format does not differentiate between `f32` and `f64`, a
@@ -1784,6 +1812,19 @@ pub trait SerializeStruct {
         Ok(())
     }
 
+    /// Attach a human-oriented annotation, such as the field's documentation,
+    /// to the struct field that is serialized next.
+    ///
+    /// Formats that can represent comments may emit the annotation next to
+    /// the field. The default implementation ignores it, which is the right
+    /// behavior for every format without such a concept.
+    #[inline]
+    fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Self::Error> {
+        let _ = key;
+        let _ = annotation;
+        Ok(())
+    }
+
     /// Finish serializing a struct.
     fn end(self) -> Result<Self::Ok, Self::Error>;
 }
@@ -1836,6 +1877,19 @@ pub trait SerializeStructVariant {
         Ok(())
     }
 
+    /// Attach a human-oriented annotation, such as the field's documentation,
+    /// to the struct variant field that is serialized next.
+    ///
+    /// Formats that can represent comments may emit the annotation next to
+    /// the field. The default implementation ignores it, which is the right
+    /// behavior for every format without such a concept.
+    #[inline]
+    fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Self::Error> {
+        let _ = key;
+        let _ = annotation;
+        Ok(())
+    }
+
     /// Finish serializing a struct variant.
     fn end(self) -> Result<Self::Ok, Self::Error>;
 }
@@ -0,0 +1,594 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialize `Rc` and `Arc` pointers while preserving sharing.
+//!
+//! The plain `Rc<T>` and `Arc<T>` impls serialize the pointee every time it is
+//! referenced and deserialize every occurrence into a separate allocation.
+//! Wrapping the pointers in [`SharedRc`] or [`SharedArc`] instead assigns an id
+//! to each allocation. The first occurrence is serialized in full together
+//! with its id and every later occurrence as a reference to that id, so on
+//! deserialization all references resolve to a single allocation again.
+//!
+//! The ids live only as long as one call to [`serialize`] or [`deserialize`]
+//! in this module, which must wrap the value containing the shared pointers.
+//! These functions have the signatures expected by `#[serde(with = "...")]`.
+//! Serializing a shared pointer outside of them is an error.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use serde::shared::SharedRc;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Graph {
+//!     nodes: Vec<SharedRc<Node>>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Node {
+//!     name: String,
+//!     edges: Vec<SharedRc<Node>>,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Document {
+//!     #[serde(with = "serde::shared")]
+//!     graph: Graph,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! A shared pointer is serialized as an enum named `Shared`. The first
+//! occurrence is the struct variant `Def { id: u64, value: T }` and every
+//! later one the newtype variant `Ref(u64)`.
+//!
+//! Reference cycles are not supported. A shared pointer that refers back to
+//! itself while it is being serialized is reported as a serialization error.
+//!
+//! # Cycle detection for plain pointers
+//!
+//! A cycle of plain `Rc` or `Arc` pointers, which can be built through a
+//! `RefCell` or `Mutex`, otherwise recurses until the stack overflows. Within
+//! a call to [`serialize_acyclic`] every `Rc` and `Arc` remembers which
+//! allocations are currently being serialized and fails with an error when it
+//! meets one of them again. Pointers that are merely shared, not cyclic, are
+//! serialized once per occurrence as usual.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//!
+//! #[derive(Serialize)]
+//! struct Node {
+//!     name: String,
+//!     children: Vec<Rc<RefCell<Node>>>,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Tree {
+//!     #[serde(serialize_with = "serde::shared::serialize_acyclic")]
+//!     root: Rc<RefCell<Node>>,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! [`SharedRc`]: struct.SharedRc.html
+//! [`SharedArc`]: struct.SharedArc.html
+//! [`serialize`]: fn.serialize.html
+//! [`deserialize`]: fn.deserialize.html
+//! [`serialize_acyclic`]: fn.serialize_acyclic.html
+
+use lib::*;
+
+use std::any::Any;
+use std::thread::LocalKey;
+
+use de::{self, Deserialize, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+         Visitor};
+use ser::{self, Serialize, SerializeStructVariant, Serializer};
+
+thread_local! {
+    // The id of each allocation and whether its definition is complete.
+    static SERIALIZE_IDS: RefCell<Option<HashMap<usize, (u64, bool)>>> = RefCell::new(None);
+    static DESERIALIZE_IDS: RefCell<Option<HashMap<u64, Box<Any>>>> = RefCell::new(None);
+    // The allocations of the plain pointers currently being serialized.
+    static ACTIVE_POINTERS: RefCell<Option<HashSet<usize>>> = RefCell::new(None);
+}
+
+/// Serializes `value`, emitting each shared pointer inside of it only once.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let _scope = Scope::enter(&SERIALIZE_IDS);
+    value.serialize(serializer)
+}
+
+/// Serializes `value`, failing with an error instead of overflowing the stack
+/// if it contains a cycle of `Rc` or `Arc` pointers.
+///
+/// Calls may nest; cycles are tracked across all of them.
+pub fn serialize_acyclic<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let active = ACTIVE_POINTERS.with(|active| active.borrow().is_some());
+    if active {
+        return value.serialize(serializer);
+    }
+    let _scope = Scope::enter(&ACTIVE_POINTERS);
+    value.serialize(serializer)
+}
+
+/// Deserializes a value, resolving the shared pointers inside of it to shared
+/// allocations.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let _scope = Scope::enter(&DESERIALIZE_IDS);
+    T::deserialize(deserializer)
+}
+
+// Installs a fresh id table for the current thread and restores the previous
+// one when dropped, so that scopes may nest.
+struct Scope<V: 'static> {
+    key: &'static LocalKey<RefCell<Option<V>>>,
+    previous: Option<V>,
+}
+
+impl<V: Default> Scope<V> {
+    fn enter(key: &'static LocalKey<RefCell<Option<V>>>) -> Self {
+        let previous = key.with(|ids| mem::replace(&mut *ids.borrow_mut(), Some(V::default())));
+        Scope {
+            key: key,
+            previous: previous,
+        }
+    }
+}
+
+impl<V> Drop for Scope<V> {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        self.key.with(|ids| *ids.borrow_mut() = previous);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const CYCLE: &'static str = "reference cycle detected while serializing";
+
+// Not public API. Used by the `Rc` and `Arc` impls of `Serialize`.
+#[doc(hidden)]
+pub fn serialize_pointee<T: ?Sized, S>(pointee: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let address = pointee as *const T as *const () as usize;
+    let entered = ACTIVE_POINTERS.with(
+        |active| match *active.borrow_mut() {
+            Some(ref mut active) => Some(active.insert(address)),
+            None => None,
+        },
+    );
+
+    match entered {
+        Some(true) => {
+            let _leave = Leave(address);
+            pointee.serialize(serializer)
+        }
+        Some(false) => Err(ser::Error::custom(CYCLE)),
+        None => pointee.serialize(serializer),
+    }
+}
+
+// Removes an allocation from the active set when its pointer is done, even if
+// serializing it panicked.
+struct Leave(usize);
+
+impl Drop for Leave {
+    fn drop(&mut self) {
+        ACTIVE_POINTERS.with(
+            |active| if let Some(ref mut active) = *active.borrow_mut() {
+                active.remove(&self.0);
+            },
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+macro_rules! shared_impl {
+    ($(#[$doc:meta])* $shared:ident, $ptr:ident) => {
+        $(#[$doc])*
+        pub struct $shared<T>(pub $ptr<T>);
+
+        impl<T> $shared<T> {
+            /// Allocates `value` in a new shared pointer.
+            pub fn new(value: T) -> Self {
+                $shared($ptr::new(value))
+            }
+
+            /// Unwraps the underlying pointer.
+            pub fn into_inner(self) -> $ptr<T> {
+                self.0
+            }
+        }
+
+        impl<T> Clone for $shared<T> {
+            fn clone(&self) -> Self {
+                $shared(self.0.clone())
+            }
+        }
+
+        impl<T> Debug for $shared<T>
+        where
+            T: Debug,
+        {
+            fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                Debug::fmt(&self.0, formatter)
+            }
+        }
+
+        impl<T> PartialEq for $shared<T>
+        where
+            T: PartialEq,
+        {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl<T> ops::Deref for $shared<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> From<$ptr<T>> for $shared<T> {
+            fn from(ptr: $ptr<T>) -> Self {
+                $shared(ptr)
+            }
+        }
+
+        impl<T> Serialize for $shared<T>
+        where
+            T: Serialize,
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let address = &*self.0 as *const T as usize;
+                serialize_shared(address, &*self.0, serializer)
+            }
+        }
+
+        impl<'de, T> Deserialize<'de> for $shared<T>
+        where
+            T: Deserialize<'de> + 'static,
+        {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserialize_shared(deserializer, $shared::new)
+            }
+        }
+    }
+}
+
+shared_impl! {
+    /// An `Rc<T>` that is serialized only once no matter how often it is
+    /// referenced.
+    ///
+    /// See the [module documentation](index.html).
+    SharedRc, Rc
+}
+
+shared_impl! {
+    /// An `Arc<T>` that is serialized only once no matter how often it is
+    /// referenced.
+    ///
+    /// See the [module documentation](index.html).
+    SharedArc, Arc
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const VARIANTS: &'static [&'static str] = &["Def", "Ref"];
+const FIELDS: &'static [&'static str] = &["id", "value"];
+
+const OUTSIDE_SERIALIZE: &'static str =
+    "shared pointers must be serialized inside of serde::shared::serialize";
+const OUTSIDE_DESERIALIZE: &'static str =
+    "shared pointers must be deserialized inside of serde::shared::deserialize";
+
+fn serialize_shared<T, S>(address: usize, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let entry = SERIALIZE_IDS.with(
+        |ids| match *ids.borrow_mut() {
+            Some(ref mut ids) => {
+                if let Some(&(id, complete)) = ids.get(&address) {
+                    Some((id, false, complete))
+                } else {
+                    let id = ids.len() as u64;
+                    ids.insert(address, (id, false));
+                    Some((id, true, false))
+                }
+            }
+            None => None,
+        },
+    );
+
+    match entry {
+        Some((id, true, _)) => {
+            let mut state = try!(serializer.serialize_struct_variant("Shared", 0, "Def", 2));
+            try!(state.serialize_field("id", &id));
+            try!(state.serialize_field("value", value));
+            SERIALIZE_IDS.with(
+                |ids| if let Some(ref mut ids) = *ids.borrow_mut() {
+                    ids.insert(address, (id, true));
+                },
+            );
+            state.end()
+        }
+        Some((id, false, true)) => serializer.serialize_newtype_variant("Shared", 1, "Ref", &id),
+        Some((_, false, false)) => Err(ser::Error::custom(CYCLE)),
+        None => Err(ser::Error::custom(OUTSIDE_SERIALIZE)),
+    }
+}
+
+fn deserialize_shared<'de, T, P, D>(deserializer: D, new: fn(T) -> P) -> Result<P, D::Error>
+where
+    T: Deserialize<'de>,
+    P: Clone + 'static,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_enum(
+        "Shared",
+        VARIANTS,
+        SharedVisitor {
+            new: new,
+            marker: PhantomData,
+        },
+    )
+}
+
+fn define<P, E>(id: u64, ptr: P) -> Result<P, E>
+where
+    P: Clone + 'static,
+    E: de::Error,
+{
+    DESERIALIZE_IDS.with(
+        |ids| match *ids.borrow_mut() {
+            Some(ref mut ids) => {
+                if ids.contains_key(&id) {
+                    return Err(E::custom(format_args!("duplicate shared id {}", id)));
+                }
+                ids.insert(id, Box::new(ptr.clone()));
+                Ok(ptr)
+            }
+            None => Err(E::custom(OUTSIDE_DESERIALIZE)),
+        },
+    )
+}
+
+fn resolve<P, E>(id: u64) -> Result<P, E>
+where
+    P: Clone + 'static,
+    E: de::Error,
+{
+    DESERIALIZE_IDS.with(
+        |ids| match *ids.borrow() {
+            Some(ref ids) => {
+                match ids.get(&id) {
+                    Some(ptr) => {
+                        match ptr.downcast_ref::<P>() {
+                            Some(ptr) => Ok(ptr.clone()),
+                            None => {
+                                Err(E::custom(format_args!("shared id {} refers to a different type", id)))
+                            }
+                        }
+                    }
+                    None => Err(E::custom(format_args!("unknown shared id {}", id))),
+                }
+            }
+            None => Err(E::custom(OUTSIDE_DESERIALIZE)),
+        },
+    )
+}
+
+struct SharedVisitor<T, P> {
+    new: fn(T) -> P,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, P> Visitor<'de> for SharedVisitor<T, P>
+where
+    T: Deserialize<'de>,
+    P: Clone + 'static,
+{
+    type Value = P;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a shared pointer")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<P, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        match try!(data.variant()) {
+            (SharedVariant::Def, variant) => {
+                let visitor = DefVisitor {
+                    new: self.new,
+                    marker: PhantomData,
+                };
+                variant.struct_variant(FIELDS, visitor)
+            }
+            (SharedVariant::Ref, variant) => resolve(try!(variant.newtype_variant())),
+        }
+    }
+}
+
+struct DefVisitor<T, P> {
+    new: fn(T) -> P,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, P> Visitor<'de> for DefVisitor<T, P>
+where
+    T: Deserialize<'de>,
+    P: Clone + 'static,
+{
+    type Value = P;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct variant Shared::Def")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<P, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let id = match try!(seq.next_element()) {
+            Some(id) => id,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let value = match try!(seq.next_element()) {
+            Some(value) => value,
+            None => return Err(de::Error::invalid_length(1, &self)),
+        };
+        define(id, (self.new)(value))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<P, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut value = None;
+        while let Some(key) = try!(map.next_key()) {
+            match key {
+                DefField::Id => {
+                    if id.is_some() {
+                        return Err(de::Error::duplicate_field("id"));
+                    }
+                    id = Some(try!(map.next_value()));
+                }
+                DefField::Value => {
+                    if value.is_some() {
+                        return Err(de::Error::duplicate_field("value"));
+                    }
+                    value = Some(try!(map.next_value()));
+                }
+            }
+        }
+        let id = match id {
+            Some(id) => id,
+            None => return Err(de::Error::missing_field("id")),
+        };
+        let value = match value {
+            Some(value) => value,
+            None => return Err(de::Error::missing_field("value")),
+        };
+        define(id, (self.new)(value))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+macro_rules! identifier {
+    ($name:ident, $expecting:expr, $names:ident, $unknown:ident, $($variant:ident = $index:expr, $str:expr,)*) => {
+        enum $name {
+            $($variant,)*
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct IdentifierVisitor;
+
+                impl<'de> Visitor<'de> for IdentifierVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str($expecting)
+                    }
+
+                    fn visit_u64<E>(self, value: u64) -> Result<$name, E>
+                    where
+                        E: de::Error,
+                    {
+                        match value {
+                            $($index => Ok($name::$variant),)*
+                            _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)),
+                        }
+                    }
+
+                    fn visit_str<E>(self, value: &str) -> Result<$name, E>
+                    where
+                        E: de::Error,
+                    {
+                        match value {
+                            $($str => Ok($name::$variant),)*
+                            _ => Err(de::Error::$unknown(value, $names)),
+                        }
+                    }
+
+                    fn visit_bytes<E>(self, value: &[u8]) -> Result<$name, E>
+                    where
+                        E: de::Error,
+                    {
+                        match str::from_utf8(value) {
+                            Ok(value) => self.visit_str(value),
+                            Err(_) => Err(de::Error::invalid_value(de::Unexpected::Bytes(value), &self)),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_identifier(IdentifierVisitor)
+            }
+        }
+    }
+}
+
+identifier! {
+    SharedVariant, "`Def` or `Ref`", VARIANTS, unknown_variant,
+    Def = 0, "Def",
+    Ref = 1, "Ref",
+}
+
+identifier! {
+    DefField, "`id` or `value`", FIELDS, unknown_field,
+    Id = 0, "id",
+    Value = 1, "value",
+}
@@ -0,0 +1,1028 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Measure the encoded length of a value without encoding it.
+//!
+//! [`SizeSerializer`] walks a value like any other `Serializer` but writes
+//! nothing. Instead it asks a [`CostModel`] how many bytes each event of the
+//! Serde data model takes in some format and adds up the answers. A format
+//! crate supplies the cost model for its encoding, after which the total can
+//! be used to allocate an output buffer of exactly the right size or to
+//! reject values that would be too large before any work is done.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use serde::size::{self, CostModel};
+//!
+//! // Fixed-width integers and floats, and a u64 length prefix in front of
+//! // every string, byte array, sequence and map. Tuples have a fixed length
+//! // so they need no prefix.
+//! struct Fixed;
+//!
+//! impl CostModel for Fixed {
+//!     fn bool(&mut self, _: bool) -> usize { 1 }
+//!     fn i8(&mut self, _: i8) -> usize { 1 }
+//!     fn i16(&mut self, _: i16) -> usize { 2 }
+//!     fn i32(&mut self, _: i32) -> usize { 4 }
+//!     fn i64(&mut self, _: i64) -> usize { 8 }
+//!     fn u8(&mut self, _: u8) -> usize { 1 }
+//!     fn u16(&mut self, _: u16) -> usize { 2 }
+//!     fn u32(&mut self, _: u32) -> usize { 4 }
+//!     fn u64(&mut self, _: u64) -> usize { 8 }
+//!     fn f32(&mut self, _: f32) -> usize { 4 }
+//!     fn f64(&mut self, _: f64) -> usize { 8 }
+//!     fn char(&mut self, v: char) -> usize { 8 + v.len_utf8() }
+//!     fn str(&mut self, v: &str) -> usize { 8 + v.len() }
+//!     fn bytes(&mut self, v: &[u8]) -> usize { 8 + v.len() }
+//!     fn none(&mut self) -> usize { 1 }
+//!     fn some(&mut self) -> usize { 1 }
+//!     fn unit(&mut self) -> usize { 0 }
+//!     fn variant(&mut self, _: &str, _: u32, _: &str) -> usize { 4 }
+//!     fn seq(&mut self, _: Option<usize>) -> usize { 8 }
+//!     fn tuple(&mut self, _: usize) -> usize { 0 }
+//!     fn map(&mut self, _: Option<usize>) -> usize { 8 }
+//! }
+//!
+//! # fn main() {
+//! let value = (7u32, "abc", vec![1u16, 2, 3]);
+//! assert_eq!(size::measure(&value, Fixed).unwrap(), 4 + (8 + 3) + (8 + 3 * 2));
+//! # }
+//! ```
+//!
+//! Hooks for the less common events have default implementations in terms
+//! of the required ones, following the way most formats represent them: a
+//! struct costs the same as a map whose keys are the field names, a tuple the
+//! same as a sequence, and a newtype struct the same as the value it wraps.
+//! Formats that represent any of these differently override the hook.
+//!
+//! The measured size is only as accurate as the cost model and only
+//! meaningful if the `Serialize` impl makes the same calls on every run.
+//! Values serialized through `collect_str` are formatted into a temporary
+//! string so that the cost model can see their content.
+//!
+//! [`MaxSize`] answers the same question for every value of a type at once,
+//! which is useful for sizing fixed buffers up front. `#[derive(Serialize)]`
+//! implements it for a type with the `#[serde(max_size)]` attribute, from the
+//! same calls the derived `Serialize` impl makes.
+//!
+//! [`MaxSize`]: trait.MaxSize.html
+//! [`SizeSerializer`]: struct.SizeSerializer.html
+//! [`CostModel`]: trait.CostModel.html
+
+use lib::*;
+
+use de::value::Error;
+use private::ser::{size_add, size_max};
+use ser::{self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+          SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+
+/// Returns the number of bytes `value` takes according to `model`.
+pub fn measure<C, T: ?Sized>(value: &T, model: C) -> Result<usize, Error>
+where
+    C: CostModel,
+    T: Serialize,
+{
+    let mut serializer = SizeSerializer::new(model);
+    try!(value.serialize(&mut serializer));
+    Ok(serializer.size())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// The number of bytes each event of the Serde data model takes in a format.
+///
+/// Every method returns the cost of one event, not counting the cost of any
+/// values nested inside it, which are reported separately. Where an event
+/// has both an opening and a closing part in the output, such as the brackets
+/// around a JSON array, the method returns the cost of both.
+pub trait CostModel {
+    /// Whether the format is human-readable. See
+    /// [`Serializer::is_human_readable`].
+    ///
+    /// [`Serializer::is_human_readable`]: ../ser/trait.Serializer.html#method.is_human_readable
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    /// The cost of a `bool`.
+    fn bool(&mut self, v: bool) -> usize;
+
+    /// The cost of an `i8`. Defaults to the cost of the same value as an
+    /// `i64`.
+    fn i8(&mut self, v: i8) -> usize {
+        self.i64(v as i64)
+    }
+
+    /// The cost of an `i16`. Defaults to the cost of the same value as an
+    /// `i64`.
+    fn i16(&mut self, v: i16) -> usize {
+        self.i64(v as i64)
+    }
+
+    /// The cost of an `i32`. Defaults to the cost of the same value as an
+    /// `i64`.
+    fn i32(&mut self, v: i32) -> usize {
+        self.i64(v as i64)
+    }
+
+    /// The cost of an `i64`.
+    fn i64(&mut self, v: i64) -> usize;
+
+    /// The cost of a `u8`. Defaults to the cost of the same value as a `u64`.
+    fn u8(&mut self, v: u8) -> usize {
+        self.u64(v as u64)
+    }
+
+    /// The cost of a `u16`. Defaults to the cost of the same value as a
+    /// `u64`.
+    fn u16(&mut self, v: u16) -> usize {
+        self.u64(v as u64)
+    }
+
+    /// The cost of a `u32`. Defaults to the cost of the same value as a
+    /// `u64`.
+    fn u32(&mut self, v: u32) -> usize {
+        self.u64(v as u64)
+    }
+
+    /// The cost of a `u64`.
+    fn u64(&mut self, v: u64) -> usize;
+
+    /// The cost of an `i128`, or `None` if the format does not support
+    /// 128-bit integers. Defaults to `None`, like
+    /// `Serializer::serialize_i128`.
+    #[cfg(feature = "integer128")]
+    fn i128(&mut self, v: i128) -> Option<usize> {
+        let _ = v;
+        None
+    }
+
+    /// The cost of a `u128`, or `None` if the format does not support
+    /// 128-bit integers. Defaults to `None`, like
+    /// `Serializer::serialize_u128`.
+    #[cfg(feature = "integer128")]
+    fn u128(&mut self, v: u128) -> Option<usize> {
+        let _ = v;
+        None
+    }
+
+    /// The cost of an `f32`. Defaults to the cost of the same value as an
+    /// `f64`.
+    fn f32(&mut self, v: f32) -> usize {
+        self.f64(v as f64)
+    }
+
+    /// The cost of an `f64`.
+    fn f64(&mut self, v: f64) -> usize;
+
+    /// The cost of a `char`.
+    fn char(&mut self, v: char) -> usize;
+
+    /// The cost of a string.
+    fn str(&mut self, v: &str) -> usize;
+
+    /// The cost of a byte array.
+    fn bytes(&mut self, v: &[u8]) -> usize;
+
+    /// The cost of `None`.
+    fn none(&mut self) -> usize;
+
+    /// The cost of `Some`, not counting the value inside. Defaults to zero.
+    fn some(&mut self) -> usize {
+        0
+    }
+
+    /// The cost of `()`.
+    fn unit(&mut self) -> usize;
+
+    /// The cost of a unit struct. Defaults to the cost of `()`.
+    fn unit_struct(&mut self, name: &'static str) -> usize {
+        let _ = name;
+        self.unit()
+    }
+
+    /// The cost of identifying an enum variant. This is the whole cost of a
+    /// unit variant, and the cost of a newtype, tuple or struct variant in
+    /// addition to that of its content.
+    fn variant(&mut self, name: &'static str, variant_index: u32, variant: &'static str) -> usize;
+
+    /// The cost of a newtype struct, not counting the value inside. Defaults
+    /// to zero.
+    fn newtype_struct(&mut self, name: &'static str) -> usize {
+        let _ = name;
+        0
+    }
+
+    /// The cost of a sequence, not counting its elements or their
+    /// separators. `len` is the length hint passed to `serialize_seq`.
+    fn seq(&mut self, len: Option<usize>) -> usize;
+
+    /// The cost of the separator in front of the element at `index` of a
+    /// sequence, tuple or tuple variant. Defaults to zero.
+    fn seq_element(&mut self, index: usize) -> usize {
+        let _ = index;
+        0
+    }
+
+    /// The cost of a tuple, not counting its elements. Defaults to the cost
+    /// of a sequence of known length.
+    fn tuple(&mut self, len: usize) -> usize {
+        self.seq(Some(len))
+    }
+
+    /// The cost of a tuple struct, not counting its fields. Defaults to the
+    /// cost of a tuple.
+    fn tuple_struct(&mut self, name: &'static str, len: usize) -> usize {
+        let _ = name;
+        self.tuple(len)
+    }
+
+    /// The cost of a map, not counting its entries or their separators.
+    /// `len` is the length hint passed to `serialize_map`.
+    fn map(&mut self, len: Option<usize>) -> usize;
+
+    /// The cost of the separators around the entry at `index` of a map or
+    /// struct, such as a comma before the key and a colon between the key and
+    /// the value. Defaults to zero.
+    fn map_entry(&mut self, index: usize) -> usize {
+        let _ = index;
+        0
+    }
+
+    /// The cost of a struct, not counting its fields. Defaults to the cost of
+    /// a map of known length.
+    fn struct_(&mut self, name: &'static str, len: usize) -> usize {
+        let _ = name;
+        self.map(Some(len))
+    }
+
+    /// The cost of the field named `key` at `index` of a struct or struct
+    /// variant, not counting its value. Defaults to the cost of a map entry
+    /// whose key is the field name as a string.
+    fn field(&mut self, index: usize, key: &'static str) -> usize {
+        self.map_entry(index).saturating_add(self.str(key))
+    }
+
+    /// The largest cost of any `bool`, used by [`MaxSize`]. Defaults to the
+    /// larger cost of `true` and `false`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_bool(&mut self) -> Option<usize> {
+        Some(cmp::max(self.bool(false), self.bool(true)))
+    }
+
+    /// The largest cost of any `i8`, used by [`MaxSize`]. Defaults to the
+    /// largest cost of all 256 values.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_i8(&mut self) -> Option<usize> {
+        (i8::MIN..i8::MAX).chain(Some(i8::MAX)).map(|v| self.i8(v)).max()
+    }
+
+    /// The largest cost of any `i16`, used by [`MaxSize`]. Defaults to the
+    /// largest cost of all 65536 values.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_i16(&mut self) -> Option<usize> {
+        (i16::MIN..i16::MAX).chain(Some(i16::MAX)).map(|v| self.i16(v)).max()
+    }
+
+    /// The largest cost of any `i32`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, because there are too many values to try.
+    /// Formats that encode integers in a fixed or bounded width should
+    /// override this and the methods for the other wide integers.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_i32(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `i64`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_i32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_i64(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `u8`, used by [`MaxSize`]. Defaults to the
+    /// largest cost of all 256 values.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_u8(&mut self) -> Option<usize> {
+        (u8::MIN..u8::MAX).chain(Some(u8::MAX)).map(|v| self.u8(v)).max()
+    }
+
+    /// The largest cost of any `u16`, used by [`MaxSize`]. Defaults to the
+    /// largest cost of all 65536 values.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_u16(&mut self) -> Option<usize> {
+        (u16::MIN..u16::MAX).chain(Some(u16::MAX)).map(|v| self.u16(v)).max()
+    }
+
+    /// The largest cost of any `u32`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_i32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_u32(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `u64`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_i32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_u64(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `i128`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_i32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    #[cfg(feature = "integer128")]
+    fn max_i128(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `u128`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_i32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    #[cfg(feature = "integer128")]
+    fn max_u128(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `f32`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, because the cost of a float in a text format
+    /// varies too much to find by sampling. Formats that encode floats in a
+    /// fixed width should override this.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_f32(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `f64`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_f32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_f64(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// The largest cost of any `char`, used by [`MaxSize`]. Defaults to
+    /// `None`, meaning unknown, for the same reason as `max_f32`.
+    ///
+    /// [`MaxSize`]: trait.MaxSize.html
+    fn max_char(&mut self) -> Option<usize> {
+        None
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A type with a largest serialized size that is known without a value.
+///
+/// `#[derive(Serialize)]` implements this trait for a type with the
+/// `#[serde(max_size)]` attribute. The bound is built from the calls the
+/// derived `Serialize` impl makes, so it follows `rename`, skipped fields and
+/// the representation of enums. Every serialized field type must implement
+/// `MaxSize` too. Strings, byte arrays, sequences and maps have no upper
+/// bound, and neither do fields with `serialize_with`, `with_readable` or
+/// `with_compact`, whose encoding the derive cannot see. A type that contains
+/// itself, for example through a `Box`, has no finite bound and must not use
+/// the attribute because computing the bound would never end.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+///
+/// extern crate serde;
+///
+/// use serde::size::{CostModel, MaxSize};
+///
+/// struct Fixed;
+///
+/// impl CostModel for Fixed {
+///     fn bool(&mut self, _: bool) -> usize { 1 }
+///     fn i64(&mut self, _: i64) -> usize { 8 }
+///     fn u8(&mut self, _: u8) -> usize { 1 }
+///     fn u64(&mut self, _: u64) -> usize { 8 }
+///     fn f64(&mut self, _: f64) -> usize { 8 }
+///     fn max_f64(&mut self) -> Option<usize> { Some(8) }
+///     fn char(&mut self, _: char) -> usize { 4 }
+///     fn str(&mut self, v: &str) -> usize { 8 + v.len() }
+///     fn bytes(&mut self, v: &[u8]) -> usize { 8 + v.len() }
+///     fn none(&mut self) -> usize { 1 }
+///     fn some(&mut self) -> usize { 1 }
+///     fn unit(&mut self) -> usize { 0 }
+///     fn variant(&mut self, _: &str, _: u32, _: &str) -> usize { 4 }
+///     fn seq(&mut self, _: Option<usize>) -> usize { 8 }
+///     fn tuple(&mut self, _: usize) -> usize { 0 }
+///     fn map(&mut self, _: Option<usize>) -> usize { 8 }
+///     fn struct_(&mut self, _: &str, _: usize) -> usize { 0 }
+///     fn field(&mut self, _: usize, _: &str) -> usize { 0 }
+/// }
+///
+/// #[derive(Serialize)]
+/// #[serde(max_size)]
+/// enum Message {
+///     Ping,
+///     Move { x: f64, y: f64, fast: Option<bool> },
+/// }
+///
+/// #[derive(Serialize)]
+/// #[serde(max_size)]
+/// struct Named {
+///     message: Message,
+///     name: String,
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Message::max_serialized_size(&mut Fixed), Some(4 + 8 + 8 + 2));
+/// assert_eq!(Named::max_serialized_size(&mut Fixed), None);
+///
+/// let ping = Message::Ping;
+/// assert_eq!(ping.serialized_size(Fixed).unwrap(), 4);
+/// # }
+/// ```
+pub trait MaxSize {
+    /// Returns the largest number of bytes any value of this type takes
+    /// according to `model`, or `None` if there is no upper bound or it does
+    /// not fit in a `usize`.
+    fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+    where
+        C: CostModel;
+
+    /// Returns the number of bytes this value takes according to `model`.
+    /// This is the same as [`measure`].
+    ///
+    /// [`measure`]: fn.measure.html
+    fn serialized_size<C>(&self, model: C) -> Result<usize, Error>
+    where
+        C: CostModel,
+        Self: Serialize,
+    {
+        measure(self, model)
+    }
+}
+
+macro_rules! primitive_max_size {
+    ($ty:ident, $method:ident) => {
+        impl MaxSize for $ty {
+            fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+            where
+                C: CostModel,
+            {
+                model.$method()
+            }
+        }
+    };
+}
+
+primitive_max_size!(bool, max_bool);
+primitive_max_size!(i8, max_i8);
+primitive_max_size!(i16, max_i16);
+primitive_max_size!(i32, max_i32);
+primitive_max_size!(i64, max_i64);
+primitive_max_size!(u8, max_u8);
+primitive_max_size!(u16, max_u16);
+primitive_max_size!(u32, max_u32);
+primitive_max_size!(u64, max_u64);
+primitive_max_size!(f32, max_f32);
+primitive_max_size!(f64, max_f64);
+primitive_max_size!(char, max_char);
+
+#[cfg(feature = "integer128")]
+primitive_max_size!(i128, max_i128);
+#[cfg(feature = "integer128")]
+primitive_max_size!(u128, max_u128);
+
+impl MaxSize for () {
+    fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+    where
+        C: CostModel,
+    {
+        Some(model.unit())
+    }
+}
+
+impl<T> MaxSize for PhantomData<T> {
+    fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+    where
+        C: CostModel,
+    {
+        Some(model.unit_struct("PhantomData"))
+    }
+}
+
+impl<T> MaxSize for Option<T>
+where
+    T: MaxSize,
+{
+    fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+    where
+        C: CostModel,
+    {
+        let none = Some(model.none());
+        let some = size_add(Some(model.some()), T::max_serialized_size(model));
+        size_max(none, some)
+    }
+}
+
+macro_rules! unbounded_max_size {
+    ($($desc:tt)+) => {
+        impl $($desc)+ {
+            fn max_serialized_size<C>(_model: &mut C) -> Option<usize>
+            where
+                C: CostModel,
+            {
+                None
+            }
+        }
+    };
+}
+
+unbounded_max_size!(MaxSize for str);
+unbounded_max_size!(MaxSize for String);
+unbounded_max_size!(<T> MaxSize for [T]);
+unbounded_max_size!(<T> MaxSize for Vec<T>);
+unbounded_max_size!(<T> MaxSize for VecDeque<T>);
+unbounded_max_size!(<T> MaxSize for LinkedList<T>);
+unbounded_max_size!(<T> MaxSize for BinaryHeap<T>);
+unbounded_max_size!(<T> MaxSize for BTreeSet<T>);
+unbounded_max_size!(<K, V> MaxSize for BTreeMap<K, V>);
+#[cfg(feature = "std")]
+unbounded_max_size!(<T, H> MaxSize for HashSet<T, H>);
+#[cfg(feature = "std")]
+unbounded_max_size!(<K, V, H> MaxSize for HashMap<K, V, H>);
+
+macro_rules! deref_max_size {
+    ($($desc:tt)+) => {
+        impl $($desc)+ {
+            fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+            where
+                C: CostModel,
+            {
+                T::max_serialized_size(model)
+            }
+        }
+    };
+}
+
+deref_max_size!(<'a, T: ?Sized> MaxSize for &'a T where T: MaxSize);
+deref_max_size!(<'a, T: ?Sized> MaxSize for &'a mut T where T: MaxSize);
+deref_max_size!(<T: ?Sized> MaxSize for Box<T> where T: MaxSize);
+deref_max_size!(<'a, T: ?Sized> MaxSize for Cow<'a, T> where T: MaxSize + ToOwned);
+deref_max_size!(<T> MaxSize for Cell<T> where T: MaxSize + Copy);
+
+impl<T> MaxSize for [T; 0] {
+    fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+    where
+        C: CostModel,
+    {
+        Some(model.tuple(0))
+    }
+}
+
+macro_rules! array_max_size {
+    ($($len:tt)+) => {
+        $(
+            impl<T> MaxSize for [T; $len]
+            where
+                T: MaxSize,
+            {
+                fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+                where
+                    C: CostModel,
+                {
+                    let mut size = Some(model.tuple($len));
+                    for index in 0..$len {
+                        size = size_add(size, Some(model.seq_element(index)));
+                        size = size_add(size, T::max_serialized_size(model));
+                    }
+                    size
+                }
+            }
+        )+
+    }
+}
+
+array_max_size!(01 02 03 04 05 06 07 08 09 10
+                11 12 13 14 15 16 17 18 19 20
+                21 22 23 24 25 26 27 28 29 30
+                31 32);
+
+macro_rules! tuple_max_size {
+    ($($len:expr => ($($n:tt $name:ident)+))+) => {
+        $(
+            impl<$($name),+> MaxSize for ($($name,)+)
+            where
+                $($name: MaxSize,)+
+            {
+                fn max_serialized_size<C>(model: &mut C) -> Option<usize>
+                where
+                    C: CostModel,
+                {
+                    let mut size = Some(model.tuple($len));
+                    $(
+                        size = size_add(size, Some(model.seq_element($n)));
+                        size = size_add(size, $name::max_serialized_size(model));
+                    )+
+                    size
+                }
+            }
+        )+
+    }
+}
+
+tuple_max_size! {
+    1 => (0 T0)
+    2 => (0 T0 1 T1)
+    3 => (0 T0 1 T1 2 T2)
+    4 => (0 T0 1 T1 2 T2 3 T3)
+    5 => (0 T0 1 T1 2 T2 3 T3 4 T4)
+    6 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5)
+    7 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6)
+    8 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7)
+    9 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8)
+    10 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9)
+    11 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10)
+    12 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11)
+    13 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12)
+    14 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13)
+    15 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13 14 T14)
+    16 => (0 T0 1 T1 2 T2 3 T3 4 T4 5 T5 6 T6 7 T7 8 T8 9 T9 10 T10 11 T11 12 T12 13 T13 14 T14 15 T15)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Serializer` that adds up the cost of a value according to a
+/// [`CostModel`].
+///
+/// [`CostModel`]: trait.CostModel.html
+pub struct SizeSerializer<C> {
+    model: C,
+    size: usize,
+}
+
+impl<C> SizeSerializer<C>
+where
+    C: CostModel,
+{
+    /// Creates a serializer measuring with `model`, starting from zero.
+    pub fn new(model: C) -> Self {
+        SizeSerializer {
+            model: model,
+            size: 0,
+        }
+    }
+
+    /// Returns the total size of everything serialized so far.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the cost model.
+    pub fn into_inner(self) -> C {
+        self.model
+    }
+
+    fn add(&mut self, cost: usize) -> Result<(), Error> {
+        match self.size.checked_add(cost) {
+            Some(size) => {
+                self.size = size;
+                Ok(())
+            }
+            None => Err(ser::Error::custom("size overflows usize")),
+        }
+    }
+}
+
+impl<'a, C> ser::Serializer for &'a mut SizeSerializer<C>
+where
+    C: CostModel,
+{
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, C>;
+    type SerializeTuple = Compound<'a, C>;
+    type SerializeTupleStruct = Compound<'a, C>;
+    type SerializeTupleVariant = Compound<'a, C>;
+    type SerializeMap = Compound<'a, C>;
+    type SerializeStruct = Compound<'a, C>;
+    type SerializeStructVariant = Compound<'a, C>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        let cost = self.model.bool(v);
+        self.add(cost)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        let cost = self.model.i8(v);
+        self.add(cost)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        let cost = self.model.i16(v);
+        self.add(cost)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        let cost = self.model.i32(v);
+        self.add(cost)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        let cost = self.model.i64(v);
+        self.add(cost)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        let cost = self.model.u8(v);
+        self.add(cost)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        let cost = self.model.u16(v);
+        self.add(cost)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        let cost = self.model.u32(v);
+        self.add(cost)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        let cost = self.model.u64(v);
+        self.add(cost)
+    }
+
+    #[cfg(feature = "integer128")]
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        match self.model.i128(v) {
+            Some(cost) => self.add(cost),
+            None => Err(ser::Error::custom("i128 is not supported")),
+        }
+    }
+
+    #[cfg(feature = "integer128")]
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        match self.model.u128(v) {
+            Some(cost) => self.add(cost),
+            None => Err(ser::Error::custom("u128 is not supported")),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        let cost = self.model.f32(v);
+        self.add(cost)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        let cost = self.model.f64(v);
+        self.add(cost)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        let cost = self.model.char(v);
+        self.add(cost)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        let cost = self.model.str(v);
+        self.add(cost)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        let cost = self.model.bytes(v);
+        self.add(cost)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        let cost = self.model.none();
+        self.add(cost)
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.model.some();
+        try!(self.add(cost));
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        let cost = self.model.unit();
+        self.add(cost)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        let cost = self.model.unit_struct(name);
+        self.add(cost)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        let cost = self.model.variant(name, variant_index, variant);
+        self.add(cost)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.model.newtype_struct(name);
+        try!(self.add(cost));
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.model.variant(name, variant_index, variant);
+        try!(self.add(cost));
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.seq(len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.tuple(len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.tuple_struct(name, len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.variant(name, variant_index, variant);
+        try!(self.add(cost));
+        let cost = self.model.tuple(len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.map(len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.struct_(name, len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, C>, Error> {
+        let cost = self.model.variant(name, variant_index, variant);
+        try!(self.add(cost));
+        let cost = self.model.struct_(name, len);
+        try!(self.add(cost));
+        Ok(Compound::new(self))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.model.is_human_readable()
+    }
+}
+
+/// Measures the elements, entries or fields of a compound value.
+pub struct Compound<'a, C: 'a> {
+    serializer: &'a mut SizeSerializer<C>,
+    index: usize,
+}
+
+impl<'a, C> Compound<'a, C>
+where
+    C: CostModel,
+{
+    fn new(serializer: &'a mut SizeSerializer<C>) -> Self {
+        Compound {
+            serializer: serializer,
+            index: 0,
+        }
+    }
+
+    fn element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.serializer.model.seq_element(self.index);
+        self.index += 1;
+        try!(self.serializer.add(cost));
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.serializer.model.field(self.index, key);
+        self.index += 1;
+        try!(self.serializer.add(cost));
+        value.serialize(&mut *self.serializer)
+    }
+}
+
+macro_rules! size_serialize_compound {
+    ($trait:ident, $method:ident($($key:ident: $key_ty:ty)*), $helper:ident) => {
+        impl<'a, C> $trait for Compound<'a, C>
+        where
+            C: CostModel,
+        {
+            type Ok = ();
+            type Error = Error;
+
+            fn $method<T: ?Sized>(&mut self, $($key: $key_ty,)* value: &T) -> Result<(), Error>
+            where
+                T: Serialize,
+            {
+                self.$helper($($key,)* value)
+            }
+
+            fn end(self) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+    };
+}
+
+size_serialize_compound!(SerializeSeq, serialize_element(), element);
+size_serialize_compound!(SerializeTuple, serialize_element(), element);
+size_serialize_compound!(SerializeTupleStruct, serialize_field(), element);
+size_serialize_compound!(SerializeTupleVariant, serialize_field(), element);
+size_serialize_compound!(SerializeStruct, serialize_field(key: &'static str), field);
+size_serialize_compound!(SerializeStructVariant, serialize_field(key: &'static str), field);
+
+impl<'a, C> SerializeMap for Compound<'a, C>
+where
+    C: CostModel,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let cost = self.serializer.model.map_entry(self.index);
+        self.index += 1;
+        try!(self.serializer.add(cost));
+        key.serialize(&mut *self.serializer)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
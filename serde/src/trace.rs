@@ -0,0 +1,997 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Wrappers that report every event passing through a serializer or
+//! deserializer.
+//!
+//! [`TraceSerializer`] and [`TraceDeserializer`] wrap an arbitrary data format
+//! and invoke a user-supplied callback with an [`Event`] for every method
+//! called on it, along with the nesting depth and the struct, field or variant
+//! name involved. Whenever the wrapped format returns an error, an event with
+//! `failed` set is reported for each level the error propagates through. This
+//! makes it possible to tell which field of a large structure made
+//! serialization or deserialization fail without switching to a different
+//! format.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use std::cell::RefCell;
+//!
+//! use serde::Deserialize;
+//! use serde::de::IntoDeserializer;
+//! use serde::de::value::Error;
+//! use serde::trace::{Event, TraceDeserializer};
+//!
+//! # fn main() {
+//! let log = RefCell::new(Vec::new());
+//! let trace = |event: Event| log.borrow_mut().push(event.to_string());
+//!
+//! let deserializer = IntoDeserializer::<Error>::into_deserializer(1u32);
+//! let value = u8::deserialize(TraceDeserializer::new(deserializer, &trace)).unwrap();
+//!
+//! assert_eq!(value, 1);
+//! assert_eq!(*log.borrow(), ["deserialize_u8", "visit_u32"]);
+//! # }
+//! ```
+//!
+//! [`TraceSerializer`]: struct.TraceSerializer.html
+//! [`TraceDeserializer`]: struct.TraceDeserializer.html
+//! [`Event`]: struct.Event.html
+
+use lib::*;
+
+use de::{self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+         Visitor};
+use ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+          SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer};
+
+/// A method call observed by a `TraceSerializer` or `TraceDeserializer`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Event<'a> {
+    /// How many compound values enclose this event. The top-level value is at
+    /// depth 0.
+    pub depth: usize,
+    /// Name of the method, for example `"serialize_field"` or `"visit_str"`.
+    pub method: &'static str,
+    /// The type, field or variant name passed to the method, or the string
+    /// being serialized or visited, if any.
+    pub name: Option<&'a str>,
+    /// Whether this event reports that the method returned an error.
+    pub failed: bool,
+}
+
+impl<'a> Display for Event<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for _ in 0..self.depth {
+            try!(formatter.write_str("  "));
+        }
+        try!(formatter.write_str(self.method));
+        if let Some(name) = self.name {
+            try!(write!(formatter, "({:?})", name));
+        }
+        if self.failed {
+            try!(formatter.write_str(" failed"));
+        }
+        Ok(())
+    }
+}
+
+fn call<F>(trace: &F, depth: usize, method: &'static str, name: Option<&str>)
+where
+    F: Fn(Event),
+{
+    trace(
+        Event {
+            depth: depth,
+            method: method,
+            name: name,
+            failed: false,
+        },
+    );
+}
+
+fn check<F, T, E>(
+    trace: &F,
+    depth: usize,
+    method: &'static str,
+    name: Option<&str>,
+    result: Result<T, E>,
+) -> Result<T, E>
+where
+    F: Fn(Event),
+{
+    if result.is_err() {
+        trace(
+            Event {
+                depth: depth,
+                method: method,
+                name: name,
+                failed: true,
+            },
+        );
+    }
+    result
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Serializer` that reports every call to a callback before forwarding it
+/// to the wrapped serializer.
+pub struct TraceSerializer<'t, S, F: 't> {
+    serializer: S,
+    trace: &'t F,
+    depth: usize,
+}
+
+impl<'t, S, F> TraceSerializer<'t, S, F>
+where
+    S: Serializer,
+    F: Fn(Event),
+{
+    /// Wraps `serializer` so that every event is passed to `trace`.
+    pub fn new(serializer: S, trace: &'t F) -> Self {
+        TraceSerializer::nested(serializer, trace, 0)
+    }
+
+    fn nested(serializer: S, trace: &'t F, depth: usize) -> Self {
+        TraceSerializer {
+            serializer: serializer,
+            trace: trace,
+            depth: depth,
+        }
+    }
+}
+
+fn compound<'t, C, F, E>(
+    trace: &'t F,
+    depth: usize,
+    method: &'static str,
+    name: Option<&str>,
+    result: Result<C, E>,
+) -> Result<Compound<'t, C, F>, E>
+where
+    F: Fn(Event),
+{
+    check(trace, depth, method, name, result).map(
+        |compound| {
+            Compound {
+                compound: compound,
+                trace: trace,
+                depth: depth,
+            }
+        },
+    )
+}
+
+struct TraceValue<'a, 't, T: ?Sized + 'a, F: 't> {
+    value: &'a T,
+    trace: &'t F,
+    depth: usize,
+}
+
+impl<'a, 't, T: ?Sized, F> Serialize for TraceValue<'a, 't, T, F>
+where
+    T: Serialize,
+    F: Fn(Event),
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value
+            .serialize(TraceSerializer::nested(serializer, self.trace, self.depth))
+    }
+}
+
+macro_rules! trace_serialize_primitive {
+    ($($(#[$attr:meta])* $method:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                let method = stringify!($method);
+                call(self.trace, self.depth, method, None);
+                check(self.trace, self.depth, method, None, self.serializer.$method(v))
+            }
+        )*
+    }
+}
+
+impl<'t, S, F> Serializer for TraceSerializer<'t, S, F>
+where
+    S: Serializer,
+    F: Fn(Event),
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeSeq = Compound<'t, S::SerializeSeq, F>;
+    type SerializeTuple = Compound<'t, S::SerializeTuple, F>;
+    type SerializeTupleStruct = Compound<'t, S::SerializeTupleStruct, F>;
+    type SerializeTupleVariant = Compound<'t, S::SerializeTupleVariant, F>;
+    type SerializeMap = Compound<'t, S::SerializeMap, F>;
+    type SerializeStruct = Compound<'t, S::SerializeStruct, F>;
+    type SerializeStructVariant = Compound<'t, S::SerializeStructVariant, F>;
+
+    trace_serialize_primitive! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        call(self.trace, self.depth, "serialize_str", Some(v));
+        let result = self.serializer.serialize_str(v);
+        check(self.trace, self.depth, "serialize_str", Some(v), result)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        call(self.trace, self.depth, "serialize_none", None);
+        let result = self.serializer.serialize_none();
+        check(self.trace, self.depth, "serialize_none", None, result)
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        call(self.trace, self.depth, "serialize_some", None);
+        let value = TraceValue {
+            value: value,
+            trace: self.trace,
+            depth: self.depth + 1,
+        };
+        let result = self.serializer.serialize_some(&value);
+        check(self.trace, self.depth, "serialize_some", None, result)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        call(self.trace, self.depth, "serialize_unit", None);
+        let result = self.serializer.serialize_unit();
+        check(self.trace, self.depth, "serialize_unit", None, result)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        call(self.trace, self.depth, "serialize_unit_struct", Some(name));
+        let result = self.serializer.serialize_unit_struct(name);
+        check(self.trace, self.depth, "serialize_unit_struct", Some(name), result)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        call(self.trace, self.depth, "serialize_unit_variant", Some(variant));
+        let result = self.serializer
+            .serialize_unit_variant(name, variant_index, variant);
+        check(self.trace, self.depth, "serialize_unit_variant", Some(variant), result)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        call(self.trace, self.depth, "serialize_newtype_struct", Some(name));
+        let value = TraceValue {
+            value: value,
+            trace: self.trace,
+            depth: self.depth + 1,
+        };
+        let result = self.serializer.serialize_newtype_struct(name, &value);
+        check(self.trace, self.depth, "serialize_newtype_struct", Some(name), result)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        call(self.trace, self.depth, "serialize_newtype_variant", Some(variant));
+        let value = TraceValue {
+            value: value,
+            trace: self.trace,
+            depth: self.depth + 1,
+        };
+        let result = self.serializer
+            .serialize_newtype_variant(name, variant_index, variant, &value);
+        check(self.trace, self.depth, "serialize_newtype_variant", Some(variant), result)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        call(self.trace, self.depth, "serialize_seq", None);
+        let result = self.serializer.serialize_seq(len);
+        compound(self.trace, self.depth, "serialize_seq", None, result)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        call(self.trace, self.depth, "serialize_tuple", None);
+        let result = self.serializer.serialize_tuple(len);
+        compound(self.trace, self.depth, "serialize_tuple", None, result)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        call(self.trace, self.depth, "serialize_tuple_struct", Some(name));
+        let result = self.serializer.serialize_tuple_struct(name, len);
+        compound(self.trace, self.depth, "serialize_tuple_struct", Some(name), result)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        call(self.trace, self.depth, "serialize_tuple_variant", Some(variant));
+        let result = self.serializer
+            .serialize_tuple_variant(name, variant_index, variant, len);
+        compound(self.trace, self.depth, "serialize_tuple_variant", Some(variant), result)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        call(self.trace, self.depth, "serialize_map", None);
+        let result = self.serializer.serialize_map(len);
+        compound(self.trace, self.depth, "serialize_map", None, result)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        call(self.trace, self.depth, "serialize_struct", Some(name));
+        let result = self.serializer.serialize_struct(name, len);
+        compound(self.trace, self.depth, "serialize_struct", Some(name), result)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        call(self.trace, self.depth, "serialize_struct_variant", Some(variant));
+        let result = self.serializer
+            .serialize_struct_variant(name, variant_index, variant, len);
+        compound(self.trace, self.depth, "serialize_struct_variant", Some(variant), result)
+    }
+
+    fn collect_str<T: ?Sized>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+    {
+        call(self.trace, self.depth, "collect_str", None);
+        let result = self.serializer.collect_str(value);
+        check(self.trace, self.depth, "collect_str", None, result)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.serializer.is_human_readable()
+    }
+}
+
+/// The compound serializer returned by `TraceSerializer` for sequences, maps,
+/// structs and variants.
+pub struct Compound<'t, C, F: 't> {
+    compound: C,
+    trace: &'t F,
+    depth: usize,
+}
+
+macro_rules! trace_serialize_compound {
+    (
+        $trait:ident,
+        $method:ident($($key:ident: $key_ty:ty)*),
+        $name:expr
+    ) => {
+        impl<'t, C, F> $trait for Compound<'t, C, F>
+        where
+            C: $trait,
+            F: Fn(Event),
+        {
+            type Ok = C::Ok;
+            type Error = C::Error;
+
+            fn $method<T: ?Sized>(&mut self, $($key: $key_ty,)* value: &T) -> Result<(), C::Error>
+            where
+                T: Serialize,
+            {
+                let method = stringify!($method);
+                call(self.trace, self.depth + 1, method, $name);
+                let result = {
+                    let value = TraceValue {
+                        value: value,
+                        trace: self.trace,
+                        depth: self.depth + 2,
+                    };
+                    self.compound.$method($($key,)* &value)
+                };
+                check(self.trace, self.depth + 1, method, $name, result)
+            }
+
+            fn end(self) -> Result<C::Ok, C::Error> {
+                call(self.trace, self.depth, "end", None);
+                check(self.trace, self.depth, "end", None, self.compound.end())
+            }
+        }
+    };
+}
+
+trace_serialize_compound!(SerializeSeq, serialize_element(), None);
+trace_serialize_compound!(SerializeTuple, serialize_element(), None);
+trace_serialize_compound!(SerializeTupleStruct, serialize_field(), None);
+trace_serialize_compound!(SerializeTupleVariant, serialize_field(), None);
+
+impl<'t, C, F> SerializeMap for Compound<'t, C, F>
+where
+    C: SerializeMap,
+    F: Fn(Event),
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), C::Error>
+    where
+        T: Serialize,
+    {
+        call(self.trace, self.depth + 1, "serialize_key", None);
+        let result = {
+            let key = TraceValue {
+                value: key,
+                trace: self.trace,
+                depth: self.depth + 2,
+            };
+            self.compound.serialize_key(&key)
+        };
+        check(self.trace, self.depth + 1, "serialize_key", None, result)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), C::Error>
+    where
+        T: Serialize,
+    {
+        call(self.trace, self.depth + 1, "serialize_value", None);
+        let result = {
+            let value = TraceValue {
+                value: value,
+                trace: self.trace,
+                depth: self.depth + 2,
+            };
+            self.compound.serialize_value(&value)
+        };
+        check(self.trace, self.depth + 1, "serialize_value", None, result)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        call(self.trace, self.depth, "end", None);
+        check(self.trace, self.depth, "end", None, self.compound.end())
+    }
+}
+
+macro_rules! trace_serialize_struct {
+    ($trait:ident) => {
+        impl<'t, C, F> $trait for Compound<'t, C, F>
+        where
+            C: $trait,
+            F: Fn(Event),
+        {
+            type Ok = C::Ok;
+            type Error = C::Error;
+
+            fn serialize_field<T: ?Sized>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), C::Error>
+            where
+                T: Serialize,
+            {
+                call(self.trace, self.depth + 1, "serialize_field", Some(key));
+                let result = {
+                    let value = TraceValue {
+                        value: value,
+                        trace: self.trace,
+                        depth: self.depth + 2,
+                    };
+                    self.compound.serialize_field(key, &value)
+                };
+                check(self.trace, self.depth + 1, "serialize_field", Some(key), result)
+            }
+
+            fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+                call(self.trace, self.depth + 1, "skip_field", Some(key));
+                let result = self.compound.skip_field(key);
+                check(self.trace, self.depth + 1, "skip_field", Some(key), result)
+            }
+
+            fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), C::Error> {
+                call(self.trace, self.depth + 1, "annotate", Some(key));
+                let result = self.compound.annotate(key, annotation);
+                check(self.trace, self.depth + 1, "annotate", Some(key), result)
+            }
+
+            fn end(self) -> Result<C::Ok, C::Error> {
+                call(self.trace, self.depth, "end", None);
+                check(self.trace, self.depth, "end", None, self.compound.end())
+            }
+        }
+    };
+}
+
+trace_serialize_struct!(SerializeStruct);
+trace_serialize_struct!(SerializeStructVariant);
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `Deserializer` that reports every call to a callback before forwarding
+/// it to the wrapped deserializer.
+///
+/// Visitor callbacks made by the wrapped deserializer, such as `visit_str` for
+/// a map key, are reported as well.
+pub struct TraceDeserializer<'t, D, F: 't> {
+    deserializer: D,
+    trace: &'t F,
+    depth: usize,
+}
+
+impl<'de, 't, D, F> TraceDeserializer<'t, D, F>
+where
+    D: Deserializer<'de>,
+    F: Fn(Event),
+{
+    /// Wraps `deserializer` so that every event is passed to `trace`.
+    pub fn new(deserializer: D, trace: &'t F) -> Self {
+        TraceDeserializer::nested(deserializer, trace, 0)
+    }
+
+    fn nested(deserializer: D, trace: &'t F, depth: usize) -> Self {
+        TraceDeserializer {
+            deserializer: deserializer,
+            trace: trace,
+            depth: depth,
+        }
+    }
+}
+
+macro_rules! trace_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*) $name:expr;)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let (trace, depth) = (self.trace, self.depth);
+                let method = stringify!($method);
+                call(trace, depth, method, $name);
+                let visitor = TraceVisitor {
+                    visitor: visitor,
+                    trace: trace,
+                    depth: depth,
+                };
+                let result = self.deserializer.$method($($arg,)* visitor);
+                check(trace, depth, method, $name, result)
+            }
+        )*
+    }
+}
+
+impl<'de, 't, D, F> Deserializer<'de> for TraceDeserializer<'t, D, F>
+where
+    D: Deserializer<'de>,
+    F: Fn(Event),
+{
+    type Error = D::Error;
+
+    trace_deserialize! {
+        deserialize_any() None;
+        deserialize_bool() None;
+        deserialize_i8() None;
+        deserialize_i16() None;
+        deserialize_i32() None;
+        deserialize_i64() None;
+        deserialize_u8() None;
+        deserialize_u16() None;
+        deserialize_u32() None;
+        deserialize_u64() None;
+        deserialize_f32() None;
+        deserialize_f64() None;
+        deserialize_char() None;
+        deserialize_str() None;
+        deserialize_string() None;
+        deserialize_bytes() None;
+        deserialize_byte_buf() None;
+        deserialize_option() None;
+        deserialize_unit() None;
+        deserialize_unit_struct(name: &'static str) Some(name);
+        deserialize_newtype_struct(name: &'static str) Some(name);
+        deserialize_seq() None;
+        deserialize_tuple(len: usize) None;
+        deserialize_tuple_struct(name: &'static str, len: usize) Some(name);
+        deserialize_map() None;
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]) Some(name);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]) Some(name);
+        deserialize_identifier() None;
+        deserialize_ignored_any() None;
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+struct TraceVisitor<'t, V, F: 't> {
+    visitor: V,
+    trace: &'t F,
+    depth: usize,
+}
+
+macro_rules! trace_visit_primitive {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                let method = stringify!($method);
+                call(self.trace, self.depth, method, None);
+                check(self.trace, self.depth, method, None, self.visitor.$method(v))
+            }
+        )*
+    }
+}
+
+impl<'de, 't, V, F> Visitor<'de> for TraceVisitor<'t, V, F>
+where
+    V: Visitor<'de>,
+    F: Fn(Event),
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    trace_visit_primitive! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_bytes(&[u8]),
+        visit_borrowed_bytes(&'de [u8]),
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    trace_visit_primitive! {
+        visit_byte_buf(Vec<u8>),
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        call(self.trace, self.depth, "visit_str", Some(v));
+        check(self.trace, self.depth, "visit_str", Some(v), self.visitor.visit_str(v))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        call(self.trace, self.depth, "visit_borrowed_str", Some(v));
+        let result = self.visitor.visit_borrowed_str(v);
+        check(self.trace, self.depth, "visit_borrowed_str", Some(v), result)
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn visit_string<E>(self, v: String) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_string", Some(&v));
+        check(trace, depth, "visit_string", None, self.visitor.visit_string(v))
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        call(self.trace, self.depth, "visit_none", None);
+        check(self.trace, self.depth, "visit_none", None, self.visitor.visit_none())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_some", None);
+        let deserializer = TraceDeserializer::nested(deserializer, trace, depth + 1);
+        check(trace, depth, "visit_some", None, self.visitor.visit_some(deserializer))
+    }
+
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        call(self.trace, self.depth, "visit_unit", None);
+        check(self.trace, self.depth, "visit_unit", None, self.visitor.visit_unit())
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_newtype_struct", None);
+        let deserializer = TraceDeserializer::nested(deserializer, trace, depth + 1);
+        let result = self.visitor.visit_newtype_struct(deserializer);
+        check(trace, depth, "visit_newtype_struct", None, result)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_seq", None);
+        let seq = TraceAccess {
+            access: seq,
+            trace: trace,
+            depth: depth + 1,
+        };
+        check(trace, depth, "visit_seq", None, self.visitor.visit_seq(seq))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_map", None);
+        let map = TraceAccess {
+            access: map,
+            trace: trace,
+            depth: depth + 1,
+        };
+        check(trace, depth, "visit_map", None, self.visitor.visit_map(map))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "visit_enum", None);
+        let data = TraceAccess {
+            access: data,
+            trace: trace,
+            depth: depth + 1,
+        };
+        check(trace, depth, "visit_enum", None, self.visitor.visit_enum(data))
+    }
+}
+
+struct TraceSeed<'t, T, F: 't> {
+    seed: T,
+    trace: &'t F,
+    depth: usize,
+}
+
+impl<'de, 't, T, F> DeserializeSeed<'de> for TraceSeed<'t, T, F>
+where
+    T: DeserializeSeed<'de>,
+    F: Fn(Event),
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed
+            .deserialize(TraceDeserializer::nested(deserializer, self.trace, self.depth))
+    }
+}
+
+struct TraceAccess<'t, A, F: 't> {
+    access: A,
+    trace: &'t F,
+    depth: usize,
+}
+
+impl<'t, A, F> TraceAccess<'t, A, F> {
+    fn seed<T>(&self, seed: T) -> TraceSeed<'t, T, F> {
+        TraceSeed {
+            seed: seed,
+            trace: self.trace,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl<'de, 't, A, F> SeqAccess<'de> for TraceAccess<'t, A, F>
+where
+    A: SeqAccess<'de>,
+    F: Fn(Event),
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        call(self.trace, self.depth, "next_element", None);
+        let seed = self.seed(seed);
+        let result = self.access.next_element_seed(seed);
+        check(self.trace, self.depth, "next_element", None, result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 't, A, F> MapAccess<'de> for TraceAccess<'t, A, F>
+where
+    A: MapAccess<'de>,
+    F: Fn(Event),
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        call(self.trace, self.depth, "next_key", None);
+        let seed = self.seed(seed);
+        let result = self.access.next_key_seed(seed);
+        check(self.trace, self.depth, "next_key", None, result)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        call(self.trace, self.depth, "next_value", None);
+        let seed = self.seed(seed);
+        let result = self.access.next_value_seed(seed);
+        check(self.trace, self.depth, "next_value", None, result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 't, A, F> EnumAccess<'de> for TraceAccess<'t, A, F>
+where
+    A: EnumAccess<'de>,
+    F: Fn(Event),
+{
+    type Error = A::Error;
+    type Variant = TraceAccess<'t, A::Variant, F>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "variant", None);
+        let seed = self.seed(seed);
+        let result = self.access.variant_seed(seed);
+        check(trace, depth, "variant", None, result).map(
+            |(value, variant)| {
+                let variant = TraceAccess {
+                    access: variant,
+                    trace: trace,
+                    depth: depth,
+                };
+                (value, variant)
+            },
+        )
+    }
+}
+
+impl<'de, 't, A, F> VariantAccess<'de> for TraceAccess<'t, A, F>
+where
+    A: VariantAccess<'de>,
+    F: Fn(Event),
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        call(self.trace, self.depth, "unit_variant", None);
+        let result = self.access.unit_variant();
+        check(self.trace, self.depth, "unit_variant", None, result)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "newtype_variant", None);
+        let seed = self.seed(seed);
+        let result = self.access.newtype_variant_seed(seed);
+        check(trace, depth, "newtype_variant", None, result)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "tuple_variant", None);
+        let visitor = TraceVisitor {
+            visitor: visitor,
+            trace: trace,
+            depth: depth + 1,
+        };
+        let result = self.access.tuple_variant(len, visitor);
+        check(trace, depth, "tuple_variant", None, result)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (trace, depth) = (self.trace, self.depth);
+        call(trace, depth, "struct_variant", None);
+        let visitor = TraceVisitor {
+            visitor: visitor,
+            trace: trace,
+            depth: depth + 1,
+        };
+        let result = self.access.struct_variant(fields, visitor);
+        check(trace, depth, "struct_variant", None, result)
+    }
+}
+
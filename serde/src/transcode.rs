@@ -0,0 +1,296 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streaming conversion from one data format directly into another.
+//!
+//! Transcoding pipes the events produced by any [`Deserializer`] straight into
+//! any [`Serializer`] without materializing the input in some intermediate
+//! representation. Memory use is bounded by the nesting depth of the input
+//! rather than by its size, which makes this suitable for converting large
+//! documents between formats or re-indenting them.
+//!
+//! Enums cannot be transcoded because the Serde data model requires variant
+//! names to be `&'static str`, which a self-describing input format is unable
+//! to provide. Newtype structs are transcoded as their inner value for the
+//! same reason.
+//!
+//! [`Deserializer`]: ../de/trait.Deserializer.html
+//! [`Serializer`]: ../ser/trait.Serializer.html
+
+use lib::*;
+
+use de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess};
+use ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+/// Transcodes the content of `deserializer` into `serializer`.
+///
+/// Errors raised on either side are reported as errors of the serializer.
+pub fn transcode<'de, D, S>(deserializer: D, serializer: S) -> Result<S::Ok, S::Error>
+where
+    D: Deserializer<'de>,
+    S: Serializer,
+{
+    Transcoder::new(deserializer).serialize(serializer)
+}
+
+/// A `Serialize` implementation that transcodes the content of a
+/// `Deserializer`.
+///
+/// This is useful for embedding a transcoded value inside of a larger
+/// structure, for example as one field of a struct being serialized. Since
+/// the deserializer is consumed, a `Transcoder` can only be serialized once.
+/// Serializing it a second time results in an error.
+pub struct Transcoder<D>(RefCell<Option<D>>);
+
+impl<'de, D> Transcoder<D>
+where
+    D: Deserializer<'de>,
+{
+    /// Constructs a new `Transcoder` that reads from `deserializer`.
+    pub fn new(deserializer: D) -> Self {
+        Transcoder(RefCell::new(Some(deserializer)))
+    }
+}
+
+impl<'de, D> Serialize for Transcoder<D>
+where
+    D: Deserializer<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0.borrow_mut().take() {
+            Some(deserializer) => {
+                deserializer
+                    .deserialize_any(Visitor(serializer))
+                    .map_err(ser::Error::custom)
+            }
+            None => Err(ser::Error::custom("Transcoder can only be serialized once")),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Visitor<S>(S);
+
+impl<'de, S> de::Visitor<'de> for Visitor<S>
+where
+    S: Serializer,
+{
+    type Value = S::Ok;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value except an enum")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_bool(v).map_err(de::Error::custom)
+    }
+
+    fn visit_i8<E>(self, v: i8) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_i8(v).map_err(de::Error::custom)
+    }
+
+    fn visit_i16<E>(self, v: i16) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_i16(v).map_err(de::Error::custom)
+    }
+
+    fn visit_i32<E>(self, v: i32) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_i32(v).map_err(de::Error::custom)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_i64(v).map_err(de::Error::custom)
+    }
+
+    fn visit_u8<E>(self, v: u8) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_u8(v).map_err(de::Error::custom)
+    }
+
+    fn visit_u16<E>(self, v: u16) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_u16(v).map_err(de::Error::custom)
+    }
+
+    fn visit_u32<E>(self, v: u32) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_u32(v).map_err(de::Error::custom)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_u64(v).map_err(de::Error::custom)
+    }
+
+    fn visit_f32<E>(self, v: f32) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_f32(v).map_err(de::Error::custom)
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_f64(v).map_err(de::Error::custom)
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_char(v).map_err(de::Error::custom)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_str(v).map_err(de::Error::custom)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_bytes(v).map_err(de::Error::custom)
+    }
+
+    fn visit_none<E>(self) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_none().map_err(de::Error::custom)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<S::Ok, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0
+            .serialize_some(&Transcoder::new(deserializer))
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_unit<E>(self) -> Result<S::Ok, E>
+    where
+        E: de::Error,
+    {
+        self.0.serialize_unit().map_err(de::Error::custom)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<S::Ok, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Transcoder::new(deserializer)
+            .serialize(self.0)
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<S::Ok, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut state = try!(self.0.serialize_seq(seq.size_hint()).map_err(de::Error::custom));
+        while let Some(()) = try!(seq.next_element_seed(ElementSeed(&mut state))) {}
+        state.end().map_err(de::Error::custom)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<S::Ok, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut state = try!(self.0.serialize_map(map.size_hint()).map_err(de::Error::custom));
+        while let Some(()) = try!(map.next_key_seed(KeySeed(&mut state))) {
+            try!(map.next_value_seed(ValueSeed(&mut state)));
+        }
+        state.end().map_err(de::Error::custom)
+    }
+}
+
+struct ElementSeed<'a, S: 'a>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for ElementSeed<'a, S>
+where
+    S: SerializeSeq,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0
+            .serialize_element(&Transcoder::new(deserializer))
+            .map_err(de::Error::custom)
+    }
+}
+
+struct KeySeed<'a, S: 'a>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for KeySeed<'a, S>
+where
+    S: SerializeMap,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0
+            .serialize_key(&Transcoder::new(deserializer))
+            .map_err(de::Error::custom)
+    }
+}
+
+struct ValueSeed<'a, S: 'a>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for ValueSeed<'a, S>
+where
+    S: SerializeMap,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.0
+            .serialize_value(&Transcoder::new(deserializer))
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Store a version number alongside persisted data and upgrade old payloads.
+//!
+//! A type implementing [`Versioned`] names its version number and the type
+//! that represented the previous version, together with a function migrating
+//! a value of the previous version to the current one. Serializing through
+//! this module writes the version number next to the value. Deserializing
+//! reads the version number first, deserializes the payload as whichever type
+//! in the chain of previous versions has that number, and migrates it forward
+//! one version at a time.
+//!
+//! The first version in a chain names [`Unversioned`] as its previous version.
+//! Version numbers start at 1; 0 is reserved for `Unversioned`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use serde::version::{Unversioned, Versioned};
+//!
+//! #[derive(Deserialize)]
+//! struct ConfigV1 {
+//!     name: String,
+//! }
+//!
+//! impl Versioned for ConfigV1 {
+//!     type Previous = Unversioned;
+//!
+//!     fn version() -> u32 {
+//!         1
+//!     }
+//!
+//!     fn migrate(previous: Unversioned) -> Self {
+//!         match previous {}
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//! }
+//!
+//! impl Versioned for Config {
+//!     type Previous = ConfigV1;
+//!
+//!     fn version() -> u32 {
+//!         2
+//!     }
+//!
+//!     fn migrate(previous: ConfigV1) -> Self {
+//!         Config {
+//!             name: previous.name,
+//!             retries: 3,
+//!         }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct SaveFile {
+//!     #[serde(with = "serde::version")]
+//!     config: Config,
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! A versioned value is serialized as a struct named `Versioned` with the
+//! fields `version` and `value`, in that order. Deserializing it from a map
+//! requires the `version` entry to come first.
+//!
+//! # The `version` attribute
+//!
+//! Instead of being versioned by the field that holds it, a type can carry
+//! its version number itself with `#[serde(version = "N")]`. The derived
+//! Serialize impl then writes the version number before the value, and the
+//! derived Deserialize impl reads it and migrates older payloads. Only the
+//! migration is written by hand, as an implementation of [`Migrate`]. The
+//! derive implements [`Versioned`] from it.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use serde::version::{Migrate, Unversioned};
+//!
+//! #[derive(Deserialize)]
+//! #[serde(version = "1")]
+//! struct ConfigV1 {
+//!     name: String,
+//! }
+//!
+//! impl Migrate for ConfigV1 {
+//!     type Previous = Unversioned;
+//!
+//!     fn migrate(previous: Unversioned) -> Self {
+//!         match previous {}
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! #[serde(version = "2")]
+//! struct Config {
+//!     name: String,
+//!     retries: u32,
+//! }
+//!
+//! impl Migrate for Config {
+//!     type Previous = ConfigV1;
+//!
+//!     fn migrate(previous: ConfigV1) -> Self {
+//!         Config {
+//!             name: previous.name,
+//!             retries: 3,
+//!         }
+//!     }
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! The attribute cannot be used on a generic type. A field holding such a type
+//! must not also use `#[serde(with = "serde::version")]`, which would write
+//! the version number twice.
+//!
+//! [`Versioned`]: trait.Versioned.html
+//! [`Unversioned`]: enum.Unversioned.html
+//! [`Migrate`]: trait.Migrate.html
+
+use lib::*;
+
+use de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, MapAccess, SeqAccess,
+         Visitor};
+use private::ser::serialize_versioned;
+use ser::{Serialize, Serializer};
+
+/// A type that is one version in a chain of persisted representations.
+pub trait Versioned: DeserializeOwned {
+    /// The representation this version replaces.
+    type Previous: Versioned;
+
+    /// The version number of this representation, starting at 1.
+    fn version() -> u32;
+
+    /// Upgrades a value of the previous version to this version.
+    fn migrate(previous: Self::Previous) -> Self;
+
+    /// Deserializes a payload of this version, which is not preceded by a
+    /// version number.
+    ///
+    /// The default uses the Deserialize impl. `#[serde(version = "N")]`
+    /// overrides it, because its Deserialize impl reads the version number.
+    fn deserialize_payload<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::deserialize(deserializer)
+    }
+}
+
+/// The migration from the previous version of a type with
+/// `#[serde(version = "N")]`, from which the derive implements [`Versioned`].
+///
+/// [`Versioned`]: trait.Versioned.html
+pub trait Migrate: Sized {
+    /// The representation this version replaces.
+    type Previous: Versioned;
+
+    /// Upgrades a value of the previous version to this version.
+    fn migrate(previous: Self::Previous) -> Self;
+}
+
+/// The previous version of the first version in a chain.
+///
+/// This type has no values, so the `migrate` function of a first version can
+/// be written as `match previous {}`.
+pub enum Unversioned {}
+
+impl<'de> Deserialize<'de> for Unversioned {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let _ = deserializer;
+        Err(de::Error::custom("unsupported version 0"))
+    }
+}
+
+impl Versioned for Unversioned {
+    type Previous = Unversioned;
+
+    fn version() -> u32 {
+        0
+    }
+
+    fn migrate(previous: Unversioned) -> Self {
+        previous
+    }
+}
+
+/// Serializes `value` together with its version number.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Versioned + Serialize,
+    S: Serializer,
+{
+    serialize_versioned(T::version(), value, serializer)
+}
+
+/// Deserializes a versioned value, migrating it to `T` if it was written by an
+/// older version.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Versioned,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_struct("Versioned", FIELDS, VersionedVisitor(PhantomData))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const FIELDS: &'static [&'static str] = &["version", "value"];
+
+struct VersionedVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for VersionedVisitor<T>
+where
+    T: Versioned,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct Versioned")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<T, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let version = match try!(seq.next_element()) {
+            Some(version) => version,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        match try!(seq.next_element_seed(VersionSeed::new(version))) {
+            Some(value) => Ok(value),
+            None => Err(de::Error::invalid_length(1, &self)),
+        }
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<T, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        match try!(map.next_key()) {
+            Some(Field::Version) => {}
+            Some(Field::Value) => return Err(de::Error::custom("expected `version` before `value`")),
+            None => return Err(de::Error::missing_field("version")),
+        }
+        let version = try!(map.next_value());
+
+        match try!(map.next_key()) {
+            Some(Field::Value) => {}
+            Some(Field::Version) => return Err(de::Error::duplicate_field("version")),
+            None => return Err(de::Error::missing_field("value")),
+        }
+        let value = try!(map.next_value_seed(VersionSeed::new(version)));
+
+        match try!(map.next_key()) {
+            Some(Field::Version) => Err(de::Error::duplicate_field("version")),
+            Some(Field::Value) => Err(de::Error::duplicate_field("value")),
+            None => Ok(value),
+        }
+    }
+}
+
+enum Field {
+    Version,
+    Value,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("`version` or `value`")
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Field, E>
+            where
+                E: de::Error,
+            {
+                match value {
+                    0 => Ok(Field::Version),
+                    1 => Ok(Field::Value),
+                    _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)),
+                }
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Field, E>
+            where
+                E: de::Error,
+            {
+                match value {
+                    "version" => Ok(Field::Version),
+                    "value" => Ok(Field::Value),
+                    _ => Err(de::Error::unknown_field(value, FIELDS)),
+                }
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> Result<Field, E>
+            where
+                E: de::Error,
+            {
+                match str::from_utf8(value) {
+                    Ok(value) => self.visit_str(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Bytes(value), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+// Deserializes the representation with the given version number and migrates
+// it to `T`.
+struct VersionSeed<T> {
+    version: u32,
+    marker: PhantomData<T>,
+}
+
+impl<T> VersionSeed<T> {
+    fn new(version: u32) -> Self {
+        VersionSeed {
+            version: version,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for VersionSeed<T>
+where
+    T: Versioned,
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let current = T::version();
+        if self.version == current {
+            T::deserialize_payload(deserializer)
+        } else if self.version < current {
+            let previous = try!(VersionSeed::<T::Previous>::new(self.version).deserialize(deserializer));
+            Ok(T::migrate(previous))
+        } else {
+            Err(de::Error::custom(format_args!("unsupported version {}", self.version)))
+        }
+    }
+}
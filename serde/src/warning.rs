@@ -0,0 +1,640 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Collect non-fatal warnings while deserializing.
+//!
+//! Input that a `Deserialize` impl accepts but that probably indicates a
+//! mistake, such as a misspelled optional field, is silently dropped by
+//! default, while `#[serde(deny_unknown_fields)]` rejects it outright. Within
+//! a call to [`deserialize`] such input is instead reported to a callback as a
+//! [`Warning`], and the value is still returned.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//!
+//! extern crate serde;
+//!
+//! use std::collections::BTreeMap;
+//!
+//! use serde::de::IntoDeserializer;
+//! use serde::de::value::Error;
+//! use serde::warning::{self, Warning};
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     port: u16,
+//! }
+//!
+//! # fn main() {
+//! let mut input = BTreeMap::new();
+//! input.insert("port", 80);
+//! input.insert("prot", 8080);
+//! let deserializer = IntoDeserializer::<Error>::into_deserializer(input);
+//!
+//! let mut warnings = Vec::new();
+//! let config: Config = warning::deserialize(deserializer, |w| warnings.push(w)).unwrap();
+//!
+//! assert_eq!(config.port, 80);
+//! assert_eq!(warnings, [Warning::IgnoredField { path: "prot".to_owned() }]);
+//! # }
+//! ```
+//!
+//! Ignored fields are detected automatically: every map value that the
+//! `Deserialize` impl skips with `IgnoredAny`, which is how derived impls
+//! skip unknown fields, is reported. `Deserialize` impls and data formats can
+//! report anything else, for example that a deprecated alias was used or that
+//! a value was converted with loss of precision, by calling [`report`]. It
+//! does nothing outside of [`deserialize`], so it is always safe to call.
+//!
+//! Untagged and internally tagged enums buffer their input before
+//! deserializing it, so fields they ignore are not reported.
+//!
+//! [`deserialize`]: fn.deserialize.html
+//! [`report`]: fn.report.html
+//! [`Warning`]: enum.Warning.html
+
+use lib::*;
+
+use de::{self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+         VariantAccess, Visitor};
+
+/// Something in the input that did not prevent deserialization.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    /// A map entry whose value was skipped, typically an unknown field of a
+    /// struct. The path ends with the key of the entry.
+    IgnoredField {
+        /// The path to the entry, like `servers[1].name`.
+        path: String,
+    },
+    /// A warning passed to `report`.
+    Custom {
+        /// The path to the value being deserialized when it was reported, or
+        /// an empty string for the top-level value.
+        path: String,
+        /// The message passed to `report`.
+        message: String,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::IgnoredField { ref path } => write!(formatter, "ignored field `{}`", path),
+            Warning::Custom { ref path, ref message } => {
+                if path.is_empty() {
+                    formatter.write_str(message)
+                } else {
+                    write!(formatter, "{} at `{}`", message, path)
+                }
+            }
+        }
+    }
+}
+
+/// Deserializes a `T`, passing every warning to `sink`.
+///
+/// The warnings are passed to `sink` in the order they occurred once
+/// deserialization has finished, whether or not it succeeded.
+pub fn deserialize<'de, D, T, F>(deserializer: D, mut sink: F) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+    F: FnMut(Warning),
+{
+    let scope = Some(Scope::new());
+    let previous = SCOPE.with(|current| mem::replace(&mut *current.borrow_mut(), scope));
+    let restore = Restore { previous: previous };
+    let result = T::deserialize(
+        WarnDeserializer {
+            deserializer: deserializer,
+            position: Position::Value,
+        },
+    );
+    let warnings = restore.finish();
+    for warning in warnings {
+        sink(warning);
+    }
+    result
+}
+
+/// Reports a warning about the value currently being deserialized.
+///
+/// This does nothing unless called from within `deserialize`.
+pub fn report<T>(message: T)
+where
+    T: Display,
+{
+    SCOPE.with(
+        |scope| if let Some(ref mut scope) = *scope.borrow_mut() {
+            let warning = Warning::Custom {
+                path: scope.path(),
+                message: message.to_string(),
+            };
+            scope.warnings.push(warning);
+        },
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+thread_local! {
+    static SCOPE: RefCell<Option<Scope>> = RefCell::new(None);
+}
+
+struct Scope {
+    path: Vec<Segment>,
+    // The last map key seen, which becomes part of the path while its value
+    // is deserialized.
+    key: Option<String>,
+    warnings: Vec<Warning>,
+}
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Scope {
+    fn new() -> Self {
+        Scope {
+            path: Vec::new(),
+            key: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match *segment {
+                Segment::Key(ref key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                }
+                Segment::Index(index) => {
+                    path.push_str(&format!("[{}]", index));
+                }
+            }
+        }
+        path
+    }
+}
+
+fn with_scope<F>(f: F)
+where
+    F: FnOnce(&mut Scope),
+{
+    SCOPE.with(
+        |scope| if let Some(ref mut scope) = *scope.borrow_mut() {
+            f(scope);
+        },
+    );
+}
+
+// Puts back the scope of an enclosing call to `deserialize`, also if the
+// `Deserialize` impl panics.
+struct Restore {
+    previous: Option<Scope>,
+}
+
+impl Restore {
+    fn finish(mut self) -> Vec<Warning> {
+        let previous = self.previous.take();
+        let scope = SCOPE.with(|scope| mem::replace(&mut *scope.borrow_mut(), previous));
+        mem::forget(self);
+        scope.map_or_else(Vec::new, |scope| scope.warnings)
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPE.with(|scope| *scope.borrow_mut() = previous);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Where the value being deserialized sits in its parent.
+#[derive(Copy, Clone, PartialEq)]
+enum Position {
+    Value,
+    // A map key, which is remembered for the path of its value.
+    Key,
+    // A map value, which is reported if it is ignored.
+    MapValue,
+}
+
+struct WarnDeserializer<D> {
+    deserializer: D,
+    position: Position,
+}
+
+macro_rules! warn_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, D::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let visitor = WarnVisitor {
+                    visitor: visitor,
+                    position: self.position,
+                };
+                self.deserializer.$method($($arg,)* visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, D> Deserializer<'de> for WarnDeserializer<D>
+where
+    D: Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    warn_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, D::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.position == Position::MapValue {
+            with_scope(
+                |scope| {
+                    let warning = Warning::IgnoredField { path: scope.path() };
+                    scope.warnings.push(warning);
+                },
+            );
+        }
+        // Nothing inside an ignored value is of interest.
+        self.deserializer.deserialize_ignored_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+struct WarnVisitor<V> {
+    visitor: V,
+    position: Position,
+}
+
+impl<V> WarnVisitor<V> {
+    fn key<T>(&self, key: T)
+    where
+        T: ToString,
+    {
+        if self.position == Position::Key {
+            with_scope(|scope| scope.key = Some(key.to_string()));
+        }
+    }
+}
+
+macro_rules! warn_visit_primitive {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<V::Value, E>
+            where
+                E: de::Error,
+            {
+                self.key(&v);
+                self.visitor.$method(v)
+            }
+        )*
+    }
+}
+
+impl<'de, V> Visitor<'de> for WarnVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    warn_visit_primitive! {
+        visit_bool(bool),
+        visit_i8(i8),
+        visit_i16(i16),
+        visit_i32(i32),
+        visit_i64(i64),
+        visit_u8(u8),
+        visit_u16(u16),
+        visit_u32(u32),
+        visit_u64(u64),
+        visit_f32(f32),
+        visit_f64(f64),
+        visit_char(char),
+        visit_str(&str),
+        visit_borrowed_str(&'de str),
+        visit_string(String),
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.key(String::from_utf8_lossy(v));
+        self.visitor.visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.key(String::from_utf8_lossy(v));
+        self.visitor.visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.key(String::from_utf8_lossy(&v));
+        self.visitor.visit_byte_buf(v)
+    }
+
+    fn visit_none<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_some(
+            WarnDeserializer {
+                deserializer: deserializer,
+                position: Position::Value,
+            },
+        )
+    }
+
+    fn visit_unit<E>(self) -> Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.visitor.visit_newtype_struct(
+            WarnDeserializer {
+                deserializer: deserializer,
+                position: Position::Value,
+            },
+        )
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.visitor.visit_seq(WarnAccess::new(seq))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        self.visitor.visit_map(WarnAccess::new(map))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        self.visitor.visit_enum(WarnAccess::new(data))
+    }
+}
+
+struct WarnSeed<T> {
+    seed: T,
+    position: Position,
+}
+
+impl<'de, T> DeserializeSeed<'de> for WarnSeed<T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.seed.deserialize(
+            WarnDeserializer {
+                deserializer: deserializer,
+                position: self.position,
+            },
+        )
+    }
+}
+
+struct WarnAccess<A> {
+    access: A,
+    index: usize,
+}
+
+impl<A> WarnAccess<A> {
+    fn new(access: A) -> Self {
+        WarnAccess {
+            access: access,
+            index: 0,
+        }
+    }
+}
+
+// Deserializes something nested under `segment` in the path.
+fn nested<T, F>(segment: Segment, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    with_scope(|scope| scope.path.push(segment));
+    let result = f();
+    with_scope(|scope| { scope.path.pop(); });
+    result
+}
+
+impl<'de, A> SeqAccess<'de> for WarnAccess<A>
+where
+    A: SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = WarnSeed {
+            seed: seed,
+            position: Position::Value,
+        };
+        let access = &mut self.access;
+        let result = nested(Segment::Index(self.index), || access.next_element_seed(seed));
+        self.index += 1;
+        result
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, A> MapAccess<'de> for WarnAccess<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        with_scope(|scope| scope.key = None);
+        let seed = WarnSeed {
+            seed: seed,
+            position: Position::Key,
+        };
+        self.access.next_key_seed(seed)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut key = None;
+        with_scope(|scope| key = scope.key.take());
+        let key = key.unwrap_or_else(|| format!("<{}>", self.index));
+        self.index += 1;
+
+        let seed = WarnSeed {
+            seed: seed,
+            position: Position::MapValue,
+        };
+        let access = &mut self.access;
+        nested(Segment::Key(key), || access.next_value_seed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, A> EnumAccess<'de> for WarnAccess<A>
+where
+    A: EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = WarnAccess<A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let seed = WarnSeed {
+            seed: seed,
+            position: Position::Value,
+        };
+        self.access
+            .variant_seed(seed)
+            .map(|(value, variant)| (value, WarnAccess::new(variant)))
+    }
+}
+
+impl<'de, A> VariantAccess<'de> for WarnAccess<A>
+where
+    A: VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.access.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let seed = WarnSeed {
+            seed: seed,
+            position: Position::Value,
+        };
+        self.access.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = WarnVisitor {
+            visitor: visitor,
+            position: Position::Value,
+        };
+        self.access.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let visitor = WarnVisitor {
+            visitor: visitor,
+            position: Position::Value,
+        };
+        self.access.struct_variant(fields, visitor)
+    }
+}
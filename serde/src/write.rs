@@ -0,0 +1,208 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Output helpers for implementing text formats.
+//!
+//! A format's `Serializer` usually writes into any `io::Write`. This covers
+//! `Vec<u8>`, and output meant for a `String` can be written into a
+//! `Vec<u8>` and converted with `String::from_utf8` at the end. Writers that
+//! make a system call per write, such as a `File`, should be wrapped in a
+//! `BufWriter` by the caller.
+//!
+//! The functions in this module cover the parts that every text format needs
+//! and that are easy to get subtly wrong or slow:
+//!
+//! - [`write_u64`] and [`write_i64`] format integers without going through
+//!   `fmt`, and [`write_f64`] and [`write_f32`] write the shortest
+//!   representation that parses back to the same float.
+//! - [`write_escaped`] copies the runs of a string that need no escaping
+//!   with one write each and hands every other byte to an escape function.
+//!
+//! ```rust
+//! # extern crate serde;
+//! #
+//! use std::io::{self, Write};
+//!
+//! use serde::write;
+//!
+//! // Writes a quoted string, escaping quotes and backslashes.
+//! fn write_quoted<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+//!     try!(writer.write_all(b"\""));
+//!     try!(write::write_escaped(
+//!         writer,
+//!         s.as_bytes(),
+//!         |b| b == b'"' || b == b'\\',
+//!         |writer, b| writer.write_all(&[b'\\', b]),
+//!     ));
+//!     writer.write_all(b"\"")
+//! }
+//!
+//! # fn main() {
+//! let mut out = Vec::new();
+//! write_quoted(&mut out, r#"say "hi""#).unwrap();
+//! out.push(b' ');
+//! write::write_i64(&mut out, -42).unwrap();
+//! assert_eq!(String::from_utf8(out).unwrap(), r#""say \"hi\"" -42"#);
+//! # }
+//! ```
+//!
+//! This module is only output plumbing. Serde itself contains no data format
+//! and does not use it. `Serializer` impls, the `to_vec` or `to_writer` entry
+//! points and when to flush remain the responsibility of each format crate.
+//!
+//! [`write_u64`]: fn.write_u64.html
+//! [`write_i64`]: fn.write_i64.html
+//! [`write_f64`]: fn.write_f64.html
+//! [`write_f32`]: fn.write_f32.html
+//! [`write_escaped`]: fn.write_escaped.html
+
+use lib::*;
+
+use std::io;
+
+// The two decimal digits of every number below 100.
+const DEC_DIGITS_LUT: &'static [u8] = b"\
+      0001020304050607080910111213141516171819\
+      2021222324252627282930313233343536373839\
+      4041424344454647484950515253545556575859\
+      6061626364656667686970717273747576777879\
+      8081828384858687888990919293949596979899";
+
+// Enough for the 20 digits of u64::MAX.
+const MAX_DIGITS: usize = 20;
+
+/// Writes `value` in decimal.
+///
+/// Smaller unsigned integer types can be written by casting them to `u64`.
+pub fn write_u64<W: ?Sized>(writer: &mut W, value: u64) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let mut buf = [0; MAX_DIGITS];
+    writer.write_all(format_u64(value, &mut buf))
+}
+
+/// Writes `value` in decimal, with a leading `-` if it is negative.
+///
+/// Smaller signed integer types can be written by casting them to `i64`.
+pub fn write_i64<W: ?Sized>(writer: &mut W, value: i64) -> io::Result<()>
+where
+    W: io::Write,
+{
+    if value < 0 {
+        try!(writer.write_all(b"-"));
+    }
+    // Two's complement negation, which is correct for i64::MIN as well.
+    let abs = if value < 0 {
+        (!(value as u64)).wrapping_add(1)
+    } else {
+        value as u64
+    };
+    write_u64(writer, abs)
+}
+
+/// Writes the shortest decimal representation that parses back to `value`.
+///
+/// Numbers that are whole are written without a fractional part, and very
+/// large and very small numbers are written in full rather than with an
+/// exponent. Infinities are written as `inf` and `-inf` and NaN as `NaN`;
+/// formats that have no representation for them should check for them first.
+pub fn write_f64<W: ?Sized>(writer: &mut W, value: f64) -> io::Result<()>
+where
+    W: io::Write,
+{
+    write!(writer, "{}", value)
+}
+
+/// Writes the shortest decimal representation that parses back to `value`
+/// as an `f32`.
+///
+/// See [`write_f64`](fn.write_f64.html).
+pub fn write_f32<W: ?Sized>(writer: &mut W, value: f32) -> io::Result<()>
+where
+    W: io::Write,
+{
+    write!(writer, "{}", value)
+}
+
+/// Writes `bytes`, passing each byte for which `needs_escape` returns true to
+/// `write_escape` instead.
+///
+/// The bytes between two escaped bytes are copied with a single write, so the
+/// cost of a string with few escapes is dominated by calls to `needs_escape`,
+/// which should be cheap. A lookup table indexed by the byte works well.
+///
+/// Escaping is byte-wise. To escape a multi-byte UTF-8 character, make
+/// `needs_escape` return true for all of its bytes and decode it in
+/// `write_escape`, or escape only ASCII bytes, which never occur inside a
+/// multi-byte character.
+pub fn write_escaped<W: ?Sized, P, E>(
+    writer: &mut W,
+    bytes: &[u8],
+    mut needs_escape: P,
+    mut write_escape: E,
+) -> io::Result<()>
+where
+    W: io::Write,
+    P: FnMut(u8) -> bool,
+    E: FnMut(&mut W, u8) -> io::Result<()>,
+{
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if !needs_escape(byte) {
+            continue;
+        }
+        if start < i {
+            try!(writer.write_all(&bytes[start..i]));
+        }
+        try!(write_escape(writer, byte));
+        start = i + 1;
+    }
+    if start < bytes.len() {
+        try!(writer.write_all(&bytes[start..]));
+    }
+    Ok(())
+}
+
+// Formats `n` into the end of `buf`, four digits at a time, and returns the
+// part of `buf` that was written.
+fn format_u64(mut n: u64, buf: &mut [u8; MAX_DIGITS]) -> &[u8] {
+    let mut curr = MAX_DIGITS;
+    let lut = DEC_DIGITS_LUT;
+
+    while n >= 10_000 {
+        let rem = (n % 10_000) as usize;
+        n /= 10_000;
+
+        let d1 = (rem / 100) * 2;
+        let d2 = (rem % 100) * 2;
+        curr -= 4;
+        buf[curr..curr + 2].copy_from_slice(&lut[d1..d1 + 2]);
+        buf[curr + 2..curr + 4].copy_from_slice(&lut[d2..d2 + 2]);
+    }
+
+    // n < 10_000 now, so it fits in a usize.
+    let mut n = n as usize;
+    if n >= 100 {
+        let d1 = (n % 100) * 2;
+        n /= 100;
+        curr -= 2;
+        buf[curr..curr + 2].copy_from_slice(&lut[d1..d1 + 2]);
+    }
+
+    if n < 10 {
+        curr -= 1;
+        buf[curr] = b'0' + n as u8;
+    } else {
+        let d1 = n * 2;
+        curr -= 2;
+        buf[curr..curr + 2].copy_from_slice(&lut[d1..d1 + 2]);
+    }
+
+    &buf[curr..]
+}
@@ -11,6 +11,7 @@ use quote::{self, Tokens, ToTokens};
 
 use bound;
 use fragment::{Fragment, Expr, Stmts, Match};
+use ser;
 use internals::ast::{Body, Container, Field, Style, Variant};
 use internals::{self, attr};
 
@@ -25,10 +26,52 @@ pub fn expand_derive_deserialize(input: &syn::DeriveInput) -> Result<Tokens, Str
     let params = Parameters::new(&cont);
     let (de_impl_generics, _, ty_generics, where_clause) = split_with_de_lifetime(&params);
     let dummy_const = Ident::new(format!("_IMPL_DESERIALIZE_FOR_{}", ident));
-    let body = Stmts(deserialize_body(&cont, &params));
     let delife = params.borrowed.de_lifetime();
+    let body = Stmts(deserialize_body(&cont, &params));
+    let body = match cont.attrs.validate() {
+        None => quote!(#body),
+        Some(validate) => {
+            let this = &params.this;
+            quote! {
+                let __result = (move || -> _serde::export::Result<#this #ty_generics, __D::Error> {
+                    #body
+                })();
+                _serde::private::de::validate(__result, #validate)
+            }
+        }
+    };
+
+    let impl_block = if let Some(version) = cont.attrs.version() {
+        quote! {
+            #[automatically_derived]
+            impl #de_impl_generics _serde::Deserialize<#delife> for #ident #ty_generics #where_clause {
+                fn deserialize<__D>(__deserializer: __D) -> _serde::export::Result<Self, __D::Error>
+                    where __D: _serde::Deserializer<#delife>
+                {
+                    _serde::version::deserialize(__deserializer)
+                }
+            }
+
+            #[automatically_derived]
+            impl _serde::version::Versioned for #ident {
+                type Previous = <Self as _serde::version::Migrate>::Previous;
+
+                fn version() -> u32 {
+                    #version
+                }
 
-    let impl_block = if let Some(remote) = cont.attrs.remote() {
+                fn migrate(previous: Self::Previous) -> Self {
+                    <Self as _serde::version::Migrate>::migrate(previous)
+                }
+
+                fn deserialize_payload<#delife, __D>(__deserializer: __D) -> _serde::export::Result<Self, __D::Error>
+                    where __D: _serde::Deserializer<#delife>
+                {
+                    #body
+                }
+            }
+        }
+    } else if let Some(remote) = cont.attrs.remote() {
         let vis = &input.vis;
         quote! {
             impl #de_impl_generics #ident #ty_generics #where_clause {
@@ -52,11 +95,18 @@ pub fn expand_derive_deserialize(input: &syn::DeriveInput) -> Result<Tokens, Str
         }
     };
 
+    let names_check = if cont.attrs.expose_names() {
+        Some(ser::expose_names_requires_serialize(&cont))
+    } else {
+        None
+    };
+
     let generated = quote! {
         #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
         const #dummy_const: () = {
             extern crate serde as _serde;
             #impl_block
+            #names_check
         };
     };
     Ok(generated)
@@ -147,21 +197,24 @@ fn build_generics(cont: &Container, borrowed: &BorrowedLifetimes) -> syn::Generi
     }
 }
 
-// Fields with a `skip_deserializing` or `deserialize_with` attribute are not
-// deserialized by us so we do not generate a bound. Fields with a `bound`
-// attribute specify their own bound so we do not generate one. All other fields
-// may need a `T: Deserialize` bound where T is the type of the field.
+// Fields with a `skip_deserializing` or `deserialize_with` attribute, or with
+// both `with_readable` and `with_compact`, are not deserialized by us so we do
+// not generate a bound. Fields with a `bound` attribute specify their own bound
+// so we do not generate one. All other fields may need a `T: Deserialize` bound
+// where T is the type of the field.
 fn needs_deserialize_bound(field: &attr::Field, variant: Option<&attr::Variant>) -> bool {
     !field.skip_deserializing() &&
     field.deserialize_with().is_none() &&
+    (field.with_readable().is_none() || field.with_compact().is_none()) &&
     field.de_bound().is_none() &&
     variant.map_or(true, |variant| variant.deserialize_with().is_none())
 }
 
-// Fields with a `default` attribute (not `default=...`), and fields with a
-// `skip_deserializing` attribute that do not also have `default=...`.
+// Fields with a `default` attribute (not `default=...`), fields with a
+// `skip_deserializing` attribute that do not also have `default=...`, and
+// fields with a `lenient` attribute (not `lenient=...`).
 fn requires_default(field: &attr::Field, _variant: Option<&attr::Variant>) -> bool {
-    field.default() == &attr::Default::Default
+    field.default() == &attr::Default::Default || field.lenient() == &attr::Default::Default
 }
 
 enum BorrowedLifetimes {
@@ -398,14 +451,14 @@ fn deserialize_seq(
                     let #var = #default;
                 }
             } else {
-                let visit = match field.attrs.deserialize_with() {
+                let visit = match field_deserialize_with(field) {
                     None => {
                         let field_ty = &field.ty;
                         quote!(try!(_serde::de::SeqAccess::next_element::<#field_ty>(&mut __seq)))
                     }
-                    Some(path) => {
+                    Some(deserialize_with) => {
                         let (wrapper, wrapper_ty) = wrap_deserialize_field_with(
-                            params, field.ty, path);
+                            params, field.ty, &deserialize_with);
                         quote!({
                             #wrapper
                             _serde::export::Option::map(
@@ -454,15 +507,16 @@ fn deserialize_seq(
 fn deserialize_newtype_struct(type_path: &Tokens, params: &Parameters, field: &Field) -> Tokens {
     let delife = params.borrowed.de_lifetime();
 
-    let value = match field.attrs.deserialize_with() {
+    let value = match field_deserialize_with(field) {
         None => {
             let field_ty = &field.ty;
             quote! {
                 try!(<#field_ty as _serde::Deserialize>::deserialize(__e))
             }
         }
-        Some(path) => {
-            let (wrapper, wrapper_ty) = wrap_deserialize_field_with(params, field.ty, path);
+        Some(deserialize_with) => {
+            let (wrapper, wrapper_ty) =
+                wrap_deserialize_field_with(params, field.ty, &deserialize_with);
             quote!({
                 #wrapper
                 try!(<#wrapper_ty as _serde::Deserialize>::deserialize(__e)).value
@@ -526,6 +580,10 @@ fn deserialize_struct(
         None => format!("struct {}", params.type_name()),
     };
 
+    // A struct collecting unknown fields can only be read from a map, where
+    // every value comes with its key.
+    let collect_unknown = !is_enum && fields.iter().any(|field| field.attrs.collect_unknown());
+
     let visit_seq = Stmts(deserialize_seq(&type_path, params, fields, true, cattrs));
 
     let (field_visitor, fields_stmt, visit_map) =
@@ -548,6 +606,10 @@ fn deserialize_struct(
         quote! {
             _serde::de::VariantAccess::struct_variant(__variant, FIELDS, #visitor_expr)
         }
+    } else if collect_unknown {
+        quote! {
+            _serde::Deserializer::deserialize_map(__deserializer, #visitor_expr)
+        }
     } else {
         let type_name = cattrs.name().deserialize_name();
         quote! {
@@ -567,6 +629,7 @@ fn deserialize_struct(
     // untagged struct variants do not get a visit_seq method
     let visit_seq = match untagged {
         Untagged::Yes => None,
+        Untagged::No if collect_unknown => None,
         Untagged::No => {
             Some(quote! {
                 #[inline]
@@ -614,6 +677,81 @@ fn deserialize_enum(
     params: &Parameters,
     variants: &[Variant],
     cattrs: &attr::Container,
+) -> Fragment {
+    if let Some(repr) = cattrs.repr() {
+        return deserialize_repr_enum(params, variants, repr);
+    }
+
+    // Untagged variants are checked to come after all the tagged ones. They
+    // are tried only if the input does not carry the tag of a tagged variant.
+    // In an untagged enum every variant is already untagged.
+    let first_untagged = match *cattrs.tag() {
+        attr::EnumTag::None => None,
+        _ => variants.iter().position(|variant| variant.attrs.untagged()),
+    };
+    match first_untagged {
+        None => deserialize_homogeneous_enum(params, variants, cattrs),
+        Some(0) => deserialize_untagged_enum(params, variants, cattrs),
+        Some(first_untagged) => {
+            let (tagged, untagged) = variants.split_at(first_untagged);
+            let tagged_frag = Expr(deserialize_homogeneous_enum(params, tagged, cattrs));
+            let tagged_names = tagged
+                .iter()
+                .filter(|variant| !variant.attrs.skip_deserializing())
+                .map(|variant| variant.attrs.name().deserialize_name());
+            let tag = match *cattrs.tag() {
+                attr::EnumTag::Internal { ref tag } |
+                attr::EnumTag::Adjacent { ref tag, .. } => quote!(_serde::export::Some(#tag)),
+                attr::EnumTag::External | attr::EnumTag::None => quote!(_serde::export::None),
+            };
+            let first_attempt = quote! {
+                if _serde::private::de::has_known_tag(&__content, #tag, &[ #(#tagged_names),* ]) {
+                    let __deserializer = _serde::private::de::ContentRefDeserializer::<__D::Error>::new(&__content);
+                    return #tagged_frag;
+                }
+            };
+            deserialize_untagged_enum_after(params, untagged, cattrs, Some(first_attempt))
+        }
+    }
+}
+
+fn deserialize_repr_enum(params: &Parameters, variants: &[Variant], repr: &Ident) -> Fragment {
+    let this = &params.this;
+
+    let attempts = variants
+        .iter()
+        .filter(|variant| !variant.attrs.skip_deserializing())
+        .map(
+            |variant| {
+                let variant_ident = &variant.ident;
+                quote! {
+                    if __value == #this::#variant_ident as #repr {
+                        return _serde::export::Ok(#this::#variant_ident);
+                    }
+                }
+            },
+        );
+
+    let unexpected = if repr.as_ref().starts_with('u') {
+        quote!(_serde::de::Unexpected::Unsigned(__value as u64))
+    } else {
+        quote!(_serde::de::Unexpected::Signed(__value as i64))
+    };
+    let expecting = format!("discriminant of enum {}", params.type_name());
+
+    quote_block! {
+        let __value = try!(<#repr as _serde::Deserialize>::deserialize(__deserializer));
+
+        #(#attempts)*
+
+        _serde::export::Err(_serde::de::Error::invalid_value(#unexpected, &#expecting))
+    }
+}
+
+fn deserialize_homogeneous_enum(
+    params: &Parameters,
+    variants: &[Variant],
+    cattrs: &attr::Container,
 ) -> Fragment {
     match *cattrs.tag() {
         attr::EnumTag::External => deserialize_externally_tagged_enum(params, variants, cattrs),
@@ -655,7 +793,7 @@ fn deserialize_externally_tagged_enum(
         }
     };
 
-    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true),);
+    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true, false),);
 
     // Match arms to extract a variant from a string
     let variant_arms = variants
@@ -748,7 +886,7 @@ fn deserialize_internally_tagged_enum(
         }
     };
 
-    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true),);
+    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true, false),);
 
     // Match arms to extract a variant from a string
     let variant_arms = variants.iter()
@@ -809,7 +947,7 @@ fn deserialize_adjacently_tagged_enum(
         }
     };
 
-    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true),);
+    let variant_visitor = Stmts(deserialize_generated_identifier(variant_names_idents, cattrs, true, false),);
 
     let ref variant_arms: Vec<_> = variants
         .iter()
@@ -1085,6 +1223,19 @@ fn deserialize_untagged_enum(
     params: &Parameters,
     variants: &[Variant],
     cattrs: &attr::Container,
+) -> Fragment {
+    deserialize_untagged_enum_after(params, variants, cattrs, None)
+}
+
+/// Deserializes an untagged enum, or the untagged variants at the end of an
+/// otherwise tagged enum. In the latter case `first_attempt` deserializes the
+/// buffered content as a tagged variant if it carries a known tag, before the
+/// untagged variants are tried.
+fn deserialize_untagged_enum_after(
+    params: &Parameters,
+    variants: &[Variant],
+    cattrs: &attr::Container,
+    first_attempt: Option<Tokens>,
 ) -> Fragment {
     let attempts = variants
         .iter()
@@ -1106,12 +1257,17 @@ fn deserialize_untagged_enum(
     // largest number of fields. I'm not sure I like that. Maybe it would be
     // better to save all the errors and combine them into one message that
     // explains why none of the variants matched.
-    let fallthrough_msg =
-        format!("data did not match any variant of untagged enum {}", params.type_name());
+    let fallthrough_msg = if first_attempt.is_some() {
+        format!("data did not match any variant of enum {}", params.type_name())
+    } else {
+        format!("data did not match any variant of untagged enum {}", params.type_name())
+    };
 
     quote_block! {
         let __content = try!(<_serde::private::de::Content as _serde::Deserialize>::deserialize(__deserializer));
 
+        #first_attempt
+
         #(
             if let _serde::export::Ok(__ok) = #attempts {
                 return _serde::export::Ok(__ok);
@@ -1271,7 +1427,7 @@ fn deserialize_externally_tagged_newtype_variant(
     field: &Field,
 ) -> Fragment {
     let this = &params.this;
-    match field.attrs.deserialize_with() {
+    match field_deserialize_with(field) {
         None => {
             let field_ty = &field.ty;
             quote_expr! {
@@ -1280,8 +1436,9 @@ fn deserialize_externally_tagged_newtype_variant(
                     #this::#variant_ident)
             }
         }
-        Some(path) => {
-            let (wrapper, wrapper_ty) = wrap_deserialize_field_with(params, field.ty, path);
+        Some(deserialize_with) => {
+            let (wrapper, wrapper_ty) =
+                wrap_deserialize_field_with(params, field.ty, &deserialize_with);
             quote_block! {
                 #wrapper
                 _serde::export::Result::map(
@@ -1299,7 +1456,7 @@ fn deserialize_untagged_newtype_variant(
     deserializer: Tokens,
 ) -> Fragment {
     let this = &params.this;
-    match field.attrs.deserialize_with() {
+    match field_deserialize_with(field) {
         None => {
             let field_ty = &field.ty;
             quote_expr! {
@@ -1308,8 +1465,9 @@ fn deserialize_untagged_newtype_variant(
                     #this::#variant_ident)
             }
         }
-        Some(path) => {
-            let (wrapper, wrapper_ty) = wrap_deserialize_field_with(params, field.ty, path);
+        Some(deserialize_with) => {
+            let (wrapper, wrapper_ty) =
+                wrap_deserialize_field_with(params, field.ty, &deserialize_with);
             quote_block! {
                 #wrapper
                 _serde::export::Result::map(
@@ -1324,12 +1482,20 @@ fn deserialize_generated_identifier(
     fields: Vec<(String, Ident)>,
     cattrs: &attr::Container,
     is_variant: bool,
+    collect_unknown: bool,
 ) -> Fragment {
     let this = quote!(__Field);
     let field_idents: &Vec<_> = &fields.iter().map(|&(_, ref ident)| ident).collect();
 
     let (ignore_variant, fallthrough) = if is_variant || cattrs.deny_unknown_fields() {
         (None, None)
+    } else if collect_unknown {
+        let other_variant = quote!(__other(_serde::export::String),);
+        let fallthrough = quote! {
+            _serde::export::Ok(__Field::__other(
+                _serde::private::de::ToUnknownKey::to_unknown_key(__value)))
+        };
+        (Some(other_variant), Some(fallthrough))
     } else {
         let ignore_variant = quote!(__ignore,);
         let fallthrough = quote!(_serde::export::Ok(__Field::__ignore));
@@ -1555,7 +1721,7 @@ fn deserialize_struct_visitor(
     let field_names_idents: Vec<_> = fields
         .iter()
         .enumerate()
-        .filter(|&(_, field)| !field.attrs.skip_deserializing())
+        .filter(|&(_, field)| !field.attrs.skip_deserializing() && !field.attrs.collect_unknown())
         .map(|(i, field)| (field.attrs.name().deserialize_name(), field_i(i)),)
         .collect();
 
@@ -1566,7 +1732,9 @@ fn deserialize_struct_visitor(
         }
     };
 
-    let field_visitor = deserialize_generated_identifier(field_names_idents, cattrs, false);
+    let collect_unknown = fields.iter().any(|field| field.attrs.collect_unknown());
+    let field_visitor =
+        deserialize_generated_identifier(field_names_idents, cattrs, false, collect_unknown);
 
     let visit_map = deserialize_map(struct_path, params, fields, cattrs);
 
@@ -1586,6 +1754,11 @@ fn deserialize_map(
         .map(|(i, field)| (field, field_i(i)))
         .collect();
 
+    // The field receiving unrecognized entries, if any.
+    let collect = fields_names
+        .iter()
+        .find(|&&(field, _)| field.attrs.collect_unknown());
+
     // Declare each field that will be deserialized.
     let let_values = fields_names
         .iter()
@@ -1593,28 +1766,34 @@ fn deserialize_map(
         .map(
             |&(field, ref name)| {
                 let field_ty = &field.ty;
-                quote! {
-                    let mut #name: _serde::export::Option<#field_ty> = _serde::export::None;
+                if field.attrs.collect_unknown() {
+                    quote! {
+                        let mut #name: #field_ty = _serde::export::Default::default();
+                    }
+                } else {
+                    quote! {
+                        let mut #name: _serde::export::Option<#field_ty> = _serde::export::None;
+                    }
                 }
             },
         );
 
     // Match arms to extract a value for a field.
     let value_arms = fields_names.iter()
-        .filter(|&&(field, _)| !field.attrs.skip_deserializing())
+        .filter(|&&(field, _)| !field.attrs.skip_deserializing() && !field.attrs.collect_unknown())
         .map(|&(field, ref name)| {
             let deser_name = field.attrs.name().deserialize_name();
 
-            let visit = match field.attrs.deserialize_with() {
+            let visit = match field_deserialize_with(field) {
                 None => {
                     let field_ty = &field.ty;
                     quote! {
                         try!(_serde::de::MapAccess::next_value::<#field_ty>(&mut __map))
                     }
                 }
-                Some(path) => {
+                Some(deserialize_with) => {
                     let (wrapper, wrapper_ty) = wrap_deserialize_field_with(
-                        params, field.ty, path);
+                        params, field.ty, &deserialize_with);
                     quote!({
                         #wrapper
                         try!(_serde::de::MapAccess::next_value::<#wrapper_ty>(&mut __map)).value
@@ -1632,7 +1811,13 @@ fn deserialize_map(
         });
 
     // Visit ignored values to consume them
-    let ignored_arm = if cattrs.deny_unknown_fields() {
+    let ignored_arm = if let Some(&(_, ref name)) = collect {
+        Some(quote! {
+            __Field::__other(__key) => {
+                try!(_serde::private::de::CollectUnknown::insert_unknown(&mut #name, __key, &mut __map));
+            }
+        })
+    } else if cattrs.deny_unknown_fields() {
         None
     } else {
         Some(quote! {
@@ -1664,7 +1849,7 @@ fn deserialize_map(
 
     let extract_values = fields_names
         .iter()
-        .filter(|&&(field, _)| !field.attrs.skip_deserializing())
+        .filter(|&&(field, _)| !field.attrs.skip_deserializing() && !field.attrs.collect_unknown())
         .map(
             |&(field, ref name)| {
                 let missing_expr = Match(expr_is_missing(&field, cattrs));
@@ -1742,13 +1927,59 @@ fn field_i(i: usize) -> Ident {
     Ident::new(format!("__field{}", i))
 }
 
+/// The function that deserializes a field, if the field is not deserialized by
+/// the `Deserialize` impl of its type. This is the `deserialize_with` function
+/// or the choice between `with_readable` and `with_compact`, followed by the
+/// `#[serde(validate)]` check and wrapped in a fallback for `#[serde(lenient)]`
+/// fields.
+fn field_deserialize_with(field: &Field) -> Option<Tokens> {
+    let field_ty = &field.ty;
+    let mut deserialize_with = field.attrs.deserialize_with().map(|path| quote!(#path));
+
+    if field.attrs.with_readable().is_some() || field.attrs.with_compact().is_some() {
+        let own = quote!(<#field_ty as _serde::Deserialize>::deserialize);
+        let readable = field.attrs
+            .with_readable()
+            .map_or_else(|| own.clone(), |path| quote!(#path::deserialize));
+        let compact = field.attrs
+            .with_compact()
+            .map_or_else(|| own.clone(), |path| quote!(#path::deserialize));
+        deserialize_with = Some(quote! {
+            (|__deserializer| _serde::private::de::deserialize_by_readability(__deserializer, #readable, #compact))
+        });
+    }
+
+    if let Some(validate) = field.attrs.validate() {
+        let deserialize = deserialize_with
+            .unwrap_or_else(|| quote!(<#field_ty as _serde::Deserialize>::deserialize));
+        deserialize_with = Some(quote! {
+            (|__deserializer| _serde::private::de::validate(#deserialize(__deserializer), #validate))
+        });
+    }
+
+    let fallback = match *field.attrs.lenient() {
+        attr::Default::None => return deserialize_with,
+        attr::Default::Default => quote!(_serde::export::Default::default),
+        attr::Default::Path(ref path) => quote!(#path),
+    };
+
+    let deserialize = deserialize_with
+        .unwrap_or_else(|| quote!(<#field_ty as _serde::Deserialize>::deserialize));
+    Some(quote! {
+        (|__deserializer| _serde::private::de::deserialize_lenient(__deserializer, #deserialize, #fallback))
+    })
+}
+
 /// This function wraps the expression in `#[serde(deserialize_with = "...")]`
 /// in a trait to prevent it from accessing the internal `Deserialize` state.
-fn wrap_deserialize_with(
+fn wrap_deserialize_with<T>(
     params: &Parameters,
     value_ty: Tokens,
-    deserialize_with: &syn::Path,
-) -> (Tokens, Tokens) {
+    deserialize_with: &T,
+) -> (Tokens, Tokens)
+where
+    T: ToTokens,
+{
     let this = &params.this;
     let (de_impl_generics, de_ty_generics, ty_generics, where_clause) = split_with_de_lifetime(params,);
     let delife = params.borrowed.de_lifetime();
@@ -1781,7 +2012,7 @@ fn wrap_deserialize_with(
 fn wrap_deserialize_field_with(
     params: &Parameters,
     field_ty: &syn::Ty,
-    deserialize_with: &syn::Path,
+    deserialize_with: &Tokens,
 ) -> (Tokens, Tokens) {
     wrap_deserialize_with(params, quote!(#field_ty), deserialize_with)
 }
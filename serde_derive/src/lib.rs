@@ -46,6 +46,7 @@ mod fragment;
 
 mod ser;
 mod de;
+mod size;
 
 #[proc_macro_derive(Serialize, attributes(serde))]
 pub fn derive_serialize(input: TokenStream) -> TokenStream {
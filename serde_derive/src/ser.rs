@@ -7,10 +7,11 @@
 // except according to those terms.
 
 use syn::{self, Ident};
-use quote::Tokens;
+use quote::{Tokens, ToTokens};
 
 use bound;
 use fragment::{Fragment, Stmts, Match};
+use size;
 use internals::ast::{Body, Container, Field, Style, Variant};
 use internals::{attr, Ctxt};
 
@@ -27,6 +28,25 @@ pub fn expand_derive_serialize(input: &syn::DeriveInput) -> Result<Tokens, Strin
     let (impl_generics, ty_generics, where_clause) = params.generics.split_for_impl();
     let dummy_const = Ident::new(format!("_IMPL_SERIALIZE_FOR_{}", ident));
     let body = Stmts(serialize_body(&cont, &params));
+    let body = match cont.attrs.version() {
+        None => quote!(#body),
+        Some(version) => {
+            quote! {
+                struct __Payload<'__a>(&'__a #ident);
+
+                impl<'__a> _serde::Serialize for __Payload<'__a> {
+                    fn serialize<__S>(&self, __serializer: __S) -> _serde::export::Result<__S::Ok, __S::Error>
+                        where __S: _serde::Serializer
+                    {
+                        let __self = self.0;
+                        #body
+                    }
+                }
+
+                _serde::private::ser::serialize_versioned(#version, &__Payload(self), __serializer)
+            }
+        }
+    };
 
     let impl_block = if let Some(remote) = cont.attrs.remote() {
         let vis = &input.vis;
@@ -52,11 +72,25 @@ pub fn expand_derive_serialize(input: &syn::DeriveInput) -> Result<Tokens, Strin
         }
     };
 
+    let names_impl = if cont.attrs.expose_names() {
+        Some(expose_names(&cont))
+    } else {
+        None
+    };
+
+    let max_size_impl = if cont.attrs.max_size() {
+        Some(size::expand_max_size(&cont))
+    } else {
+        None
+    };
+
     let generated = quote! {
         #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
         const #dummy_const: () = {
             extern crate serde as _serde;
             #impl_block
+            #names_impl
+            #max_size_impl
         };
     };
     Ok(generated)
@@ -74,9 +108,62 @@ fn precondition(cx: &Ctxt, cont: &Container) {
     }
 }
 
+/// The `FIELDS` or `VARIANTS` constant for `#[serde(expose_names)]`, listing
+/// the names the derived Serialize impl writes. Associated consts need Rust
+/// 1.20, unlike the rest of the generated code.
+fn expose_names(cont: &Container) -> Tokens {
+    let ident = &cont.ident;
+    let generics = bound::without_defaults(cont.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let constant = match cont.body {
+        Body::Enum(ref variants) => {
+            let names = variants
+                .iter()
+                .filter(|variant| !variant.attrs.skip_serializing() && !variant.attrs.untagged())
+                .map(|variant| variant.attrs.name().serialize_name());
+            quote! {
+                /// The names of the variants of this enum.
+                pub const VARIANTS: &'static [&'static str] = &[ #(#names),* ];
+            }
+        }
+        Body::Struct(_, ref fields) => {
+            let names = fields
+                .iter()
+                .filter(|field| !field.attrs.skip_serializing() && !field.attrs.collect_unknown())
+                .map(|field| field.attrs.name().serialize_name());
+            quote! {
+                /// The names of the fields of this struct.
+                pub const FIELDS: &'static [&'static str] = &[ #(#names),* ];
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #constant
+        }
+    }
+}
+
+/// Emitted by the Deserialize derive for `#[serde(expose_names)]`. The
+/// constants come from the Serialize derive, so the attribute is rejected on
+/// a type that does not implement Serialize under the same bounds as the
+/// derived impl.
+pub fn expose_names_requires_serialize(cont: &Container) -> Tokens {
+    let ident = &cont.ident;
+    let params = Parameters::new(cont);
+    let (impl_generics, ty_generics, where_clause) = params.generics.split_for_impl();
+
+    quote! {
+        trait ExposeNamesRequiresSerialize: _serde::Serialize {}
+        impl #impl_generics ExposeNamesRequiresSerialize for #ident #ty_generics #where_clause {}
+    }
+}
+
 struct Parameters {
     /// Variable holding the value being serialized. Either `self` for local
-    /// types or `__self` for remote types.
+    /// types or `__self` for remote and versioned types.
     self_var: Ident,
 
     /// Path to the type the impl is for. Either a single `Ident` for local
@@ -89,12 +176,16 @@ struct Parameters {
 
     /// Type has a `serde(remote = "...")` attribute.
     is_remote: bool,
+
+    /// Type has a `repr(packed)` attribute, so its fields are read by copy
+    /// and every serialized field must be `Copy`.
+    is_packed: bool,
 }
 
 impl Parameters {
     fn new(cont: &Container) -> Self {
         let is_remote = cont.attrs.remote().is_some();
-        let self_var = if is_remote {
+        let self_var = if is_remote || cont.attrs.version().is_some() {
             Ident::new("__self")
         } else {
             Ident::new("self")
@@ -112,6 +203,7 @@ impl Parameters {
             this: this,
             generics: generics,
             is_remote: is_remote,
+            is_packed: cont.attrs.is_packed(),
         }
     }
 
@@ -143,14 +235,16 @@ fn build_generics(cont: &Container) -> syn::Generics {
     }
 }
 
-// Fields with a `skip_serializing` or `serialize_with` attribute, or which
-// belong to a variant with a `serialize_with` attribute, are not serialized by
-// us so we do not generate a bound. Fields with a `bound` attribute specify
-// their own bound so we do not generate one. All other fields may need a `T:
-// Serialize` bound where T is the type of the field.
+// Fields with a `skip_serializing` or `serialize_with` attribute, with both
+// `with_readable` and `with_compact`, or which belong to a variant with a
+// `serialize_with` attribute, are not serialized by us so we do not generate a
+// bound. Fields with a `bound` attribute specify their own bound so we do not
+// generate one. All other fields may need a `T: Serialize` bound where T is
+// the type of the field.
 fn needs_serialize_bound(field: &attr::Field, variant: Option<&attr::Variant>) -> bool {
     !field.skip_serializing() &&
     field.serialize_with().is_none() &&
+    (field.with_readable().is_none() || field.with_compact().is_none()) &&
     field.ser_bound().is_none() &&
     variant.map_or(true, |variant| variant.serialize_with().is_none())
 }
@@ -206,8 +300,8 @@ fn serialize_newtype_struct(
     let type_name = cattrs.name().serialize_name();
 
     let mut field_expr = get_field(params, field, 0);
-    if let Some(path) = field.attrs.serialize_with() {
-        field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+    if let Some(path) = field_serialize_with(field) {
+        field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
     }
 
     quote_expr! {
@@ -241,12 +335,17 @@ fn serialize_tuple_struct(
 fn serialize_struct(params: &Parameters, fields: &[Field], cattrs: &attr::Container) -> Fragment {
     assert!(fields.len() as u64 <= u32::MAX as u64);
 
+    if let Some(collect) = fields.iter().find(|field| field.attrs.collect_unknown()) {
+        return serialize_struct_as_map(params, fields, collect);
+    }
+
     let serialize_fields = serialize_struct_visitor(
         fields,
         params,
         false,
         quote!(_serde::ser::SerializeStruct::serialize_field),
-        quote!(_serde::ser::SerializeStruct::skip_field),
+        Some(quote!(_serde::ser::SerializeStruct::skip_field)),
+        annotate_func(cattrs, quote!(_serde::ser::SerializeStruct::annotate)),
     );
 
     let type_name = cattrs.name().serialize_name();
@@ -278,6 +377,49 @@ fn serialize_struct(params: &Parameters, fields: &[Field], cattrs: &attr::Contai
     }
 }
 
+/// A struct with a `#[serde(collect_unknown)]` field is serialized as a map so
+/// that the collected entries can follow the named fields.
+fn serialize_struct_as_map(params: &Parameters, fields: &[Field], collect: &Field) -> Fragment {
+    let serialize_fields = serialize_struct_visitor(
+        fields,
+        params,
+        false,
+        quote!(_serde::ser::SerializeMap::serialize_entry),
+        None,
+        None,
+    );
+
+    let len = fields
+        .iter()
+        .filter(|&field| !field.attrs.skip_serializing() && !field.attrs.collect_unknown())
+        .map(
+            |field| match field.attrs.skip_serializing_if() {
+                None => quote!(1),
+                Some(path) => {
+                    let ident = field.ident.clone().expect("struct has unnamed fields");
+                    let field_expr = get_field(params, field, ident);
+                    quote!(if #path(#field_expr) { 0 } else { 1 })
+                }
+            },
+        )
+        .fold(quote!(0), |sum, expr| quote!(#sum + #expr));
+
+    let collect_ident = collect.ident.clone().expect("struct has unnamed fields");
+    let collect_expr = get_field(params, collect, collect_ident);
+
+    quote_block! {
+        let __serde_len = #len + _serde::private::ser::unknown_len(#collect_expr);
+        let mut __serde_state = try!(_serde::Serializer::serialize_map(
+            __serializer,
+            _serde::export::Some(__serde_len)));
+        #(#serialize_fields)*
+        for (__key, __value) in #collect_expr {
+            try!(_serde::ser::SerializeMap::serialize_entry(&mut __serde_state, __key, __value));
+        }
+        _serde::ser::SerializeMap::end(__serde_state)
+    }
+}
+
 fn serialize_enum(params: &Parameters, variants: &[Variant], cattrs: &attr::Container) -> Fragment {
     assert!(variants.len() as u64 <= u32::MAX as u64);
 
@@ -370,18 +512,23 @@ fn serialize_variant(
         };
 
         let body = Match(
-            match *cattrs.tag() {
-                attr::EnumTag::External => {
-                    serialize_externally_tagged_variant(params, variant, variant_index, cattrs)
-                }
-                attr::EnumTag::Internal { ref tag } => {
-                    serialize_internally_tagged_variant(params, variant, cattrs, tag)
+            if let Some(repr) = cattrs.repr() {
+                serialize_repr_variant(params, variant, repr)
+            } else {
+                match *cattrs.tag() {
+                    _ if variant.attrs.untagged() => serialize_untagged_variant(params, variant, cattrs),
+                    attr::EnumTag::External => {
+                        serialize_externally_tagged_variant(params, variant, variant_index, cattrs)
+                    }
+                    attr::EnumTag::Internal { ref tag } => {
+                        serialize_internally_tagged_variant(params, variant, cattrs, tag)
+                    }
+                    attr::EnumTag::Adjacent {
+                        ref tag,
+                        ref content,
+                    } => serialize_adjacently_tagged_variant(params, variant, cattrs, tag, content),
+                    attr::EnumTag::None => serialize_untagged_variant(params, variant, cattrs),
                 }
-                attr::EnumTag::Adjacent {
-                    ref tag,
-                    ref content,
-                } => serialize_adjacently_tagged_variant(params, variant, cattrs, tag, content),
-                attr::EnumTag::None => serialize_untagged_variant(params, variant, cattrs),
             },
         );
 
@@ -391,6 +538,15 @@ fn serialize_variant(
     }
 }
 
+fn serialize_repr_variant(params: &Parameters, variant: &Variant, repr: &Ident) -> Fragment {
+    let this = &params.this;
+    let variant_ident = &variant.ident;
+
+    quote_expr! {
+        _serde::Serialize::serialize(&(#this::#variant_ident as #repr), __serializer)
+    }
+}
+
 fn serialize_externally_tagged_variant(
     params: &Parameters,
     variant: &Variant,
@@ -427,8 +583,8 @@ fn serialize_externally_tagged_variant(
         Style::Newtype => {
             let field = &variant.fields[0];
             let mut field_expr = quote!(__field0);
-            if let Some(path) = field.attrs.serialize_with() {
-                field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+            if let Some(path) = field_serialize_with(field) {
+                field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
             }
 
             quote_expr! {
@@ -461,6 +617,7 @@ fn serialize_externally_tagged_variant(
                 params,
                 &variant.fields,
                 &type_name,
+                cattrs,
             )
         }
     }
@@ -505,8 +662,8 @@ fn serialize_internally_tagged_variant(
         Style::Newtype => {
             let field = &variant.fields[0];
             let mut field_expr = quote!(__field0);
-            if let Some(path) = field.attrs.serialize_with() {
-                field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+            if let Some(path) = field_serialize_with(field) {
+                field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
             }
 
             quote_expr! {
@@ -529,6 +686,7 @@ fn serialize_internally_tagged_variant(
                 params,
                 &variant.fields,
                 &type_name,
+                cattrs,
             )
         }
         Style::Tuple => unreachable!("checked in serde_derive_internals"),
@@ -566,8 +724,8 @@ fn serialize_adjacently_tagged_variant(
                 Style::Newtype => {
                     let field = &variant.fields[0];
                     let mut field_expr = quote!(__field0);
-                    if let Some(path) = field.attrs.serialize_with() {
-                        field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+                    if let Some(path) = field_serialize_with(field) {
+                        field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
                     }
 
                     quote_expr! {
@@ -583,6 +741,7 @@ fn serialize_adjacently_tagged_variant(
                         params,
                         &variant.fields,
                         &variant_name,
+                        cattrs,
                     )
                 }
             }
@@ -677,8 +836,8 @@ fn serialize_untagged_variant(
         Style::Newtype => {
             let field = &variant.fields[0];
             let mut field_expr = quote!(__field0);
-            if let Some(path) = field.attrs.serialize_with() {
-                field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+            if let Some(path) = field_serialize_with(field) {
+                field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
             }
 
             quote_expr! {
@@ -688,7 +847,13 @@ fn serialize_untagged_variant(
         Style::Tuple => serialize_tuple_variant(TupleVariant::Untagged, params, &variant.fields),
         Style::Struct => {
             let type_name = cattrs.name().serialize_name();
-            serialize_struct_variant(StructVariant::Untagged, params, &variant.fields, &type_name)
+            serialize_struct_variant(
+                StructVariant::Untagged,
+                params,
+                &variant.fields,
+                &type_name,
+                cattrs,
+            )
         }
     }
 }
@@ -762,24 +927,28 @@ fn serialize_struct_variant<'a>(
     params: &Parameters,
     fields: &[Field],
     name: &str,
+    cattrs: &attr::Container,
 ) -> Fragment {
-    let (method, skip_method) = match context {
+    let (method, skip_method, annotate_method) = match context {
         StructVariant::ExternallyTagged { .. } => {
             (
                 quote!(_serde::ser::SerializeStructVariant::serialize_field),
-                quote!(_serde::ser::SerializeStructVariant::skip_field),
+                Some(quote!(_serde::ser::SerializeStructVariant::skip_field)),
+                annotate_func(cattrs, quote!(_serde::ser::SerializeStructVariant::annotate)),
             )
         }
         StructVariant::InternallyTagged { .. } |
         StructVariant::Untagged => {
             (
                 quote!(_serde::ser::SerializeStruct::serialize_field),
-                quote!(_serde::ser::SerializeStruct::skip_field),
+                Some(quote!(_serde::ser::SerializeStruct::skip_field)),
+                annotate_func(cattrs, quote!(_serde::ser::SerializeStruct::annotate)),
             )
         }
     };
 
-    let serialize_fields = serialize_struct_visitor(fields, params, true, method, skip_method);
+    let serialize_fields =
+        serialize_struct_visitor(fields, params, true, method, skip_method, annotate_method);
 
     let mut serialized_fields = fields
         .iter()
@@ -871,8 +1040,8 @@ fn serialize_tuple_struct_visitor(
                     .skip_serializing_if()
                     .map(|path| quote!(#path(#field_expr)));
 
-                if let Some(path) = field.attrs.serialize_with() {
-                    field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+                if let Some(path) = field_serialize_with(field) {
+                    field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
                 }
 
                 let ser = quote! {
@@ -893,11 +1062,12 @@ fn serialize_struct_visitor(
     params: &Parameters,
     is_enum: bool,
     func: Tokens,
-    skip_func: Tokens,
+    skip_func: Option<Tokens>,
+    annotate_func: Option<Tokens>,
 ) -> Vec<Tokens> {
     fields
         .iter()
-        .filter(|&field| !field.attrs.skip_serializing())
+        .filter(|&field| !field.attrs.skip_serializing() && !field.attrs.collect_unknown())
         .map(
             |field| {
                 let field_ident = field.ident.clone().expect("struct has unnamed field");
@@ -914,17 +1084,32 @@ fn serialize_struct_visitor(
                     .skip_serializing_if()
                     .map(|path| quote!(#path(#field_expr)));
 
-                if let Some(path) = field.attrs.serialize_with() {
-                    field_expr = wrap_serialize_field_with(params, field.ty, path, field_expr);
+                if let Some(path) = field_serialize_with(field) {
+                    field_expr = wrap_serialize_field_with(params, field.ty, &path, field_expr);
                 }
 
+                let annotate = match (annotate_func.as_ref(), field.attrs.doc()) {
+                    (Some(annotate_func), Some(doc)) => {
+                        Some(quote!(try!(#annotate_func(&mut __serde_state, #key_expr, #doc));))
+                    }
+                    _ => None,
+                };
+
                 let ser = quote! {
+                    #annotate
                     try!(#func(&mut __serde_state, #key_expr, #field_expr));
                 };
 
-                match skip {
-                    None => ser,
-                    Some(skip) => {
+                match (skip, skip_func.as_ref()) {
+                    (None, _) => ser,
+                    (Some(skip), None) => {
+                        quote! {
+                            if !#skip {
+                                #ser
+                            }
+                        }
+                    }
+                    (Some(skip), Some(skip_func)) => {
                         quote! {
                             if !#skip {
                                 #ser
@@ -939,10 +1124,45 @@ fn serialize_struct_visitor(
         .collect()
 }
 
+/// The function that passes the doc comment of a field to the serializer, if
+/// the container has `#[serde(annotate)]`.
+fn annotate_func(cattrs: &attr::Container, func: Tokens) -> Option<Tokens> {
+    if cattrs.annotate() {
+        Some(func)
+    } else {
+        None
+    }
+}
+
+/// The function that serializes a field, if the field is not serialized by the
+/// `Serialize` impl of its type. This is the `serialize_with` function or the
+/// choice between `with_readable` and `with_compact`.
+fn field_serialize_with(field: &Field) -> Option<Tokens> {
+    if let Some(path) = field.attrs.serialize_with() {
+        return Some(quote!(#path));
+    }
+
+    if field.attrs.with_readable().is_none() && field.attrs.with_compact().is_none() {
+        return None;
+    }
+
+    let field_ty = &field.ty;
+    let own = quote!(<#field_ty as _serde::Serialize>::serialize);
+    let readable = field.attrs
+        .with_readable()
+        .map_or_else(|| own.clone(), |path| quote!(#path::serialize));
+    let compact = field.attrs
+        .with_compact()
+        .map_or_else(|| own.clone(), |path| quote!(#path::serialize));
+    Some(quote! {
+        (|__value, __serializer| _serde::private::ser::serialize_by_readability(__value, __serializer, #readable, #compact))
+    })
+}
+
 fn wrap_serialize_field_with(
     params: &Parameters,
     field_ty: &syn::Ty,
-    serialize_with: &syn::Path,
+    serialize_with: &Tokens,
     field_expr: Tokens,
 ) -> Tokens {
     wrap_serialize_with(params,
@@ -968,12 +1188,15 @@ fn wrap_serialize_variant_with(
     wrap_serialize_with(params, serialize_with, field_tys.as_slice(), field_exprs.as_slice())
 }
 
-fn wrap_serialize_with(
+fn wrap_serialize_with<T>(
     params: &Parameters,
-    serialize_with: &syn::Path,
+    serialize_with: &T,
     field_tys: &[&syn::Ty],
     field_exprs: &[Tokens],
-) -> Tokens {
+) -> Tokens
+where
+    T: ToTokens,
+{
     let this = &params.this;
     let (_, ty_generics, where_clause) = params.generics.split_for_impl();
 
@@ -1025,12 +1248,21 @@ where
     match (params.is_remote, field.attrs.getter()) {
         (false, None) => {
             let ident = ident.into();
-            quote!(&#self_var.#ident)
+            if params.is_packed {
+                let ty = field.ty;
+                quote!(&_serde::private::ser::copy_packed::<#ty>({#self_var.#ident}))
+            } else {
+                quote!(&#self_var.#ident)
+            }
         }
         (true, None) => {
             let ty = field.ty;
             let ident = ident.into();
-            quote!(_serde::private::ser::constrain::<#ty>(&#self_var.#ident))
+            if params.is_packed {
+                quote!(&_serde::private::ser::copy_packed::<#ty>({#self_var.#ident}))
+            } else {
+                quote!(_serde::private::ser::constrain::<#ty>(&#self_var.#ident))
+            }
         }
         (true, Some(getter)) => {
             let ty = field.ty;
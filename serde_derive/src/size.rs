@@ -0,0 +1,308 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `serde::size::MaxSize` impl for `#[serde(max_size)]`.
+//!
+//! Every function here mirrors a function of the same name in `ser.rs` and
+//! returns an expression of type `Option<usize>` that bounds the cost of the
+//! calls that function makes on the serializer. Anything that is not
+//! serialized by a derived impl, such as a `serialize_with` function, is
+//! unbounded.
+
+use syn;
+use quote::Tokens;
+
+use bound;
+use internals::ast::{Body, Container, Field, Style, Variant};
+use internals::attr;
+
+pub fn expand_max_size(cont: &Container) -> Tokens {
+    let ident = &cont.ident;
+    let generics = build_generics(cont);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = max_size_body(cont);
+    let body = match cont.attrs.version() {
+        None => body,
+        Some(_) => quote!(_serde::private::ser::versioned_size(__model, #body)),
+    };
+
+    quote! {
+        impl #impl_generics _serde::size::MaxSize for #ident #ty_generics #where_clause {
+            fn max_serialized_size<__C>(__model: &mut __C) -> _serde::export::Option<usize>
+                where __C: _serde::size::CostModel
+            {
+                #body
+            }
+        }
+    }
+}
+
+// All the generics in the input, plus a bound `T: MaxSize` for each generic
+// field type that the derived Serialize impl serializes itself.
+fn build_generics(cont: &Container) -> syn::Generics {
+    let generics = bound::without_defaults(cont.generics);
+    bound::with_bound(
+        cont,
+        &generics,
+        needs_max_size_bound,
+        &path!(_serde::size::MaxSize),
+    )
+}
+
+fn needs_max_size_bound(field: &attr::Field, variant: Option<&attr::Variant>) -> bool {
+    !field.skip_serializing() && !has_serialize_with(field) &&
+    variant.map_or(true, |variant| variant.serialize_with().is_none())
+}
+
+fn has_serialize_with(field: &attr::Field) -> bool {
+    field.serialize_with().is_some() || field.with_readable().is_some() ||
+    field.with_compact().is_some()
+}
+
+fn max_size_body(cont: &Container) -> Tokens {
+    if let Some(into_type) = cont.attrs.into_type() {
+        return quote!(<#into_type as _serde::size::MaxSize>::max_serialized_size(__model));
+    }
+
+    let type_name = cont.attrs.name().serialize_name();
+    match cont.body {
+        Body::Enum(ref variants) => max_size_enum(variants, &cont.attrs),
+        Body::Struct(Style::Struct, ref fields) => {
+            if fields.iter().any(|field| field.attrs.collect_unknown()) {
+                // The collected entries have no upper bound.
+                return none();
+            }
+            max_size_struct(&type_name, fields, 0)
+        }
+        Body::Struct(Style::Tuple, ref fields) => {
+            let len = fields.len();
+            let header = some(quote!(__model.tuple_struct(#type_name, #len)));
+            sum(Some(header).into_iter().chain(max_size_elements(fields)))
+        }
+        Body::Struct(Style::Newtype, ref fields) => {
+            let header = some(quote!(__model.newtype_struct(#type_name)));
+            sum(vec![header, max_size_field(&fields[0])])
+        }
+        Body::Struct(Style::Unit, _) => some(quote!(__model.unit_struct(#type_name))),
+    }
+}
+
+fn max_size_enum(variants: &[Variant], cattrs: &attr::Container) -> Tokens {
+    let type_name = cattrs.name().serialize_name();
+
+    // Skipped variants fail to serialize, so they write nothing.
+    variants
+        .iter()
+        .enumerate()
+        .filter(|&(_, variant)| !variant.attrs.skip_serializing())
+        .map(
+            |(variant_index, variant)| {
+                max_size_variant(variant, variant_index as u32, &type_name, cattrs)
+            },
+        )
+        .fold(
+            some(quote!(0)),
+            |max, size| quote!(_serde::private::ser::size_max(#max, #size)),
+        )
+}
+
+fn max_size_variant(
+    variant: &Variant,
+    variant_index: u32,
+    type_name: &str,
+    cattrs: &attr::Container,
+) -> Tokens {
+    if let Some(repr) = cattrs.repr() {
+        return quote!(<#repr as _serde::size::MaxSize>::max_serialized_size(__model));
+    }
+
+    if variant.attrs.serialize_with().is_some() {
+        return none();
+    }
+
+    match *cattrs.tag() {
+        _ if variant.attrs.untagged() => max_size_untagged_variant(variant, type_name),
+        attr::EnumTag::External => {
+            max_size_externally_tagged_variant(variant, variant_index, type_name)
+        }
+        attr::EnumTag::Internal { ref tag } => {
+            max_size_internally_tagged_variant(variant, type_name, tag)
+        }
+        attr::EnumTag::Adjacent {
+            ref tag,
+            ref content,
+        } => max_size_adjacently_tagged_variant(variant, type_name, tag, content),
+        attr::EnumTag::None => max_size_untagged_variant(variant, type_name),
+    }
+}
+
+fn max_size_externally_tagged_variant(
+    variant: &Variant,
+    variant_index: u32,
+    type_name: &str,
+) -> Tokens {
+    let variant_name = variant.attrs.name().serialize_name();
+    let tag = some(quote!(__model.variant(#type_name, #variant_index, #variant_name)));
+
+    let content = match variant.style {
+        Style::Unit => return tag,
+        Style::Newtype => max_size_field(&variant.fields[0]),
+        Style::Tuple => max_size_tuple(&variant.fields),
+        Style::Struct => max_size_struct(type_name, &variant.fields, 0),
+    };
+    sum(vec![tag, content])
+}
+
+fn max_size_internally_tagged_variant(variant: &Variant, type_name: &str, tag: &str) -> Tokens {
+    let variant_name = variant.attrs.name().serialize_name();
+    let tag = vec![
+        some(quote!(_serde::private::ser::field_size(__model, 0, 0, #tag))),
+        some(quote!(__model.str(#variant_name))),
+    ];
+
+    match variant.style {
+        Style::Unit => {
+            let header = some(quote!(_serde::private::ser::struct_size(__model, #type_name, 1, 1)));
+            sum(Some(header).into_iter().chain(tag))
+        }
+        // Goes through `serialize_tagged_newtype`, whose calls depend on the
+        // content.
+        Style::Newtype => none(),
+        Style::Struct => {
+            // The tag is the first field of the struct.
+            let fields = max_size_struct(type_name, &variant.fields, 1);
+            sum(tag.into_iter().chain(Some(fields)))
+        }
+        Style::Tuple => unreachable!("checked in serde_derive_internals"),
+    }
+}
+
+fn max_size_adjacently_tagged_variant(
+    variant: &Variant,
+    type_name: &str,
+    tag: &str,
+    content: &str,
+) -> Tokens {
+    let variant_name = variant.attrs.name().serialize_name();
+    let tag = vec![
+        some(quote!(_serde::private::ser::field_size(__model, 0, 0, #tag))),
+        some(quote!(__model.str(#variant_name))),
+    ];
+
+    let value = match variant.style {
+        Style::Unit => {
+            let header = some(quote!(_serde::private::ser::struct_size(__model, #type_name, 1, 1)));
+            return sum(Some(header).into_iter().chain(tag));
+        }
+        Style::Newtype => max_size_field(&variant.fields[0]),
+        Style::Tuple => max_size_tuple(&variant.fields),
+        Style::Struct => max_size_struct(&variant_name, &variant.fields, 0),
+    };
+
+    let header = some(quote!(_serde::private::ser::struct_size(__model, #type_name, 2, 2)));
+    let content_key = some(quote!(_serde::private::ser::field_size(__model, 1, 1, #content)));
+    sum(
+        Some(header)
+            .into_iter()
+            .chain(tag)
+            .chain(vec![content_key, value]),
+    )
+}
+
+fn max_size_untagged_variant(variant: &Variant, type_name: &str) -> Tokens {
+    match variant.style {
+        Style::Unit => some(quote!(__model.unit())),
+        Style::Newtype => max_size_field(&variant.fields[0]),
+        Style::Tuple => max_size_tuple(&variant.fields),
+        Style::Struct => max_size_struct(type_name, &variant.fields, 0),
+    }
+}
+
+/// The length and the elements of a tuple variant.
+fn max_size_tuple(fields: &[Field]) -> Tokens {
+    let len = fields.len();
+    let header = some(quote!(__model.tuple(#len)));
+    sum(Some(header).into_iter().chain(max_size_elements(fields)))
+}
+
+/// The header and the fields of a struct or struct variant. `first_index` is
+/// the index of the first field, after any tag the serializer writes first.
+fn max_size_struct(name: &str, fields: &[Field], first_index: usize) -> Tokens {
+    let fields: Vec<_> = fields
+        .iter()
+        .filter(|&field| !field.attrs.skip_serializing())
+        .collect();
+
+    let max_len = first_index + fields.len();
+    let min_len = max_len -
+                  fields
+                      .iter()
+                      .filter(|field| field.attrs.skip_serializing_if().is_some())
+                      .count();
+    let header = some(quote!(_serde::private::ser::struct_size(__model, #name, #min_len, #max_len)));
+
+    let mut parts = vec![header];
+    let mut skippable = 0;
+    for (index, field) in fields.into_iter().enumerate() {
+        let max_index = first_index + index;
+        let min_index = max_index - skippable;
+        let key = field.attrs.name().serialize_name();
+        parts.push(
+            some(quote!(_serde::private::ser::field_size(__model, #min_index, #max_index, #key))),
+        );
+        parts.push(max_size_field(field));
+        if field.attrs.skip_serializing_if().is_some() {
+            skippable += 1;
+        }
+    }
+    sum(parts)
+}
+
+/// The separators and values of the elements of a tuple struct or variant.
+fn max_size_elements(fields: &[Field]) -> Vec<Tokens> {
+    let mut parts = Vec::new();
+    let mut skippable = 0;
+    for (max_index, field) in fields.iter().enumerate() {
+        let min_index = max_index - skippable;
+        parts.push(some(quote!(_serde::private::ser::element_size(__model, #min_index, #max_index))));
+        parts.push(max_size_field(field));
+        if field.attrs.skip_serializing_if().is_some() {
+            skippable += 1;
+        }
+    }
+    parts
+}
+
+fn max_size_field(field: &Field) -> Tokens {
+    if has_serialize_with(&field.attrs) {
+        return none();
+    }
+    let ty = field.ty;
+    quote!(<#ty as _serde::size::MaxSize>::max_serialized_size(__model))
+}
+
+fn sum<I>(parts: I) -> Tokens
+where
+    I: IntoIterator<Item = Tokens>,
+{
+    parts
+        .into_iter()
+        .fold(
+            some(quote!(0)),
+            |sum, part| quote!(_serde::private::ser::size_add(#sum, #part)),
+        )
+}
+
+fn some(cost: Tokens) -> Tokens {
+    quote!(_serde::export::Some(#cost))
+}
+
+fn none() -> Tokens {
+    quote!(_serde::export::None)
+}
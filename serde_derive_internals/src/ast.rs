@@ -59,15 +59,15 @@ impl<'a> Container<'a> {
         match body {
             Body::Enum(ref mut variants) => {
                 for ref mut variant in variants {
-                    variant.attrs.rename_by_rule(attrs.rename_all());
+                    variant.attrs.rename_by_rules(attrs.rename_all_rules());
                     for ref mut field in &mut variant.fields {
-                        field.attrs.rename_by_rule(variant.attrs.rename_all());
+                        field.attrs.rename_by_rules(variant.attrs.rename_all_rules());
                     }
                 }
             }
             Body::Struct(_, ref mut fields) => {
                 for field in fields {
-                    field.attrs.rename_by_rule(attrs.rename_all());
+                    field.attrs.rename_by_rules(attrs.rename_all_rules());
                 }
             }
         }
@@ -11,6 +11,7 @@ use syn;
 use syn::MetaItem::{List, NameValue, Word};
 use syn::NestedMetaItem::{Literal, MetaItem};
 use synom::IResult;
+use std::cmp;
 use std::collections::BTreeSet;
 use std::str::FromStr;
 
@@ -99,13 +100,33 @@ impl Name {
     }
 }
 
+/// The `rename_all` rules of a container or variant, which may differ between
+/// serializing and deserializing.
+#[derive(Debug)]
+pub struct RenameAllRules {
+    serialize: RenameRule,
+    deserialize: RenameRule,
+}
+
+impl RenameAllRules {
+    /// Return the rule applied to the children when serializing.
+    pub fn serialize_rule(&self) -> &RenameRule {
+        &self.serialize
+    }
+
+    /// Return the rule applied to the children when deserializing.
+    pub fn deserialize_rule(&self) -> &RenameRule {
+        &self.deserialize
+    }
+}
+
 /// Represents container (e.g. struct) attribute information
 #[derive(Debug)]
 pub struct Container {
     name: Name,
     deny_unknown_fields: bool,
     default: Default,
-    rename_all: RenameRule,
+    rename_all: RenameAllRules,
     ser_bound: Option<Vec<syn::WherePredicate>>,
     de_bound: Option<Vec<syn::WherePredicate>>,
     tag: EnumTag,
@@ -113,6 +134,13 @@ pub struct Container {
     into_type: Option<syn::Ty>,
     remote: Option<syn::Path>,
     identifier: Identifier,
+    validate: Option<syn::Path>,
+    is_packed: bool,
+    repr: Option<syn::Ident>,
+    expose_names: bool,
+    annotate: bool,
+    max_size: bool,
+    version: Option<u32>,
 }
 
 /// Styles of representing an enum.
@@ -171,7 +199,8 @@ impl Container {
         let mut de_name = Attr::none(cx, "rename");
         let mut deny_unknown_fields = BoolAttr::none(cx, "deny_unknown_fields");
         let mut default = Attr::none(cx, "default");
-        let mut rename_all = Attr::none(cx, "rename_all");
+        let mut ser_rename_all = Attr::none(cx, "rename_all");
+        let mut de_rename_all = Attr::none(cx, "rename_all");
         let mut ser_bound = Attr::none(cx, "bound");
         let mut de_bound = Attr::none(cx, "bound");
         let mut untagged = BoolAttr::none(cx, "untagged");
@@ -182,6 +211,12 @@ impl Container {
         let mut remote = Attr::none(cx, "remote");
         let mut field_identifier = BoolAttr::none(cx, "field_identifier");
         let mut variant_identifier = BoolAttr::none(cx, "variant_identifier");
+        let mut validate = Attr::none(cx, "validate");
+        let mut repr = Attr::none(cx, "repr");
+        let mut expose_names = BoolAttr::none(cx, "expose_names");
+        let mut annotate = BoolAttr::none(cx, "annotate");
+        let mut max_size = BoolAttr::none(cx, "max_size");
+        let mut version = Attr::none(cx, "version");
 
         for meta_items in item.attrs.iter().filter_map(get_serde_meta_items) {
             for meta_item in meta_items {
@@ -204,15 +239,17 @@ impl Container {
 
                     // Parse `#[serde(rename_all = "foo")]`
                     MetaItem(NameValue(ref name, ref lit)) if name == "rename_all" => {
-                        if let Ok(s) = get_string_from_lit(cx, name.as_ref(), name.as_ref(), lit) {
-                            match RenameRule::from_str(&s) {
-                                Ok(rename_rule) => rename_all.set(rename_rule),
-                                Err(()) => {
-                                    cx.error(format!("unknown rename rule for #[serde(rename_all \
-                                                      = {:?})]",
-                                                     s))
-                                }
-                            }
+                        if let Ok(rule) = parse_lit_into_rename_rule(cx, name.as_ref(), name.as_ref(), lit) {
+                            ser_rename_all.set(rule);
+                            de_rename_all.set(rule);
+                        }
+                    }
+
+                    // Parse `#[serde(rename_all(serialize = "foo", deserialize = "bar"))]`
+                    MetaItem(List(ref name, ref meta_items)) if name == "rename_all" => {
+                        if let Ok((ser, de)) = get_rename_all_rules(cx, meta_items) {
+                            ser_rename_all.set_opt(ser);
+                            de_rename_all.set_opt(de);
                         }
                     }
 
@@ -344,9 +381,69 @@ impl Container {
                         variant_identifier.set_true();
                     }
 
+                    // Parse `#[serde(validate = "...")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "validate" => {
+                        if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
+                            validate.set(path);
+                        }
+                    }
+
+                    // Parse `#[serde(repr = "u8")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "repr" => {
+                        if let Ok(s) = get_string_from_lit(cx, name.as_ref(), name.as_ref(), lit) {
+                            match item.body {
+                                syn::Body::Enum(_) => {
+                                    match &*s {
+                                        "u8" | "u16" | "u32" | "u64" |
+                                        "i8" | "i16" | "i32" | "i64" => {
+                                            repr.set(syn::Ident::new(s));
+                                        }
+                                        _ => {
+                                            cx.error(format!("unknown integer type for \
+                                                              #[serde(repr = {:?})]", s))
+                                        }
+                                    }
+                                }
+                                syn::Body::Struct(_) => {
+                                    cx.error("#[serde(repr = \"...\")] can only be used on enums")
+                                }
+                            }
+                        }
+                    }
+
+                    // Parse `#[serde(expose_names)]`, which requires Rust 1.20
+                    MetaItem(Word(ref name)) if name == "expose_names" => {
+                        expose_names.set_true();
+                    }
+
+                    // Parse `#[serde(annotate)]`
+                    MetaItem(Word(ref name)) if name == "annotate" => {
+                        annotate.set_true();
+                    }
+
+                    // Parse `#[serde(max_size)]`
+                    MetaItem(Word(ref name)) if name == "max_size" => {
+                        max_size.set_true();
+                    }
+
+                    // Parse `#[serde(version = "2")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "version" => {
+                        let value = match *lit {
+                            syn::Lit::Str(ref s, _) => s.parse::<u32>().ok(),
+                            _ => None,
+                        };
+                        match value {
+                            Some(value) if value >= 1 => version.set(value),
+                            _ => {
+                                cx.error("expected serde version attribute to be an integer \
+                                          from 1 to 4294967295: `version = \"1\"`")
+                            }
+                        }
+                    }
+
                     MetaItem(ref meta_item) => {
-                        cx.error(format!("unknown serde container attribute `{}`",
-                                         meta_item.name()));
+                        let position = format!("`{}`", item.ident);
+                        cx.error(unknown_attribute("container", &position, meta_item.name(), CONTAINER_ATTRS));
                     }
 
                     Literal(_) => {
@@ -356,6 +453,31 @@ impl Container {
             }
         }
 
+        // Fields of a `#[repr(packed)]` struct may be unaligned, so references
+        // to them must not be taken. The Serialize derive copies each field
+        // out instead, which requires the serialized fields to be `Copy`.
+        let is_packed = item.attrs
+            .iter()
+            .any(
+                |attr| match attr.value {
+                    List(ref name, ref items) if name == "repr" => {
+                        items.iter().any(
+                            |item| match *item {
+                                MetaItem(Word(ref name)) |
+                                MetaItem(List(ref name, _)) => name == "packed",
+                                _ => false,
+                            },
+                        )
+                    }
+                    _ => false,
+                },
+            );
+
+        let repr = repr.get();
+        if let Some(ref repr) = repr {
+            check_repr_discriminants(cx, item, repr);
+        }
+
         Container {
             name: Name {
                 serialize: ser_name.get().unwrap_or_else(|| item.ident.to_string()),
@@ -363,7 +485,10 @@ impl Container {
             },
             deny_unknown_fields: deny_unknown_fields.get(),
             default: default.get().unwrap_or(Default::None),
-            rename_all: rename_all.get().unwrap_or(RenameRule::None),
+            rename_all: RenameAllRules {
+                serialize: ser_rename_all.get().unwrap_or(RenameRule::None),
+                deserialize: de_rename_all.get().unwrap_or(RenameRule::None),
+            },
             ser_bound: ser_bound.get(),
             de_bound: de_bound.get(),
             tag: decide_tag(cx, item, untagged, internal_tag, content),
@@ -371,6 +496,13 @@ impl Container {
             into_type: into_type.get(),
             remote: remote.get(),
             identifier: decide_identifier(cx, item, field_identifier, variant_identifier),
+            validate: validate.get(),
+            is_packed: is_packed,
+            repr: repr,
+            expose_names: expose_names.get(),
+            annotate: annotate.get(),
+            max_size: max_size.get(),
+            version: version.get(),
         }
     }
 
@@ -378,7 +510,13 @@ impl Container {
         &self.name
     }
 
+    /// Return the serialize rule. Use `rename_all_rules` to get the
+    /// deserialize rule as well.
     pub fn rename_all(&self) -> &RenameRule {
+        &self.rename_all.serialize
+    }
+
+    pub fn rename_all_rules(&self) -> &RenameAllRules {
         &self.rename_all
     }
 
@@ -417,6 +555,48 @@ impl Container {
     pub fn identifier(&self) -> Identifier {
         self.identifier
     }
+
+    /// The function checking a deserialized value before it is returned.
+    pub fn validate(&self) -> Option<&syn::Path> {
+        self.validate.as_ref()
+    }
+
+    pub fn is_packed(&self) -> bool {
+        self.is_packed
+    }
+
+    /// The integer type a field-less enum is represented as, instead of its
+    /// variant names.
+    pub fn repr(&self) -> Option<&syn::Ident> {
+        self.repr.as_ref()
+    }
+
+    /// Whether the names of the fields or variants are made available as
+    /// `FIELDS` or `VARIANTS` constants on the type.
+    ///
+    /// The constants are inherent associated consts, which need Rust 1.20 or
+    /// newer. The rest of the derived code compiles on Rust 1.15, so only
+    /// types with this attribute raise the minimum version.
+    pub fn expose_names(&self) -> bool {
+        self.expose_names
+    }
+
+    /// Whether the doc comments of the fields are passed to the serializer as
+    /// annotations.
+    pub fn annotate(&self) -> bool {
+        self.annotate
+    }
+
+    /// Whether the Serialize derive also implements `serde::size::MaxSize`.
+    pub fn max_size(&self) -> bool {
+        self.max_size
+    }
+
+    /// The version number written before the value, for a container whose
+    /// older representations are migrated by `serde::version::Migrate`.
+    pub fn version(&self) -> Option<u32> {
+        self.version
+    }
 }
 
 fn decide_tag(
@@ -506,10 +686,11 @@ pub struct Variant {
     name: Name,
     ser_renamed: bool,
     de_renamed: bool,
-    rename_all: RenameRule,
+    rename_all: RenameAllRules,
     skip_deserializing: bool,
     skip_serializing: bool,
     other: bool,
+    untagged: bool,
     serialize_with: Option<syn::Path>,
     deserialize_with: Option<syn::Path>,
 }
@@ -520,8 +701,10 @@ impl Variant {
         let mut de_name = Attr::none(cx, "rename");
         let mut skip_deserializing = BoolAttr::none(cx, "skip_deserializing");
         let mut skip_serializing = BoolAttr::none(cx, "skip_serializing");
-        let mut rename_all = Attr::none(cx, "rename_all");
+        let mut ser_rename_all = Attr::none(cx, "rename_all");
+        let mut de_rename_all = Attr::none(cx, "rename_all");
         let mut other = BoolAttr::none(cx, "other");
+        let mut untagged = BoolAttr::none(cx, "untagged");
         let mut serialize_with = Attr::none(cx, "serialize_with");
         let mut deserialize_with = Attr::none(cx, "deserialize_with");
 
@@ -546,15 +729,17 @@ impl Variant {
 
                     // Parse `#[serde(rename_all = "foo")]`
                     MetaItem(NameValue(ref name, ref lit)) if name == "rename_all" => {
-                        if let Ok(s) = get_string_from_lit(cx, name.as_ref(), name.as_ref(), lit) {
-                            match RenameRule::from_str(&s) {
-                                Ok(rename_rule) => rename_all.set(rename_rule),
-                                Err(()) => {
-                                    cx.error(format!("unknown rename rule for #[serde(rename_all \
-                                                      = {:?})]",
-                                                     s))
-                                }
-                            }
+                        if let Ok(rule) = parse_lit_into_rename_rule(cx, name.as_ref(), name.as_ref(), lit) {
+                            ser_rename_all.set(rule);
+                            de_rename_all.set(rule);
+                        }
+                    }
+
+                    // Parse `#[serde(rename_all(serialize = "foo", deserialize = "bar"))]`
+                    MetaItem(List(ref name, ref meta_items)) if name == "rename_all" => {
+                        if let Ok((ser, de)) = get_rename_all_rules(cx, meta_items) {
+                            ser_rename_all.set_opt(ser);
+                            de_rename_all.set_opt(de);
                         }
                     }
 
@@ -573,6 +758,11 @@ impl Variant {
                         other.set_true();
                     }
 
+                    // Parse `#[serde(untagged)]`
+                    MetaItem(Word(ref name)) if name == "untagged" => {
+                        untagged.set_true();
+                    }
+
                     // Parse `#[serde(with = "...")]`
                     MetaItem(NameValue(ref name, ref lit)) if name == "with" => {
                         if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
@@ -600,7 +790,8 @@ impl Variant {
                     }
 
                     MetaItem(ref meta_item) => {
-                        cx.error(format!("unknown serde variant attribute `{}`", meta_item.name()));
+                        let position = format!("variant `{}`", variant.ident);
+                        cx.error(unknown_attribute("variant", &position, meta_item.name(), VARIANT_ATTRS));
                     }
 
                     Literal(_) => {
@@ -621,10 +812,14 @@ impl Variant {
             },
             ser_renamed: ser_renamed,
             de_renamed: de_renamed,
-            rename_all: rename_all.get().unwrap_or(RenameRule::None),
+            rename_all: RenameAllRules {
+                serialize: ser_rename_all.get().unwrap_or(RenameRule::None),
+                deserialize: de_rename_all.get().unwrap_or(RenameRule::None),
+            },
             skip_deserializing: skip_deserializing.get(),
             skip_serializing: skip_serializing.get(),
             other: other.get(),
+            untagged: untagged.get(),
             serialize_with: serialize_with.get(),
             deserialize_with: deserialize_with.get(),
         }
@@ -634,6 +829,7 @@ impl Variant {
         &self.name
     }
 
+    /// Apply the same rule to the serialize and deserialize names.
     pub fn rename_by_rule(&mut self, rule: &RenameRule) {
         if !self.ser_renamed {
             self.name.serialize = rule.apply_to_variant(&self.name.serialize);
@@ -643,7 +839,22 @@ impl Variant {
         }
     }
 
+    pub fn rename_by_rules(&mut self, rules: &RenameAllRules) {
+        if !self.ser_renamed {
+            self.name.serialize = rules.serialize.apply_to_variant(&self.name.serialize);
+        }
+        if !self.de_renamed {
+            self.name.deserialize = rules.deserialize.apply_to_variant(&self.name.deserialize);
+        }
+    }
+
+    /// Return the serialize rule. Use `rename_all_rules` to get the
+    /// deserialize rule as well.
     pub fn rename_all(&self) -> &RenameRule {
+        &self.rename_all.serialize
+    }
+
+    pub fn rename_all_rules(&self) -> &RenameAllRules {
         &self.rename_all
     }
 
@@ -659,6 +870,12 @@ impl Variant {
         self.other
     }
 
+    /// Whether this variant is represented without a tag regardless of how
+    /// the rest of the enum is tagged.
+    pub fn untagged(&self) -> bool {
+        self.untagged
+    }
+
     pub fn serialize_with(&self) -> Option<&syn::Path> {
         self.serialize_with.as_ref()
     }
@@ -678,12 +895,18 @@ pub struct Field {
     skip_deserializing: bool,
     skip_serializing_if: Option<syn::Path>,
     default: Default,
+    lenient: Default,
+    collect_unknown: bool,
+    validate: Option<syn::Path>,
     serialize_with: Option<syn::Path>,
     deserialize_with: Option<syn::Path>,
+    with_readable: Option<syn::Path>,
+    with_compact: Option<syn::Path>,
     ser_bound: Option<Vec<syn::WherePredicate>>,
     de_bound: Option<Vec<syn::WherePredicate>>,
     borrowed_lifetimes: BTreeSet<syn::Lifetime>,
     getter: Option<syn::Path>,
+    doc: Option<String>,
 }
 
 /// Represents the default to use for a field when deserializing.
@@ -706,8 +929,13 @@ impl Field {
         let mut skip_deserializing = BoolAttr::none(cx, "skip_deserializing");
         let mut skip_serializing_if = Attr::none(cx, "skip_serializing_if");
         let mut default = Attr::none(cx, "default");
+        let mut lenient = Attr::none(cx, "lenient");
+        let mut collect_unknown = BoolAttr::none(cx, "collect_unknown");
+        let mut validate = Attr::none(cx, "validate");
         let mut serialize_with = Attr::none(cx, "serialize_with");
         let mut deserialize_with = Attr::none(cx, "deserialize_with");
+        let mut with_readable = Attr::none(cx, "with_readable");
+        let mut with_compact = Attr::none(cx, "with_compact");
         let mut ser_bound = Attr::none(cx, "bound");
         let mut de_bound = Attr::none(cx, "bound");
         let mut borrowed_lifetimes = Attr::none(cx, "borrow");
@@ -749,6 +977,30 @@ impl Field {
                         }
                     }
 
+                    // Parse `#[serde(lenient)]`
+                    MetaItem(Word(ref name)) if name == "lenient" => {
+                        lenient.set(Default::Default);
+                    }
+
+                    // Parse `#[serde(lenient = "...")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "lenient" => {
+                        if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
+                            lenient.set(Default::Path(path));
+                        }
+                    }
+
+                    // Parse `#[serde(collect_unknown)]`
+                    MetaItem(Word(ref name)) if name == "collect_unknown" => {
+                        collect_unknown.set_true();
+                    }
+
+                    // Parse `#[serde(validate = "...")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "validate" => {
+                        if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
+                            validate.set(path);
+                        }
+                    }
+
                     // Parse `#[serde(skip_serializing)]`
                     MetaItem(Word(ref name)) if name == "skip_serializing" => {
                         skip_serializing.set_true();
@@ -798,6 +1050,20 @@ impl Field {
                         }
                     }
 
+                    // Parse `#[serde(with_readable = "...")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "with_readable" => {
+                        if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
+                            with_readable.set(path);
+                        }
+                    }
+
+                    // Parse `#[serde(with_compact = "...")]`
+                    MetaItem(NameValue(ref name, ref lit)) if name == "with_compact" => {
+                        if let Ok(path) = parse_lit_into_path(cx, name.as_ref(), lit) {
+                            with_compact.set(path);
+                        }
+                    }
+
                     // Parse `#[serde(bound = "D: Serialize")]`
                     MetaItem(NameValue(ref name, ref lit)) if name == "bound" => {
                         if let Ok(where_predicates) =
@@ -850,7 +1116,8 @@ impl Field {
                     }
 
                     MetaItem(ref meta_item) => {
-                        cx.error(format!("unknown serde field attribute `{}`", meta_item.name()),);
+                        let position = format!("field `{}`", ident);
+                        cx.error(unknown_attribute("field", &position, meta_item.name(), FIELD_ATTRS));
                     }
 
                     Literal(_) => {
@@ -860,6 +1127,14 @@ impl Field {
             }
         }
 
+        if (with_readable.value.is_some() || with_compact.value.is_some()) &&
+           (serialize_with.value.is_some() || deserialize_with.value.is_some()) {
+            cx.error(
+                "#[serde(with_readable)] and #[serde(with_compact)] cannot be combined with \
+                 #[serde(with)], #[serde(serialize_with)] or #[serde(deserialize_with)]",
+            );
+        }
+
         // Is skip_deserializing, initialize the field to Default::default()
         // unless a different default is specified by `#[serde(default = "...")]`
         if skip_deserializing.0.value.is_some() {
@@ -905,12 +1180,18 @@ impl Field {
             skip_deserializing: skip_deserializing.get(),
             skip_serializing_if: skip_serializing_if.get(),
             default: default.get().unwrap_or(Default::None),
+            lenient: lenient.get().unwrap_or(Default::None),
+            collect_unknown: collect_unknown.get(),
+            validate: validate.get(),
             serialize_with: serialize_with.get(),
             deserialize_with: deserialize_with.get(),
+            with_readable: with_readable.get(),
+            with_compact: with_compact.get(),
             ser_bound: ser_bound.get(),
             de_bound: de_bound.get(),
             borrowed_lifetimes: borrowed_lifetimes,
             getter: getter.get(),
+            doc: get_doc(&field.attrs),
         }
     }
 
@@ -918,6 +1199,7 @@ impl Field {
         &self.name
     }
 
+    /// Apply the same rule to the serialize and deserialize names.
     pub fn rename_by_rule(&mut self, rule: &RenameRule) {
         if !self.ser_renamed {
             self.name.serialize = rule.apply_to_field(&self.name.serialize);
@@ -927,6 +1209,15 @@ impl Field {
         }
     }
 
+    pub fn rename_by_rules(&mut self, rules: &RenameAllRules) {
+        if !self.ser_renamed {
+            self.name.serialize = rules.serialize.apply_to_field(&self.name.serialize);
+        }
+        if !self.de_renamed {
+            self.name.deserialize = rules.deserialize.apply_to_field(&self.name.deserialize);
+        }
+    }
+
     pub fn skip_serializing(&self) -> bool {
         self.skip_serializing
     }
@@ -943,6 +1234,27 @@ impl Field {
         &self.default
     }
 
+    /// The value to substitute if the field fails to deserialize, or
+    /// `Default::None` if the error should be returned.
+    ///
+    /// A lenient field is buffered before it is deserialized, which needs a
+    /// self-describing format and the `std` or `alloc` feature of serde.
+    /// Errors from reading the input itself are still returned.
+    pub fn lenient(&self) -> &Default {
+        &self.lenient
+    }
+
+    /// Whether this field receives the entries of the struct that do not match
+    /// any other field.
+    pub fn collect_unknown(&self) -> bool {
+        self.collect_unknown
+    }
+
+    /// The function checking the deserialized value of this field.
+    pub fn validate(&self) -> Option<&syn::Path> {
+        self.validate.as_ref()
+    }
+
     pub fn serialize_with(&self) -> Option<&syn::Path> {
         self.serialize_with.as_ref()
     }
@@ -951,6 +1263,17 @@ impl Field {
         self.deserialize_with.as_ref()
     }
 
+    /// The module used in place of the field's own impls by human-readable
+    /// formats.
+    pub fn with_readable(&self) -> Option<&syn::Path> {
+        self.with_readable.as_ref()
+    }
+
+    /// The module used in place of the field's own impls by compact formats.
+    pub fn with_compact(&self) -> Option<&syn::Path> {
+        self.with_compact.as_ref()
+    }
+
     pub fn ser_bound(&self) -> Option<&[syn::WherePredicate]> {
         self.ser_bound.as_ref().map(|vec| &vec[..])
     }
@@ -966,6 +1289,118 @@ impl Field {
     pub fn getter(&self) -> Option<&syn::Path> {
         self.getter.as_ref()
     }
+
+    /// The doc comment of the field, if it has one.
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_ref().map(|doc| &doc[..])
+    }
+}
+
+const CONTAINER_ATTRS: &'static [&'static str] = &[
+    "rename",
+    "rename_all",
+    "deny_unknown_fields",
+    "default",
+    "bound",
+    "untagged",
+    "tag",
+    "content",
+    "from",
+    "into",
+    "remote",
+    "field_identifier",
+    "variant_identifier",
+    "validate",
+    "repr",
+    "expose_names",
+    "annotate",
+    "max_size",
+    "version",
+];
+
+const VARIANT_ATTRS: &'static [&'static str] = &[
+    "rename",
+    "rename_all",
+    "skip_deserializing",
+    "skip_serializing",
+    "other",
+    "untagged",
+    "with",
+    "serialize_with",
+    "deserialize_with",
+];
+
+const FIELD_ATTRS: &'static [&'static str] = &[
+    "rename",
+    "default",
+    "lenient",
+    "collect_unknown",
+    "validate",
+    "skip_serializing",
+    "skip_deserializing",
+    "skip",
+    "skip_serializing_if",
+    "serialize_with",
+    "deserialize_with",
+    "with",
+    "with_readable",
+    "with_compact",
+    "bound",
+    "borrow",
+    "getter",
+];
+
+/// The error for an attribute that did not match any of the supported forms.
+/// `position` names the container, variant or field the attribute is on, since
+/// the error cannot point at the attribute itself. Misspelled names get a
+/// suggestion, other names the list of valid ones.
+fn unknown_attribute(kind: &str, position: &str, name: &str, known: &[&str]) -> String {
+    if known.contains(&name) {
+        return format!("malformed serde {} attribute `{}` on {}", kind, name, position);
+    }
+
+    let closest = known
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .min();
+    match closest {
+        Some((distance, candidate)) if distance <= cmp::max(1, name.len() / 3) => {
+            format!(
+                "unknown serde {} attribute `{}` on {}, did you mean `{}`?",
+                kind,
+                name,
+                position,
+                candidate
+            )
+        }
+        _ => {
+            let expected: Vec<_> = known.iter().map(|name| format!("`{}`", name)).collect();
+            format!(
+                "unknown serde {} attribute `{}` on {}, expected one of {}",
+                kind,
+                name,
+                position,
+                expected.join(", ")
+            )
+        }
+    }
+}
+
+/// The Levenshtein distance between two attribute names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(cmp::min(substitution, cmp::min(insertion, deletion)));
+        }
+        previous = current;
+    }
+    previous[b.len()]
 }
 
 type SerAndDe<T> = (Option<T>, Option<T>);
@@ -1016,6 +1451,13 @@ fn get_renames(cx: &Ctxt, items: &[syn::NestedMetaItem]) -> Result<SerAndDe<Stri
     get_ser_and_de(cx, "rename", items, get_string_from_lit)
 }
 
+fn get_rename_all_rules(
+    cx: &Ctxt,
+    items: &[syn::NestedMetaItem],
+) -> Result<SerAndDe<RenameRule>, ()> {
+    get_ser_and_de(cx, "rename_all", items, parse_lit_into_rename_rule)
+}
+
 fn get_where_predicates(
     cx: &Ctxt,
     items: &[syn::NestedMetaItem],
@@ -1030,6 +1472,130 @@ pub fn get_serde_meta_items(attr: &syn::Attribute) -> Option<Vec<syn::NestedMeta
     }
 }
 
+/// The text of the doc comments on an item, one line per `///` comment or
+/// `#[doc = "..."]` attribute.
+fn get_doc(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<&str> = attrs
+        .iter()
+        .filter_map(
+            |attr| match attr.value {
+                NameValue(ref name, syn::Lit::Str(ref doc, _)) if name == "doc" => Some(doc.as_str()),
+                _ => None,
+            },
+        )
+        .map(
+            |doc| {
+                // Depending on the compiler, doc comments reach the derive
+                // either as `#[doc = " text"]` or as the sugared comment.
+                let doc = if doc.starts_with("///") || doc.starts_with("//!") {
+                    &doc[3..]
+                } else if doc.starts_with("/**") && doc.ends_with("*/") {
+                    &doc[3..doc.len() - 2]
+                } else {
+                    doc
+                };
+                let doc = if doc.starts_with(' ') { &doc[1..] } else { doc };
+                doc.trim_right()
+            },
+        )
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+// An enum with `#[serde(repr = "...")]` is converted with `as`, which would
+// silently truncate a discriminant that does not fit and let distinct variants
+// alias each other. Discriminants given by an expression other than an integer
+// literal are left for rustc to evaluate.
+fn check_repr_discriminants(cx: &Ctxt, item: &syn::DeriveInput, repr: &syn::Ident) {
+    for attr in &item.attrs {
+        if let List(ref name, ref items) = attr.value {
+            if name == "repr" {
+                for item in items {
+                    if let MetaItem(Word(ref int_ty)) = *item {
+                        if is_integer_type(int_ty.as_ref()) && int_ty != repr {
+                            cx.error(
+                                format!(
+                                    "#[serde(repr = \"{}\")] does not match #[repr({})]",
+                                    repr,
+                                    int_ty
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let variants = match item.body {
+        syn::Body::Enum(ref variants) => variants,
+        syn::Body::Struct(_) => return,
+    };
+
+    let signed = repr.as_ref().starts_with('i');
+    let bits = repr.as_ref()[1..].parse::<u32>().unwrap();
+    // Discriminants are kept as sign and magnitude so that the full range of
+    // both u64 and i64 is representable.
+    let mut next = Some((false, 0u64));
+    for variant in variants {
+        let value = match variant.discriminant {
+            Some(ref expr) => literal_discriminant(expr),
+            None => next,
+        };
+        let (negative, magnitude) = match value {
+            Some(value) => value,
+            None => return,
+        };
+
+        let fits = if signed {
+            let limit = 1u64 << (bits - 1);
+            magnitude < limit || negative && magnitude == limit
+        } else {
+            !negative && magnitude <= u64::max_value() >> (64 - bits)
+        };
+        if !fits {
+            cx.error(
+                format!(
+                    "discriminant of `{}` does not fit in #[serde(repr = \"{}\")]",
+                    variant.ident,
+                    repr
+                ),
+            );
+        }
+
+        next = if negative {
+            Some((magnitude > 1, magnitude - 1))
+        } else {
+            magnitude.checked_add(1).map(|magnitude| (false, magnitude))
+        };
+    }
+}
+
+fn literal_discriminant(expr: &syn::ConstExpr) -> Option<(bool, u64)> {
+    match *expr {
+        syn::ConstExpr::Lit(syn::Lit::Int(value, _)) => Some((false, value)),
+        syn::ConstExpr::Unary(syn::UnOp::Neg, ref expr) => {
+            literal_discriminant(expr)
+                .map(|(negative, magnitude)| (!negative && magnitude != 0, magnitude))
+        }
+        syn::ConstExpr::Paren(ref expr) => literal_discriminant(expr),
+        _ => None,
+    }
+}
+
+fn is_integer_type(name: &str) -> bool {
+    match name {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" |
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => true,
+        _ => false,
+    }
+}
+
 fn get_string_from_lit(
     cx: &Ctxt,
     attr_name: &str,
@@ -1050,6 +1616,20 @@ fn get_string_from_lit(
     }
 }
 
+fn parse_lit_into_rename_rule(
+    cx: &Ctxt,
+    attr_name: &str,
+    meta_item_name: &str,
+    lit: &syn::Lit,
+) -> Result<RenameRule, ()> {
+    let string = try!(get_string_from_lit(cx, attr_name, meta_item_name, lit));
+    RenameRule::from_str(&string).map_err(
+        |()| {
+            cx.error(format!("unknown rename rule for #[serde(rename_all = {:?})]", string))
+        },
+    )
+}
+
 fn parse_lit_into_path(cx: &Ctxt, attr_name: &str, lit: &syn::Lit) -> Result<syn::Path, ()> {
     let string = try!(get_string_from_lit(cx, attr_name, attr_name, lit));
     syn::parse_path(&string).map_err(|err| cx.error(err))
@@ -11,7 +11,7 @@ use std::str::FromStr;
 
 use self::RenameRule::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum RenameRule {
     /// Don't apply a default rename rule.
     None,
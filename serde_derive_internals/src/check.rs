@@ -7,7 +7,7 @@
 // except according to those terms.
 
 use ast::{Body, Container, Style};
-use attr::Identifier;
+use attr::{EnumTag, Identifier};
 use Ctxt;
 
 /// Cross-cutting checks that require looking at more than a single attrs
@@ -16,6 +16,13 @@ pub fn check(cx: &Ctxt, cont: &Container) {
     check_getter(cx, cont);
     check_identifier(cx, cont);
     check_variant_skip_attrs(cx, cont);
+    check_collect_unknown(cx, cont);
+    check_untagged_variants(cx, cont);
+    check_repr(cx, cont);
+    check_expose_names(cx, cont);
+    check_annotate(cx, cont);
+    check_max_size(cx, cont);
+    check_version(cx, cont);
 }
 
 /// Getters are only allowed inside structs (not enums) with the `remote`
@@ -150,3 +157,204 @@ fn check_variant_skip_attrs(cx: &Ctxt, cont: &Container) {
         }
     }
 }
+
+/// Variants with the `untagged` attribute are tried after all the tagged
+/// variants, so they must come last. Identifiers are never untagged.
+fn check_untagged_variants(cx: &Ctxt, cont: &Container) {
+    let variants = match cont.body {
+        Body::Enum(ref variants) => variants,
+        Body::Struct(_, _) => {
+            return;
+        }
+    };
+
+    let mut seen_untagged = false;
+    for variant in variants {
+        if variant.attrs.untagged() {
+            seen_untagged = true;
+            match cont.attrs.identifier() {
+                Identifier::No => {}
+                Identifier::Field | Identifier::Variant => {
+                    cx.error("#[serde(untagged)] is not allowed in an identifier");
+                    return;
+                }
+            }
+        } else if seen_untagged {
+            cx.error(
+                "all variants with the #[serde(untagged)] attribute must be placed at the end \
+                 of the enum",
+            );
+            return;
+        }
+    }
+}
+
+/// An enum represented by its discriminants must be externally tagged and have
+/// only unit variants.
+fn check_repr(cx: &Ctxt, cont: &Container) {
+    let variants = match (cont.attrs.repr(), &cont.body) {
+        (Some(_), &Body::Enum(ref variants)) => variants,
+        _ => {
+            return;
+        }
+    };
+
+    match *cont.attrs.tag() {
+        EnumTag::External => {}
+        EnumTag::Internal { .. } | EnumTag::Adjacent { .. } | EnumTag::None => {
+            cx.error("#[serde(repr = \"...\")] cannot be used on a tagged or untagged enum");
+        }
+    }
+
+    match cont.attrs.identifier() {
+        Identifier::No => {}
+        Identifier::Field | Identifier::Variant => {
+            cx.error("#[serde(repr = \"...\")] cannot be used on an identifier");
+        }
+    }
+
+    for variant in variants {
+        match variant.style {
+            Style::Unit => {}
+            _ => {
+                cx.error(
+                    format!(
+                        "#[serde(repr = \"...\")] requires unit variants, but `{}` has fields",
+                        variant.ident
+                    ),
+                );
+            }
+        }
+        if variant.attrs.untagged() {
+            cx.error("#[serde(untagged)] variants cannot be used with #[serde(repr = \"...\")]");
+        }
+    }
+}
+
+/// Only structs with named fields and enums have names to expose.
+fn check_expose_names(cx: &Ctxt, cont: &Container) {
+    if !cont.attrs.expose_names() {
+        return;
+    }
+
+    match cont.body {
+        Body::Enum(_) | Body::Struct(Style::Struct, _) => {}
+        Body::Struct(_, _) => {
+            cx.error("#[serde(expose_names)] can only be used on structs with named fields and enums");
+        }
+    }
+}
+
+/// Annotations are attached to named fields, so there is nothing to annotate
+/// in a tuple, newtype or unit struct.
+fn check_annotate(cx: &Ctxt, cont: &Container) {
+    if !cont.attrs.annotate() {
+        return;
+    }
+
+    match cont.body {
+        Body::Enum(_) | Body::Struct(Style::Struct, _) => {}
+        Body::Struct(_, _) => {
+            cx.error("#[serde(annotate)] can only be used on structs with named fields and enums");
+        }
+    }
+}
+
+/// `MaxSize` is implemented for the type itself, which a remote derive does
+/// not own.
+fn check_max_size(cx: &Ctxt, cont: &Container) {
+    if cont.attrs.max_size() && cont.attrs.remote().is_some() {
+        cx.error("#[serde(max_size)] cannot be used with #[serde(remote = \"...\")]");
+    }
+}
+
+/// A versioned container implements `serde::version::Versioned`, which has no
+/// lifetime to borrow from, and its payload is written by the derived code
+/// itself rather than by a remote or converted type.
+fn check_version(cx: &Ctxt, cont: &Container) {
+    if cont.attrs.version().is_none() {
+        return;
+    }
+
+    if !cont.generics.lifetimes.is_empty() || !cont.generics.ty_params.is_empty() {
+        cx.error("#[serde(version = ...)] cannot be used on a generic type");
+    }
+
+    if cont.attrs.remote().is_some() {
+        cx.error("#[serde(version = ...)] cannot be used with #[serde(remote = \"...\")]");
+    }
+
+    if cont.attrs.from_type().is_some() || cont.attrs.into_type().is_some() {
+        cx.error(
+            "#[serde(version = ...)] cannot be used with #[serde(from = \"...\")] or \
+             #[serde(into = \"...\")]",
+        );
+    }
+
+    match cont.attrs.identifier() {
+        Identifier::No => {}
+        Identifier::Field | Identifier::Variant => {
+            cx.error("#[serde(version = ...)] cannot be used on an identifier");
+        }
+    }
+}
+
+/// The `collect_unknown` attribute may be used on at most one field of a struct
+/// with named fields. It does not combine with attributes that change how the
+/// field itself is (de)serialized, or with `deny_unknown_fields`.
+fn check_collect_unknown(cx: &Ctxt, cont: &Container) {
+    let (style, fields) = match cont.body {
+        Body::Enum(ref variants) => {
+            for variant in variants {
+                if variant.fields.iter().any(|field| field.attrs.collect_unknown()) {
+                    cx.error("#[serde(collect_unknown)] is not allowed in an enum");
+                }
+            }
+            return;
+        }
+        Body::Struct(style, ref fields) => (style, fields),
+    };
+
+    let collect: Vec<_> = fields
+        .iter()
+        .filter(|field| field.attrs.collect_unknown())
+        .collect();
+    if collect.is_empty() {
+        return;
+    }
+
+    match style {
+        Style::Struct => {}
+        _ => {
+            cx.error("#[serde(collect_unknown)] can only be used in structs with named fields");
+            return;
+        }
+    }
+
+    if collect.len() > 1 {
+        cx.error("#[serde(collect_unknown)] may be used on at most one field");
+    }
+
+    if cont.attrs.deny_unknown_fields() {
+        cx.error(
+            "#[serde(collect_unknown)] cannot be used together with \
+             #[serde(deny_unknown_fields)]",
+        );
+    }
+
+    for field in collect {
+        let ident = field.ident.as_ref().expect("struct has unnamed field");
+        let conflicts = [
+            ("skip_serializing", field.attrs.skip_serializing()),
+            ("skip_deserializing", field.attrs.skip_deserializing()),
+            ("serialize_with", field.attrs.serialize_with().is_some()),
+            ("deserialize_with", field.attrs.deserialize_with().is_some()),
+        ];
+        for &(attr, conflict) in &conflicts {
+            if conflict {
+                cx.error(format!("field `{}` cannot have both #[serde(collect_unknown)] and \
+                                  #[serde({})]", ident, attr));
+            }
+        }
+    }
+}
@@ -0,0 +1,568 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::io::{self, Write};
+use std::thread;
+use std::vec;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+                VariantAccess, Visitor};
+
+use error::Error;
+
+/// A kind of hostile input produced by an `AdversarialDeserializer`.
+///
+/// Apart from the attack, the deserializer answers every request with a small
+/// value of the requested type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attack {
+    /// Answers the request numbered `at`, counting from zero, with a value of
+    /// the wrong type or out of range. `answer` selects one of twelve such
+    /// values: a bool, `i64::MIN`, `u64::MAX`, NaN, the largest char, a
+    /// string containing a nul, invalid UTF-8 bytes, a unit, a `None`, a
+    /// `Some`, an empty sequence or an empty map.
+    WrongType {
+        /// The request to answer wrongly.
+        at: usize,
+        /// Which wrong value to produce, modulo twelve.
+        answer: usize,
+    },
+    /// Ends the sequence, tuple, map or struct numbered `at` after half of
+    /// its elements while its size hint still promises all of them.
+    Truncated {
+        /// The compound value to cut short.
+        at: usize,
+    },
+    /// Reports a size hint of `usize::MAX` for every sequence and map.
+    HugeSizeHint,
+    /// Repeats the first key of every map and struct.
+    DuplicateKeys,
+    /// Answers `deserialize_any` and `deserialize_ignored_any` with a
+    /// sequence of one sequence of one sequence and so on, `depth` levels
+    /// deep.
+    DeepNesting {
+        /// How many sequences to nest.
+        depth: usize,
+    },
+}
+
+/// A `Deserializer` that produces hostile input.
+///
+/// A `Deserialize` impl may reject any of it with an error, but should never
+/// panic, allocate memory in proportion to a size hint, or recurse without
+/// bound. [`assert_de_robust`] runs every attack against a type.
+///
+/// The deserializer errors out beyond a fixed nesting depth, so recursive
+/// types terminate. Strings and byte arrays are never borrowed from the input.
+///
+/// [`assert_de_robust`]: fn.assert_de_robust.html
+#[derive(Debug)]
+pub struct AdversarialDeserializer {
+    attack: Attack,
+    requests: usize,
+    compounds: usize,
+    depth: usize,
+    identifier: Option<&'static str>,
+    struck: bool,
+}
+
+// Below this depth sequences, maps and options have content; at or beyond it
+// they are empty.
+const SHALLOW: usize = 4;
+
+// Beyond this depth, in addition to any requested nesting, every request fails.
+const DEPTH_LIMIT: usize = 16;
+
+const WRONG_ANSWERS: usize = 12;
+
+// The nesting depth used for `Attack::DeepNesting` by `assert_de_robust`.
+const DEEP: usize = 128;
+
+impl AdversarialDeserializer {
+    /// Creates a deserializer carrying out `attack`.
+    pub fn new(attack: Attack) -> Self {
+        AdversarialDeserializer {
+            attack: attack,
+            requests: 0,
+            compounds: 0,
+            depth: 0,
+            identifier: None,
+            struck: false,
+        }
+    }
+
+    /// Whether the attack was carried out. `WrongType`, `Truncated` and
+    /// `DuplicateKeys` only strike if the value being deserialized requests
+    /// enough, or the right kind of, data.
+    pub fn struck(&self) -> bool {
+        self.struck
+    }
+
+    fn answer<'de, V>(&mut self, request: Request, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let index = self.requests;
+        self.requests += 1;
+        let identifier = self.identifier.take();
+
+        let limit = match self.attack {
+            Attack::DeepNesting { depth } => depth + DEPTH_LIMIT,
+            _ => DEPTH_LIMIT,
+        };
+        if self.depth > limit {
+            return Err(de::Error::custom("adversarial nesting limit reached"));
+        }
+
+        if let Attack::WrongType { at, answer } = self.attack {
+            if at == index {
+                self.struck = true;
+                return self.wrong(answer, visitor);
+            }
+        }
+
+        let shallow = self.depth < SHALLOW;
+        match request {
+            Request::Any => {
+                if let Attack::DeepNesting { depth } = self.attack {
+                    if self.depth < depth {
+                        self.struck = true;
+                        return self.seq(1, visitor);
+                    }
+                }
+                visitor.visit_unit()
+            }
+            Request::Bool => visitor.visit_bool(false),
+            Request::I8 => visitor.visit_i8(0),
+            Request::I16 => visitor.visit_i16(0),
+            Request::I32 => visitor.visit_i32(0),
+            Request::I64 => visitor.visit_i64(0),
+            Request::U8 => visitor.visit_u8(0),
+            Request::U16 => visitor.visit_u16(0),
+            Request::U32 => visitor.visit_u32(0),
+            Request::U64 => visitor.visit_u64(0),
+            Request::F32 => visitor.visit_f32(0.0),
+            Request::F64 => visitor.visit_f64(0.0),
+            Request::Char => visitor.visit_char('a'),
+            Request::Str => visitor.visit_str(identifier.unwrap_or("a")),
+            Request::String => visitor.visit_string(identifier.unwrap_or("a").to_owned()),
+            Request::Bytes => visitor.visit_bytes(b"a"),
+            Request::ByteBuf => visitor.visit_byte_buf(b"a".to_vec()),
+            Request::Identifier => {
+                match identifier {
+                    Some(name) => visitor.visit_str(name),
+                    None => visitor.visit_u64(0),
+                }
+            }
+            Request::Option => {
+                if shallow {
+                    self.nested(|de| visitor.visit_some(de))
+                } else {
+                    visitor.visit_none()
+                }
+            }
+            Request::Unit => visitor.visit_unit(),
+            Request::NewtypeStruct => self.nested(|de| visitor.visit_newtype_struct(de)),
+            Request::Seq => self.seq(if shallow { 2 } else { 0 }, visitor),
+            Request::Tuple(len) => self.seq(len, visitor),
+            Request::Map => self.map(if shallow { vec![None] } else { Vec::new() }, visitor),
+            Request::Struct(fields) => self.map(fields.iter().map(|&field| Some(field)).collect(), visitor),
+            Request::Enum(variants) => {
+                let variant = if variants.is_empty() {
+                    None
+                } else {
+                    Some(variants[index % variants.len()])
+                };
+                self.nested(
+                    |de| {
+                        visitor.visit_enum(
+                            Enum {
+                                de: de,
+                                variant: variant,
+                            },
+                        )
+                    },
+                )
+            }
+        }
+    }
+
+    fn wrong<'de, V>(&mut self, answer: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match answer % WRONG_ANSWERS {
+            0 => visitor.visit_bool(true),
+            1 => visitor.visit_i64(i64::min_value()),
+            2 => visitor.visit_u64(u64::max_value()),
+            3 => visitor.visit_f64(::std::f64::NAN),
+            4 => visitor.visit_char('\u{10ffff}'),
+            5 => visitor.visit_str("\u{0}\u{feff}"),
+            6 => visitor.visit_bytes(&[0xff, 0xfe]),
+            7 => visitor.visit_unit(),
+            8 => visitor.visit_none(),
+            9 => self.nested(|de| visitor.visit_some(de)),
+            10 => {
+                visitor.visit_seq(
+                    Seq {
+                        de: self,
+                        remaining: 0,
+                        hint: Some(0),
+                    },
+                )
+            }
+            _ => {
+                visitor.visit_map(
+                    Map {
+                        de: self,
+                        keys: Vec::new().into_iter(),
+                        hint: Some(0),
+                    },
+                )
+            }
+        }
+    }
+
+    fn nested<T, F>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Applies `Truncated` and `HugeSizeHint` to a compound value of `len`
+    // elements, returning the number of elements to produce and the size hint.
+    fn compound(&mut self, len: usize) -> (usize, Option<usize>) {
+        let index = self.compounds;
+        self.compounds += 1;
+
+        match self.attack {
+            Attack::Truncated { at } if at == index => {
+                self.struck = true;
+                (len / 2, Some(len))
+            }
+            Attack::HugeSizeHint => {
+                self.struck = true;
+                (len, Some(usize::max_value()))
+            }
+            _ => (len, Some(len)),
+        }
+    }
+
+    fn seq<'de, V>(&mut self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (len, hint) = self.compound(len);
+        self.nested(
+            |de| {
+                visitor.visit_seq(
+                    Seq {
+                        de: de,
+                        remaining: len,
+                        hint: hint,
+                    },
+                )
+            },
+        )
+    }
+
+    // Produces a map with one entry per key. A key of `None` is answered like
+    // any other request, a named key as the identifier with that name.
+    fn map<'de, V>(&mut self, mut keys: Vec<Option<&'static str>>, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let Attack::DuplicateKeys = self.attack {
+            if let Some(&first) = keys.first() {
+                self.struck = true;
+                keys.push(first);
+            }
+        }
+        let (len, hint) = self.compound(keys.len());
+        keys.truncate(len);
+        self.nested(
+            |de| {
+                visitor.visit_map(
+                    Map {
+                        de: de,
+                        keys: keys.into_iter(),
+                        hint: hint,
+                    },
+                )
+            },
+        )
+    }
+}
+
+enum Request {
+    Any,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Char,
+    Str,
+    String,
+    Bytes,
+    ByteBuf,
+    Identifier,
+    Option,
+    Unit,
+    NewtypeStruct,
+    Seq,
+    Tuple(usize),
+    Map,
+    Struct(&'static [&'static str]),
+    Enum(&'static [&'static str]),
+}
+
+macro_rules! forward_request {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $request:expr,)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                $(let _ = $arg;)*
+                self.answer($request, visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for &'a mut AdversarialDeserializer {
+    type Error = Error;
+
+    forward_request! {
+        deserialize_any() => Request::Any,
+        deserialize_bool() => Request::Bool,
+        deserialize_i8() => Request::I8,
+        deserialize_i16() => Request::I16,
+        deserialize_i32() => Request::I32,
+        deserialize_i64() => Request::I64,
+        deserialize_u8() => Request::U8,
+        deserialize_u16() => Request::U16,
+        deserialize_u32() => Request::U32,
+        deserialize_u64() => Request::U64,
+        deserialize_f32() => Request::F32,
+        deserialize_f64() => Request::F64,
+        deserialize_char() => Request::Char,
+        deserialize_str() => Request::Str,
+        deserialize_string() => Request::String,
+        deserialize_bytes() => Request::Bytes,
+        deserialize_byte_buf() => Request::ByteBuf,
+        deserialize_option() => Request::Option,
+        deserialize_unit() => Request::Unit,
+        deserialize_unit_struct(name: &'static str) => Request::Unit,
+        deserialize_newtype_struct(name: &'static str) => Request::NewtypeStruct,
+        deserialize_seq() => Request::Seq,
+        deserialize_tuple(len: usize) => Request::Tuple(len),
+        deserialize_tuple_struct(name: &'static str, len: usize) => Request::Tuple(len),
+        deserialize_map() => Request::Map,
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]) => Request::Struct(fields),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]) => Request::Enum(variants),
+        deserialize_identifier() => Request::Identifier,
+        deserialize_ignored_any() => Request::Any,
+    }
+}
+
+struct Seq<'a> {
+    de: &'a mut AdversarialDeserializer,
+    remaining: usize,
+    hint: Option<usize>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Seq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.hint
+    }
+}
+
+struct Map<'a> {
+    de: &'a mut AdversarialDeserializer,
+    keys: vec::IntoIter<Option<&'static str>>,
+    hint: Option<usize>,
+}
+
+impl<'de, 'a> MapAccess<'de> for Map<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.keys.next() {
+            Some(key) => {
+                self.de.identifier = key;
+                seed.deserialize(&mut *self.de).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.hint
+    }
+}
+
+struct Enum<'a> {
+    de: &'a mut AdversarialDeserializer,
+    variant: Option<&'static str>,
+}
+
+impl<'de, 'a> EnumAccess<'de> for Enum<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.de.identifier = self.variant;
+        let value = try!(seed.deserialize(&mut *self.de));
+        Ok((value, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for Enum<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.seq(len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.map(fields.iter().map(|&field| Some(field)).collect(), visitor)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Asserts that deserializing `T` from hostile input never panics.
+///
+/// Every `Attack` is tried: a wrong value at each request and a truncation of
+/// each compound value in turn, huge size hints, duplicate keys, and
+/// sequences nested 128 levels deep. Errors are expected and ignored. A panic
+/// propagates to the caller after the attack that caused it is printed to
+/// stderr. An allocation proportional to a huge size hint fails with a
+/// capacity overflow panic, and unbounded recursion overflows the stack,
+/// which aborts the test process.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// #
+/// # extern crate serde_test;
+/// #
+/// # use std::collections::BTreeMap;
+/// # use serde_test::assert_de_robust;
+/// #
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+///     limits: BTreeMap<String, u32>,
+///     tags: Option<Vec<String>>,
+/// }
+///
+/// # fn main() {
+/// assert_de_robust::<Config>();
+/// # }
+/// ```
+pub fn assert_de_robust<T>()
+where
+    T: DeserializeOwned,
+{
+    for at in 0.. {
+        let mut struck = false;
+        for answer in 0..WRONG_ANSWERS {
+            struck |= attack::<T>(Attack::WrongType { at: at, answer: answer });
+        }
+        if !struck {
+            break;
+        }
+    }
+
+    for at in 0.. {
+        if !attack::<T>(Attack::Truncated { at: at }) {
+            break;
+        }
+    }
+
+    attack::<T>(Attack::HugeSizeHint);
+    attack::<T>(Attack::DuplicateKeys);
+    attack::<T>(Attack::DeepNesting { depth: DEEP });
+}
+
+// Deserializes a `T` under `attack`, returning whether the attack struck.
+fn attack<T>(attack: Attack) -> bool
+where
+    T: DeserializeOwned,
+{
+    let _report = Report(attack);
+    let mut de = AdversarialDeserializer::new(attack);
+    let _ = T::deserialize(&mut de);
+    de.struck()
+}
+
+struct Report(Attack);
+
+impl Drop for Report {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let _ = writeln!(io::stderr(), "panicked while deserializing under {:?}", self.0);
+        }
+    }
+}
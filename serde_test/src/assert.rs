@@ -163,7 +163,16 @@ pub fn assert_ser_tokens_error<T>(value: &T, tokens: &[Token], error: &str)
 where
     T: Serialize,
 {
-    let mut ser = Serializer::new(tokens);
+    assert_ser_tokens_error_readable(value, tokens, error, None)
+}
+
+// Not public API
+#[doc(hidden)]
+pub fn assert_ser_tokens_error_readable<T>(value: &T, tokens: &[Token], error: &str, human_readable: Option<bool>)
+where
+    T: Serialize,
+{
+    let mut ser = Serializer::readable(tokens, human_readable);
     match value.serialize(&mut ser) {
         Ok(_) => panic!("value serialized successfully"),
         Err(e) => assert_eq!(e, *error),
@@ -259,7 +268,16 @@ pub fn assert_de_tokens_error<'de, T>(tokens: &'de [Token], error: &str)
 where
     T: Deserialize<'de>,
 {
-    let mut de = Deserializer::new(tokens);
+    assert_de_tokens_error_readable::<T>(tokens, error, None)
+}
+
+// Not public API
+#[doc(hidden)]
+pub fn assert_de_tokens_error_readable<'de, T>(tokens: &'de [Token], error: &str, human_readable: Option<bool>)
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::readable(tokens, human_readable);
     match T::deserialize(&mut de) {
         Ok(_) => panic!("tokens deserialized successfully"),
         Err(e) => assert_eq!(e, *error),
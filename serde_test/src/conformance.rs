@@ -0,0 +1,908 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A battery of checks for data format implementations.
+//!
+//! A format crate implements [`Format`] for its `Serializer` and
+//! `Deserializer` pair and calls [`assert_conformance`] from a test. Every
+//! check serializes a value, deserializes the output back into the same type
+//! and compares the result with the original. The checks cover:
+//!
+//! - every primitive type, including the extreme values of each integer type,
+//!   and strings and byte arrays with characters that commonly need escaping;
+//! - `Option`, including `Some(None)`, unit, unit structs, newtype structs,
+//!   tuples, tuple structs and structs;
+//! - enums with unit, newtype, tuple and struct variants, nested inside each
+//!   other and inside other compound values;
+//! - maps with string keys that are empty or need escaping, integer keys and
+//!   char keys;
+//! - sequences, maps, strings and byte arrays with a hundred thousand or more
+//!   elements;
+//! - errors raised by `Serialize` and `Deserialize` impls, which must be
+//!   returned with their message, and input that does not match the type
+//!   being deserialized, which must be an error rather than a panic.
+//!
+//! Not every format supports the whole data model. [`check`] returns a
+//! [`Report`] instead of panicking, so that a format can document its known
+//! gaps by filtering the failures by name.
+//!
+//! This module is available with the `conformance` feature of `serde_test`.
+//!
+//! [`Format`]: trait.Format.html
+//! [`assert_conformance`]: fn.assert_conformance.html
+//! [`check`]: fn.check.html
+//! [`Report`]: struct.Report.html
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display};
+use std::iter;
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+                VariantAccess, Visitor};
+use serde::ser::{self, SerializeStruct, SerializeStructVariant, SerializeTupleStruct,
+                 SerializeTupleVariant};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A data format under test, as a pair of entry points that serialize any
+/// value and deserialize it back.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_test;
+/// #
+/// # use serde::Serialize;
+/// # use serde::de::DeserializeOwned;
+/// # use serde_test::conformance::Format;
+/// #
+/// # mod my_format {
+/// #     use serde::Serialize;
+/// #     use serde::de::DeserializeOwned;
+/// #     pub type Error = ::std::fmt::Error;
+/// #     pub fn to_string<T: ?Sized + Serialize>(_: &T) -> Result<String, Error> { unimplemented!() }
+/// #     pub fn from_str<T: DeserializeOwned>(_: &str) -> Result<T, Error> { unimplemented!() }
+/// # }
+/// #
+/// struct MyFormat;
+///
+/// impl Format for MyFormat {
+///     type Output = String;
+///     type Error = my_format::Error;
+///
+///     fn serialize<T: ?Sized + Serialize>(&self, value: &T) -> Result<String, Self::Error> {
+///         my_format::to_string(value)
+///     }
+///
+///     fn deserialize<T: DeserializeOwned>(&self, output: &String) -> Result<T, Self::Error> {
+///         my_format::from_str(output)
+///     }
+/// }
+/// #
+/// # fn main() {}
+/// ```
+pub trait Format {
+    /// The serialized form of a value, for example `String` or `Vec<u8>`.
+    type Output: Debug;
+
+    /// The error returned by serialization and deserialization.
+    type Error: Display;
+
+    /// Serializes a value into the format.
+    fn serialize<T: ?Sized + Serialize>(&self, value: &T) -> Result<Self::Output, Self::Error>;
+
+    /// Deserializes a value from the output of `serialize`.
+    fn deserialize<T: DeserializeOwned>(&self, output: &Self::Output) -> Result<T, Self::Error>;
+}
+
+/// The result of a single check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    /// The name of the check, for example `"i64 min"` or `"enum nested"`.
+    pub name: &'static str,
+    /// Why the check failed, or `None` if it passed.
+    pub failure: Option<String>,
+}
+
+impl Outcome {
+    /// Whether the check passed.
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.failure {
+            None => write!(formatter, "ok {}", self.name),
+            Some(ref failure) => write!(formatter, "FAILED {}: {}", self.name, failure),
+        }
+    }
+}
+
+/// The outcomes of all checks run by [`check`], in the order they ran.
+///
+/// The `Display` impl prints a summary line followed by every failure.
+///
+/// [`check`]: fn.check.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    outcomes: Vec<Outcome>,
+}
+
+impl Report {
+    /// Every outcome, passed or failed.
+    pub fn outcomes(&self) -> &[Outcome] {
+        &self.outcomes
+    }
+
+    /// The outcomes of the checks that failed.
+    pub fn failures(&self) -> Vec<&Outcome> {
+        self.outcomes.iter().filter(|outcome| !outcome.passed()).collect()
+    }
+
+    /// Whether every check passed.
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.iter().all(Outcome::passed)
+    }
+
+    fn run<F>(&mut self, name: &'static str, check: F)
+    where
+        F: FnOnce() -> Result<(), String>,
+    {
+        let failure = match panic::catch_unwind(AssertUnwindSafe(check)) {
+            Ok(Ok(())) => None,
+            Ok(Err(failure)) => Some(failure),
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    *message
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    &message[..]
+                } else {
+                    "Box<Any>"
+                };
+                Some(format!("panicked: {}", message))
+            }
+        };
+        self.outcomes.push(Outcome { name: name, failure: failure });
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let failures = self.failures();
+        try!(write!(
+            formatter,
+            "{} checks, {} passed, {} failed",
+            self.outcomes.len(),
+            self.outcomes.len() - failures.len(),
+            failures.len()
+        ));
+        for failure in failures {
+            try!(write!(formatter, "\n{}", failure));
+        }
+        Ok(())
+    }
+}
+
+/// Runs every check against a format.
+///
+/// A check that panics is reported as failed; the panic does not propagate.
+pub fn check<F: Format>(format: &F) -> Report {
+    let mut report = Report { outcomes: Vec::new() };
+
+    check_primitives(format, &mut report);
+    check_options(format, &mut report);
+    check_structs(format, &mut report);
+    check_enums(format, &mut report);
+    check_collections(format, &mut report);
+    check_huge(format, &mut report);
+    check_errors(format, &mut report);
+
+    report
+}
+
+/// Runs every check against a format and panics with the report if any of
+/// them failed.
+pub fn assert_conformance<F: Format>(format: &F) {
+    let report = check(format);
+    if !report.is_ok() {
+        panic!("{}", report);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn check_primitives<F: Format>(format: &F, report: &mut Report) {
+    round_trip(format, report, "bool true", true);
+    round_trip(format, report, "bool false", false);
+
+    round_trip(format, report, "i8 min", i8::min_value());
+    round_trip(format, report, "i8 max", i8::max_value());
+    round_trip(format, report, "i16 min", i16::min_value());
+    round_trip(format, report, "i16 max", i16::max_value());
+    round_trip(format, report, "i32 min", i32::min_value());
+    round_trip(format, report, "i32 max", i32::max_value());
+    round_trip(format, report, "i64 min", i64::min_value());
+    round_trip(format, report, "i64 max", i64::max_value());
+    round_trip(format, report, "i64 zero", 0i64);
+
+    round_trip(format, report, "u8 max", u8::max_value());
+    round_trip(format, report, "u16 max", u16::max_value());
+    round_trip(format, report, "u32 max", u32::max_value());
+    round_trip(format, report, "u64 max", u64::max_value());
+    round_trip(format, report, "u64 zero", 0u64);
+
+    round_trip(format, report, "f32", -1.5f32);
+    round_trip(format, report, "f32 max", ::std::f32::MAX);
+    round_trip(format, report, "f32 min positive", ::std::f32::MIN_POSITIVE);
+    round_trip(format, report, "f64", 0.1f64);
+    round_trip(format, report, "f64 max", ::std::f64::MAX);
+    round_trip(format, report, "f64 min", ::std::f64::MIN);
+    round_trip(format, report, "f64 min positive", ::std::f64::MIN_POSITIVE);
+
+    round_trip(format, report, "char", 'a');
+    round_trip(format, report, "char escaped", '"');
+    round_trip(format, report, "char max", '\u{10ffff}');
+
+    round_trip(format, report, "string empty", String::new());
+    round_trip(format, report, "string", "serde".to_owned());
+    round_trip(format, report, "string escaped", "\"\\/\n\r\t\u{0}\u{1f}\u{7f}".to_owned());
+    round_trip(format, report, "string unicode", "\u{e9}\u{3b1}\u{4e2d}\u{1f600}".to_owned());
+
+    round_trip(format, report, "bytes empty", Bytes(Vec::new()));
+    round_trip(format, report, "bytes", Bytes(vec![0, 1, 127, 128, 255]));
+
+    round_trip(format, report, "unit", ());
+}
+
+fn check_options<F: Format>(format: &F, report: &mut Report) {
+    round_trip(format, report, "option none", None::<u8>);
+    round_trip(format, report, "option some", Some(0u8));
+    round_trip(format, report, "option some none", Some(None::<u8>));
+    round_trip(format, report, "option some some", Some(Some(0u8)));
+    round_trip(format, report, "option some unit", Some(()));
+    round_trip(format, report, "option in seq", vec![Some(1u8), None, Some(2)]);
+}
+
+fn check_structs<F: Format>(format: &F, report: &mut Report) {
+    round_trip(format, report, "unit struct", UnitStruct);
+    round_trip(format, report, "newtype struct", NewtypeStruct(-1));
+    round_trip(format, report, "tuple", (1u8, "two".to_owned(), Some(3.0f64)));
+    round_trip(format, report, "tuple struct", TupleStruct(1, -1));
+    round_trip(format, report, "struct", record());
+    round_trip(
+        format,
+        report,
+        "struct empty option",
+        Record {
+            id: 0,
+            label: None,
+            shape: Shape::Empty,
+        },
+    );
+}
+
+fn check_enums<F: Format>(format: &F, report: &mut Report) {
+    round_trip(format, report, "enum unit variant", Shape::Empty);
+    round_trip(format, report, "enum newtype variant", Shape::Circle(7));
+    round_trip(format, report, "enum tuple variant", Shape::Line(-3, 4));
+    round_trip(
+        format,
+        report,
+        "enum struct variant",
+        Shape::Group {
+            name: "empty".to_owned(),
+            members: Vec::new(),
+        },
+    );
+    round_trip(format, report, "enum nested", group());
+    round_trip(format, report, "enum in option", Some(Shape::Circle(1)));
+    round_trip(
+        format,
+        report,
+        "enum in map",
+        vec![("a".to_owned(), Shape::Empty), ("b".to_owned(), Shape::Line(1, 2))]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    );
+}
+
+fn check_collections<F: Format>(format: &F, report: &mut Report) {
+    round_trip(format, report, "seq empty", Vec::<u8>::new());
+    round_trip(format, report, "seq nested", vec![vec![], vec![1u8], vec![2, 3]]);
+    round_trip(format, report, "map empty", BTreeMap::<String, u8>::new());
+
+    let keys = ["", " ", "\"", "\\", "\n", "\u{0}", "1", "null", "\u{1f600}"];
+    round_trip(
+        format,
+        report,
+        "map string keys",
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), i as u8))
+            .collect::<BTreeMap<_, _>>(),
+    );
+    round_trip(
+        format,
+        report,
+        "map integer keys",
+        vec![(i64::min_value(), 0u8), (-1, 1), (0, 2), (i64::max_value(), 3)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    );
+    round_trip(
+        format,
+        report,
+        "map char keys",
+        vec![('a', 0u8), ('"', 1), ('\u{10ffff}', 2)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+    );
+
+    let mut nested = BTreeMap::new();
+    nested.insert("inner".to_owned(), vec![BTreeMap::new(), keyed(3)]);
+    round_trip(format, report, "map nested", nested);
+}
+
+fn check_huge<F: Format>(format: &F, report: &mut Report) {
+    const LEN: usize = 100_000;
+    const BYTES: usize = 1 << 20;
+
+    round_trip(format, report, "huge seq", (0..LEN as u32).collect::<Vec<_>>());
+    round_trip(format, report, "huge map", keyed(LEN));
+    round_trip(format, report, "huge string", iter::repeat('x').take(BYTES).collect::<String>());
+    round_trip(format, report, "huge bytes", Bytes(vec![0xff; BYTES]));
+    round_trip(
+        format,
+        report,
+        "huge seq of enums",
+        (0..LEN as u32).map(Shape::Circle).collect::<Vec<_>>(),
+    );
+}
+
+fn check_errors<F: Format>(format: &F, report: &mut Report) {
+    report.run(
+        "error from serialize",
+        || match format.serialize(&(1u8, vec![Fail])) {
+            Ok(output) => Err(format!("serialized to {}", abbreviate(&output))),
+            Err(err) => expect_message(err),
+        },
+    );
+
+    report.run(
+        "error from deserialize",
+        || {
+            let output = try!(serialize(format, &(0u8,)));
+            match format.deserialize::<(Fail,)>(&output) {
+                Ok(_) => Err("deserialized successfully".to_owned()),
+                Err(err) => expect_message(err),
+            }
+        },
+    );
+
+    report.run(
+        "error on type mismatch",
+        || {
+            let output = try!(serialize(format, &"not a number"));
+            match format.deserialize::<u32>(&output) {
+                Ok(n) => Err(format!("deserialized a string as {}", n)),
+                Err(_) => Ok(()),
+            }
+        },
+    );
+
+    report.run(
+        "error on missing elements",
+        || {
+            let output = try!(serialize(format, &(1u8, 2u8)));
+            match format.deserialize::<(u8, u8, u8)>(&output) {
+                Ok(value) => Err(format!("deserialized a 2-tuple as {:?}", value)),
+                Err(_) => Ok(()),
+            }
+        },
+    );
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+fn round_trip<F, T>(format: &F, report: &mut Report, name: &'static str, value: T)
+where
+    F: Format,
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    report.run(
+        name,
+        || {
+            let output = try!(serialize(format, &value));
+            let deserialized = match format.deserialize::<T>(&output) {
+                Ok(deserialized) => deserialized,
+                Err(err) => {
+                    return Err(
+                        format!(
+                            "failed to deserialize: {}\nvalue: {}\nserialized: {}",
+                            err,
+                            abbreviate(&value),
+                            abbreviate(&output)
+                        ),
+                    )
+                }
+            };
+            if deserialized != value {
+                return Err(
+                    format!(
+                        "changed in round trip\nvalue: {}\nserialized: {}\ndeserialized: {}",
+                        abbreviate(&value),
+                        abbreviate(&output),
+                        abbreviate(&deserialized)
+                    ),
+                );
+            }
+            Ok(())
+        },
+    );
+}
+
+fn serialize<F, T>(format: &F, value: &T) -> Result<F::Output, String>
+where
+    F: Format,
+    T: Serialize + Debug,
+{
+    format
+        .serialize(value)
+        .map_err(|err| format!("failed to serialize: {}\nvalue: {}", err, abbreviate(value)))
+}
+
+// The huge values would make a failure unreadable.
+fn abbreviate<T: ?Sized + Debug>(value: &T) -> String {
+    const MAX: usize = 200;
+
+    let debug = format!("{:?}", value);
+    if debug.chars().count() <= MAX {
+        debug
+    } else {
+        let mut abbreviated: String = debug.chars().take(MAX).collect();
+        abbreviated.push_str("...");
+        abbreviated
+    }
+}
+
+const MESSAGE: &'static str = "conformance check error";
+
+fn expect_message<E: Display>(err: E) -> Result<(), String> {
+    let err = err.to_string();
+    if err.contains(MESSAGE) {
+        Ok(())
+    } else {
+        Err(format!("the error `{}` does not contain the message `{}`", err, MESSAGE))
+    }
+}
+
+fn keyed(len: usize) -> BTreeMap<String, u8> {
+    (0..len).map(|i| (i.to_string(), i as u8)).collect()
+}
+
+fn record() -> Record {
+    Record {
+        id: u32::max_value(),
+        label: Some("record".to_owned()),
+        shape: group(),
+    }
+}
+
+fn group() -> Shape {
+    Shape::Group {
+        name: "outer".to_owned(),
+        members: vec![
+            Shape::Empty,
+            Shape::Circle(1),
+            Shape::Line(2, 3),
+            Shape::Group {
+                name: "inner".to_owned(),
+                members: vec![Shape::Circle(4)],
+            },
+        ],
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The values checked above. Serde_test cannot depend on serde_derive, so
+// their impls are written out by hand.
+
+#[derive(Debug, PartialEq)]
+struct Bytes(Vec<u8>);
+
+#[derive(Debug, PartialEq)]
+struct UnitStruct;
+
+#[derive(Debug, PartialEq)]
+struct NewtypeStruct(i32);
+
+#[derive(Debug, PartialEq)]
+struct TupleStruct(i32, i32);
+
+#[derive(Debug, PartialEq)]
+struct Record {
+    id: u32,
+    label: Option<String>,
+    shape: Shape,
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Empty,
+    Circle(u32),
+    Line(i32, i32),
+    Group { name: String, members: Vec<Shape> },
+}
+
+// Fails to serialize and to deserialize.
+#[derive(Debug, PartialEq)]
+struct Fail;
+
+const RECORD_FIELDS: &'static [&'static str] = &["id", "label", "shape"];
+const SHAPE_VARIANTS: &'static [&'static str] = &["Empty", "Circle", "Line", "Group"];
+const GROUP_FIELDS: &'static [&'static str] = &["name", "members"];
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl Serialize for UnitStruct {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_struct("UnitStruct")
+    }
+}
+
+impl Serialize for NewtypeStruct {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("NewtypeStruct", &self.0)
+    }
+}
+
+impl Serialize for TupleStruct {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_tuple_struct("TupleStruct", 2));
+        try!(state.serialize_field(&self.0));
+        try!(state.serialize_field(&self.1));
+        state.end()
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = try!(serializer.serialize_struct("Record", 3));
+        try!(state.serialize_field("id", &self.id));
+        try!(state.serialize_field("label", &self.label));
+        try!(state.serialize_field("shape", &self.shape));
+        state.end()
+    }
+}
+
+impl Serialize for Shape {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Shape::Empty => serializer.serialize_unit_variant("Shape", 0, "Empty"),
+            Shape::Circle(ref radius) => {
+                serializer.serialize_newtype_variant("Shape", 1, "Circle", radius)
+            }
+            Shape::Line(ref from, ref to) => {
+                let mut state = try!(serializer.serialize_tuple_variant("Shape", 2, "Line", 2));
+                try!(state.serialize_field(from));
+                try!(state.serialize_field(to));
+                state.end()
+            }
+            Shape::Group { ref name, ref members } => {
+                let mut state = try!(serializer.serialize_struct_variant("Shape", 3, "Group", 2));
+                try!(state.serialize_field("name", name));
+                try!(state.serialize_field("members", members));
+                state.end()
+            }
+        }
+    }
+}
+
+impl Serialize for Fail {
+    fn serialize<S: Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom(MESSAGE))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes(v))
+            }
+
+            // Formats without a byte array type may serialize one as a
+            // sequence of integers.
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes, A::Error> {
+                let mut bytes = Vec::new();
+                while let Some(byte) = try!(seq.next_element()) {
+                    bytes.push(byte);
+                }
+                Ok(Bytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnitStruct {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UnitStructVisitor;
+
+        impl<'de> Visitor<'de> for UnitStructVisitor {
+            type Value = UnitStruct;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("unit struct UnitStruct")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<UnitStruct, E> {
+                Ok(UnitStruct)
+            }
+        }
+
+        deserializer.deserialize_unit_struct("UnitStruct", UnitStructVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NewtypeStruct {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NewtypeStructVisitor;
+
+        impl<'de> Visitor<'de> for NewtypeStructVisitor {
+            type Value = NewtypeStruct;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("tuple struct NewtypeStruct")
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<NewtypeStruct, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Deserialize::deserialize(deserializer).map(NewtypeStruct)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NewtypeStruct, A::Error> {
+                let value = try!(next_element(&mut seq, 0, &self));
+                Ok(NewtypeStruct(value))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct("NewtypeStruct", NewtypeStructVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for TupleStruct {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TupleStructVisitor;
+
+        impl<'de> Visitor<'de> for TupleStructVisitor {
+            type Value = TupleStruct;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("tuple struct TupleStruct")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TupleStruct, A::Error> {
+                let first = try!(next_element(&mut seq, 0, &self));
+                let second = try!(next_element(&mut seq, 1, &self));
+                Ok(TupleStruct(first, second))
+            }
+        }
+
+        deserializer.deserialize_tuple_struct("TupleStruct", 2, TupleStructVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RecordVisitor;
+
+        impl<'de> Visitor<'de> for RecordVisitor {
+            type Value = Record;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct Record")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Record, A::Error> {
+                Ok(
+                    Record {
+                        id: try!(next_element(&mut seq, 0, &self)),
+                        label: try!(next_element(&mut seq, 1, &self)),
+                        shape: try!(next_element(&mut seq, 2, &self)),
+                    },
+                )
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Record, A::Error> {
+                let mut id = None;
+                let mut label = None;
+                let mut shape = None;
+                while let Some(field) = try!(map.next_key_seed(Identifier(RECORD_FIELDS))) {
+                    match field {
+                        0 => id = Some(try!(map.next_value())),
+                        1 => label = Some(try!(map.next_value())),
+                        _ => shape = Some(try!(map.next_value())),
+                    }
+                }
+                Ok(
+                    Record {
+                        id: try!(id.ok_or_else(|| de::Error::missing_field("id"))),
+                        label: try!(label.ok_or_else(|| de::Error::missing_field("label"))),
+                        shape: try!(shape.ok_or_else(|| de::Error::missing_field("shape"))),
+                    },
+                )
+            }
+        }
+
+        deserializer.deserialize_struct("Record", RECORD_FIELDS, RecordVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shape {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ShapeVisitor;
+
+        impl<'de> Visitor<'de> for ShapeVisitor {
+            type Value = Shape;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("enum Shape")
+            }
+
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Shape, A::Error> {
+                let (variant, access) = try!(data.variant_seed(Identifier(SHAPE_VARIANTS)));
+                match variant {
+                    0 => {
+                        try!(access.unit_variant());
+                        Ok(Shape::Empty)
+                    }
+                    1 => access.newtype_variant().map(Shape::Circle),
+                    2 => access.tuple_variant(2, LineVisitor),
+                    _ => access.struct_variant(GROUP_FIELDS, GroupVisitor),
+                }
+            }
+        }
+
+        struct LineVisitor;
+
+        impl<'de> Visitor<'de> for LineVisitor {
+            type Value = Shape;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("tuple variant Shape::Line")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Shape, A::Error> {
+                let from = try!(next_element(&mut seq, 0, &self));
+                let to = try!(next_element(&mut seq, 1, &self));
+                Ok(Shape::Line(from, to))
+            }
+        }
+
+        struct GroupVisitor;
+
+        impl<'de> Visitor<'de> for GroupVisitor {
+            type Value = Shape;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct variant Shape::Group")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Shape, A::Error> {
+                Ok(
+                    Shape::Group {
+                        name: try!(next_element(&mut seq, 0, &self)),
+                        members: try!(next_element(&mut seq, 1, &self)),
+                    },
+                )
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Shape, A::Error> {
+                let mut name = None;
+                let mut members = None;
+                while let Some(field) = try!(map.next_key_seed(Identifier(GROUP_FIELDS))) {
+                    match field {
+                        0 => name = Some(try!(map.next_value())),
+                        _ => members = Some(try!(map.next_value())),
+                    }
+                }
+                Ok(
+                    Shape::Group {
+                        name: try!(name.ok_or_else(|| de::Error::missing_field("name"))),
+                        members: try!(members.ok_or_else(|| de::Error::missing_field("members"))),
+                    },
+                )
+            }
+        }
+
+        deserializer.deserialize_enum("Shape", SHAPE_VARIANTS, ShapeVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fail {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        try!(de::IgnoredAny::deserialize(deserializer));
+        Err(de::Error::custom(MESSAGE))
+    }
+}
+
+fn next_element<'de, A, T, V>(seq: &mut A, index: usize, visitor: &V) -> Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+    V: Visitor<'de>,
+{
+    match try!(seq.next_element()) {
+        Some(value) => Ok(value),
+        None => Err(de::Error::invalid_length(index, visitor)),
+    }
+}
+
+// Deserializes a field or variant identifier into its index in a list of
+// names. The identifier may be given as its name or as its index.
+struct Identifier(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for Identifier {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Identifier {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "one of {:?}", self.0)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<usize, E> {
+        if v < self.0.len() as u64 {
+            Ok(v as usize)
+        } else {
+            Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<usize, E> {
+        match self.0.iter().position(|name| *name == v) {
+            Some(index) => Ok(index),
+            None => Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<usize, E> {
+        match self.0.iter().position(|name| name.as_bytes() == v) {
+            Some(index) => Ok(index),
+            None => Err(de::Error::invalid_value(de::Unexpected::Bytes(v), &self)),
+        }
+    }
+}
@@ -165,15 +165,25 @@ mod de;
 mod error;
 
 mod token;
+mod adversarial;
 mod assert;
+mod record;
+mod roundtrip;
+
+#[cfg(feature = "conformance")]
+pub mod conformance;
 
 pub use token::Token;
+pub use adversarial::{Attack, AdversarialDeserializer, assert_de_robust};
 pub use assert::{assert_tokens, assert_ser_tokens, assert_ser_tokens_error,
                  assert_de_tokens, assert_de_tokens_error};
+pub use record::{Call, RecordingSerializer};
+pub use roundtrip::{assert_round_trip, assert_round_trip_all};
 
 // Not public API.
 #[doc(hidden)]
-pub use assert::{assert_tokens_readable, assert_de_tokens_readable, assert_ser_tokens_readable};
+pub use assert::{assert_tokens_readable, assert_de_tokens_readable, assert_de_tokens_error_readable,
+                 assert_ser_tokens_error_readable, assert_ser_tokens_readable};
 
 // Not public API.
 #[doc(hidden)]
@@ -0,0 +1,458 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Debug, Display};
+
+use serde::{ser, Serialize};
+
+use error::Error;
+
+/// A single method call made on a `RecordingSerializer`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    /// How many compound values (seqs, maps, structs, variants, newtypes and
+    /// options) enclose this call.
+    pub depth: usize,
+    /// Name of the method that was called, for example `"serialize_field"`.
+    pub method: &'static str,
+    /// The arguments of the call in `Debug` format, separated by commas.
+    pub args: String,
+}
+
+impl Display for Call {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for _ in 0..self.depth {
+            try!(formatter.write_str("  "));
+        }
+        write!(formatter, "{}({})", self.method, self.args)
+    }
+}
+
+/// A `Serializer` that records every call made on it instead of producing any
+/// output.
+///
+/// This is useful for finding out exactly what a handwritten `Serialize` impl
+/// does. The recorded log can be inspected through `calls` or printed using
+/// the `Display` impl, which renders one call per line indented by nesting
+/// depth.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// #
+/// # extern crate serde;
+/// # extern crate serde_test;
+/// #
+/// # use serde::Serialize;
+/// # use serde_test::RecordingSerializer;
+/// #
+/// # fn main() {
+/// #[derive(Serialize)]
+/// struct S {
+///     a: Option<u8>,
+/// }
+///
+/// let mut recording = RecordingSerializer::new();
+/// S { a: Some(1) }.serialize(&mut recording).unwrap();
+///
+/// assert_eq!(recording.to_string(), "\
+/// serialize_struct(\"S\", 1)
+///   serialize_field(\"a\")
+///     serialize_some()
+///       serialize_u8(1)
+/// end()
+/// ");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingSerializer {
+    calls: Vec<Call>,
+    depth: usize,
+    is_human_readable: bool,
+}
+
+impl RecordingSerializer {
+    /// Creates a human-readable recording serializer with an empty log.
+    pub fn new() -> Self {
+        RecordingSerializer::readable(true)
+    }
+
+    /// Creates a recording serializer that reports the given value from
+    /// `is_human_readable`.
+    pub fn readable(is_human_readable: bool) -> Self {
+        RecordingSerializer {
+            calls: Vec::new(),
+            depth: 0,
+            is_human_readable: is_human_readable,
+        }
+    }
+
+    /// The calls recorded so far, in the order they were made.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// Consumes the serializer, returning the recorded calls.
+    pub fn into_calls(self) -> Vec<Call> {
+        self.calls
+    }
+
+    fn record(&mut self, method: &'static str, args: String) {
+        let depth = self.depth;
+        self.calls.push(Call { depth: depth, method: method, args: args });
+    }
+
+    fn nested<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.depth += 1;
+        let result = value.serialize(&mut *self);
+        self.depth -= 1;
+        result
+    }
+
+    fn begin(&mut self, method: &'static str, args: String) {
+        self.record(method, args);
+        self.depth += 1;
+    }
+
+    fn end(&mut self) {
+        self.depth -= 1;
+        self.record("end", String::new());
+    }
+}
+
+impl Default for RecordingSerializer {
+    fn default() -> Self {
+        RecordingSerializer::new()
+    }
+}
+
+impl Display for RecordingSerializer {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for call in &self.calls {
+            try!(writeln!(formatter, "{}", call));
+        }
+        Ok(())
+    }
+}
+
+fn debug<T: ?Sized + Debug>(v: &T) -> String {
+    format!("{:?}", v)
+}
+
+macro_rules! record_primitive {
+    ($($(#[$attr:meta])* $method:ident($ty:ty),)*) => {
+        $(
+            $(#[$attr])*
+            fn $method(self, v: $ty) -> Result<(), Error> {
+                self.record(stringify!($method), debug(&v));
+                Ok(())
+            }
+        )*
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    record_primitive! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        #[cfg(feature = "integer128")]
+        serialize_i128(i128),
+        #[cfg(feature = "integer128")]
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.record("serialize_unit", String::new());
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Error> {
+        self.record("serialize_unit_struct", debug(name));
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        let args = format!("{:?}, {}, {:?}", name, variant_index, variant);
+        self.record("serialize_unit_variant", args);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_newtype_struct", debug(name));
+        self.nested(value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        let args = format!("{:?}, {}, {:?}", name, variant_index, variant);
+        self.record("serialize_newtype_variant", args);
+        self.nested(value)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.record("serialize_none", String::new());
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_some", String::new());
+        self.nested(value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+        self.begin("serialize_seq", debug(&len));
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, Error> {
+        self.begin("serialize_tuple", debug(&len));
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self, Error> {
+        self.begin("serialize_tuple_struct", format!("{:?}, {}", name, len));
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self, Error> {
+        let args = format!("{:?}, {}, {:?}, {}", name, variant_index, variant, len);
+        self.begin("serialize_tuple_variant", args);
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, Error> {
+        self.begin("serialize_map", debug(&len));
+        Ok(self)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self, Error> {
+        self.begin("serialize_struct", format!("{:?}, {}", name, len));
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self, Error> {
+        let args = format!("{:?}, {}, {:?}, {}", name, variant_index, variant, len);
+        self.begin("serialize_struct_variant", args);
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.is_human_readable
+    }
+}
+
+impl<'a> ser::SerializeSeq for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_element", String::new());
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_element", String::new());
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_field", String::new());
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_field", String::new());
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_key", String::new());
+        self.nested(key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_value", String::new());
+        self.nested(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_field", debug(key));
+        self.nested(value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        self.record("skip_field", debug(key));
+        Ok(())
+    }
+
+    fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Error> {
+        self.record("annotate", format!("{:?}, {:?}", key, annotation));
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for &'a mut RecordingSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.record("serialize_field", debug(key));
+        self.nested(value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        self.record("skip_field", debug(key));
+        Ok(())
+    }
+
+    fn annotate(&mut self, key: &'static str, annotation: &'static str) -> Result<(), Error> {
+        self.record("annotate", format!("{:?}, {:?}", key, annotation));
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        RecordingSerializer::end(self);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::fmt::{Debug, Display};
+
+/// Asserts that `value` survives a round trip through a data format.
+///
+/// The format is given as a pair of functions, one serializing a value into
+/// the format's representation and one deserializing it back. For a typical
+/// format crate these are its `to_*` and `from_*` entry points.
+///
+/// ```rust
+/// # extern crate serde_test;
+/// #
+/// # use serde_test::assert_round_trip;
+/// #
+/// # mod format {
+/// #     use std::num::ParseIntError;
+/// #     pub fn to_string(v: &u32) -> Result<String, ParseIntError> { Ok(v.to_string()) }
+/// #     pub fn from_str(s: &str) -> Result<u32, ParseIntError> { s.parse() }
+/// # }
+/// #
+/// # fn main() {
+/// assert_round_trip(&5u32, format::to_string, |s| format::from_str(s));
+/// # }
+/// ```
+pub fn assert_round_trip<T, R, S, D, SE, DE>(value: &T, serialize: S, deserialize: D)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+    R: Debug,
+    S: Fn(&T) -> Result<R, SE>,
+    D: Fn(&R) -> Result<T, DE>,
+    SE: Display,
+    DE: Display,
+{
+    if let Err(msg) = round_trip(value, &serialize, &deserialize) {
+        panic!("value {}", msg);
+    }
+}
+
+/// Runs `assert_round_trip` on every value produced by `values`.
+///
+/// This is the hook for randomized testing: pass an iterator drawing values
+/// from a random generator, for example one built on `quickcheck::Arbitrary`,
+/// and every generated value is checked against the format. The panic message
+/// includes the position of the first value that fails to round trip.
+///
+/// ```rust
+/// # extern crate serde_test;
+/// #
+/// # use serde_test::assert_round_trip_all;
+/// #
+/// # mod format {
+/// #     use std::num::ParseIntError;
+/// #     pub fn to_string(v: &u32) -> Result<String, ParseIntError> { Ok(v.to_string()) }
+/// #     pub fn from_str(s: &str) -> Result<u32, ParseIntError> { s.parse() }
+/// # }
+/// #
+/// # fn main() {
+/// // A simple linear congruential generator standing in for a real one.
+/// let values = (0..100u32).scan(1u32, |state, _| {
+///     *state = state.wrapping_mul(1103515245).wrapping_add(12345);
+///     Some(*state)
+/// });
+///
+/// assert_round_trip_all(values, format::to_string, |s| format::from_str(s));
+/// # }
+/// ```
+pub fn assert_round_trip_all<I, T, R, S, D, SE, DE>(values: I, serialize: S, deserialize: D)
+where
+    I: IntoIterator<Item = T>,
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+    R: Debug,
+    S: Fn(&T) -> Result<R, SE>,
+    D: Fn(&R) -> Result<T, DE>,
+    SE: Display,
+    DE: Display,
+{
+    for (i, value) in values.into_iter().enumerate() {
+        if let Err(msg) = round_trip(&value, &serialize, &deserialize) {
+            panic!("value #{} {}", i, msg);
+        }
+    }
+}
+
+fn round_trip<T, R, S, D, SE, DE>(value: &T, serialize: &S, deserialize: &D) -> Result<(), String>
+where
+    T: PartialEq + Debug,
+    R: Debug,
+    S: Fn(&T) -> Result<R, SE>,
+    D: Fn(&R) -> Result<T, DE>,
+    SE: Display,
+    DE: Display,
+{
+    let serialized = match serialize(value) {
+        Ok(serialized) => serialized,
+        Err(err) => return Err(format!("failed to serialize: {}\nvalue: {:?}", err, value)),
+    };
+
+    let deserialized = match deserialize(&serialized) {
+        Ok(deserialized) => deserialized,
+        Err(err) => {
+            return Err(
+                format!(
+                    "failed to deserialize: {}\nvalue: {:?}\nserialized: {:?}",
+                    err,
+                    value,
+                    serialized
+                ),
+            )
+        }
+    };
+
+    if deserialized != *value {
+        return Err(
+            format!(
+                "changed in round trip\nvalue: {:?}\nserialized: {:?}\ndeserialized: {:?}",
+                value,
+                serialized,
+                deserialized
+            ),
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)] //~ ERROR: proc-macro derive panicked
+#[serde(deny_unknown_fields)] //~^ HELP: #[serde(collect_unknown)] cannot be used together with #[serde(deny_unknown_fields)]
+struct S {
+    a: u8,
+    #[serde(collect_unknown)]
+    other: BTreeMap<String, u8>,
+}
+
+fn main() {}
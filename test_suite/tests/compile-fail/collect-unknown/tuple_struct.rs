@@ -0,0 +1,18 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)] //~ ERROR: proc-macro derive panicked
+struct S(u8, #[serde(collect_unknown)] BTreeMap<String, u8>);
+//~^^ HELP: #[serde(collect_unknown)] can only be used in structs with named fields
+
+fn main() {}
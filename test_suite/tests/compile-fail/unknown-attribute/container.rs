@@ -10,7 +10,7 @@
 extern crate serde_derive;
 
 #[derive(Serialize)] //~ ERROR: proc-macro derive panicked
-#[serde(abc="xyz")] //~^ HELP: unknown serde container attribute `abc`
+#[serde(abc="xyz")] //~^ HELP: unknown serde container attribute `abc` on `A`
 struct A {
     x: u32,
 }
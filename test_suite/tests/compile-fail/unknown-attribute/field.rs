@@ -11,7 +11,7 @@ extern crate serde_derive;
 
 #[derive(Serialize)] //~ ERROR: proc-macro derive panicked
 struct C {
-    #[serde(abc="xyz")] //~^^ HELP: unknown serde field attribute `abc`
+    #[serde(abc="xyz")] //~^^ HELP: unknown serde field attribute `abc` on field `x`
     x: u32,
 }
 
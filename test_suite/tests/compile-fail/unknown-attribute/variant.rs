@@ -11,7 +11,7 @@ extern crate serde_derive;
 
 #[derive(Serialize)] //~ ERROR: proc-macro derive panicked
 enum E {
-    #[serde(abc="xyz")] //~^^ HELP: unknown serde variant attribute `abc`
+    #[serde(abc="xyz")] //~^^ HELP: unknown serde variant attribute `abc` on variant `V`
     V,
 }
 
@@ -0,0 +1,142 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::de::{Deserialize, Deserializer, IgnoredAny, SeqAccess, Visitor};
+
+extern crate serde_test;
+use serde_test::{AdversarialDeserializer, Attack, assert_de_robust};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Record {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+    parent: Option<Box<Record>>,
+}
+
+#[derive(Deserialize, Debug)]
+enum Shape {
+    Unit,
+    Newtype(f64),
+    Tuple(i8, char),
+    Struct { a: Vec<u8>, b: BTreeMap<String, bool> },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Untagged {
+    Int(u32),
+    Pair(String, String),
+    List(Vec<Untagged>),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum Internal {
+    A { x: u8 },
+    B(BTreeMap<String, u8>),
+}
+
+#[derive(Deserialize, Debug)]
+struct Tree(Vec<Tree>);
+
+#[test]
+fn test_derived_impls_survive() {
+    assert_de_robust::<Record>();
+    assert_de_robust::<Vec<Shape>>();
+    assert_de_robust::<Untagged>();
+    assert_de_robust::<Internal>();
+    assert_de_robust::<Tree>();
+    assert_de_robust::<IgnoredAny>();
+}
+
+#[test]
+fn test_std_impls_survive() {
+    assert_de_robust::<(String, Vec<u8>, HashMap<String, Option<char>>)>();
+    assert_de_robust::<[Box<str>; 3]>();
+    assert_de_robust::<std::net::SocketAddr>();
+    assert_de_robust::<std::time::Duration>();
+    assert_de_robust::<std::path::PathBuf>();
+}
+
+#[test]
+fn test_well_typed_without_attack() {
+    // A truncation of a compound value that does not exist is no attack at
+    // all, so the value deserializes normally.
+    let mut de = AdversarialDeserializer::new(Attack::Truncated { at: 1000 });
+    let record = Record::deserialize(&mut de).unwrap();
+    assert!(!de.struck());
+    assert_eq!(record.name, "a");
+    assert_eq!(record.tags, vec!["a".to_owned(), "a".to_owned()]);
+    assert!(record.parent.is_some());
+}
+
+#[test]
+fn test_attacks_strike() {
+    let mut de = AdversarialDeserializer::new(Attack::WrongType { at: 0, answer: 0 });
+    assert!(u8::deserialize(&mut de).is_err());
+    assert!(de.struck());
+
+    let mut de = AdversarialDeserializer::new(Attack::Truncated { at: 0 });
+    assert!(<(u8, u8)>::deserialize(&mut de).is_err());
+    assert!(de.struck());
+
+    let mut de = AdversarialDeserializer::new(Attack::DuplicateKeys);
+    let err = Record::deserialize(&mut de).unwrap_err();
+    assert_eq!(err.to_string(), "duplicate field `id`");
+
+    let mut de = AdversarialDeserializer::new(Attack::DeepNesting { depth: 10 });
+    IgnoredAny::deserialize(&mut de).unwrap();
+    assert!(de.struck());
+}
+
+// Trusts the size hint, as a Deserialize impl should not.
+struct Greedy;
+
+impl<'de> Deserialize<'de> for Greedy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct GreedyVisitor;
+
+        impl<'de> Visitor<'de> for GreedyVisitor {
+            type Value = Greedy;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a sequence")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Greedy, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut bytes = Vec::<u64>::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = try!(seq.next_element()) {
+                    bytes.push(byte);
+                }
+                Ok(Greedy)
+            }
+        }
+
+        deserializer.deserialize_seq(GreedyVisitor)
+    }
+}
+
+#[test]
+#[should_panic(expected = "capacity overflow")]
+fn test_huge_size_hint_exposes_greedy_allocation() {
+    assert_de_robust::<Greedy>();
+}
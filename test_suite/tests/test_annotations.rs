@@ -11,13 +11,18 @@
 #[macro_use]
 extern crate serde_derive;
 
+#[macro_use]
 extern crate serde;
 use self::serde::{Serialize, Serializer, Deserialize, Deserializer};
 use self::serde::de::{self, Unexpected};
 
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+
 extern crate serde_test;
 use self::serde_test::{Token, assert_tokens, assert_ser_tokens, assert_de_tokens,
-                       assert_de_tokens_error};
+                       assert_de_tokens_error, assert_tokens_readable, assert_de_tokens_readable,
+                       assert_de_tokens_error_readable};
 
 trait MyDefault: Sized {
     fn my_default() -> Self;
@@ -1041,6 +1046,555 @@ fn test_deserialize_with_enum() {
     );
 }
 
+#[derive(Debug, PartialEq, Deserialize)]
+struct LenientStruct<A> {
+    #[serde(lenient)]
+    a: A,
+    #[serde(lenient = "fallback_u8")]
+    b: u8,
+    c: u8,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct LenientNewtype(#[serde(lenient)] Option<u8>);
+
+fn fallback_u8() -> u8 {
+    99
+}
+
+#[test]
+fn test_lenient_struct() {
+    assert_de_tokens_readable(
+        &LenientStruct { a: 1, b: 2, c: 3 },
+        &[
+            Token::Struct { name: "LenientStruct", len: 3 },
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::Str("b"),
+            Token::U8(2),
+
+            Token::Str("c"),
+            Token::U8(3),
+
+            Token::StructEnd,
+        ],
+        Some(true),
+    );
+
+    assert_de_tokens_readable(
+        &LenientStruct { a: 0, b: 99, c: 3 },
+        &[
+            Token::Struct { name: "LenientStruct", len: 3 },
+
+            Token::Str("a"),
+            Token::Str("N/A"),
+
+            Token::Str("b"),
+            Token::Seq { len: Some(1) },
+            Token::Str("nested"),
+            Token::SeqEnd,
+
+            Token::Str("c"),
+            Token::U8(3),
+
+            Token::StructEnd,
+        ],
+        Some(true),
+    );
+
+    assert_de_tokens_readable(
+        &LenientStruct { a: 0, b: 99, c: 3 },
+        &[
+            Token::Seq { len: Some(3) },
+            Token::Bool(true),
+            Token::I64(-1),
+            Token::U8(3),
+            Token::SeqEnd,
+        ],
+        Some(true),
+    );
+
+    assert_de_tokens_error::<LenientStruct<i32>>(
+        &[
+            Token::Struct { name: "LenientStruct", len: 3 },
+
+            Token::Str("c"),
+            Token::Str("N/A"),
+        ],
+        "invalid type: string \"N/A\", expected u8",
+    );
+}
+
+#[test]
+fn test_lenient_newtype() {
+    assert_de_tokens_readable(
+        &LenientNewtype(None),
+        &[
+            Token::NewtypeStruct { name: "LenientNewtype" },
+            Token::Str("N/A"),
+        ],
+        Some(true),
+    );
+}
+
+#[test]
+fn test_lenient_input_error() {
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{Error, MapDeserializer};
+
+    // A value that fails while it is being read, like an I/O or syntax error.
+    struct Unreadable;
+
+    impl<'de> IntoDeserializer<'de, Error> for Unreadable {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Unreadable {
+        type Error = Error;
+
+        fn deserialize_any<V>(self, _: V) -> Result<V::Value, Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            Err(de::Error::custom("unexpected end of input"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    let map = MapDeserializer::new(vec![("a", Unreadable)].into_iter());
+    let err = LenientStruct::<i32>::deserialize(map).unwrap_err();
+    assert_eq!(err.to_string(), "unexpected end of input");
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct LenientCompact {
+    #[serde(lenient)]
+    ip: Option<Ipv4Addr>,
+    #[serde(lenient, with_compact = "as_u64")]
+    n: u32,
+}
+
+#[test]
+fn test_lenient_compact() {
+    assert_de_tokens_readable(
+        &LenientCompact {
+            ip: Some(Ipv4Addr::new(1, 2, 3, 4)),
+            n: 5,
+        },
+        &[
+            Token::Struct { name: "LenientCompact", len: 2 },
+
+            Token::Str("ip"),
+            Token::Some,
+            Token::Tuple { len: 4 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::U8(3),
+            Token::U8(4),
+            Token::TupleEnd,
+
+            Token::Str("n"),
+            Token::U64(5),
+
+            Token::StructEnd,
+        ],
+        Some(false),
+    );
+
+    assert_de_tokens_readable(
+        &LenientCompact { ip: None, n: 0 },
+        &[
+            Token::Struct { name: "LenientCompact", len: 2 },
+
+            Token::Str("ip"),
+            Token::Some,
+            Token::Str("1.2.3.4"),
+
+            Token::Str("n"),
+            Token::Str("5"),
+
+            Token::StructEnd,
+        ],
+        Some(false),
+    );
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CollectUnknownStruct<V> {
+    a: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b: Option<i32>,
+    #[serde(collect_unknown)]
+    other: BTreeMap<String, V>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct CollectUnknownHashMap {
+    a: i32,
+    #[serde(collect_unknown)]
+    other: HashMap<String, String>,
+}
+
+#[test]
+fn test_collect_unknown_struct() {
+    let mut other = BTreeMap::new();
+    other.insert("x".to_owned(), 2);
+    other.insert("y".to_owned(), 3);
+
+    assert_tokens(
+        &CollectUnknownStruct {
+            a: 1,
+            b: None,
+            other: other.clone(),
+        },
+        &[
+            Token::Map { len: Some(3) },
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::Str("x"),
+            Token::I32(2),
+
+            Token::Str("y"),
+            Token::I32(3),
+
+            Token::MapEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &CollectUnknownStruct {
+            a: 1,
+            b: Some(4),
+            other: other,
+        },
+        &[
+            Token::Struct { name: "CollectUnknownStruct", len: 2 },
+
+            Token::Str("y"),
+            Token::I32(3),
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::Bytes(b"x"),
+            Token::I32(2),
+
+            Token::Str("b"),
+            Token::Some,
+            Token::I32(4),
+
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &CollectUnknownStruct::<i32> {
+            a: 1,
+            b: None,
+            other: BTreeMap::new(),
+        },
+        &[
+            Token::Map { len: Some(1) },
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::MapEnd,
+        ],
+    );
+
+    let mut other = HashMap::new();
+    other.insert("x".to_owned(), "z".to_owned());
+
+    assert_de_tokens(
+        &CollectUnknownHashMap { a: 1, other: other },
+        &[
+            Token::Map { len: Some(2) },
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::Str("x"),
+            Token::Str("z"),
+
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_collect_unknown_struct_errors() {
+    assert_de_tokens_error::<CollectUnknownStruct<i32>>(
+        &[
+            Token::Map { len: Some(1) },
+
+            Token::Str("x"),
+            Token::I32(2),
+
+            Token::MapEnd,
+        ],
+        "missing field `a`",
+    );
+
+    assert_de_tokens_error::<CollectUnknownStruct<i32>>(
+        &[
+            Token::Map { len: Some(2) },
+
+            Token::Str("a"),
+            Token::I32(1),
+
+            Token::Str("x"),
+            Token::Str("y"),
+
+            Token::MapEnd,
+        ],
+        "invalid type: string \"y\", expected i32",
+    );
+
+    assert_de_tokens_error::<CollectUnknownStruct<i32>>(
+        &[Token::Seq { len: Some(1) }],
+        "invalid type: sequence, expected struct CollectUnknownStruct",
+    );
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(validate = "ValidateStruct::check")]
+struct ValidateStruct {
+    min: u8,
+    #[serde(validate = "non_zero")]
+    max: u8,
+    #[serde(lenient, validate = "non_zero")]
+    step: u8,
+}
+
+impl ValidateStruct {
+    fn check(&self) -> Result<(), String> {
+        if self.min <= self.max {
+            Ok(())
+        } else {
+            Err(format!("min {} is greater than max {}", self.min, self.max))
+        }
+    }
+}
+
+fn non_zero(value: &u8) -> Result<(), &'static str> {
+    if *value == 0 {
+        Err("value must not be zero")
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(validate = "ValidateEnum::check")]
+enum ValidateEnum {
+    Percent(u8),
+}
+
+impl ValidateEnum {
+    fn check(&self) -> Result<(), String> {
+        match *self {
+            ValidateEnum::Percent(p) if p > 100 => Err(format!("{}% is out of range", p)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_validate() {
+    assert_de_tokens_readable(
+        &ValidateStruct { min: 1, max: 2, step: 1 },
+        &[
+            Token::Struct { name: "ValidateStruct", len: 3 },
+
+            Token::Str("min"),
+            Token::U8(1),
+
+            Token::Str("max"),
+            Token::U8(2),
+
+            Token::Str("step"),
+            Token::U8(1),
+
+            Token::StructEnd,
+        ],
+        Some(true),
+    );
+
+    assert_de_tokens_readable(
+        &ValidateStruct { min: 1, max: 2, step: 0 },
+        &[
+            Token::Struct { name: "ValidateStruct", len: 3 },
+
+            Token::Str("min"),
+            Token::U8(1),
+
+            Token::Str("max"),
+            Token::U8(2),
+
+            Token::Str("step"),
+            Token::U8(0),
+
+            Token::StructEnd,
+        ],
+        Some(true),
+    );
+
+    assert_de_tokens(
+        &ValidateEnum::Percent(100),
+        &[
+            Token::NewtypeVariant { name: "ValidateEnum", variant: "Percent" },
+            Token::U8(100),
+        ],
+    );
+}
+
+#[test]
+fn test_validate_errors() {
+    assert_de_tokens_error_readable::<ValidateStruct>(
+        &[
+            Token::Struct { name: "ValidateStruct", len: 3 },
+
+            Token::Str("min"),
+            Token::U8(3),
+
+            Token::Str("max"),
+            Token::U8(2),
+
+            Token::Str("step"),
+            Token::U8(1),
+
+            Token::StructEnd,
+        ],
+        "min 3 is greater than max 2",
+        Some(true),
+    );
+
+    assert_de_tokens_error::<ValidateStruct>(
+        &[
+            Token::Struct { name: "ValidateStruct", len: 3 },
+
+            Token::Str("min"),
+            Token::U8(0),
+
+            Token::Str("max"),
+            Token::U8(0),
+        ],
+        "value must not be zero",
+    );
+
+    assert_de_tokens_error::<ValidateStruct>(
+        &[Token::Seq { len: Some(3) }, Token::U8(0), Token::U8(0)],
+        "value must not be zero",
+    );
+
+    assert_de_tokens_error::<ValidateEnum>(
+        &[
+            Token::NewtypeVariant { name: "ValidateEnum", variant: "Percent" },
+            Token::U8(101),
+        ],
+        "101% is out of range",
+    );
+}
+
+mod as_string {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = try!(String::deserialize(deserializer));
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+mod as_u64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(*value as u64)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(|value| value as u32)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ReadableCompact {
+    #[serde(with_readable = "as_string", with_compact = "as_u64")]
+    both: u32,
+    #[serde(with_readable = "as_string")]
+    readable_only: u32,
+}
+
+#[test]
+fn test_with_readable_and_compact() {
+    let value = ReadableCompact {
+        both: 1,
+        readable_only: 2,
+    };
+
+    assert_tokens_readable(
+        &value,
+        &[
+            Token::Struct { name: "ReadableCompact", len: 2 },
+
+            Token::Str("both"),
+            Token::Str("1"),
+
+            Token::Str("readable_only"),
+            Token::Str("2"),
+
+            Token::StructEnd,
+        ],
+        Some(true),
+    );
+
+    assert_tokens_readable(
+        &value,
+        &[
+            Token::Struct { name: "ReadableCompact", len: 2 },
+
+            Token::Str("both"),
+            Token::U64(1),
+
+            Token::Str("readable_only"),
+            Token::U32(2),
+
+            Token::StructEnd,
+        ],
+        Some(false),
+    );
+}
+
 #[test]
 fn test_missing_renamed_field_struct() {
     assert_de_tokens_error::<RenameStruct>(
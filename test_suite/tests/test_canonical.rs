@@ -0,0 +1,232 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeMap;
+
+extern crate serde_test;
+use serde_test::{RecordingSerializer, Token, assert_ser_tokens_error_readable,
+                 assert_ser_tokens_readable};
+use serde::canonical::CanonicalSerializer;
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+#[derive(Serialize)]
+struct Canonical<T: Serialize> {
+    #[serde(serialize_with = "serde::canonical::serialize")]
+    value: T,
+}
+
+fn assert_canonical<T: Serialize>(value: T, tokens: &[Token]) {
+    let mut expected = vec![Token::Struct { name: "Canonical", len: 1 }, Token::Str("value")];
+    expected.extend_from_slice(tokens);
+    expected.push(Token::StructEnd);
+    assert_ser_tokens_readable(&Canonical { value: value }, &expected, Some(true));
+}
+
+#[test]
+fn test_sorted_map() {
+    let mut map = HashMap::new();
+    for i in 0..10 {
+        map.insert(10 - i, i);
+    }
+
+    let mut tokens = vec![Token::Map { len: Some(10) }];
+    for i in 1..11 {
+        tokens.push(Token::I32(i));
+        tokens.push(Token::I32(10 - i));
+    }
+    tokens.push(Token::MapEnd);
+    assert_canonical(map, &tokens);
+}
+
+#[test]
+fn test_nested_maps() {
+    let mut inner = HashMap::new();
+    inner.insert("b", ());
+    inner.insert("a", ());
+    let mut outer = HashMap::new();
+    outer.insert(vec![2, 1], Some(inner.clone()));
+    outer.insert(vec![1, 2, 3], None);
+    outer.insert(vec![1, 2], Some(inner));
+
+    assert_canonical(
+        outer,
+        &[
+            Token::Map { len: Some(3) },
+            Token::Seq { len: Some(2) },
+            Token::I32(1),
+            Token::I32(2),
+            Token::SeqEnd,
+            Token::Some,
+            Token::Map { len: Some(2) },
+            Token::Str("a"),
+            Token::Unit,
+            Token::Str("b"),
+            Token::Unit,
+            Token::MapEnd,
+            Token::Seq { len: Some(3) },
+            Token::I32(1),
+            Token::I32(2),
+            Token::I32(3),
+            Token::SeqEnd,
+            Token::None,
+            Token::Seq { len: Some(2) },
+            Token::I32(2),
+            Token::I32(1),
+            Token::SeqEnd,
+            Token::Some,
+            Token::Map { len: Some(2) },
+            Token::Str("a"),
+            Token::Unit,
+            Token::Str("b"),
+            Token::Unit,
+            Token::MapEnd,
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_floats() {
+    assert_canonical(-0.0f64, &[Token::F64(0.0)]);
+    assert_canonical(
+        vec![1.5f32, -0.0],
+        &[
+            Token::Seq { len: Some(2) },
+            Token::F32(1.5),
+            Token::F32(0.0),
+            Token::SeqEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_preserves_readability() {
+    let mut map = HashMap::new();
+    map.insert(Ipv4Addr::new(10, 0, 0, 2), 0);
+    map.insert(Ipv4Addr::new(10, 0, 0, 1), 1);
+
+    assert_ser_tokens_readable(
+        &Canonical { value: map },
+        &[
+            Token::Struct { name: "Canonical", len: 1 },
+            Token::Str("value"),
+            Token::Map { len: Some(2) },
+            Token::Tuple { len: 4 },
+            Token::U8(10),
+            Token::U8(0),
+            Token::U8(0),
+            Token::U8(1),
+            Token::TupleEnd,
+            Token::I32(1),
+            Token::Tuple { len: 4 },
+            Token::U8(10),
+            Token::U8(0),
+            Token::U8(0),
+            Token::U8(2),
+            Token::TupleEnd,
+            Token::I32(0),
+            Token::MapEnd,
+            Token::StructEnd,
+        ],
+        Some(false),
+    );
+}
+
+struct DuplicateKeys;
+
+impl Serialize for DuplicateKeys {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = try!(serializer.serialize_map(None));
+        try!(map.serialize_entry("a", &1));
+        try!(map.serialize_entry("a", &2));
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct Duplicate {
+    #[serde(serialize_with = "serde::canonical::serialize")]
+    value: DuplicateKeys,
+}
+
+#[test]
+fn test_duplicate_keys() {
+    assert_ser_tokens_error_readable(
+        &Duplicate { value: DuplicateKeys },
+        &[Token::Struct { name: "Duplicate", len: 1 }, Token::Str("value")],
+        "duplicate map key in canonical form",
+        Some(true),
+    );
+}
+
+fn canonical_calls<T: Serialize>(value: T) -> Vec<String> {
+    let mut recording = RecordingSerializer::new();
+    value
+        .serialize(CanonicalSerializer::new(&mut recording))
+        .unwrap();
+    recording.calls().iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_skip_field() {
+    #[derive(Serialize)]
+    struct S {
+        a: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        b: Option<u8>,
+    }
+
+    assert_eq!(
+        canonical_calls(S { a: 1, b: None }),
+        [
+            "serialize_struct(\"S\", 1)",
+            "  serialize_field(\"a\")",
+            "    serialize_u8(1)",
+            "  skip_field(\"b\")",
+            "end()",
+        ]
+    );
+}
+
+#[test]
+fn test_integer128_keys() {
+    let mut map = HashMap::new();
+    map.insert(1i128 << 100, ());
+    map.insert(-1i128 << 100, ());
+    map.insert(0i128, ());
+
+    assert_eq!(
+        canonical_calls(map),
+        [
+            "serialize_map(Some(3))",
+            "  serialize_key()",
+            "    serialize_i128(-1267650600228229401496703205376)",
+            "  serialize_value()",
+            "    serialize_unit()",
+            "  serialize_key()",
+            "    serialize_i128(0)",
+            "  serialize_value()",
+            "    serialize_unit()",
+            "  serialize_key()",
+            "    serialize_i128(1267650600228229401496703205376)",
+            "  serialize_value()",
+            "    serialize_unit()",
+            "end()",
+        ]
+    );
+}
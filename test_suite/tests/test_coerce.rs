@@ -0,0 +1,178 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::Deserialize;
+use serde::coerce::{CoerceDeserializer, FloatToInt, Overflow, Policy};
+
+extern crate serde_test;
+use serde_test::Token;
+
+use std::fmt::Debug;
+
+fn coerce<'de, T>(tokens: &'de [Token], policy: Policy) -> Result<T, String>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = serde_test::Deserializer::new(tokens);
+    T::deserialize(CoerceDeserializer::new(&mut de, policy)).map_err(|e| e.to_string())
+}
+
+fn assert_coerce<'de, T>(tokens: &'de [Token], policy: Policy, expected: T)
+where
+    T: Deserialize<'de> + PartialEq + Debug,
+{
+    assert_eq!(coerce(tokens, policy), Ok(expected));
+}
+
+fn assert_coerce_error<'de, T>(tokens: &'de [Token], policy: Policy, error: &str)
+where
+    T: Deserialize<'de> + PartialEq + Debug,
+{
+    assert_eq!(coerce::<T>(tokens, policy), Err(error.to_owned()));
+}
+
+#[test]
+fn test_strict() {
+    let strict = Policy::strict();
+    assert_coerce(&[Token::U64(7)], strict, 7u8);
+    assert_coerce(&[Token::I8(-1)], strict, -1i64);
+    assert_coerce(&[Token::U8(1)], strict, 1f32);
+    assert_coerce_error::<u8>(
+        &[Token::U16(300)],
+        strict,
+        "invalid value: integer `300`, expected u8",
+    );
+    assert_coerce_error::<u32>(
+        &[Token::F64(42.0)],
+        strict,
+        "invalid type: floating point `42`, expected u32",
+    );
+    assert_coerce_error::<u32>(&[Token::Str("42")], strict, "invalid type: string \"42\", expected u32");
+}
+
+#[test]
+fn test_float_to_int() {
+    let exact = Policy::strict().float_to_int(FloatToInt::Exact);
+    assert_coerce(&[Token::F64(42.0)], exact, 42u32);
+    assert_coerce(&[Token::F32(-3.0)], exact, -3i16);
+    assert_coerce_error::<u32>(
+        &[Token::F64(42.5)],
+        exact,
+        "invalid type: floating point `42.5`, expected u32",
+    );
+    assert_coerce_error::<u8>(
+        &[Token::F64(300.0)],
+        exact,
+        "invalid type: floating point `300`, expected u8",
+    );
+
+    let truncate = Policy::strict().float_to_int(FloatToInt::Truncate);
+    assert_coerce(&[Token::F64(42.9)], truncate, 42u32);
+    assert_coerce(&[Token::F64(-2.7)], truncate, -2i8);
+    assert_coerce_error::<u64>(
+        &[Token::F64(::std::f64::NAN)],
+        truncate,
+        "invalid type: floating point `NaN`, expected u64",
+    );
+}
+
+#[test]
+fn test_overflow() {
+    let wrap = Policy::strict().overflow(Overflow::Wrap);
+    assert_coerce(&[Token::U16(300)], wrap, 44u8);
+    assert_coerce(&[Token::I32(-1)], wrap, 255u8);
+    assert_coerce(&[Token::U64(u64::max_value())], wrap, -1i64);
+
+    let saturate = Policy::strict().overflow(Overflow::Saturate);
+    assert_coerce(&[Token::U16(300)], saturate, 255u8);
+    assert_coerce(&[Token::I32(-1)], saturate, 0u8);
+    assert_coerce(&[Token::I64(-1000)], saturate, -128i8);
+    assert_coerce(&[Token::U64(u64::max_value())], saturate, i64::max_value());
+
+    let floats = saturate.float_to_int(FloatToInt::Exact);
+    assert_coerce(&[Token::F64(1e30)], floats, u64::max_value());
+    assert_coerce(&[Token::F64(-1e30)], floats, i32::min_value());
+
+    // Wrapping is only defined for integers.
+    let wrap_floats = wrap.float_to_int(FloatToInt::Exact);
+    assert_coerce(&[Token::F64(300.0)], wrap_floats, 44u8);
+    assert_coerce_error::<u64>(
+        &[Token::F64(1e30)],
+        wrap_floats,
+        "invalid type: floating point `1000000000000000000000000000000`, expected u64",
+    );
+}
+
+#[test]
+fn test_parse_strings() {
+    let parse = Policy::strict().parse_strings(true);
+    assert_coerce(&[Token::Str("8080")], parse, 8080u16);
+    assert_coerce(&[Token::String("-5")], parse, -5i32);
+    assert_coerce(&[Token::BorrowedStr("0.5")], parse, 0.5f64);
+    assert_coerce(&[Token::Str("7")], parse, 7f32);
+    assert_coerce_error::<u8>(
+        &[Token::Str("300")],
+        parse,
+        "invalid type: string \"300\", expected u8",
+    );
+    assert_coerce_error::<u32>(
+        &[Token::Str("42.0")],
+        parse,
+        "invalid type: string \"42.0\", expected u32",
+    );
+    assert_coerce_error::<u32>(&[Token::Str(" 1")], parse, "invalid type: string \" 1\", expected u32");
+    assert_coerce(&[Token::Str("8080")], parse, "8080".to_owned());
+
+    let lenient = parse.float_to_int(FloatToInt::Exact);
+    assert_coerce(&[Token::Str("42.0")], lenient, 42u32);
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Reading {
+    sensor: String,
+    values: Vec<u8>,
+    limit: Option<u16>,
+}
+
+#[test]
+fn test_nested() {
+    let policy = Policy::strict()
+        .float_to_int(FloatToInt::Truncate)
+        .overflow(Overflow::Saturate)
+        .parse_strings(true);
+
+    let tokens = [
+        Token::Struct { name: "Reading", len: 3 },
+        Token::Str("sensor"),
+        Token::Str("12"),
+        Token::Str("values"),
+        Token::Seq { len: Some(3) },
+        Token::F64(1.5),
+        Token::I64(-4),
+        Token::Str("999"),
+        Token::SeqEnd,
+        Token::Str("limit"),
+        Token::Some,
+        Token::F32(70000.0),
+        Token::StructEnd,
+    ];
+
+    assert_coerce(
+        &tokens,
+        policy,
+        Reading {
+            sensor: "12".to_owned(),
+            values: vec![1, 0, 255],
+            limit: Some(65535),
+        },
+    );
+}
@@ -0,0 +1,170 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde;
+use serde::Serialize;
+use serde::de::{self, DeserializeOwned};
+use serde::de::value::Error;
+use serde::private::de::{Content as De, ContentDeserializer};
+use serde::private::ser::{Content as Ser, ContentSerializer};
+
+extern crate serde_test;
+use serde_test::conformance::{self, Format};
+
+/// A self-describing in-memory format that serializes into the buffered
+/// `Content` used by untagged enums.
+struct ContentFormat;
+
+impl Format for ContentFormat {
+    type Output = Ser;
+    type Error = Error;
+
+    fn serialize<T: ?Sized + Serialize>(&self, value: &T) -> Result<Ser, Error> {
+        value.serialize(ContentSerializer::new())
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, output: &Ser) -> Result<T, Error> {
+        T::deserialize(ContentDeserializer::new(convert(output)))
+    }
+}
+
+// Represents enums the way the externally tagged representation does.
+fn convert(content: &Ser) -> De<'static> {
+    fn seq(elements: &[Ser]) -> De<'static> {
+        De::Seq(elements.iter().map(convert).collect())
+    }
+
+    fn fields(fields: &[(&'static str, Ser)]) -> De<'static> {
+        De::Map(fields.iter().map(|&(key, ref value)| (De::Str(key), convert(value))).collect())
+    }
+
+    fn variant(variant: &'static str, value: De<'static>) -> De<'static> {
+        De::Map(vec![(De::Str(variant), value)])
+    }
+
+    match *content {
+        Ser::Bool(v) => De::Bool(v),
+        Ser::U8(v) => De::U8(v),
+        Ser::U16(v) => De::U16(v),
+        Ser::U32(v) => De::U32(v),
+        Ser::U64(v) => De::U64(v),
+        Ser::I8(v) => De::I8(v),
+        Ser::I16(v) => De::I16(v),
+        Ser::I32(v) => De::I32(v),
+        Ser::I64(v) => De::I64(v),
+        Ser::U128(_) | Ser::I128(_) => panic!("deserializer Content has no 128-bit integers"),
+        Ser::F32(v) => De::F32(v),
+        Ser::F64(v) => De::F64(v),
+        Ser::Char(v) => De::Char(v),
+        Ser::String(ref v) => De::String(v.clone()),
+        Ser::Bytes(ref v) => De::ByteBuf(v.clone()),
+        Ser::None => De::None,
+        Ser::Some(ref v) => De::Some(Box::new(convert(v))),
+        Ser::Unit | Ser::UnitStruct(_) => De::Unit,
+        Ser::UnitVariant(_, _, v) => De::Str(v),
+        Ser::NewtypeStruct(_, ref v) => convert(v),
+        Ser::NewtypeVariant(_, _, v, ref value) => variant(v, convert(value)),
+        Ser::Seq(ref v) | Ser::Tuple(ref v) | Ser::TupleStruct(_, ref v) => seq(v),
+        Ser::TupleVariant(_, _, v, ref elements) => variant(v, seq(elements)),
+        Ser::Map(ref v) => De::Map(v.iter().map(|&(ref k, ref v)| (convert(k), convert(v))).collect()),
+        Ser::Struct(_, ref v) => fields(v),
+        Ser::StructVariant(_, _, v, ref value) => variant(v, fields(value)),
+    }
+}
+
+/// The content format without support for `Some(None)`, which it flattens to
+/// `None` like a format with a single `null` value would.
+struct FlatOptions;
+
+impl Format for FlatOptions {
+    type Output = Ser;
+    type Error = Error;
+
+    fn serialize<T: ?Sized + Serialize>(&self, value: &T) -> Result<Ser, Error> {
+        ContentFormat.serialize(value).map(flatten)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, output: &Ser) -> Result<T, Error> {
+        ContentFormat.deserialize(output)
+    }
+}
+
+fn flatten(content: Ser) -> Ser {
+    match content {
+        Ser::Some(inner) => {
+            match *inner {
+                Ser::None => Ser::None,
+                inner => Ser::Some(Box::new(flatten(inner))),
+            }
+        }
+        other => other,
+    }
+}
+
+/// A format that cannot serialize anything.
+struct Unsupported;
+
+impl Format for Unsupported {
+    type Output = ();
+    type Error = Error;
+
+    fn serialize<T: ?Sized + Serialize>(&self, _: &T) -> Result<(), Error> {
+        Err(de::Error::custom("unsupported"))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, _: &()) -> Result<T, Error> {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_conforming_format() {
+    conformance::assert_conformance(&ContentFormat);
+
+    let report = conformance::check(&ContentFormat);
+    assert!(report.outcomes().len() > 50);
+    assert!(report.outcomes().iter().any(|outcome| outcome.name == "huge seq"));
+    assert!(report.failures().is_empty());
+}
+
+#[test]
+fn test_known_gap() {
+    let report = conformance::check(&FlatOptions);
+    let failures = report.failures();
+
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].name, "option some none");
+    assert_eq!(
+        failures[0].to_string(),
+        "FAILED option some none: changed in round trip\n\
+         value: Some(None)\n\
+         serialized: None\n\
+         deserialized: None"
+    );
+}
+
+#[test]
+fn test_failing_format() {
+    let report = conformance::check(&Unsupported);
+
+    assert!(!report.is_ok());
+    assert_eq!(
+        report.outcomes()[0].to_string(),
+        "FAILED bool true: failed to serialize: unsupported\nvalue: true"
+    );
+    let summary = report.to_string();
+    let first_line = summary.lines().next().unwrap();
+    let n = report.outcomes().len();
+    assert_eq!(first_line, format!("{} checks, 0 passed, {} failed", n, n));
+}
+
+#[test]
+#[should_panic(expected = "FAILED option some none")]
+fn test_assert_conformance_panics() {
+    conformance::assert_conformance(&FlatOptions);
+}
@@ -15,12 +15,17 @@ use std::path::{Path, PathBuf};
 use std::time::{Duration, UNIX_EPOCH};
 use std::default::Default;
 use std::ffi::{CString, OsString};
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::ops::Bound;
 use std::rc::Rc;
 use std::sync::Arc;
 
 #[cfg(feature = "unstable")]
 use std::ffi::CStr;
+#[cfg(feature = "unstable")]
+use std::nonzero::NonZero;
 
+#[macro_use]
 extern crate serde;
 use serde::Deserialize;
 
@@ -737,6 +742,47 @@ declare_tests! {
             Token::SeqEnd,
         ],
     }
+    test_range_inclusive {
+        1u32..=2u32 => &[
+            Token::Struct { name: "RangeInclusive", len: 2 },
+                Token::Str("start"),
+                Token::U32(1),
+
+                Token::Str("end"),
+                Token::U32(2),
+            Token::StructEnd,
+        ],
+        1u32..=2u32 => &[
+            Token::Seq { len: Some(2) },
+                Token::U64(1),
+                Token::U64(2),
+            Token::SeqEnd,
+        ],
+    }
+    test_bound {
+        Bound::Unbounded::<()> => &[
+            Token::Enum { name: "Bound" },
+            Token::Str("Unbounded"),
+            Token::Unit,
+        ],
+        Bound::Included(0u8) => &[
+            Token::Enum { name: "Bound" },
+            Token::Str("Included"),
+            Token::U8(0),
+        ],
+        Bound::Excluded(0u8) => &[
+            Token::Enum { name: "Bound" },
+            Token::Str("Excluded"),
+            Token::U8(0),
+        ],
+    }
+    test_nonzero {
+        NonZeroU8::new(1).unwrap() => &[Token::U8(1)],
+        NonZeroU16::new(1).unwrap() => &[Token::U16(1)],
+        NonZeroU32::new(1).unwrap() => &[Token::U32(1)],
+        NonZeroU64::new(1).unwrap() => &[Token::U64(1)],
+        NonZeroUsize::new(1).unwrap() => &[Token::U64(1)],
+    }
     test_path {
         Path::new("/usr/local/lib") => &[
             Token::BorrowedStr("/usr/local/lib"),
@@ -746,6 +792,9 @@ declare_tests! {
         PathBuf::from("/usr/local/lib") => &[
             Token::String("/usr/local/lib"),
         ],
+        PathBuf::from("/usr/local/lib") => &[
+            Token::Bytes(b"/usr/local/lib"),
+        ],
     }
     test_cstring {
         CString::new("abc").unwrap() => &[
@@ -899,6 +948,61 @@ fn test_osstring() {
     assert_de_tokens_ignore(&tokens);
 }
 
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_path_buf() {
+    use std::os::unix::ffi::OsStringExt;
+
+    let value = PathBuf::from(OsString::from_vec(vec![b'/', 0xff]));
+    assert_de_tokens(&value, &[Token::ByteBuf(&[b'/', 0xff])]);
+}
+
+#[cfg(any(unix, windows))]
+#[test]
+fn test_osstring_from_str() {
+    use serde::Deserializer;
+    use serde::de::Visitor;
+    use serde::de::value::Error;
+
+    // A format that passes a string to the visitor of deserialize_enum when
+    // the input is not in the enum form.
+    struct StrInput<'a>(&'a str);
+
+    impl<'de, 'a> Deserializer<'de> for StrInput<'a> {
+        type Error = Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_str(self.0)
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    for s in &["abc", "Unix", "Windows"] {
+        assert_eq!(OsString::deserialize(StrInput(s)).unwrap(), OsString::from(*s));
+    }
+}
+
+#[cfg(any(unix, windows))]
+#[test]
+fn test_osstring_unknown_variant() {
+    assert_de_tokens_error::<OsString>(
+        &[
+            Token::Enum { name: "OsString" },
+            Token::Str("abc"),
+            Token::Unit,
+        ],
+        "unknown variant `abc`, expected `Unix` or `Windows`",
+    );
+}
+
 #[cfg(windows)]
 #[test]
 fn test_osstring() {
@@ -933,7 +1037,7 @@ fn test_cstr() {
 fn test_cstr_internal_null() {
     assert_de_tokens_error::<Box<CStr>>(
         &[Token::Bytes(b"a\0c")],
-        "nul byte found in provided data at position: 1",
+        "invalid value: byte array, expected no nul bytes, found one at position 1",
     );
 }
 
@@ -942,7 +1046,16 @@ fn test_cstr_internal_null() {
 fn test_cstr_internal_null_end() {
     assert_de_tokens_error::<Box<CStr>>(
         &[Token::Bytes(b"ac\0")],
-        "nul byte found in provided data at position: 2",
+        "invalid value: byte array, expected no nul bytes, found one at position 2",
+    );
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn test_nonzero_zero() {
+    assert_de_tokens_error::<NonZero<i32>>(
+        &[Token::I32(0)],
+        "invalid value: integer `0`, expected a non-zero value",
     );
 }
 
@@ -1037,13 +1150,13 @@ declare_error_tests! {
         &[
             Token::Bytes(b"a\0c"),
         ],
-        "nul byte found in provided data at position: 1",
+        "invalid value: byte array, expected no nul bytes, found one at position 1",
     }
     test_cstring_internal_null_end<CString> {
         &[
             Token::Bytes(b"ac\0"),
         ],
-        "nul byte found in provided data at position: 2",
+        "invalid value: byte array, expected no nul bytes, found one at position 2",
     }
     test_unit_from_empty_seq<()> {
         &[
@@ -1169,4 +1282,10 @@ declare_error_tests! {
         ],
         "invalid type: sequence, expected unit struct UnitStruct",
     }
+    test_nonzero_from_zero<NonZeroU64> {
+        &[
+            Token::U64(0),
+        ],
+        "invalid value: integer `0`, expected a non-zero value",
+    }
 }
@@ -0,0 +1,122 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::digest::{hash_of, hash_with};
+use serde::ser::SerializeSeq;
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hasher;
+
+// Records the bytes written to it instead of hashing them.
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Hasher for Recorder {
+    fn finish(&self) -> u64 {
+        self.0.len() as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+fn bytes_of<T: Serialize>(value: &T) -> Vec<u8> {
+    hash_with(value, Recorder::default()).unwrap().0
+}
+
+#[derive(Serialize)]
+struct Point {
+    x: u16,
+    y: Option<i8>,
+}
+
+#[test]
+fn test_framing() {
+    assert_eq!(
+        bytes_of(&Point { x: 0x0102, y: Some(-1) }),
+        [
+            27, 5, 0, 0, 0, 0, 0, 0, 0, b'P', b'o', b'i', b'n', b't',
+            29, 1, 0, 0, 0, 0, 0, 0, 0, b'x', 7, 0x02, 0x01,
+            29, 1, 0, 0, 0, 0, 0, 0, 0, b'y', 16, 2, 0xff,
+            30,
+        ]
+    );
+}
+
+#[test]
+fn test_distinct_values() {
+    let hash = |value: &(&str, &str)| hash_of::<DefaultHasher, _>(value).unwrap();
+    assert_eq!(hash(&("ab", "c")), hash(&("ab", "c")));
+    assert_ne!(hash(&("ab", "c")), hash(&("a", "bc")));
+
+    assert_ne!(bytes_of(&1u8), bytes_of(&1u16));
+    assert_ne!(bytes_of(&vec![vec![1], vec![]]), bytes_of(&vec![vec![], vec![1]]));
+    assert_ne!(bytes_of(&Some(())), bytes_of(&None::<()>));
+}
+
+struct Display;
+
+impl fmt::Display for Display {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}-{}", "abc", 12)
+    }
+}
+
+struct Collected;
+
+impl Serialize for Collected {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&Display)
+    }
+}
+
+#[test]
+fn test_collect_str() {
+    assert_eq!(bytes_of(&Collected), bytes_of(&"abc-12"));
+}
+
+struct UnsizedSeq;
+
+impl Serialize for UnsizedSeq {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = try!(serializer.serialize_seq(None));
+        try!(seq.serialize_element(&1u8));
+        seq.end()
+    }
+}
+
+#[test]
+fn test_length_hint_ignored() {
+    assert_eq!(bytes_of(&UnsizedSeq), bytes_of(&vec![1u8]));
+}
+
+#[test]
+fn test_integer128() {
+    assert_eq!(
+        bytes_of(&-2i128),
+        [31, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+         0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(
+        bytes_of(&(1u128 << 64)),
+        [32, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
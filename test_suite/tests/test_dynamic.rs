@@ -0,0 +1,139 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::dynamic::{Descriptor, DynamicMap, DynamicStruct};
+
+extern crate serde_test;
+use serde_test::{Token, assert_ser_tokens_readable};
+
+use std::net::Ipv4Addr;
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn test_map_keeps_insertion_order() {
+    let mut map = DynamicMap::new();
+    map.insert("b", 1u8);
+    map.insert(String::from("a"), vec!["x"]);
+    map.insert("b", Some(Point { x: 1, y: 2 }));
+    assert_eq!(map.len(), 3);
+
+    assert_ser_tokens_readable(
+        &map,
+        &[
+            Token::Map { len: Some(3) },
+            Token::Str("b"),
+            Token::U8(1),
+            Token::Str("a"),
+            Token::Seq { len: Some(1) },
+            Token::Str("x"),
+            Token::SeqEnd,
+            Token::Str("b"),
+            Token::Some,
+            Token::Struct { name: "Point", len: 2 },
+            Token::Str("x"),
+            Token::I32(1),
+            Token::Str("y"),
+            Token::I32(2),
+            Token::StructEnd,
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+}
+
+#[test]
+fn test_struct_follows_descriptor() {
+    let descriptor = Descriptor::new("Person").field("name").field("age").field("email");
+    let name = String::from("Ferris");
+
+    let mut person = DynamicStruct::new(&descriptor);
+    person.set("age", 7u8).unwrap();
+    person.set("name", &name).unwrap();
+    assert!(person.is_set("age"));
+    assert!(!person.is_set("email"));
+
+    assert_ser_tokens_readable(
+        &person,
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("name"),
+            Token::Str("Ferris"),
+            Token::Str("age"),
+            Token::U8(7),
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+
+    person.set("age", 8u8).unwrap();
+    assert!(person.unset("name"));
+    assert!(!person.unset("name"));
+
+    assert_ser_tokens_readable(
+        &person,
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("age"),
+            Token::U8(8),
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+}
+
+#[test]
+fn test_struct_unknown_field() {
+    let descriptor = Descriptor::new("Person").field("name");
+    let mut person = DynamicStruct::new(&descriptor);
+
+    let err = person.set("email", "ferris@example.com").unwrap_err();
+    assert_eq!(err.to_string(), "no field `email` in Person");
+    assert!(!person.is_set("email"));
+}
+
+#[test]
+fn test_values_see_readability() {
+    let mut map = DynamicMap::new();
+    map.insert("ip", Ipv4Addr::new(127, 0, 0, 1));
+
+    assert_ser_tokens_readable(
+        &map,
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("ip"),
+            Token::Str("127.0.0.1"),
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+
+    assert_ser_tokens_readable(
+        &map,
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("ip"),
+            Token::Tuple { len: 4 },
+            Token::U8(127),
+            Token::U8(0),
+            Token::U8(0),
+            Token::U8(1),
+            Token::TupleEnd,
+            Token::MapEnd,
+        ],
+        Some(false),
+    );
+}
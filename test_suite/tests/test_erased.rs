@@ -0,0 +1,215 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::erased::{Erase, ErasedSerialize, ErasedSerializer, Error};
+use serde::ser::{self, SerializeStruct};
+
+extern crate serde_test;
+use serde_test::{RecordingSerializer, Token, assert_ser_tokens, assert_ser_tokens_error,
+                 assert_ser_tokens_readable};
+
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    y: Option<i32>,
+}
+
+#[derive(Serialize)]
+enum Shape {
+    Circle(u8),
+    Line(u8, u8),
+    Square { side: u8 },
+}
+
+#[derive(Serialize)]
+struct Name(&'static str);
+
+struct Failing;
+
+impl Serialize for Failing {
+    fn serialize<S: Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(ser::Error::custom("failing on purpose"))
+    }
+}
+
+// Serializes whether the serializer is human-readable.
+struct Readability;
+
+impl Serialize for Readability {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        serializer.serialize_bool(human_readable)
+    }
+}
+
+#[test]
+fn test_heterogeneous_values() {
+    let values: Vec<Box<ErasedSerialize>> = vec![
+        Box::new(Point { x: 1, y: None }),
+        Box::new(Shape::Line(2, 3)),
+        Box::new(Shape::Circle(4)),
+        Box::new(Shape::Square { side: 5 }),
+        Box::new(Name("n")),
+        Box::new(Some(())),
+        Box::new("str"),
+    ];
+
+    assert_ser_tokens(
+        &values,
+        &[
+            Token::Seq { len: Some(7) },
+            Token::Struct { name: "Point", len: 1 },
+            Token::Str("x"),
+            Token::I32(1),
+            Token::StructEnd,
+            Token::TupleVariant { name: "Shape", variant: "Line", len: 2 },
+            Token::U8(2),
+            Token::U8(3),
+            Token::TupleVariantEnd,
+            Token::NewtypeVariant { name: "Shape", variant: "Circle" },
+            Token::U8(4),
+            Token::StructVariant { name: "Shape", variant: "Square", len: 1 },
+            Token::Str("side"),
+            Token::U8(5),
+            Token::StructVariantEnd,
+            Token::NewtypeStruct { name: "Name" },
+            Token::Str("n"),
+            Token::Some,
+            Token::Unit,
+            Token::Str("str"),
+            Token::SeqEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_map_of_trait_objects() {
+    let mut map: BTreeMap<&str, Box<ErasedSerialize + Send + Sync>> = BTreeMap::new();
+    map.insert("a", Box::new(vec![1u8]));
+    map.insert("b", Box::new((true, 'c')));
+
+    assert_ser_tokens(
+        &map,
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("a"),
+            Token::Seq { len: Some(1) },
+            Token::U8(1),
+            Token::SeqEnd,
+            Token::Str("b"),
+            Token::Tuple { len: 2 },
+            Token::Bool(true),
+            Token::Char('c'),
+            Token::TupleEnd,
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_readability() {
+    for &human_readable in &[true, false] {
+        let value: Box<ErasedSerialize> = Box::new(Readability);
+        assert_ser_tokens_readable(&value, &[Token::Bool(human_readable)], Some(human_readable));
+    }
+}
+
+#[test]
+fn test_custom_error() {
+    let values: Vec<Box<ErasedSerialize>> = vec![Box::new(1u8), Box::new(Failing)];
+
+    assert_ser_tokens_error(
+        &values,
+        &[Token::Seq { len: Some(2) }, Token::U8(1)],
+        "failing on purpose",
+    );
+}
+
+// Compiled once, without knowing the data format.
+fn write_point(serializer: &mut ErasedSerializer) -> Result<(), Error> {
+    let mut state = try!(serializer.serialize_struct("Point", 2));
+    try!(state.serialize_field("x", &1));
+    try!(state.skip_field("y"));
+    state.end()
+}
+
+#[test]
+fn test_erased_serializer() {
+    let mut recording = RecordingSerializer::new();
+    {
+        let mut erased = Erase::new(&mut recording);
+        let result = write_point(&mut erased);
+        erased.finish(result).unwrap();
+    }
+
+    assert_eq!(recording.to_string(), "\
+serialize_struct(\"Point\", 2)
+  serialize_field(\"x\")
+    serialize_i32(1)
+  skip_field(\"y\")
+end()
+");
+}
+
+#[test]
+fn test_annotate_and_integer128() {
+    #[derive(Serialize)]
+    #[serde(annotate)]
+    struct Big {
+        /// Signed.
+        i: i128,
+        /// Unsigned.
+        u: u128,
+    }
+
+    let value: Box<ErasedSerialize> = Box::new(Big { i: -1 << 100, u: 1 << 100 });
+    let mut recording = RecordingSerializer::new();
+    value.serialize(&mut recording).unwrap();
+
+    assert_eq!(recording.to_string(), "\
+serialize_struct(\"Big\", 2)
+  annotate(\"i\", \"Signed.\")
+  serialize_field(\"i\")
+    serialize_i128(-1267650600228229401496703205376)
+  annotate(\"u\", \"Unsigned.\")
+  serialize_field(\"u\")
+    serialize_u128(1267650600228229401496703205376)
+end()
+");
+}
+
+#[test]
+fn test_out_of_order() {
+    let mut recording = RecordingSerializer::new();
+    let mut erased = Erase::new(&mut recording);
+    let result = erased.erased_end();
+
+    assert_eq!(
+        erased.finish(result).unwrap_err().to_string(),
+        "erased serializer method called out of order"
+    );
+}
+
+#[test]
+fn test_incomplete() {
+    let mut recording = RecordingSerializer::new();
+    let erased = Erase::new(&mut recording);
+
+    assert_eq!(
+        erased.finish(Ok(())).unwrap_err().to_string(),
+        "erased serialization did not complete"
+    );
+}
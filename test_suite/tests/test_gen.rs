@@ -515,6 +515,27 @@ fn test_gen() {
         Tuple(&'a str, &'static str),
         Newtype(&'static str),
     }
+
+    #[derive(Serialize, Deserialize)]
+    #[repr(packed)]
+    struct PackedStruct {
+        a: u8,
+        #[serde(skip_serializing_if = "is_zero")]
+        b: u8,
+        #[serde(serialize_with = "ser_u64")]
+        c: u64,
+    }
+    assert::<PackedStruct>();
+
+    #[derive(Serialize, Deserialize)]
+    #[repr(C, packed)]
+    struct PackedTupleStruct(u8, u32);
+    assert::<PackedTupleStruct>();
+
+    #[derive(Serialize, Deserialize)]
+    #[repr(packed)]
+    struct PackedNewtypeStruct(u64);
+    assert::<PackedNewtypeStruct>();
 }
 
 //////////////////////////////////////////////////////////////////////////
@@ -582,3 +603,7 @@ pub fn deserialize_some_other_variant<'de, D>(_: D) -> StdResult<(String, u8), D
 }
 
 pub fn is_zero(n: &u8) -> bool { *n == 0 }
+
+pub fn ser_u64<S: Serializer>(n: &u64, serializer: S) -> StdResult<S::Ok, S::Error> {
+    serializer.serialize_u64(*n)
+}
@@ -0,0 +1,734 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+
+extern crate serde_test;
+use serde_test::{Token, assert_de_tokens, assert_de_tokens_error,
+                 assert_de_tokens_error_readable, assert_de_tokens_readable, assert_ser_tokens,
+                 assert_tokens, assert_tokens_readable};
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+macro_rules! btreemap {
+    () => {
+        BTreeMap::new()
+    };
+    ($($key:expr => $value:expr),+) => {
+        {
+            let mut map = BTreeMap::new();
+            $(map.insert($key, $value);)+
+            map
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Pairs {
+    #[serde(with = "serde::helpers::map_as_pairs")]
+    map: BTreeMap<(u32, u32), char>,
+}
+
+#[test]
+fn test_map_as_pairs() {
+    assert_tokens(
+        &Pairs { map: btreemap![(0, 1) => 'a', (2, 3) => 'b'] },
+        &[
+            Token::Struct { name: "Pairs", len: 1 },
+            Token::Str("map"),
+            Token::Seq { len: Some(2) },
+            Token::Tuple { len: 2 },
+            Token::Tuple { len: 2 },
+            Token::U32(0),
+            Token::U32(1),
+            Token::TupleEnd,
+            Token::Char('a'),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Tuple { len: 2 },
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleEnd,
+            Token::Char('b'),
+            Token::TupleEnd,
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_tokens(
+        &Pairs { map: btreemap![] },
+        &[
+            Token::Struct { name: "Pairs", len: 1 },
+            Token::Str("map"),
+            Token::Seq { len: Some(0) },
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct HashPairs {
+    #[serde(with = "serde::helpers::map_as_pairs")]
+    map: HashMap<u8, u8>,
+}
+
+#[test]
+fn test_map_as_pairs_duplicate_key() {
+    let mut map = HashMap::new();
+    map.insert(1, 3);
+
+    assert_de_tokens(
+        &HashPairs { map: map },
+        &[
+            Token::Struct { name: "HashPairs", len: 1 },
+            Token::Str("map"),
+            Token::Seq { len: None },
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(3),
+            Token::TupleEnd,
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_map_as_pairs_invalid() {
+    assert_de_tokens_error::<HashPairs>(
+        &[
+            Token::Struct { name: "HashPairs", len: 1 },
+            Token::Str("map"),
+            Token::Map { len: Some(0) },
+        ],
+        "invalid type: map, expected a sequence of key-value pairs",
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Patch {
+    #[serde(default,
+            skip_serializing_if = "Option::is_none",
+            with = "serde::helpers::double_option")]
+    value: Option<Option<u8>>,
+}
+
+#[test]
+fn test_double_option() {
+    assert_tokens(
+        &Patch { value: None },
+        &[
+            Token::Struct { name: "Patch", len: 0 },
+            Token::StructEnd,
+        ],
+    );
+
+    assert_tokens(
+        &Patch { value: Some(None) },
+        &[
+            Token::Struct { name: "Patch", len: 1 },
+            Token::Str("value"),
+            Token::None,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_tokens(
+        &Patch { value: Some(Some(1)) },
+        &[
+            Token::Struct { name: "Patch", len: 1 },
+            Token::Str("value"),
+            Token::Some,
+            Token::U8(1),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &Patch { value: Some(None) },
+        &[
+            Token::Struct { name: "Patch", len: 1 },
+            Token::Str("value"),
+            Token::Unit,
+            Token::StructEnd,
+        ],
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Timestamps {
+    #[serde(with = "serde::helpers::system_time::seconds")]
+    seconds: SystemTime,
+    #[serde(with = "serde::helpers::system_time::milliseconds")]
+    milliseconds: SystemTime,
+    #[serde(with = "serde::helpers::system_time::rfc3339")]
+    rfc3339: SystemTime,
+}
+
+fn timestamps_tokens(seconds: i64, milliseconds: i64, rfc3339: &'static str) -> Vec<Token> {
+    vec![
+        Token::Struct { name: "Timestamps", len: 3 },
+        Token::Str("seconds"),
+        Token::I64(seconds),
+        Token::Str("milliseconds"),
+        Token::I64(milliseconds),
+        Token::Str("rfc3339"),
+        Token::Str(rfc3339),
+        Token::StructEnd,
+    ]
+}
+
+#[test]
+fn test_system_time() {
+    let epoch = Timestamps {
+        seconds: UNIX_EPOCH,
+        milliseconds: UNIX_EPOCH,
+        rfc3339: UNIX_EPOCH,
+    };
+    assert_tokens(&epoch, &timestamps_tokens(0, 0, "1970-01-01T00:00:00Z"));
+
+    let time = UNIX_EPOCH + Duration::new(1_509_702_067, 250_000_000);
+    let exact = Timestamps {
+        seconds: UNIX_EPOCH + Duration::from_secs(1_509_702_067),
+        milliseconds: time,
+        rfc3339: time,
+    };
+    assert_tokens(
+        &exact,
+        &timestamps_tokens(1_509_702_067, 1_509_702_067_250, "2017-11-03T09:41:07.25Z"),
+    );
+
+    let time = UNIX_EPOCH - Duration::new(86_401, 500_000_000);
+    let before_epoch = Timestamps {
+        seconds: UNIX_EPOCH - Duration::from_secs(86_402),
+        milliseconds: time,
+        rfc3339: time,
+    };
+    assert_tokens(
+        &before_epoch,
+        &timestamps_tokens(-86_402, -86_401_500, "1969-12-30T23:59:58.5Z"),
+    );
+
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+    assert_tokens(
+        &Timestamps {
+            seconds: leap_day,
+            milliseconds: leap_day,
+            rfc3339: leap_day,
+        },
+        &timestamps_tokens(951_782_400, 951_782_400_000, "2000-02-29T00:00:00Z"),
+    );
+}
+
+#[test]
+fn test_system_time_rounds_down() {
+    let time = UNIX_EPOCH - Duration::new(0, 1);
+    assert_ser_tokens(
+        &Timestamps {
+            seconds: time,
+            milliseconds: time,
+            rfc3339: time,
+        },
+        &timestamps_tokens(-1, -1, "1969-12-31T23:59:59.999999999Z"),
+    );
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+struct Rfc3339 {
+    #[serde(with = "serde::helpers::system_time::rfc3339")]
+    time: SystemTime,
+}
+
+#[test]
+fn test_rfc3339_de() {
+    let cases = [
+        ("2017-11-03T09:41:07Z", 1_509_702_067, 0),
+        ("2017-11-03t09:41:07z", 1_509_702_067, 0),
+        ("2017-11-03 09:41:07Z", 1_509_702_067, 0),
+        ("2017-11-03T10:41:07+01:00", 1_509_702_067, 0),
+        ("2017-11-03T00:11:07-09:30", 1_509_702_067, 0),
+        ("2017-11-03T09:41:07.123456789123Z", 1_509_702_067, 123_456_789),
+        ("2016-12-31T23:59:60Z", 1_483_228_800, 0),
+    ];
+    for &(input, secs, nanos) in &cases {
+        assert_de_tokens(
+            &Rfc3339 { time: UNIX_EPOCH + Duration::new(secs, nanos) },
+            &[
+                Token::Struct { name: "Rfc3339", len: 1 },
+                Token::Str("time"),
+                Token::Str(input),
+                Token::StructEnd,
+            ],
+        );
+    }
+}
+
+#[test]
+fn test_rfc3339_de_invalid() {
+    let cases = [
+        "2017-11-03",
+        "2017-11-03T09:41:07",
+        "2017-11-03T09:41:07.Z",
+        "2017-11-03T09:41:07+0100",
+        "2017-13-03T09:41:07Z",
+        "2017-02-29T09:41:07Z",
+        "2017-11-03T24:00:00Z",
+        "2017-11-03T09:41:07Z ",
+        "17-11-03T09:41:07Z",
+    ];
+    for input in &cases {
+        assert_de_tokens_error::<Rfc3339>(
+            &[
+                Token::Struct { name: "Rfc3339", len: 1 },
+                Token::Str("time"),
+                Token::Str(input),
+            ],
+            &format!("invalid value: string {:?}, expected an RFC 3339 timestamp", input),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Durations {
+    #[serde(with = "serde::helpers::duration::seconds")]
+    seconds: Duration,
+    #[serde(with = "serde::helpers::duration::milliseconds")]
+    milliseconds: Duration,
+    #[serde(with = "serde::helpers::duration::seconds_f64")]
+    seconds_f64: Duration,
+    #[serde(with = "serde::helpers::duration::milliseconds_f64")]
+    milliseconds_f64: Duration,
+}
+
+#[test]
+fn test_duration() {
+    assert_tokens(
+        &Durations {
+            seconds: Duration::from_secs(90),
+            milliseconds: Duration::new(90, 250_000_000),
+            seconds_f64: Duration::new(90, 250_000_000),
+            milliseconds_f64: Duration::new(90, 250_500_000),
+        },
+        &[
+            Token::Struct { name: "Durations", len: 4 },
+            Token::Str("seconds"),
+            Token::U64(90),
+            Token::Str("milliseconds"),
+            Token::U64(90_250),
+            Token::Str("seconds_f64"),
+            Token::F64(90.25),
+            Token::Str("milliseconds_f64"),
+            Token::F64(90_250.5),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &Durations {
+            seconds: Duration::from_secs(1),
+            milliseconds: Duration::from_secs(1),
+            seconds_f64: Duration::new(0, 100_000_000),
+            milliseconds_f64: Duration::new(0, 300_000),
+        },
+        &[
+            Token::Struct { name: "Durations", len: 4 },
+            Token::Str("seconds"),
+            Token::U64(1),
+            Token::Str("milliseconds"),
+            Token::U64(1_000),
+            Token::Str("seconds_f64"),
+            Token::F64(0.1),
+            Token::Str("milliseconds_f64"),
+            Token::F64(0.3),
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_duration_invalid() {
+    assert_de_tokens_error::<Durations>(
+        &[
+            Token::Struct { name: "Durations", len: 4 },
+            Token::Str("seconds_f64"),
+            Token::F64(-1.0),
+        ],
+        "invalid value: floating point `-1`, expected a non-negative number of seconds",
+    );
+
+    assert_de_tokens_error::<Durations>(
+        &[
+            Token::Struct { name: "Durations", len: 4 },
+            Token::Str("milliseconds_f64"),
+            Token::F64(::std::f64::NAN),
+        ],
+        "invalid value: floating point `NaN`, expected a non-negative number of milliseconds",
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Base64 {
+    #[serde(with = "serde::helpers::base64")]
+    bytes: Vec<u8>,
+}
+
+fn base64_tokens(token: Token) -> [Token; 4] {
+    [
+        Token::Struct { name: "Base64", len: 1 },
+        Token::Str("bytes"),
+        token,
+        Token::StructEnd,
+    ]
+}
+
+#[test]
+fn test_base64() {
+    let cases: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+        (&[0xfb, 0xff], "+/8="),
+    ];
+    for &(bytes, encoded) in cases {
+        let value = Base64 { bytes: bytes.to_vec() };
+        assert_tokens_readable(&value, &base64_tokens(Token::Str(encoded)), Some(true));
+        assert_tokens_readable(&value, &base64_tokens(Token::Bytes(bytes)), Some(false));
+    }
+
+    assert_de_tokens_readable(
+        &Base64 { bytes: b"fo".to_vec() },
+        &base64_tokens(Token::Str("Zm8")),
+        Some(true),
+    );
+}
+
+#[test]
+fn test_base64_invalid() {
+    for input in &["Z", "Zm9v=", "Zm9v====", "Zm-v"] {
+        assert_de_tokens_error_readable::<Base64>(
+            &base64_tokens(Token::Str(input))[..3],
+            &format!("invalid value: string {:?}, expected a base64 string", input),
+            Some(true),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Hex {
+    #[serde(with = "serde::helpers::hex")]
+    bytes: [u8; 4],
+}
+
+fn hex_tokens(token: Token) -> [Token; 4] {
+    [
+        Token::Struct { name: "Hex", len: 1 },
+        Token::Str("bytes"),
+        token,
+        Token::StructEnd,
+    ]
+}
+
+#[test]
+fn test_hex() {
+    let value = Hex { bytes: [0xde, 0xad, 0x00, 0x0f] };
+    assert_tokens_readable(&value, &hex_tokens(Token::Str("dead000f")), Some(true));
+    assert_tokens_readable(&value, &hex_tokens(Token::Bytes(&[0xde, 0xad, 0x00, 0x0f])), Some(false));
+    assert_de_tokens_readable(&value, &hex_tokens(Token::Str("DEAD000F")), Some(true));
+}
+
+#[test]
+fn test_hex_invalid() {
+    assert_de_tokens_error_readable::<Hex>(
+        &hex_tokens(Token::Str("dead00f"))[..3],
+        "invalid value: string \"dead00f\", expected a hex string",
+        Some(true),
+    );
+    assert_de_tokens_error_readable::<Hex>(
+        &hex_tokens(Token::Str("deadbeefxx"))[..3],
+        "invalid value: string \"deadbeefxx\", expected a hex string",
+        Some(true),
+    );
+    assert_de_tokens_error_readable::<Hex>(
+        &hex_tokens(Token::Str("deadbeef00"))[..3],
+        "invalid length 5, expected 4 bytes",
+        Some(true),
+    );
+    assert_de_tokens_error_readable::<Hex>(
+        &hex_tokens(Token::Str("dead000f"))[..3],
+        "invalid type: string \"dead000f\", expected a byte array",
+        Some(false),
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct NullAsDefault {
+    #[serde(with = "serde::helpers::null_as_default")]
+    count: u64,
+    #[serde(default, deserialize_with = "serde::helpers::null_as_default::deserialize")]
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_null_as_default() {
+    assert_ser_tokens(
+        &NullAsDefault {
+            count: 3,
+            tags: vec!["a".to_owned()],
+        },
+        &[
+            Token::Struct { name: "NullAsDefault", len: 2 },
+            Token::Str("count"),
+            Token::U64(3),
+            Token::Str("tags"),
+            Token::Seq { len: Some(1) },
+            Token::Str("a"),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &NullAsDefault {
+            count: 3,
+            tags: vec!["a".to_owned()],
+        },
+        &[
+            Token::Struct { name: "NullAsDefault", len: 2 },
+            Token::Str("count"),
+            Token::Some,
+            Token::U64(3),
+            Token::Str("tags"),
+            Token::Some,
+            Token::Seq { len: Some(1) },
+            Token::Str("a"),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &NullAsDefault {
+            count: 0,
+            tags: Vec::new(),
+        },
+        &[
+            Token::Struct { name: "NullAsDefault", len: 2 },
+            Token::Str("count"),
+            Token::None,
+            Token::Str("tags"),
+            Token::Unit,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &NullAsDefault {
+            count: 0,
+            tags: Vec::new(),
+        },
+        &[
+            Token::Struct { name: "NullAsDefault", len: 1 },
+            Token::Str("count"),
+            Token::None,
+            Token::StructEnd,
+        ],
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct EmptyAsNone {
+    #[serde(with = "serde::helpers::empty_as_none")]
+    name: Option<String>,
+    #[serde(deserialize_with = "serde::helpers::empty_as_none::deserialize")]
+    age: Option<u32>,
+}
+
+#[test]
+fn test_empty_as_none() {
+    assert_de_tokens(
+        &EmptyAsNone {
+            name: Some("x".to_owned()),
+            age: Some(30),
+        },
+        &[
+            Token::Struct { name: "EmptyAsNone", len: 2 },
+            Token::Str("name"),
+            Token::Str("x"),
+            Token::Str("age"),
+            Token::Some,
+            Token::Str("30"),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &EmptyAsNone { name: None, age: None },
+        &[
+            Token::Struct { name: "EmptyAsNone", len: 2 },
+            Token::Str("name"),
+            Token::Str(""),
+            Token::Str("age"),
+            Token::Str(""),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &EmptyAsNone { name: None, age: None },
+        &[
+            Token::Struct { name: "EmptyAsNone", len: 2 },
+            Token::Str("name"),
+            Token::None,
+            Token::Str("age"),
+            Token::Unit,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_ser_tokens(
+        &EmptyAsNone { name: None, age: Some(30) },
+        &[
+            Token::Struct { name: "EmptyAsNone", len: 2 },
+            Token::Str("name"),
+            Token::Str(""),
+            Token::Str("age"),
+            Token::Some,
+            Token::U32(30),
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_empty_as_none_invalid() {
+    assert_de_tokens_error::<EmptyAsNone>(
+        &[
+            Token::Struct { name: "EmptyAsNone", len: 2 },
+            Token::Str("age"),
+            Token::Str("thirty"),
+        ],
+        "invalid digit found in string",
+    );
+}
+
+//////////////////////////////////////////////////////////////////////////
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Cache {
+    url: String,
+    #[serde(default)]
+    pool: u32,
+}
+
+impl FromStr for Cache {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<Self, String> {
+        if url.is_empty() {
+            return Err("empty url".to_owned());
+        }
+        Ok(Cache { url: url.to_owned(), pool: 0 })
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Service {
+    #[serde(with = "serde::helpers::string_or_struct")]
+    cache: Cache,
+}
+
+#[test]
+fn test_string_or_struct() {
+    assert_de_tokens(
+        &Service { cache: Cache { url: "redis://host".to_owned(), pool: 0 } },
+        &[
+            Token::Struct { name: "Service", len: 1 },
+            Token::Str("cache"),
+            Token::Str("redis://host"),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &Service { cache: Cache { url: "redis://host".to_owned(), pool: 8 } },
+        &[
+            Token::Struct { name: "Service", len: 1 },
+            Token::Str("cache"),
+            Token::Map { len: Some(2) },
+            Token::Str("url"),
+            Token::Str("redis://host"),
+            Token::Str("pool"),
+            Token::U32(8),
+            Token::MapEnd,
+            Token::StructEnd,
+        ],
+    );
+
+    assert_ser_tokens(
+        &Service { cache: Cache { url: "redis://host".to_owned(), pool: 8 } },
+        &[
+            Token::Struct { name: "Service", len: 1 },
+            Token::Str("cache"),
+            Token::Struct { name: "Cache", len: 2 },
+            Token::Str("url"),
+            Token::Str("redis://host"),
+            Token::Str("pool"),
+            Token::U32(8),
+            Token::StructEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_string_or_struct_invalid() {
+    assert_de_tokens_error::<Service>(
+        &[
+            Token::Struct { name: "Service", len: 1 },
+            Token::Str("cache"),
+            Token::Str(""),
+        ],
+        "empty url",
+    );
+
+    assert_de_tokens_error::<Service>(
+        &[
+            Token::Struct { name: "Service", len: 1 },
+            Token::Str("cache"),
+            Token::U32(1),
+        ],
+        "invalid type: integer `1`, expected a string or a map",
+    );
+}
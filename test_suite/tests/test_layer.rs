@@ -0,0 +1,178 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate serde;
+use serde::de::IntoDeserializer;
+use serde::de::value::{Error, MapDeserializer};
+use serde::layer::Layers;
+
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    name: String,
+    server: Server,
+    tags: Vec<String>,
+    #[serde(default)]
+    log: Option<String>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+// A self-describing config source, like a parsed TOML document.
+enum Value {
+    Str(&'static str),
+    Port(u16),
+    Tags(Vec<&'static str>),
+    Null,
+    Table(BTreeMap<&'static str, Value>),
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = ValueDeserializer;
+
+    fn into_deserializer(self) -> ValueDeserializer {
+        ValueDeserializer(self)
+    }
+}
+
+struct ValueDeserializer(Value);
+
+impl<'de> serde::Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        match self.0 {
+            Value::Str(s) => visitor.visit_borrowed_str(s),
+            Value::Port(n) => visitor.visit_u16(n),
+            Value::Tags(tags) => {
+                serde::Deserializer::deserialize_any(tags.into_deserializer(), visitor)
+            }
+            Value::Null => visitor.visit_none(),
+            Value::Table(map) => visitor.visit_map(MapDeserializer::new(map.into_iter())),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+fn table(entries: Vec<(&'static str, Value)>) -> Value {
+    Value::Table(entries.into_iter().collect())
+}
+
+fn layers(sources: Vec<Value>) -> Layers<'static> {
+    let mut layers = Layers::new();
+    for source in sources {
+        layers.add(source.into_deserializer()).unwrap();
+    }
+    layers
+}
+
+fn defaults() -> Value {
+    table(
+        vec![
+            ("name", Value::Str("app")),
+            (
+                "server",
+                table(
+                    vec![
+                        ("host", Value::Str("localhost")),
+                        ("port", Value::Port(80)),
+                    ],
+                ),
+            ),
+            ("tags", Value::Tags(vec!["a", "b"])),
+            ("log", Value::Str("info")),
+        ],
+    )
+}
+
+#[test]
+fn test_nested_fields_override() {
+    let user = table(vec![("server", table(vec![("port", Value::Port(8080))]))]);
+
+    let config: Config = layers(vec![defaults(), user]).deserialize().unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "app".to_owned(),
+            server: Server {
+                host: "localhost".to_owned(),
+                port: 8080,
+            },
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            log: Some("info".to_owned()),
+        }
+    );
+}
+
+#[test]
+fn test_later_layers_win() {
+    let system = table(vec![("name", Value::Str("system")), ("tags", Value::Tags(vec!["c"]))]);
+    let user = table(vec![("name", Value::Str("user")), ("log", Value::Null)]);
+
+    let config: Config = layers(vec![defaults(), system, user]).deserialize().unwrap();
+    assert_eq!(config.name, "user");
+    assert_eq!(config.tags, vec!["c".to_owned()]);
+    assert_eq!(config.log, None);
+}
+
+#[test]
+fn test_non_map_replaces_map() {
+    let user = table(vec![("server", Value::Str("remote"))]);
+
+    let err = layers(vec![defaults(), user]).deserialize::<Config>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"remote\", expected struct Server"
+    );
+}
+
+#[test]
+fn test_integer_keys() {
+    let mut base = BTreeMap::new();
+    base.insert(1u8, "one");
+    base.insert(2u8, "two");
+    let mut over = BTreeMap::new();
+    over.insert(2i64, "deux");
+    over.insert(3i64, "trois");
+
+    let mut layers = Layers::new();
+    layers.add::<_, Error>(base.into_deserializer()).unwrap();
+    layers.add::<_, Error>(over.into_deserializer()).unwrap();
+
+    let merged: BTreeMap<u8, String> = layers.deserialize().unwrap();
+    let expected: BTreeMap<u8, String> = vec![(1, "one"), (2, "deux"), (3, "trois")]
+        .into_iter()
+        .map(|(k, v)| (k, v.to_owned()))
+        .collect();
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn test_missing_field_in_all_layers() {
+    let user = table(vec![("name", Value::Str("user"))]);
+
+    let err = layers(vec![user]).deserialize::<Config>().unwrap_err();
+    assert_eq!(err.to_string(), "missing field `server`");
+}
@@ -11,6 +11,8 @@
 #[macro_use]
 extern crate serde_derive;
 
+extern crate serde;
+
 extern crate serde_test;
 use self::serde_test::{Token, assert_tokens, assert_ser_tokens, assert_de_tokens,
                        assert_de_tokens_error};
@@ -577,6 +579,178 @@ fn test_untagged_enum() {
     );
 }
 
+#[test]
+fn test_untagged_variant() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum Internal {
+        A { a: u8 },
+        #[serde(untagged)]
+        Legacy(String),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum External {
+        A(u8),
+        #[serde(untagged)]
+        B { b: u8 },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        A(u8),
+        #[serde(untagged)]
+        Other(u8),
+    }
+
+    assert_tokens(
+        &Internal::A { a: 1 },
+        &[
+            Token::Struct { name: "Internal", len: 2 },
+
+            Token::Str("type"),
+            Token::Str("A"),
+
+            Token::Str("a"),
+            Token::U8(1),
+
+            Token::StructEnd,
+        ],
+    );
+
+    assert_tokens(&Internal::Legacy("a".to_owned()), &[Token::Str("a")]);
+
+    assert_de_tokens(
+        &External::A(1),
+        &[
+            Token::Map { len: Some(1) },
+
+            Token::Str("A"),
+            Token::U8(1),
+
+            Token::MapEnd,
+        ],
+    );
+
+    assert_tokens(
+        &External::B { b: 2 },
+        &[
+            Token::Struct { name: "External", len: 1 },
+
+            Token::Str("b"),
+            Token::U8(2),
+
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens_error::<Internal>(
+        &[
+            Token::Map { len: Some(1) },
+
+            Token::Str("type"),
+            Token::Str("B"),
+
+            Token::MapEnd,
+        ],
+        "data did not match any variant of enum Internal",
+    );
+
+    assert_de_tokens(
+        &Adjacent::A(1),
+        &[
+            Token::Map { len: Some(2) },
+
+            Token::Str("t"),
+            Token::Str("A"),
+
+            Token::Str("c"),
+            Token::U8(1),
+
+            Token::MapEnd,
+        ],
+    );
+
+    assert_de_tokens(&Adjacent::Other(3), &[Token::U8(3)]);
+
+    // An error inside a tagged variant is not replaced by the untagged
+    // fallthrough.
+    assert_de_tokens_error::<Internal>(
+        &[
+            Token::Map { len: Some(2) },
+
+            Token::Str("type"),
+            Token::Str("A"),
+
+            Token::Str("a"),
+            Token::Str("x"),
+
+            Token::MapEnd,
+        ],
+        "invalid type: string \"x\", expected u8",
+    );
+
+    assert_de_tokens_error::<External>(
+        &[
+            Token::Map { len: Some(1) },
+
+            Token::Str("A"),
+            Token::Str("x"),
+
+            Token::MapEnd,
+        ],
+        "invalid type: string \"x\", expected u8",
+    );
+}
+
+#[test]
+fn test_repr_enum() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(repr = "u8")]
+    enum Unsigned {
+        A,
+        B = 7,
+        C,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(repr = "i32")]
+    enum Signed {
+        Negative = -1,
+        Positive = 1,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(repr = "i8")]
+    #[repr(i8)]
+    enum Bounds {
+        Min = -128,
+        Max = 127,
+    }
+
+    assert_tokens(&Unsigned::A, &[Token::U8(0)]);
+    assert_tokens(&Unsigned::B, &[Token::U8(7)]);
+    assert_tokens(&Unsigned::C, &[Token::U8(8)]);
+    assert_tokens(&Signed::Negative, &[Token::I32(-1)]);
+    assert_tokens(&Signed::Positive, &[Token::I32(1)]);
+    assert_tokens(&Bounds::Min, &[Token::I8(-128)]);
+    assert_tokens(&Bounds::Max, &[Token::I8(127)]);
+
+    assert_de_tokens_error::<Unsigned>(
+        &[Token::U8(1)],
+        "invalid value: integer `1`, expected discriminant of enum Unsigned",
+    );
+    assert_de_tokens_error::<Signed>(
+        &[Token::I32(0)],
+        "invalid value: integer `0`, expected discriminant of enum Signed",
+    );
+    assert_de_tokens_error::<Unsigned>(
+        &[Token::Str("A")],
+        "invalid type: string \"A\", expected u8",
+    );
+}
+
 #[test]
 fn test_internally_tagged_enum() {
     #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -1267,3 +1441,175 @@ fn test_rename_all() {
         ]
     );
 }
+
+#[test]
+fn test_rename_all_serialize_deserialize() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename_all(serialize = "camelCase", deserialize = "SCREAMING_SNAKE_CASE"))]
+    enum E {
+        #[serde(rename_all(deserialize = "kebab-case"))]
+        SerializeSeq {
+            serialize_seq: bool,
+        },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(rename_all(serialize = "PascalCase"))]
+    struct S {
+        serialize_seq: bool,
+        #[serde(rename(deserialize = "renamed"))]
+        serialize_map: bool,
+    }
+
+    assert_ser_tokens(
+        &E::SerializeSeq { serialize_seq: true },
+        &[
+            Token::StructVariant { name: "E", variant: "serializeSeq", len: 1 },
+            Token::Str("serialize_seq"),
+            Token::Bool(true),
+            Token::StructVariantEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &E::SerializeSeq { serialize_seq: true },
+        &[
+            Token::StructVariant { name: "E", variant: "SERIALIZE_SEQ", len: 1 },
+            Token::Str("serialize-seq"),
+            Token::Bool(true),
+            Token::StructVariantEnd,
+        ],
+    );
+
+    assert_ser_tokens(
+        &S {
+             serialize_seq: true,
+             serialize_map: false,
+         },
+        &[
+            Token::Struct { name: "S", len: 2 },
+            Token::Str("SerializeSeq"),
+            Token::Bool(true),
+            Token::Str("SerializeMap"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens(
+        &S {
+             serialize_seq: true,
+             serialize_map: false,
+         },
+        &[
+            Token::Struct { name: "S", len: 2 },
+            Token::Str("serialize_seq"),
+            Token::Bool(true),
+            Token::Str("renamed"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_expose_names() {
+    #[derive(Serialize, Deserialize)]
+    #[serde(expose_names, rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct S<T> {
+        first_field: T,
+        #[serde(skip_serializing)]
+        skipped: u8,
+        #[serde(rename(serialize = "other", deserialize = "second"))]
+        second_field: u8,
+    }
+
+    #[derive(Serialize)]
+    #[serde(expose_names)]
+    #[allow(dead_code)]
+    enum E {
+        A,
+        #[serde(rename = "b")]
+        B(u8),
+        #[serde(skip_serializing)]
+        C,
+        #[serde(untagged)]
+        D(String),
+    }
+
+    assert_eq!(S::<String>::FIELDS, &["firstField", "other"]);
+    assert_eq!(E::VARIANTS, &["A", "b"]);
+}
+
+#[test]
+fn test_annotate() {
+    #[derive(Serialize)]
+    #[serde(annotate, rename_all = "kebab-case")]
+    struct S {
+        /// Address to listen on.
+        listen_addr: &'static str,
+        /// Number of worker threads.
+        ///
+        /// Defaults to the number of CPUs.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        workers: Option<u8>,
+        #[doc = "Explicit doc attribute."]
+        verbose: bool,
+        undocumented: bool,
+    }
+
+    #[derive(Serialize)]
+    #[serde(annotate)]
+    enum E {
+        V {
+            /// Inside a variant.
+            x: u8,
+        },
+    }
+
+    #[derive(Serialize)]
+    struct Plain {
+        /// Not passed on without #[serde(annotate)].
+        x: u8,
+    }
+
+    fn calls<T: serde::Serialize>(value: T) -> Vec<String> {
+        let mut recording = serde_test::RecordingSerializer::new();
+        value.serialize(&mut recording).unwrap();
+        recording
+            .calls()
+            .iter()
+            .filter(|call| call.method == "annotate")
+            .map(|call| call.args.clone())
+            .collect()
+    }
+
+    let s = S {
+        listen_addr: "[::]:80",
+        workers: None,
+        verbose: false,
+        undocumented: false,
+    };
+    assert_eq!(
+        calls(s),
+        [
+            "\"listen-addr\", \"Address to listen on.\"",
+            "\"verbose\", \"Explicit doc attribute.\"",
+        ]
+    );
+
+    let s = S {
+        listen_addr: "[::]:80",
+        workers: Some(4),
+        verbose: false,
+        undocumented: false,
+    };
+    assert_eq!(
+        calls(s)[1],
+        "\"workers\", \"Number of worker threads.\\n\\nDefaults to the number of CPUs.\""
+    );
+
+    assert_eq!(calls(E::V { x: 0 }), ["\"x\", \"Inside a variant.\""]);
+    assert_eq!(calls(Plain { x: 0 }).len(), 0);
+}
@@ -0,0 +1,118 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::Deserialize;
+use serde::matching::{Matcher, MatchingDeserializer};
+
+extern crate serde_test;
+use serde_test::{Deserializer, Token};
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+struct Account {
+    user_id: u32,
+    profile: Profile,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    display_name: String,
+}
+
+#[derive(Deserialize, PartialEq, Debug)]
+enum Event {
+    Login { user_id: u32 },
+}
+
+fn deserialize<'de, T>(tokens: &'de [Token], matcher: &Matcher) -> Result<T, String>
+where
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::new(tokens);
+    T::deserialize(MatchingDeserializer::new(&mut de, matcher)).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_case_insensitive() {
+    let tokens = [
+        Token::Struct { name: "Account", len: 2 },
+        Token::Str("UserID"),
+        Token::U32(1),
+        Token::Str("PROFILE"),
+        Token::Map { len: Some(1) },
+        Token::Str("display-name"),
+        Token::Str("a"),
+        Token::MapEnd,
+        Token::StructEnd,
+    ];
+    let expected = Account {
+        user_id: 1,
+        profile: Profile { display_name: "a".to_owned() },
+    };
+    assert_eq!(deserialize(&tokens, &Matcher::new().case_insensitive()), Ok(expected));
+
+    assert_eq!(
+        deserialize::<Account>(&tokens, &Matcher::new()),
+        Err("unknown field `UserID`, expected `user_id` or `profile`".to_owned())
+    );
+}
+
+#[test]
+fn test_alias() {
+    let matcher = Matcher::new().alias("uid", "user_id").alias("nick", "display_name");
+    let tokens = [
+        Token::Map { len: Some(2) },
+        Token::Str("uid"),
+        Token::U32(7),
+        Token::Str("profile"),
+        Token::Map { len: Some(1) },
+        Token::Str("nick"),
+        Token::Str("b"),
+        Token::MapEnd,
+        Token::MapEnd,
+    ];
+    let expected = Account {
+        user_id: 7,
+        profile: Profile { display_name: "b".to_owned() },
+    };
+    assert_eq!(deserialize(&tokens, &matcher), Ok(expected));
+}
+
+#[test]
+fn test_struct_variant() {
+    let tokens = [
+        Token::StructVariant { name: "Event", variant: "Login", len: 1 },
+        Token::Str("userId"),
+        Token::U32(3),
+        Token::StructVariantEnd,
+    ];
+    assert_eq!(
+        deserialize(&tokens, &Matcher::new().case_insensitive()),
+        Ok(Event::Login { user_id: 3 })
+    );
+}
+
+#[test]
+fn test_map_keys_unchanged() {
+    use std::collections::BTreeMap;
+
+    let tokens = [
+        Token::Map { len: Some(1) },
+        Token::Str("UserID"),
+        Token::U32(1),
+        Token::MapEnd,
+    ];
+    let mut expected = BTreeMap::new();
+    expected.insert("UserID".to_owned(), 1);
+    assert_eq!(deserialize(&tokens, &Matcher::new().case_insensitive()), Ok(expected));
+}
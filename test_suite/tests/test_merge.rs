@@ -0,0 +1,163 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::merge::Merge;
+use serde::ser::SerializeStruct;
+
+extern crate serde_test;
+use serde_test::{Token, assert_ser_tokens_error_readable, assert_ser_tokens_readable};
+
+use std::collections::BTreeMap;
+
+// Merging passes the readability of the outer serializer on to the merged
+// values, so it has to be specified.
+fn assert_ser_tokens<T: Serialize>(value: &T, tokens: &[Token]) {
+    assert_ser_tokens_readable(value, tokens, Some(true));
+}
+
+fn assert_ser_tokens_error<T: Serialize>(value: &T, tokens: &[Token], error: &str) {
+    assert_ser_tokens_error_readable(value, tokens, error, Some(true));
+}
+
+#[derive(Serialize)]
+struct Envelope {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Login {
+    user: &'static str,
+}
+
+#[derive(Serialize)]
+struct Wrapper(Login);
+
+#[derive(Serialize)]
+enum Payload {
+    Login { user: &'static str },
+}
+
+// Serializes whether the serializer is human-readable.
+struct Readability;
+
+impl Serialize for Readability {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let human_readable = serializer.is_human_readable();
+        let mut state = try!(serializer.serialize_struct("Readability", 1));
+        try!(state.serialize_field("human_readable", &human_readable));
+        state.end()
+    }
+}
+
+#[test]
+fn test_merge_structs() {
+    let envelope = Envelope { id: 1, trace: None };
+    let login = Login { user: "ferris" };
+
+    assert_ser_tokens(
+        &Merge(&envelope, &login),
+        &[
+            Token::Map { len: None },
+            Token::Str("id"),
+            Token::U64(1),
+            Token::Str("user"),
+            Token::Str("ferris"),
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_merge_nested_and_maps() {
+    let envelope = Envelope { id: 1, trace: Some("abc".to_owned()) };
+    let mut extra = BTreeMap::new();
+    extra.insert(7, true);
+
+    assert_ser_tokens(
+        &Merge(&envelope, Merge(Wrapper(Login { user: "ferris" }), &extra)),
+        &[
+            Token::Map { len: None },
+            Token::Str("id"),
+            Token::U64(1),
+            Token::Str("trace"),
+            Token::Some,
+            Token::Str("abc"),
+            Token::Str("user"),
+            Token::Str("ferris"),
+            Token::I32(7),
+            Token::Bool(true),
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_merge_options() {
+    let login = Login { user: "ferris" };
+
+    assert_ser_tokens(
+        &Merge(Some(&login), None::<Login>),
+        &[
+            Token::Map { len: None },
+            Token::Str("user"),
+            Token::Str("ferris"),
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_merge_readability() {
+    for &human_readable in &[true, false] {
+        assert_ser_tokens_readable(
+            &Merge(Readability, Readability),
+            &[
+                Token::Map { len: None },
+                Token::Str("human_readable"),
+                Token::Bool(human_readable),
+                Token::Str("human_readable"),
+                Token::Bool(human_readable),
+                Token::MapEnd,
+            ],
+            Some(human_readable),
+        );
+    }
+}
+
+#[test]
+fn test_merge_not_map_like() {
+    let envelope = Envelope { id: 1, trace: None };
+
+    assert_ser_tokens_error(
+        &Merge(&envelope, vec![1, 2]),
+        &[Token::Map { len: None }, Token::Str("id"), Token::U64(1)],
+        "can only merge maps and structs, found a sequence",
+    );
+    assert_ser_tokens_error(
+        &Merge(Payload::Login { user: "ferris" }, &envelope),
+        &[Token::Map { len: None }],
+        "can only merge maps and structs, found an enum variant",
+    );
+    assert_ser_tokens_error(
+        &Merge(&envelope, "text"),
+        &[Token::Map { len: None }, Token::Str("id"), Token::U64(1)],
+        "can only merge maps and structs, found a string",
+    );
+    assert_ser_tokens_error(
+        &Merge(&envelope, 1u128 << 100),
+        &[Token::Map { len: None }, Token::Str("id"), Token::U64(1)],
+        "can only merge maps and structs, found an integer",
+    );
+}
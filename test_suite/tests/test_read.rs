@@ -0,0 +1,114 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde;
+use serde::read::{IoRead, Position, Read, Reference, SliceRead, StrRead};
+
+use std::io::{self, Cursor};
+
+const INPUT: &'static str = "ab cd\nef\n\ngh";
+
+// Reads every word of INPUT, checking the reader's position along the way.
+fn check_words<'de, R: Read<'de>>(mut read: R) -> Vec<Vec<u8>> {
+    let mut scratch = Vec::new();
+    let mut words = Vec::new();
+
+    assert_eq!(read.position(), Position { line: 1, column: 0, offset: 0 });
+    loop {
+        words.push(read.take_while(&mut scratch, |b| b != b' ' && b != b'\n').unwrap().to_vec());
+        match read.next().unwrap() {
+            Some(_) => {}
+            None => break,
+        }
+    }
+    assert_eq!(read.position(), Position { line: 4, column: 2, offset: 12 });
+    assert_eq!(read.peek().unwrap(), None);
+    assert_eq!(read.next().unwrap(), None);
+    words
+}
+
+fn expected_words() -> Vec<Vec<u8>> {
+    vec![b"ab".to_vec(), b"cd".to_vec(), b"ef".to_vec(), Vec::new(), b"gh".to_vec()]
+}
+
+#[test]
+fn test_slice_read() {
+    assert_eq!(check_words(SliceRead::new(INPUT.as_bytes())), expected_words());
+}
+
+#[test]
+fn test_str_read() {
+    assert_eq!(check_words(StrRead::new(INPUT)), expected_words());
+
+    let mut read = StrRead::new("é!");
+    assert_eq!(read.remaining(), Some("é!"));
+    read.next().unwrap();
+    assert_eq!(read.remaining(), None);
+    read.next().unwrap();
+    assert_eq!(read.remaining(), Some("!"));
+}
+
+#[test]
+fn test_io_read() {
+    assert_eq!(check_words(IoRead::new(Cursor::new(INPUT))), expected_words());
+}
+
+#[test]
+fn test_peek_and_discard() {
+    let mut read = IoRead::new(Cursor::new("x\ny"));
+    assert_eq!(read.peek().unwrap(), Some(b'x'));
+    assert_eq!(read.peek().unwrap(), Some(b'x'));
+    assert_eq!(read.position().offset, 0);
+    read.discard();
+    assert_eq!(read.position(), Position { line: 1, column: 1, offset: 1 });
+    read.discard();
+    assert_eq!(read.position().offset, 1);
+    assert_eq!(read.next().unwrap(), Some(b'\n'));
+    assert_eq!(read.position(), Position { line: 2, column: 0, offset: 2 });
+
+    let mut read = SliceRead::new(b"xy");
+    assert_eq!(read.peek().unwrap(), Some(b'x'));
+    read.discard();
+    assert_eq!(read.remaining(), b"y");
+}
+
+#[test]
+fn test_borrowing() {
+    let input = b"borrowed rest".to_vec();
+    let word = {
+        let mut read = SliceRead::new(&input);
+        let mut scratch = Vec::new();
+        match read.take_while(&mut scratch, |b| b != b' ').unwrap() {
+            Reference::Borrowed(word) => word,
+            Reference::Copied(_) => panic!("expected a borrowed slice"),
+        }
+    };
+    assert_eq!(word, b"borrowed");
+
+    let mut read = IoRead::new(Cursor::new(&input));
+    let mut scratch = Vec::new();
+    match read.take_while(&mut scratch, |b| b != b' ').unwrap() {
+        Reference::Copied(word) => assert_eq!(word, b"borrowed"),
+        Reference::Borrowed(_) => panic!("expected a copied slice"),
+    }
+}
+
+struct Failing;
+
+impl io::Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "broken pipe"))
+    }
+}
+
+#[test]
+fn test_io_error() {
+    let mut read = IoRead::new(Failing);
+    assert_eq!(read.peek().unwrap_err().to_string(), "broken pipe");
+    assert_eq!(read.next().unwrap_err().to_string(), "broken pipe");
+}
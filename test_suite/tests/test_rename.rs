@@ -0,0 +1,164 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::rename::{NameKind, RenameSerializer};
+
+extern crate serde_test;
+use serde_test::{Token, assert_ser_tokens, assert_ser_tokens_readable};
+
+use std::collections::BTreeMap;
+
+// Serializes the wrapped value through a `RenameSerializer` that prefixes
+// fields with `x_` and upper-cases variants.
+struct Renamed<T>(T);
+
+impl<T> Serialize for Renamed<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let rename = |kind: NameKind, name: &str| match kind {
+            NameKind::Field => format!("x_{}", name),
+            NameKind::Variant => name.to_uppercase(),
+        };
+        self.0.serialize(RenameSerializer::new(serializer, &rename))
+    }
+}
+
+#[derive(Serialize)]
+struct Outer {
+    id: u32,
+    inner: Vec<Inner>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Inner {
+    kind: Kind,
+}
+
+#[derive(Serialize)]
+enum Kind {
+    Unit,
+    Newtype(u8),
+    Tuple(u8, u8),
+    Struct { a: u8 },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Tagged {
+    Point { x: u8 },
+}
+
+#[test]
+fn test_nested_fields() {
+    let value = Outer {
+        id: 1,
+        inner: vec![Inner { kind: Kind::Unit }],
+        note: None,
+    };
+
+    assert_ser_tokens(
+        &Renamed(value),
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("x_id"),
+            Token::U32(1),
+            Token::Str("x_inner"),
+            Token::Seq { len: Some(1) },
+            Token::Map { len: Some(1) },
+            Token::Str("x_kind"),
+            Token::Str("UNIT"),
+            Token::MapEnd,
+            Token::SeqEnd,
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_variants() {
+    assert_ser_tokens(
+        &Renamed(Kind::Newtype(1)),
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("NEWTYPE"),
+            Token::U8(1),
+            Token::MapEnd,
+        ],
+    );
+
+    assert_ser_tokens_readable(
+        &Renamed(Kind::Tuple(1, 2)),
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("TUPLE"),
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::SeqEnd,
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+
+    assert_ser_tokens_readable(
+        &Renamed(Kind::Struct { a: 1 }),
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("STRUCT"),
+            Token::Map { len: Some(1) },
+            Token::Str("x_a"),
+            Token::U8(1),
+            Token::MapEnd,
+            Token::MapEnd,
+        ],
+        Some(true),
+    );
+}
+
+#[test]
+fn test_internal_tag_is_a_field() {
+    assert_ser_tokens(
+        &Renamed(Tagged::Point { x: 1 }),
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("x_type"),
+            Token::Str("Point"),
+            Token::Str("x_x"),
+            Token::U8(1),
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_map_keys_unchanged() {
+    let mut map = BTreeMap::new();
+    map.insert("id", Kind::Unit);
+
+    assert_ser_tokens(
+        &Renamed(map),
+        &[
+            Token::Map { len: Some(1) },
+            Token::Str("id"),
+            Token::Str("UNIT"),
+            Token::MapEnd,
+        ],
+    );
+}
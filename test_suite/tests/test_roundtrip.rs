@@ -7,9 +7,10 @@
 // except according to those terms.
 
 extern crate serde_test;
-use self::serde_test::{Token, assert_tokens_readable};
+use self::serde_test::{Token, assert_round_trip, assert_round_trip_all, assert_tokens_readable};
 
 use std::net;
+use std::num::ParseFloatError;
 
 #[macro_use]
 #[allow(unused_macros)]
@@ -51,3 +52,32 @@ fn socked_addr_roundtrip() {
         Some(false),
     );
 }
+
+// A lossy text format that only keeps one decimal place.
+fn to_string(v: &f64) -> Result<String, ParseFloatError> {
+    Ok(format!("{:.1}", v))
+}
+
+fn from_str(s: &str) -> Result<f64, ParseFloatError> {
+    s.parse()
+}
+
+#[test]
+fn test_round_trip() {
+    assert_round_trip(&1.5, to_string, |s| from_str(s));
+    assert_round_trip_all(vec![0.0, -2.5, 1e3], to_string, |s| from_str(s));
+}
+
+#[test]
+#[should_panic(expected = "value changed in round trip")]
+fn test_round_trip_changed() {
+    assert_round_trip(&1.25, to_string, |s| from_str(s));
+}
+
+#[test]
+#[should_panic(expected = "value #2 failed to deserialize: invalid float literal")]
+fn test_round_trip_all_deserialize_error() {
+    assert_round_trip_all(vec![1.0, 2.0, 3.0], to_string, |s: &String| {
+        if s == "3.0" { from_str("x") } else { from_str(s) }
+    });
+}
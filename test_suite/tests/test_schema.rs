@@ -0,0 +1,124 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::BTreeMap;
+
+extern crate serde;
+use serde::schema::{describe, Schema, Variant};
+
+#[derive(Deserialize)]
+struct Unit;
+
+#[derive(Deserialize)]
+struct Newtype(u8);
+
+#[derive(Deserialize)]
+struct Tuple(i32, Option<String>);
+
+#[derive(Deserialize)]
+struct Struct {
+    a: bool,
+    #[serde(rename = "B")]
+    b: Vec<char>,
+    #[serde(default)]
+    c: BTreeMap<String, f32>,
+}
+
+#[derive(Deserialize)]
+enum Inner {
+    A,
+    B(u16),
+}
+
+#[derive(Deserialize)]
+enum Outer {
+    Unit,
+    Tuple(Inner, Inner),
+    Struct { inner: Option<Inner> },
+}
+
+#[derive(Deserialize)]
+struct Recursive {
+    next: Option<Box<Recursive>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Untagged {
+    A(u8),
+}
+
+#[test]
+fn test_describe_primitives() {
+    assert_eq!(describe::<u64>().unwrap(), Schema::U64);
+    assert_eq!(describe::<String>().unwrap(), Schema::Str);
+    assert_eq!(describe::<&str>().unwrap(), Schema::Str);
+    assert_eq!(describe::<()>().unwrap(), Schema::Unit);
+    assert_eq!(
+        describe::<(u8, Vec<i8>)>().unwrap(),
+        Schema::Tuple(vec![Schema::U8, Schema::Seq(Box::new(Schema::I8))])
+    );
+}
+
+#[test]
+fn test_describe_structs() {
+    assert_eq!(describe::<Unit>().unwrap(), Schema::UnitStruct("Unit"));
+    assert_eq!(
+        describe::<Newtype>().unwrap(),
+        Schema::NewtypeStruct("Newtype", Box::new(Schema::U8))
+    );
+    assert_eq!(
+        describe::<Tuple>().unwrap(),
+        Schema::TupleStruct("Tuple", vec![Schema::I32, Schema::Option(Box::new(Schema::Str))])
+    );
+    assert_eq!(
+        describe::<Struct>().unwrap(),
+        Schema::Struct(
+            "Struct",
+            vec![
+                ("a", Schema::Bool),
+                ("B", Schema::Seq(Box::new(Schema::Char))),
+                ("c", Schema::Map(Box::new(Schema::Str), Box::new(Schema::F32))),
+            ],
+        )
+    );
+}
+
+#[test]
+fn test_describe_enums() {
+    let inner = Schema::Enum(
+        "Inner",
+        vec![("A", Variant::Unit), ("B", Variant::Newtype(Schema::U16))],
+    );
+    assert_eq!(
+        describe::<Outer>().unwrap(),
+        Schema::Enum(
+            "Outer",
+            vec![
+                ("Unit", Variant::Unit),
+                ("Tuple", Variant::Tuple(vec![inner.clone(), inner.clone()])),
+                ("Struct", Variant::Struct(vec![("inner", Schema::Option(Box::new(inner)))])),
+            ],
+        )
+    );
+}
+
+#[test]
+fn test_describe_errors() {
+    assert_eq!(
+        describe::<Recursive>().unwrap_err().to_string(),
+        "cannot describe recursive type `Recursive`"
+    );
+    assert_eq!(
+        describe::<Untagged>().unwrap_err().to_string(),
+        "cannot describe a type that relies on deserialize_any"
+    );
+}
@@ -14,6 +14,8 @@ use std::net;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, UNIX_EPOCH};
 use std::ffi::CString;
+use std::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize};
+use std::ops::Bound;
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -47,6 +49,25 @@ struct Struct {
     c: i32,
 }
 
+// The u64 field follows a u8, so it is unaligned and has to be copied out
+// before it is serialized.
+#[derive(Serialize)]
+#[repr(packed)]
+struct PackedStruct {
+    a: u8,
+    #[serde(skip_serializing_if = "is_zero")]
+    b: u8,
+    c: u64,
+}
+
+#[derive(Serialize)]
+#[repr(C, packed)]
+struct PackedTupleStruct(u8, u32);
+
+fn is_zero(n: &u8) -> bool {
+    *n == 0
+}
+
 #[derive(Serialize, PartialEq, Debug)]
 enum Enum {
     Unit,
@@ -305,6 +326,35 @@ declare_tests! {
             Token::StructEnd,
         ],
     }
+    test_packed_struct {
+        PackedStruct { a: 1, b: 2, c: 0x0102030405060708 } => &[
+            Token::Struct { name: "PackedStruct", len: 3 },
+                Token::Str("a"),
+                Token::U8(1),
+
+                Token::Str("b"),
+                Token::U8(2),
+
+                Token::Str("c"),
+                Token::U64(0x0102030405060708),
+            Token::StructEnd,
+        ],
+        PackedStruct { a: 1, b: 0, c: u64::max_value() } => &[
+            Token::Struct { name: "PackedStruct", len: 2 },
+                Token::Str("a"),
+                Token::U8(1),
+
+                Token::Str("c"),
+                Token::U64(u64::max_value()),
+            Token::StructEnd,
+        ],
+        PackedTupleStruct(1, 0x01020304) => &[
+            Token::TupleStruct { name: "PackedTupleStruct", len: 2 },
+                Token::U8(1),
+                Token::U32(0x01020304),
+            Token::TupleStructEnd,
+        ],
+    }
     test_enum {
         Enum::Unit => &[Token::UnitVariant { name: "Enum", variant: "Unit" }],
         Enum::One(42) => &[Token::NewtypeVariant { name: "Enum", variant: "One" }, Token::I32(42)],
@@ -369,6 +419,37 @@ declare_tests! {
             Token::StructEnd,
         ],
     }
+    test_range_inclusive {
+        1u32..=2u32 => &[
+            Token::Struct { name: "RangeInclusive", len: 2 },
+                Token::Str("start"),
+                Token::U32(1),
+
+                Token::Str("end"),
+                Token::U32(2),
+            Token::StructEnd,
+        ],
+    }
+    test_bound {
+        Bound::Unbounded::<()> => &[
+            Token::UnitVariant { name: "Bound", variant: "Unbounded" },
+        ],
+        Bound::Included(0u8) => &[
+            Token::NewtypeVariant { name: "Bound", variant: "Included" },
+            Token::U8(0),
+        ],
+        Bound::Excluded(0u8) => &[
+            Token::NewtypeVariant { name: "Bound", variant: "Excluded" },
+            Token::U8(0),
+        ],
+    }
+    test_nonzero {
+        NonZeroU8::new(1).unwrap() => &[Token::U8(1)],
+        NonZeroU16::new(1).unwrap() => &[Token::U16(1)],
+        NonZeroU32::new(1).unwrap() => &[Token::U32(1)],
+        NonZeroU64::new(1).unwrap() => &[Token::U64(1)],
+        NonZeroUsize::new(1).unwrap() => &[Token::U64(1)],
+    }
     test_path {
         Path::new("/usr/local/lib") => &[
             Token::Str("/usr/local/lib"),
@@ -548,3 +629,24 @@ fn test_enum_skipped() {
         "the enum variant Enum::SkippedMap cannot be serialized",
     );
 }
+
+#[test]
+fn test_integer128() {
+    use serde::Serialize;
+
+    let mut recording = serde_test::RecordingSerializer::new();
+    i128::min_value().serialize(&mut recording).unwrap();
+    u128::max_value().serialize(&mut recording).unwrap();
+    let calls: Vec<_> = recording.calls().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        calls,
+        [
+            "serialize_i128(-170141183460469231731687303715884105728)",
+            "serialize_u128(340282366920938463463374607431768211455)",
+        ]
+    );
+
+    // Formats that do not support 128-bit integers reject them.
+    assert_ser_tokens_error(&1i128, &[], "i128 is not supported");
+    assert_ser_tokens_error(&1u128, &[], "u128 is not supported");
+}
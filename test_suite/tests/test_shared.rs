@@ -0,0 +1,243 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+extern crate serde;
+use serde::Deserialize;
+use serde::shared::{SharedArc, SharedRc};
+
+extern crate serde_test;
+use serde_test::{Deserializer, Token, assert_de_tokens_error, assert_ser_tokens,
+                 assert_ser_tokens_error, assert_tokens};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Doc {
+    #[serde(with = "serde::shared")]
+    items: Vec<SharedRc<String>>,
+}
+
+#[test]
+fn test_shared_rc() {
+    let a = SharedRc::new("a".to_owned());
+    let b = SharedRc::new("b".to_owned());
+    let doc = Doc { items: vec![a.clone(), b, a] };
+
+    let tokens = [
+        Token::Struct { name: "Doc", len: 1 },
+        Token::Str("items"),
+        Token::Seq { len: Some(3) },
+
+        Token::StructVariant { name: "Shared", variant: "Def", len: 2 },
+        Token::Str("id"),
+        Token::U64(0),
+        Token::Str("value"),
+        Token::Str("a"),
+        Token::StructVariantEnd,
+
+        Token::StructVariant { name: "Shared", variant: "Def", len: 2 },
+        Token::Str("id"),
+        Token::U64(1),
+        Token::Str("value"),
+        Token::Str("b"),
+        Token::StructVariantEnd,
+
+        Token::NewtypeVariant { name: "Shared", variant: "Ref" },
+        Token::U64(0),
+
+        Token::SeqEnd,
+        Token::StructEnd,
+    ];
+    assert_tokens(&doc, &tokens);
+
+    let mut de = Deserializer::new(&tokens);
+    let doc = Doc::deserialize(&mut de).unwrap();
+    assert!(Rc::ptr_eq(&doc.items[0].0, &doc.items[2].0));
+    assert!(!Rc::ptr_eq(&doc.items[0].0, &doc.items[1].0));
+}
+
+#[test]
+fn test_shared_arc() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Pair(#[serde(with = "serde::shared")] (SharedArc<u8>, SharedArc<u8>));
+
+    let one = SharedArc::new(1);
+    let tokens = [
+        Token::NewtypeStruct { name: "Pair" },
+        Token::Tuple { len: 2 },
+        Token::StructVariant { name: "Shared", variant: "Def", len: 2 },
+        Token::Str("id"),
+        Token::U64(0),
+        Token::Str("value"),
+        Token::U8(1),
+        Token::StructVariantEnd,
+        Token::NewtypeVariant { name: "Shared", variant: "Ref" },
+        Token::U64(0),
+        Token::TupleEnd,
+    ];
+    assert_tokens(&Pair((one.clone(), one)), &tokens);
+
+    let mut de = Deserializer::new(&tokens);
+    let Pair((a, b)) = Pair::deserialize(&mut de).unwrap();
+    assert!(Arc::ptr_eq(&a.0, &b.0));
+}
+
+#[test]
+fn test_shared_errors() {
+    assert_ser_tokens_error(
+        &SharedRc::new(1),
+        &[],
+        "shared pointers must be serialized inside of serde::shared::serialize",
+    );
+
+    assert_de_tokens_error::<Doc>(
+        &[
+            Token::Struct { name: "Doc", len: 1 },
+            Token::Str("items"),
+            Token::Seq { len: Some(1) },
+            Token::NewtypeVariant { name: "Shared", variant: "Ref" },
+            Token::U64(3),
+        ],
+        "unknown shared id 3",
+    );
+}
+
+#[derive(Serialize)]
+struct Node {
+    name: &'static str,
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+#[derive(Serialize)]
+struct Acyclic {
+    #[serde(serialize_with = "serde::shared::serialize_acyclic")]
+    node: Rc<RefCell<Node>>,
+}
+
+#[test]
+fn test_acyclic_shared_pointers() {
+    let leaf = Rc::new(RefCell::new(Node { name: "leaf", next: None }));
+    let pair = vec![leaf.clone(), leaf];
+
+    #[derive(Serialize)]
+    struct Pair(#[serde(serialize_with = "serde::shared::serialize_acyclic")] Vec<Rc<RefCell<Node>>>);
+
+    let node = [
+        Token::Struct { name: "Node", len: 2 },
+        Token::Str("name"),
+        Token::Str("leaf"),
+        Token::Str("next"),
+        Token::None,
+        Token::StructEnd,
+    ];
+    let mut tokens = vec![Token::NewtypeStruct { name: "Pair" }, Token::Seq { len: Some(2) }];
+    tokens.extend_from_slice(&node);
+    tokens.extend_from_slice(&node);
+    tokens.push(Token::SeqEnd);
+    assert_ser_tokens(&Pair(pair), &tokens);
+}
+
+#[test]
+fn test_rc_cycle() {
+    let a = Rc::new(RefCell::new(Node { name: "a", next: None }));
+    let b = Rc::new(RefCell::new(Node { name: "b", next: Some(a.clone()) }));
+    a.borrow_mut().next = Some(b);
+
+    assert_ser_tokens_error(
+        &Acyclic { node: a.clone() },
+        &[
+            Token::Struct { name: "Acyclic", len: 1 },
+            Token::Str("node"),
+            Token::Struct { name: "Node", len: 2 },
+            Token::Str("name"),
+            Token::Str("a"),
+            Token::Str("next"),
+            Token::Some,
+            Token::Struct { name: "Node", len: 2 },
+            Token::Str("name"),
+            Token::Str("b"),
+            Token::Str("next"),
+            Token::Some,
+        ],
+        "reference cycle detected while serializing",
+    );
+
+    // Break the cycle so the nodes are freed.
+    a.borrow_mut().next = None;
+}
+
+#[test]
+fn test_arc_cycle() {
+    #[derive(Serialize)]
+    struct Cell {
+        next: Option<Arc<Mutex<Cell>>>,
+    }
+
+    #[derive(Serialize)]
+    struct Root(#[serde(serialize_with = "serde::shared::serialize_acyclic")] Arc<Mutex<Cell>>);
+
+    let cell = Arc::new(Mutex::new(Cell { next: None }));
+    let root = Root(cell.clone());
+    cell.lock().unwrap().next = Some(cell.clone());
+
+    // Without cycle detection this would lock the mutex a second time.
+    assert_ser_tokens_error(
+        &root,
+        &[
+            Token::NewtypeStruct { name: "Root" },
+            Token::Struct { name: "Cell", len: 1 },
+            Token::Str("next"),
+            Token::Some,
+        ],
+        "reference cycle detected while serializing",
+    );
+
+    cell.lock().unwrap().next = None;
+}
+
+#[test]
+fn test_shared_cycle() {
+    struct Cyclic {
+        this: RefCell<Option<SharedRc<Cyclic>>>,
+    }
+
+    impl serde::Serialize for Cyclic {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            self.this.serialize(serializer)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Wrapper(#[serde(serialize_with = "serde::shared::serialize")] SharedRc<Cyclic>);
+
+    let cyclic = SharedRc::new(Cyclic { this: RefCell::new(None) });
+    *cyclic.this.borrow_mut() = Some(cyclic.clone());
+
+    assert_ser_tokens_error(
+        &Wrapper(cyclic.clone()),
+        &[
+            Token::NewtypeStruct { name: "Wrapper" },
+            Token::StructVariant { name: "Shared", variant: "Def", len: 2 },
+            Token::Str("id"),
+            Token::U64(0),
+            Token::Str("value"),
+            Token::Some,
+        ],
+        "reference cycle detected while serializing",
+    );
+
+    *cyclic.this.borrow_mut() = None;
+}
@@ -0,0 +1,352 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Serialize, Serializer};
+use serde::size::{CostModel, MaxSize, measure};
+
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+// Compact JSON without escapes, enough to check the totals by hand.
+struct Json;
+
+impl CostModel for Json {
+    fn bool(&mut self, v: bool) -> usize {
+        if v { 4 } else { 5 }
+    }
+
+    fn i64(&mut self, v: i64) -> usize {
+        v.to_string().len()
+    }
+
+    fn max_i32(&mut self) -> Option<usize> {
+        // -2147483648
+        Some(11)
+    }
+
+    fn u64(&mut self, v: u64) -> usize {
+        v.to_string().len()
+    }
+
+    fn f64(&mut self, v: f64) -> usize {
+        v.to_string().len()
+    }
+
+    fn char(&mut self, v: char) -> usize {
+        2 + v.len_utf8()
+    }
+
+    fn str(&mut self, v: &str) -> usize {
+        2 + v.len()
+    }
+
+    fn bytes(&mut self, v: &[u8]) -> usize {
+        2 + v.len().saturating_sub(1) + v.iter().map(|b| b.to_string().len()).sum::<usize>()
+    }
+
+    fn none(&mut self) -> usize {
+        4
+    }
+
+    fn unit(&mut self) -> usize {
+        4
+    }
+
+    fn variant(&mut self, _name: &str, _index: u32, variant: &str) -> usize {
+        // `"Variant"` alone, or `{"Variant":` and `}` around the content.
+        // Overcounts unit variants by the braces and colon.
+        3 + self.str(variant)
+    }
+
+    fn seq(&mut self, _len: Option<usize>) -> usize {
+        2
+    }
+
+    fn seq_element(&mut self, index: usize) -> usize {
+        if index == 0 { 0 } else { 1 }
+    }
+
+    fn map(&mut self, _len: Option<usize>) -> usize {
+        2
+    }
+
+    fn map_entry(&mut self, index: usize) -> usize {
+        if index == 0 { 1 } else { 2 }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(max_size)]
+struct Point {
+    x: i32,
+    y: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(max_size)]
+enum Shape {
+    Dot(Point),
+    Line(Point, Point),
+    Circle { radius: u8 },
+}
+
+#[test]
+fn test_primitives() {
+    assert_eq!(measure(&true, Json).unwrap(), 4);
+    assert_eq!(measure(&-120i8, Json).unwrap(), 4);
+    assert_eq!(measure(&1.5f32, Json).unwrap(), 3);
+    assert_eq!(measure("abc", Json).unwrap(), 5);
+    assert_eq!(measure(&'\u{e9}', Json).unwrap(), 4);
+    assert_eq!(measure(&None::<u8>, Json).unwrap(), 4);
+    assert_eq!(measure(&Some(10u8), Json).unwrap(), 2);
+}
+
+#[test]
+fn test_compounds() {
+    // [1,22,333]
+    assert_eq!(measure(&vec![1, 22, 333], Json).unwrap(), 10);
+    // [1,"a"]
+    assert_eq!(measure(&(1, "a"), Json).unwrap(), 7);
+
+    // {"a":1,"bb":22}
+    let mut map = BTreeMap::new();
+    map.insert("a", 1);
+    map.insert("bb", 22);
+    assert_eq!(measure(&map, Json).unwrap(), 15);
+
+    // {"x":-5,"y":null}
+    assert_eq!(measure(&Point { x: -5, y: None }, Json).unwrap(), 17);
+}
+
+#[test]
+fn test_variants() {
+    let point = || Point { x: 1, y: Some(true) };
+
+    // {"Dot":{"x":1,"y":true}}
+    assert_eq!(measure(&Shape::Dot(point()), Json).unwrap(), 24);
+    // {"Line":[{"x":1,"y":true},{"x":1,"y":true}]}
+    assert_eq!(measure(&Shape::Line(point(), point()), Json).unwrap(), 44);
+    // {"Circle":{"radius":3}}
+    assert_eq!(measure(&Shape::Circle { radius: 3 }, Json).unwrap(), 23);
+}
+
+struct Tag;
+
+impl Serialize for Tag {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&format_args!("{}-{}", "v", 12))
+    }
+}
+
+#[test]
+fn test_collect_str() {
+    assert_eq!(measure(&Tag, Json).unwrap(), 6);
+}
+
+struct Compact;
+
+impl CostModel for Compact {
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn bool(&mut self, _: bool) -> usize { 1 }
+    fn i64(&mut self, _: i64) -> usize { 8 }
+    fn max_i32(&mut self) -> Option<usize> { Some(8) }
+    fn u8(&mut self, _: u8) -> usize { 1 }
+    fn u64(&mut self, _: u64) -> usize { 8 }
+    fn f64(&mut self, _: f64) -> usize { 8 }
+    fn max_f64(&mut self) -> Option<usize> { Some(8) }
+    fn char(&mut self, _: char) -> usize { 4 }
+    fn max_char(&mut self) -> Option<usize> { Some(4) }
+    fn str(&mut self, v: &str) -> usize { 8 + v.len() }
+    fn bytes(&mut self, v: &[u8]) -> usize { 8 + v.len() }
+    fn none(&mut self) -> usize { 1 }
+    fn some(&mut self) -> usize { 1 }
+    fn unit(&mut self) -> usize { 0 }
+    fn variant(&mut self, _: &str, _: u32, _: &str) -> usize { 4 }
+    fn seq(&mut self, _: Option<usize>) -> usize { 8 }
+    fn tuple(&mut self, _: usize) -> usize { 0 }
+    fn map(&mut self, _: Option<usize>) -> usize { 8 }
+    fn struct_(&mut self, _: &str, _: usize) -> usize { 0 }
+    fn field(&mut self, _: usize, _: &str) -> usize { 0 }
+}
+
+#[test]
+fn test_max_size() {
+    // {"x":-2147483648,"y":false}
+    assert_eq!(Point::max_serialized_size(&mut Json), Some(27));
+    // {"Line":[<point>,<point>]}
+    assert_eq!(Shape::max_serialized_size(&mut Json), Some(9 + 2 + 27 + 1 + 27));
+    assert_eq!(<(u8, char)>::max_serialized_size(&mut Compact), Some(5));
+
+    assert_eq!(Point::max_serialized_size(&mut Compact), Some(10));
+    assert_eq!(Shape::max_serialized_size(&mut Compact), Some(24));
+
+    assert_eq!(f64::max_serialized_size(&mut Json), None);
+    assert_eq!(<Option<String>>::max_serialized_size(&mut Compact), None);
+    assert_eq!(<(u8, Vec<u8>)>::max_serialized_size(&mut Compact), None);
+
+    let point = Point { x: 1, y: None };
+    assert_eq!(point.serialized_size(Json).unwrap(), 16);
+}
+
+// The bound follows the names and fields the Serialize impl writes, even where
+// they differ from what the Deserialize impl reads.
+#[derive(Serialize, Deserialize)]
+#[serde(max_size)]
+struct OneWay {
+    #[serde(skip_deserializing)]
+    extra: u8,
+    #[serde(rename(serialize = "a_much_longer_name", deserialize = "n"))]
+    n: bool,
+}
+
+#[derive(Serialize)]
+#[serde(max_size)]
+struct Header {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<u8>,
+    version: u8,
+}
+
+#[derive(Serialize)]
+#[serde(max_size, tag = "type")]
+enum Event {
+    Start,
+    Stop { code: u8 },
+    #[serde(skip_serializing)]
+    #[allow(dead_code)]
+    Internal(String),
+}
+
+#[derive(Serialize)]
+#[serde(max_size)]
+struct Pair<T> {
+    a: T,
+    b: T,
+}
+
+#[derive(Serialize)]
+#[serde(max_size)]
+struct Custom {
+    #[serde(serialize_with = "serialize_as_str")]
+    n: u8,
+}
+
+fn serialize_as_str<S>(n: &u8, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(n)
+}
+
+#[test]
+fn test_max_size_follows_serialize() {
+    // {"extra":255,"a_much_longer_name":false}
+    let worst = OneWay { extra: 255, n: false };
+    assert_eq!(OneWay::max_serialized_size(&mut Json), Some(40));
+    assert_eq!(measure(&worst, Json).unwrap(), 40);
+
+    // {"checksum":255,"version":255}, plus one because the bound allows for
+    // `null`, which is skipped.
+    assert_eq!(Header::max_serialized_size(&mut Json), Some(31));
+    let header = Header { checksum: Some(255), version: 255 };
+    assert_eq!(measure(&header, Json).unwrap(), 30);
+    let header = Header { checksum: None, version: 255 };
+    assert_eq!(measure(&header, Json).unwrap(), 15);
+
+    // {"type":"Stop","code":255}
+    assert_eq!(Event::max_serialized_size(&mut Json), Some(26));
+    assert_eq!(measure(&Event::Stop { code: 255 }, Json).unwrap(), 26);
+    // {"type":"Start"}
+    assert_eq!(measure(&Event::Start, Json).unwrap(), 16);
+
+    // {"a":"x","b":"y"} has no bound, {"a":false,"b":false} does.
+    assert_eq!(<Pair<String>>::max_serialized_size(&mut Json), None);
+    assert_eq!(<Pair<bool>>::max_serialized_size(&mut Json), Some(21));
+
+    // The derive cannot see what `serialize_with` writes.
+    assert_eq!(Custom::max_serialized_size(&mut Compact), None);
+}
+
+#[test]
+fn test_overridden_defaults() {
+    assert_eq!(measure(&Point { x: 1, y: Some(false) }, Compact).unwrap(), 10);
+    assert_eq!(measure(&Shape::Circle { radius: 3 }, Compact).unwrap(), 5);
+    assert_eq!(measure(&Ipv4Addr::new(127, 0, 0, 1), Compact).unwrap(), 4);
+    assert_eq!(measure(&Ipv4Addr::new(127, 0, 0, 1), Json).unwrap(), 11);
+}
+
+struct Huge;
+
+impl CostModel for Huge {
+    fn bool(&mut self, _: bool) -> usize { usize::max_value() }
+    fn i64(&mut self, _: i64) -> usize { 0 }
+    fn u64(&mut self, _: u64) -> usize { 0 }
+    fn f64(&mut self, _: f64) -> usize { 0 }
+    fn char(&mut self, _: char) -> usize { 0 }
+    fn str(&mut self, _: &str) -> usize { 0 }
+    fn bytes(&mut self, _: &[u8]) -> usize { 0 }
+    fn none(&mut self) -> usize { 0 }
+    fn unit(&mut self) -> usize { 0 }
+    fn variant(&mut self, _: &str, _: u32, _: &str) -> usize { 0 }
+    fn seq(&mut self, _: Option<usize>) -> usize { 1 }
+    fn map(&mut self, _: Option<usize>) -> usize { 0 }
+}
+
+#[test]
+fn test_overflow() {
+    assert_eq!(measure(&true, Huge).unwrap(), usize::max_value());
+    let err = measure(&vec![true], Huge).unwrap_err();
+    assert_eq!(err.to_string(), "size overflows usize");
+    assert_eq!(<(bool,)>::max_serialized_size(&mut Huge), None);
+}
+
+struct Wide;
+
+impl CostModel for Wide {
+    fn bool(&mut self, _: bool) -> usize { 1 }
+    fn i64(&mut self, _: i64) -> usize { 8 }
+    fn u64(&mut self, _: u64) -> usize { 8 }
+    fn i128(&mut self, _: i128) -> Option<usize> { Some(16) }
+    fn u128(&mut self, _: u128) -> Option<usize> { Some(16) }
+    fn f64(&mut self, _: f64) -> usize { 8 }
+    fn char(&mut self, _: char) -> usize { 4 }
+    fn str(&mut self, v: &str) -> usize { v.len() }
+    fn bytes(&mut self, v: &[u8]) -> usize { v.len() }
+    fn none(&mut self) -> usize { 1 }
+    fn unit(&mut self) -> usize { 0 }
+    fn variant(&mut self, _: &str, _: u32, _: &str) -> usize { usize::max_value() }
+    fn seq(&mut self, _: Option<usize>) -> usize { 8 }
+    fn map(&mut self, _: Option<usize>) -> usize { usize::max_value() }
+}
+
+#[test]
+fn test_integer128() {
+    assert_eq!(measure(&(1i128, 2u128), Wide).unwrap(), 8 + 32);
+
+    // Models that do not override the hooks reject 128-bit integers.
+    assert_eq!(measure(&1i128, Json).unwrap_err().to_string(), "i128 is not supported");
+    assert_eq!(measure(&1u128, Json).unwrap_err().to_string(), "u128 is not supported");
+}
+
+#[test]
+fn test_variant_overflow() {
+    assert_eq!(
+        measure(&Shape::Circle { radius: 3 }, Wide).unwrap_err().to_string(),
+        "size overflows usize"
+    );
+}
@@ -0,0 +1,134 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::cell::RefCell;
+
+extern crate serde;
+use serde::{Deserialize, Serialize};
+use serde::trace::{Event, TraceDeserializer, TraceSerializer};
+
+extern crate serde_test;
+use serde_test::{Deserializer, RecordingSerializer, Token};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Outer {
+    a: Option<u8>,
+    b: Inner,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Inner {
+    c: u8,
+}
+
+#[test]
+fn test_trace_serializer() {
+    let log = RefCell::new(Vec::new());
+    let trace = |event: Event| log.borrow_mut().push(event.to_string());
+
+    let value = Outer { a: Some(1), b: Inner { c: 2 } };
+    let mut recording = RecordingSerializer::new();
+    value
+        .serialize(TraceSerializer::new(&mut recording, &trace))
+        .unwrap();
+
+    assert_eq!(
+        *log.borrow(),
+        [
+            "serialize_struct(\"Outer\")",
+            "  serialize_field(\"a\")",
+            "    serialize_some",
+            "      serialize_u8",
+            "  serialize_field(\"b\")",
+            "    serialize_struct(\"Inner\")",
+            "      serialize_field(\"c\")",
+            "        serialize_u8",
+            "    end",
+            "end",
+        ]
+    );
+    assert_eq!(recording.calls().len(), 10);
+}
+
+#[test]
+fn test_trace_serializer_skip_and_annotate() {
+    #[derive(Serialize)]
+    #[serde(annotate)]
+    struct S {
+        /// Always present.
+        a: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        b: Option<u8>,
+    }
+
+    let log = RefCell::new(Vec::new());
+    let trace = |event: Event| log.borrow_mut().push(event.to_string());
+
+    let mut recording = RecordingSerializer::new();
+    S { a: 1, b: None }
+        .serialize(TraceSerializer::new(&mut recording, &trace))
+        .unwrap();
+
+    assert_eq!(
+        *log.borrow(),
+        [
+            "serialize_struct(\"S\")",
+            "  annotate(\"a\")",
+            "  serialize_field(\"a\")",
+            "    serialize_u8",
+            "  skip_field(\"b\")",
+            "end",
+        ]
+    );
+
+    let mut direct = RecordingSerializer::new();
+    S { a: 1, b: None }.serialize(&mut direct).unwrap();
+    assert_eq!(recording.calls(), direct.calls());
+}
+
+#[test]
+fn test_trace_deserializer_failure() {
+    let log = RefCell::new(Vec::new());
+    let trace = |event: Event| log.borrow_mut().push(event.to_string());
+
+    let tokens = [
+        Token::Map { len: Some(2) },
+        Token::Str("a"),
+        Token::None,
+        Token::Str("b"),
+        Token::Map { len: Some(1) },
+        Token::Str("c"),
+        Token::Str("oops"),
+    ];
+    let mut de = Deserializer::new(&tokens);
+    let err = Outer::deserialize(TraceDeserializer::new(&mut de, &trace)).unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: string \"oops\", expected u8");
+
+    let failures: Vec<_> = log.borrow()
+        .iter()
+        .filter(|line| line.ends_with(" failed"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        failures,
+        [
+            "        visit_str(\"oops\") failed",
+            "        deserialize_u8 failed",
+            "      next_value failed",
+            "    visit_map failed",
+            "    deserialize_struct(\"Inner\") failed",
+            "  next_value failed",
+            "visit_map failed",
+            "deserialize_struct(\"Outer\") failed",
+        ]
+    );
+    assert!(log.borrow().contains(&"    visit_str(\"b\")".to_owned()));
+}
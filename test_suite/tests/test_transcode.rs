@@ -0,0 +1,68 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde;
+use serde::transcode::Transcoder;
+
+extern crate serde_test;
+use serde_test::{Token, assert_ser_tokens, assert_ser_tokens_error, Deserializer};
+
+#[test]
+fn test_transcode_primitives() {
+    let input = [Token::Bool(true)];
+    let mut de = Deserializer::new(&input);
+    assert_ser_tokens(&Transcoder::new(&mut de), &[Token::Bool(true)]);
+
+    let input = [Token::String("abc")];
+    let mut de = Deserializer::new(&input);
+    assert_ser_tokens(&Transcoder::new(&mut de), &[Token::Str("abc")]);
+
+    let input = [Token::Some, Token::I32(-1)];
+    let mut de = Deserializer::new(&input);
+    assert_ser_tokens(&Transcoder::new(&mut de), &[Token::Some, Token::I32(-1)]);
+}
+
+#[test]
+fn test_transcode_nested() {
+    let input = [
+        Token::Map { len: Some(2) },
+        Token::Str("a"),
+        Token::Seq { len: Some(2) },
+        Token::U8(1),
+        Token::None,
+        Token::SeqEnd,
+        Token::Str("b"),
+        Token::NewtypeStruct { name: "Meters" },
+        Token::F64(1.5),
+        Token::MapEnd,
+    ];
+    let mut de = Deserializer::new(&input);
+    assert_ser_tokens(
+        &Transcoder::new(&mut de),
+        &[
+            Token::Map { len: Some(2) },
+            Token::Str("a"),
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::None,
+            Token::SeqEnd,
+            Token::Str("b"),
+            Token::F64(1.5),
+            Token::MapEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_transcode_once() {
+    let input = [Token::Unit];
+    let mut de = Deserializer::new(&input);
+    let transcoder = Transcoder::new(&mut de);
+    assert_ser_tokens(&transcoder, &[Token::Unit]);
+    assert_ser_tokens_error(&transcoder, &[], "Transcoder can only be serialized once");
+}
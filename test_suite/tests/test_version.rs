@@ -0,0 +1,201 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::version::{Migrate, Unversioned, Versioned};
+
+extern crate serde_test;
+use serde_test::{Token, assert_de_tokens, assert_de_tokens_error, assert_tokens};
+
+#[derive(Deserialize)]
+struct PointV1(i32);
+
+impl Versioned for PointV1 {
+    type Previous = Unversioned;
+
+    fn version() -> u32 {
+        1
+    }
+
+    fn migrate(previous: Unversioned) -> Self {
+        match previous {}
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Versioned for Point {
+    type Previous = PointV1;
+
+    fn version() -> u32 {
+        2
+    }
+
+    fn migrate(previous: PointV1) -> Self {
+        Point { x: previous.0, y: 0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Save {
+    #[serde(with = "serde::version")]
+    point: Point,
+}
+
+#[test]
+fn test_current_version() {
+    assert_tokens(
+        &Save { point: Point { x: 1, y: 2 } },
+        &[
+            Token::Struct { name: "Save", len: 1 },
+            Token::Str("point"),
+            Token::Struct { name: "Versioned", len: 2 },
+            Token::Str("version"),
+            Token::U32(2),
+            Token::Str("value"),
+            Token::Struct { name: "Point", len: 2 },
+            Token::Str("x"),
+            Token::I32(1),
+            Token::Str("y"),
+            Token::I32(2),
+            Token::StructEnd,
+            Token::StructEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_migrate_previous_version() {
+    assert_de_tokens(
+        &Save { point: Point { x: 5, y: 0 } },
+        &[
+            Token::Struct { name: "Save", len: 1 },
+            Token::Str("point"),
+            Token::Seq { len: Some(2) },
+            Token::U32(1),
+            Token::NewtypeStruct { name: "PointV1" },
+            Token::I32(5),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_unsupported_versions() {
+    for &(version, error) in &[(0, "unsupported version 0"), (3, "unsupported version 3")] {
+        assert_de_tokens_error::<Save>(
+            &[
+                Token::Struct { name: "Save", len: 1 },
+                Token::Str("point"),
+                Token::Struct { name: "Versioned", len: 2 },
+                Token::Str("version"),
+                Token::U32(version),
+                Token::Str("value"),
+            ],
+            error,
+        );
+    }
+}
+
+#[test]
+fn test_version_must_come_first() {
+    assert_de_tokens_error::<Save>(
+        &[
+            Token::Struct { name: "Save", len: 1 },
+            Token::Str("point"),
+            Token::Map { len: Some(2) },
+            Token::Str("value"),
+        ],
+        "expected `version` before `value`",
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(version = "1")]
+struct SizeV1(u32);
+
+impl Migrate for SizeV1 {
+    type Previous = Unversioned;
+
+    fn migrate(previous: Unversioned) -> Self {
+        match previous {}
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(version = "2")]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+impl Migrate for Size {
+    type Previous = SizeV1;
+
+    fn migrate(previous: SizeV1) -> Self {
+        Size {
+            width: previous.0,
+            height: previous.0,
+        }
+    }
+}
+
+#[test]
+fn test_version_attribute() {
+    assert_tokens(
+        &Size { width: 1, height: 2 },
+        &[
+            Token::Struct { name: "Versioned", len: 2 },
+            Token::Str("version"),
+            Token::U32(2),
+            Token::Str("value"),
+            Token::Struct { name: "Size", len: 2 },
+            Token::Str("width"),
+            Token::U32(1),
+            Token::Str("height"),
+            Token::U32(2),
+            Token::StructEnd,
+            Token::StructEnd,
+        ],
+    );
+}
+
+#[test]
+fn test_version_attribute_migrates() {
+    assert_de_tokens(
+        &Size { width: 3, height: 3 },
+        &[
+            Token::Struct { name: "Versioned", len: 2 },
+            Token::Str("version"),
+            Token::U32(1),
+            Token::Str("value"),
+            Token::NewtypeStruct { name: "SizeV1" },
+            Token::U32(3),
+            Token::StructEnd,
+        ],
+    );
+
+    assert_de_tokens_error::<Size>(
+        &[
+            Token::Struct { name: "Versioned", len: 2 },
+            Token::Str("version"),
+            Token::U32(3),
+            Token::Str("value"),
+        ],
+        "unsupported version 3",
+    );
+}
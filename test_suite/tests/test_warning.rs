@@ -0,0 +1,154 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+use serde::{Deserialize, Deserializer};
+use serde::warning::{self, Warning};
+
+extern crate serde_test;
+use serde_test::Token;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Server {
+    #[serde(deserialize_with = "deserialize_port")]
+    port: u16,
+}
+
+// Accepts ports above u16::MAX by clamping them, with a warning.
+fn deserialize_port<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let port = try!(u32::deserialize(deserializer));
+    if port > 65535 {
+        warning::report(format_args!("port {} clamped to 65535", port));
+        Ok(65535)
+    } else {
+        Ok(port as u16)
+    }
+}
+
+fn deserialize_tokens<'de, T>(tokens: &'de [Token]) -> (Result<T, String>, Vec<Warning>)
+where
+    T: Deserialize<'de>,
+{
+    let mut warnings = Vec::new();
+    let mut deserializer = serde_test::Deserializer::new(tokens);
+    let result = warning::deserialize(&mut deserializer, |w| warnings.push(w));
+    (result.map_err(|e| e.to_string()), warnings)
+}
+
+fn server(extra: &'static str, port: u32) -> Vec<Token> {
+    vec![
+        Token::Struct { name: "Server", len: 2 },
+        Token::Str(extra),
+        Token::Seq { len: Some(1) },
+        Token::Bool(true),
+        Token::SeqEnd,
+        Token::Str("port"),
+        Token::U32(port),
+        Token::StructEnd,
+    ]
+}
+
+fn config(servers: Vec<Vec<Token>>) -> Vec<Token> {
+    let mut tokens = vec![
+        Token::Struct { name: "Config", len: 3 },
+        Token::Str("name"),
+        Token::Str("app"),
+        Token::Str("nmae"),
+        Token::Str("typo"),
+        Token::Str("servers"),
+        Token::Seq { len: Some(servers.len()) },
+    ];
+    for server in servers {
+        tokens.extend(server);
+    }
+    tokens.extend(vec![Token::SeqEnd, Token::StructEnd]);
+    tokens
+}
+
+#[test]
+fn test_ignored_fields() {
+    let tokens = config(vec![server("a", 80), server("b", 443)]);
+    let (value, warnings) = deserialize_tokens::<Config>(&tokens);
+
+    assert_eq!(
+        value,
+        Ok(
+            Config {
+                name: "app".to_owned(),
+                servers: vec![Server { port: 80 }, Server { port: 443 }],
+            },
+        )
+    );
+    assert_eq!(
+        warnings,
+        [
+            Warning::IgnoredField { path: "nmae".to_owned() },
+            Warning::IgnoredField { path: "servers[0].a".to_owned() },
+            Warning::IgnoredField { path: "servers[1].b".to_owned() },
+        ]
+    );
+    assert_eq!(warnings[1].to_string(), "ignored field `servers[0].a`");
+}
+
+#[test]
+fn test_reported_warnings() {
+    let tokens = config(vec![server("a", 70000)]);
+    let (value, warnings) = deserialize_tokens::<Config>(&tokens);
+
+    assert_eq!(value.unwrap().servers, [Server { port: 65535 }]);
+    assert_eq!(
+        warnings[2],
+        Warning::Custom {
+            path: "servers[0].port".to_owned(),
+            message: "port 70000 clamped to 65535".to_owned(),
+        }
+    );
+    assert_eq!(
+        warnings[2].to_string(),
+        "port 70000 clamped to 65535 at `servers[0].port`"
+    );
+}
+
+#[test]
+fn test_warnings_on_error() {
+    let tokens = [
+        Token::Struct { name: "Server", len: 2 },
+        Token::Str("extra"),
+        Token::Unit,
+        Token::Str("port"),
+        Token::Str("eighty"),
+    ];
+    let (value, warnings) = deserialize_tokens::<Server>(&tokens);
+
+    assert_eq!(
+        value,
+        Err("invalid type: string \"eighty\", expected u32".to_owned())
+    );
+    assert_eq!(warnings, [Warning::IgnoredField { path: "extra".to_owned() }]);
+}
+
+#[test]
+fn test_report_outside_deserialize() {
+    warning::report("nobody is listening");
+
+    let tokens = [Token::U32(70000)];
+    let mut de = serde_test::Deserializer::new(&tokens);
+    assert_eq!(deserialize_port(&mut de).unwrap(), 65535);
+}
@@ -0,0 +1,115 @@
+// Copyright 2017 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate serde;
+use serde::write::{write_escaped, write_f32, write_f64, write_i64, write_u64};
+
+use std::io::{self, Write};
+use std::{f32, f64, i64, u64};
+
+fn written<F>(f: F) -> String
+where
+    F: FnOnce(&mut Vec<u8>) -> io::Result<()>,
+{
+    let mut out = Vec::new();
+    f(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_integers() {
+    let unsigned = [0, 1, 9, 10, 99, 100, 999, 1000, 9999, 10000, 123456789, u64::MAX];
+    for &n in &unsigned {
+        assert_eq!(written(|out| write_u64(out, n)), n.to_string());
+    }
+
+    let signed = [0, -1, 1, -9999, 10000, -10001, i64::MAX, i64::MIN];
+    for &n in &signed {
+        assert_eq!(written(|out| write_i64(out, n)), n.to_string());
+    }
+
+    let mut n = 1u64;
+    while let Some(next) = n.checked_mul(7) {
+        assert_eq!(written(|out| write_u64(out, n)), n.to_string());
+        n = next;
+    }
+}
+
+#[test]
+fn test_floats() {
+    for &f in &[0.0, -0.0, 1.0, 0.1, -2.5, 1e300, 5e-324, f64::MAX, f64::INFINITY, f64::NAN] {
+        let s = written(|out| write_f64(out, f));
+        if f.is_nan() {
+            assert_eq!(s, "NaN");
+        } else {
+            assert_eq!(s.parse::<f64>().unwrap(), f);
+        }
+    }
+
+    assert_eq!(written(|out| write_f32(out, 0.1)), "0.1");
+    assert_eq!(written(|out| write_f32(out, f32::NEG_INFINITY)), "-inf");
+}
+
+// Counts the writes made on it.
+struct Counting {
+    out: Vec<u8>,
+    writes: usize,
+}
+
+impl Write for Counting {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.out.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn escape_json(s: &str) -> (String, usize) {
+    let mut writer = Counting { out: Vec::new(), writes: 0 };
+    write_escaped(
+        &mut writer,
+        s.as_bytes(),
+        |b| b < 0x20 || b == b'"' || b == b'\\',
+        |writer, b| match b {
+            b'\n' => writer.write_all(b"\\n"),
+            b'"' | b'\\' => writer.write_all(&[b'\\', b]),
+            _ => write!(writer, "\\u{:04x}", b),
+        },
+    ).unwrap();
+    (String::from_utf8(writer.out).unwrap(), writer.writes)
+}
+
+#[test]
+fn test_escaped() {
+    assert_eq!(escape_json(""), (String::new(), 0));
+    assert_eq!(escape_json("plain text é"), ("plain text é".to_owned(), 1));
+    assert_eq!(escape_json("a\"b\\c\n"), ("a\\\"b\\\\c\\n".to_owned(), 6));
+    assert_eq!(escape_json("\u{1}\u{1f}").0, "\\u0001\\u001f");
+}
+
+#[test]
+fn test_io_errors() {
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Ok(0)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    assert!(write_u64(&mut Full, 1).is_err());
+    assert!(write_escaped(&mut Full, b"ab", |_| false, |_, _| Ok(())).is_err());
+}